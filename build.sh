@@ -1,3 +1,2 @@
-cargo run bootstrap/sl.sl
-gcc -o bootstrap/sl bootstrap/sl.c
+cargo run -- build bootstrap/sl.sl
 ./bootstrap/sl $1
\ No newline at end of file
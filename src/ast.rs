@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+use crate::diagnostics::Diagnostic;
+use crate::lexer::{TokenKind, TokenLocation};
+use crate::symbol::Symbol;
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone)] pub enum Statement {
+    Generic(Box<Statement>, Vec<(Symbol, Option<Type>)>, TokenLocation),
+    Annotated(Box<Statement>, Vec<Annotation>, TokenLocation),
+    Annotation(Symbol, Vec<(Symbol, Type)>, TokenLocation),
+    Struct(Symbol, Vec<(Symbol, Type)>, TokenLocation),
+    Enum(Symbol, Type, Vec<(Symbol, Expression, TokenLocation)>, TokenLocation),
+    StructEnum(Symbol, Vec<(Symbol, Vec<(Symbol, Type)>)>, TokenLocation),
+    JavaEnum(Symbol, Vec<(Symbol, Type)>, Vec<(Symbol, Vec<Expression>)>, TokenLocation),
+    TypeAlias(Symbol, Vec<Type>, TokenLocation),
+    Function(Symbol, Vec<(Symbol, Type)>, Type, Vec<Statement>, TokenLocation),
+    StructFunction(Symbol, Symbol, Vec<(Symbol, Type)>, Type, Vec<Statement>, TokenLocation),
+    Variable(Symbol, Type, Expression, TokenLocation),
+    Constant(Symbol, Type, Expression, TokenLocation),
+    Return(Expression, TokenLocation),
+    While(Expression, Vec<Statement>, TokenLocation),
+    // The loop variable, what it goes over and the body, at the variable's name
+    For(Symbol, Expression, Vec<Statement>, TokenLocation),
+    Break(TokenLocation),
+    Continue(TokenLocation),
+    If(Expression, Vec<Statement>, Vec<Statement>, TokenLocation),
+    External(Box<Statement>, TokenLocation),
+    Inline(Box<Statement>, TokenLocation),
+    Import(String, TokenLocation),
+    Expression(Expression, TokenLocation),
+}
+impl Statement {
+    pub fn location(&self) -> TokenLocation {
+        match self {
+            Statement::Generic(_, _, location) => location.clone(),
+            Statement::Annotated(_, _, location) => location.clone(),
+            Statement::Annotation(_, _, location) => location.clone(),
+            Statement::Struct(_, _, location) => location.clone(),
+            Statement::Enum(_, _, _, location) => location.clone(),
+            Statement::StructEnum(_, _, location) => location.clone(),
+            Statement::JavaEnum(_, _, _, location) => location.clone(),
+            Statement::TypeAlias(_, _, location) => location.clone(),
+            Statement::Function(_, _, _, _, location) => location.clone(),
+            Statement::StructFunction(_, _, _, _, _, location) => location.clone(),
+            Statement::Variable(_, _, _, location) => location.clone(),
+            Statement::Constant(_, _, _, location) => location.clone(),
+            Statement::Return(_, location) => location.clone(),
+            Statement::While(_, _, location) => location.clone(),
+            Statement::For(_, _, _, location) => location.clone(),
+            Statement::Break(location) => location.clone(),
+            Statement::Continue(location) => location.clone(),
+            Statement::If(_, _, _, location) => location.clone(),
+            Statement::External(_, location) => location.clone(),
+            Statement::Inline(_, location) => location.clone(),
+            Statement::Import(_, location) => location.clone(),
+            Statement::Expression(_, location) => location.clone(),
+        }
+    }
+    // How many statements these are, counting the ones in blocks and function bodies
+    pub fn count(statements: &[Statement]) -> usize {
+        statements.iter().map(|statement| match statement {
+            Statement::Annotated(inner, _, _) | Statement::External(inner, _) | Statement::Inline(inner, _) | Statement::Generic(inner, _, _) => Statement::count(std::slice::from_ref(inner)),
+            Statement::Function(_, _, _, body, _) | Statement::StructFunction(_, _, _, _, body, _) | Statement::While(_, body, _) | Statement::For(_, _, body, _) => 1 + Statement::count(body),
+            Statement::If(_, body, else_body, _) => 1 + Statement::count(body) + Statement::count(else_body),
+            _ => 1,
+        }).sum()
+    }
+    // The type a top-level statement defines, looking through annotations
+    pub fn declared_type(&self) -> Option<&Symbol> {
+        match self {
+            Statement::Annotated(statement, _, _) => statement.declared_type(),
+            Statement::Struct(name, _, _)
+            | Statement::Enum(name, _, _, _)
+            | Statement::StructEnum(name, _, _)
+            | Statement::JavaEnum(name, _, _, _)
+            | Statement::TypeAlias(name, _, _) => Some(name),
+            _ => None,
+        }
+    }
+    // Types named in this declaration, each with whether it's used by value
+    // rather than behind a pointer
+    pub fn type_dependencies(&self) -> Vec<(Symbol, bool)> {
+        let mut names: Vec<(Symbol, bool)> = vec![];
+        match self {
+            Statement::Annotated(statement, _, _) => return statement.type_dependencies(),
+            Statement::Struct(_, fields, _) | Statement::JavaEnum(_, fields, _, _) => {
+                for (_, t) in fields.iter() {
+                    t.dependencies(true, &mut names);
+                }
+            }
+            Statement::StructEnum(_, variants, _) => {
+                for (_, fields) in variants.iter() {
+                    for (_, t) in fields.iter() {
+                        t.dependencies(true, &mut names);
+                    }
+                }
+            }
+            Statement::Enum(_, t, _, _) => t.dependencies(true, &mut names),
+            Statement::TypeAlias(_, types, _) => {
+                for t in types.iter() {
+                    t.dependencies(true, &mut names);
+                }
+            }
+            _ => {}
+        }
+        names
+    }
+}
+// Which top-level types each type declaration needs defined before it in the
+// generated C, keyed by name along with the declaration's index. Structs are
+// only needed first when used by value; typedef and enum names always are.
+pub fn type_graph(statements: &[Statement]) -> HashMap<Symbol, (usize, Vec<Symbol>)> {
+    let mut structs: Vec<&Symbol> = vec![];
+    for statement in statements.iter() {
+        let inner: &Statement = match statement {
+            Statement::Annotated(statement, _, _) => statement,
+            statement => statement,
+        };
+        if let Statement::Struct(name, _, _) | Statement::StructEnum(name, _, _) | Statement::JavaEnum(name, _, _, _) = inner {
+            structs.push(name);
+        }
+    }
+    let mut graph: HashMap<Symbol, (usize, Vec<Symbol>)> = HashMap::new();
+    for (index, statement) in statements.iter().enumerate() {
+        if let Some(name) = statement.declared_type() {
+            let dependencies: Vec<Symbol> = statement.type_dependencies().into_iter()
+                .filter(|(dependency, by_value)| *by_value || !structs.contains(&dependency))
+                .map(|(dependency, _)| dependency)
+                .collect();
+            graph.insert(*name, (index, dependencies));
+        }
+    }
+    graph
+}
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone)] pub struct Annotation {
+    pub name: Symbol,
+    pub arguments: Vec<Expression>,
+    pub location: TokenLocation,
+}
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)] pub enum Expression {
+    Number(i64, TokenLocation),
+    String(String, TokenLocation),
+    Char(String, TokenLocation),
+    Boolean(bool, TokenLocation),
+    Identifier(Symbol, TokenLocation),
+    Null,
+    Call(Symbol, Vec<Expression>, TokenLocation),
+    GenericCall(Symbol, Vec<Type>, Vec<Expression>, TokenLocation),
+    Member(Box<Expression>, Box<Expression>, TokenLocation),
+    NamedArgument(Symbol, Box<Expression>, TokenLocation),
+    Cast(Box<Expression>, Type, TokenLocation),
+    UnsafeCast(Box<Expression>, Type, TokenLocation),
+    SizeOf(Type, TokenLocation),
+    Index(Box<Expression>, Box<Expression>, TokenLocation),
+    Array(Vec<Expression>, TokenLocation),
+    Map(Vec<(Expression, Expression)>, TokenLocation),
+    New(Symbol, Vec<Expression>, TokenLocation),
+    Ternary(Box<Expression>, Box<Expression>, Box<Expression>, TokenLocation),
+    Assignment(Box<Expression>, Box<Expression>, TokenLocation),
+    Binary(TokenKind, Box<Expression>, Box<Expression>, TokenLocation),
+    Unary(TokenKind, Box<Expression>, TokenLocation),
+    Grouping(Box<Expression>, TokenLocation),
+    AddressOf(Box<Expression>, TokenLocation),
+    Dereference(Box<Expression>, TokenLocation),
+    Range(Box<Expression>, Box<Expression>, TokenLocation),
+    Type(Type, TokenLocation),
+
+    // What a parse error leaves in the tree, which isn't kept with serde
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Error(Diagnostic),
+    Empty,
+}
+impl Expression {
+    pub fn location(&self) -> TokenLocation {
+        match self {
+            Expression::Number(_, location) => location.clone(),
+            Expression::String(_, location) => location.clone(),
+            Expression::Char(_, location) => location.clone(),
+            Expression::Boolean(_, location) => location.clone(),
+            Expression::Identifier(_, location) => location.clone(),
+            Expression::Null => TokenLocation { start: 0, end: 0 },
+            Expression::Call(_, _, location) => location.clone(),
+            Expression::GenericCall(_, _, _, location) => location.clone(),
+            Expression::Member(_, _, location) => location.clone(),
+            Expression::NamedArgument(_, _, location) => location.clone(),
+            Expression::Cast(_, _, location) => location.clone(),
+            Expression::UnsafeCast(_, _, location) => location.clone(),
+            Expression::SizeOf(_, location) => location.clone(),
+            Expression::Index(_, _, location) => location.clone(),
+            Expression::Array(_, location) => location.clone(),
+            Expression::Map(_, location) => location.clone(),
+            Expression::New(_, _, location) => location.clone(),
+            Expression::Ternary(_, _, _, location) => location.clone(),
+            Expression::Assignment(_, _, location) => location.clone(),
+            Expression::Grouping(_, location) => location.clone(),
+            Expression::Binary(_, _, _, location) => location.clone(),
+            Expression::Unary(_, _, location) => location.clone(),
+            Expression::AddressOf(_, location) => location.clone(),
+            Expression::Dereference(_, location) => location.clone(),
+            Expression::Range(_, _, location) => location.clone(),
+            Expression::Type(_, location) => location.clone(),
+
+            Expression::Error(error) => error.location(),
+            Expression::Empty => TokenLocation { start: 0, end: 0 },
+        }
+    }
+}
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+#[allow(dead_code)]
+pub enum Type {
+    Int(TokenLocation),
+    Usize(TokenLocation),
+    String(TokenLocation),
+    CString(TokenLocation),
+    Char(TokenLocation),
+    Bool(TokenLocation),
+    Void(TokenLocation),
+
+    Struct(Symbol, TokenLocation),
+    Enum(Symbol, TokenLocation),
+    Function(Vec<Type>, Box<Type>, TokenLocation),
+
+    Pointer(Box<Type>, TokenLocation),
+    Array(Box<Type>, Box<Expression>, TokenLocation),
+    DynamicArray(Box<Type>, TokenLocation),
+    Map(Box<Type>, Box<Type>, TokenLocation),
+    
+    Volatile(Box<Type>, TokenLocation),
+    Const(Box<Type>, TokenLocation),
+    Restrict(Box<Type>, TokenLocation),
+
+    GenericType(Symbol, TokenLocation),
+
+    Unknown(Symbol, TokenLocation),
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Error(Diagnostic, TokenLocation),
+}
+impl Type {
+    pub fn location(&self) -> TokenLocation {
+        match self {
+            Type::Int(location) => location.clone(),
+            Type::Usize(location) => location.clone(),
+            Type::String(location) => location.clone(),
+            Type::CString(location) => location.clone(),
+            Type::Char(location) => location.clone(),
+            Type::Bool(location) => location.clone(),
+            Type::Void(location) => location.clone(),
+            Type::Struct(_, location) => location.clone(),
+            Type::Enum(_, location) => location.clone(),
+            Type::Function(_, _, location) => location.clone(),
+            Type::Pointer(_, location) => location.clone(),
+            Type::Array(_, _, location) => location.clone(),
+            Type::DynamicArray(_, location) => location.clone(),
+            Type::Map(_, _, location) => location.clone(),
+            Type::Volatile(_, location) => location.clone(),
+            Type::Const(_, location) => location.clone(),
+            Type::Restrict(_, location) => location.clone(),
+            Type::GenericType(_, location) => location.clone(),
+            Type::Unknown(_, location) => location.clone(),
+            Type::Error(_, location) => location.clone(),
+        }
+    }
+    // Named types this one refers to, and whether by value
+    pub fn dependencies(&self, by_value: bool, names: &mut Vec<(Symbol, bool)>) {
+        match self {
+            Type::Struct(name, _) | Type::Enum(name, _) | Type::Unknown(name, _) if !name.is_empty() => names.push((*name, by_value)),
+            Type::Array(t, _, _) | Type::Const(t, _) | Type::Volatile(t, _) | Type::Restrict(t, _) => t.dependencies(by_value, names),
+            Type::Pointer(t, _) | Type::DynamicArray(t, _) => t.dependencies(false, names),
+            Type::Map(key, value, _) => {
+                key.dependencies(false, names);
+                value.dependencies(false, names);
+            }
+            Type::Function(args, return_type, _) => {
+                for arg in args.iter() {
+                    arg.dependencies(false, names);
+                }
+                return_type.dependencies(false, names);
+            }
+            _ => {}
+        }
+    }
+    // The type as it would be written in .sl source, used in diagnostics
+    pub fn name(&self) -> String {
+        match self {
+            Type::Int(_) => "int".to_string(),
+            Type::Usize(_) => "usize".to_string(),
+            Type::String(_) => "string".to_string(),
+            Type::CString(_) => "cstring".to_string(),
+            Type::Char(_) => "char".to_string(),
+            Type::Bool(_) => "bool".to_string(),
+            Type::Void(_) => "void".to_string(),
+            Type::Struct(name, _) => name.to_string(),
+            Type::Enum(name, _) => name.to_string(),
+            Type::Function(args, return_type, _) => {
+                let args: Vec<String> = args.iter().map(|arg| arg.name()).collect();
+                format!("func({}): {}", args.join(", "), return_type.name())
+            }
+            Type::Pointer(t, _) => format!("{}*", t.name()),
+            Type::Array(t, size, _) => match &**size {
+                Expression::Number(size, _) => format!("{}[{}]", t.name(), size),
+                _ => format!("{}[...]", t.name()),
+            },
+            Type::DynamicArray(t, _) => format!("{}[]", t.name()),
+            Type::Map(key, value, _) => format!("map[{}, {}]", key.name(), value.name()),
+            Type::Volatile(t, _) => format!("volatile {}", t.name()),
+            Type::Const(t, _) => format!("const {}", t.name()),
+            Type::Restrict(t, _) => format!("restrict {}", t.name()),
+            Type::GenericType(name, _) => name.to_string(),
+            Type::Unknown(name, _) => if name.is_empty() { "unknown".to_string() } else { name.to_string() },
+            Type::Error(_, _) => "error".to_string(),
+        }
+    }
+}
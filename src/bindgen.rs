@@ -0,0 +1,745 @@
+use std::collections::{HashMap, HashSet};
+use crate::ast::{Statement, Type};
+use crate::codegen::{RESERVED_PREFIX, SPELLED_TYPES};
+use crate::lexer::{Lexer, Token, TokenKind};
+use crate::unparse::Unparser;
+// A C header's declarations written as `external` ones, for `sl bindgen`. The
+// header goes through the C preprocessor first, and what it declares itself,
+// rather than what it includes, is read back from the preprocessor's output:
+//
+//     let mut bindgen: Bindgen = Bindgen::new("std/time.h", &preprocessed);
+//     bindgen.parse();
+//     print!("{}", bindgen.source());
+//
+// Typedefs, and C's types that .sl has no word for such as `double` or
+// `long`, become `external type`s, which aren't checked. Those C writes in
+// more than one word are named with underscores, as in `unsigned_long`, and
+// the generated C spells them out again. `int`, `char`, `_Bool` and `size_t`
+// are .sl's own `int`, `char`, `bool` and `usize`. A struct is declared with
+// the fields .sl can say, since C has the rest. What can't be declared at all,
+// such as a variadic function or one taking a union, is listed in a comment.
+#[derive(Debug, Clone, PartialEq)] pub enum CType {
+    // A built-in type, as in `unsigned long`, or a typedef's name
+    Named(String),
+    Struct(String),
+    Union(String),
+    Enum(String),
+    Pointer(Box<CType>),
+    Const(Box<CType>),
+    Volatile(Box<CType>),
+    Array(Box<CType>, Option<i64>),
+    // The parameters, whether more can follow them, and the return type
+    Function(Vec<Declarator>, bool, Box<CType>),
+}
+// A declared name, if one was given, and its type
+pub type Declarator = (Option<String>, CType);
+// A function's parameters, and whether more can follow them
+pub type Parameters = (Vec<Declarator>, bool);
+impl CType {
+    // Without the qualifiers on the type itself, which don't matter to a
+    // parameter or what's returned
+    pub fn unqualified(&self) -> &CType {
+        match self {
+            CType::Const(t) | CType::Volatile(t) => t.unqualified(),
+            t => t,
+        }
+    }
+}
+// A token of the preprocessed header, and whether it's from the header itself
+#[derive(Debug, Clone)] pub struct CToken {
+    pub value: String,
+    pub own: bool,
+}
+// What the header declares, in order
+#[derive(Debug, Clone, PartialEq)] pub enum Declaration {
+    Struct(String),
+    Typedef(String),
+    Function(String, CType),
+    Variable(String, CType),
+}
+// The storage and type a declaration starts with, as in `extern const char`
+#[derive(Debug, Clone)] pub struct Specifiers {
+    pub typedef: bool,
+    pub static_: bool,
+    pub t: CType,
+}
+// External types and structs that what's been bound so far refers to
+#[derive(Debug, Clone, Default)] pub struct Needs {
+    pub types: Vec<String>,
+    pub structs: Vec<String>,
+}
+impl Needs {
+    pub fn add(&mut self, other: Needs) {
+        for name in other.types {
+            if !self.types.contains(&name) {
+                self.types.push(name);
+            }
+        }
+        for name in other.structs {
+            if !self.structs.contains(&name) {
+                self.structs.push(name);
+            }
+        }
+    }
+}
+const STORAGE: [&str; 11] = ["typedef", "extern", "static", "inline", "__inline", "__inline__", "_Noreturn", "__extension__", "register", "_Thread_local", "__thread"];
+const QUALIFIERS: [&str; 12] = ["const", "__const", "volatile", "__volatile", "__volatile__", "restrict", "__restrict", "__restrict__", "_Atomic", "_Nonnull", "_Nullable", "_Null_unspecified"];
+const BUILT_IN: [&str; 14] = ["void", "char", "short", "int", "long", "float", "double", "signed", "__signed__", "unsigned", "_Bool", "bool", "_Complex", "__int128"];
+// What's followed by something in parentheses that says nothing about the type
+const ATTRIBUTES: [&str; 7] = ["__attribute__", "__attribute", "__asm__", "__asm", "asm", "__declspec", "_Alignas"];
+#[derive(Debug, Clone)] pub struct Bindgen {
+    // The header as `import` names it
+    pub header: String,
+    pub tokens: Vec<CToken>,
+    pub current: usize,
+    pub typedefs: HashSet<String>,
+    // Every named struct's fields, from whichever file defines it
+    pub structs: HashMap<String, Vec<Declarator>>,
+    pub declarations: Vec<Declaration>,
+}
+impl Bindgen {
+    // `preprocessed` is what the preprocessor made of `#include`ing the header,
+    // with the line markers that say which file each line came from
+    pub fn new(header: &str, preprocessed: &str) -> Self {
+        Self {
+            header: header.to_string(),
+            tokens: Bindgen::tokens(preprocessed),
+            current: 0,
+            typedefs: HashSet::new(),
+            structs: HashMap::new(),
+            declarations: vec![],
+        }
+    }
+    // The `#include` that brings the header in
+    pub fn include(header: &str) -> String {
+        match header.strip_prefix("std/") {
+            Some(header) => format!("#include <{}>\n", header),
+            None => format!("#include \"{}\"\n", header),
+        }
+    }
+    // Splits the preprocessor's output into words and punctuation, each marked
+    // with whether it's the header's. A line marker such as
+    // `# 1 "/usr/include/stdio.h" 1` enters a file with its flag 1 and returns
+    // to one with 2; the header is the file entered from stdin.
+    pub fn tokens(preprocessed: &str) -> Vec<CToken> {
+        let mut tokens: Vec<CToken> = vec![];
+        let mut files: Vec<String> = vec![];
+        let mut header: Option<String> = None;
+        for line in preprocessed.lines() {
+            if let Some(marker) = line.trim_start().strip_prefix('#') {
+                let words: Vec<&str> = marker.split_whitespace().collect();
+                let words: &[&str] = if words.first() == Some(&"line") { &words[1..] } else { &words };
+                if let [number, name, flags @ ..] = words {
+                    if number.parse::<usize>().is_ok() && name.starts_with('"') {
+                        let name: String = name.trim_matches('"').to_string();
+                        if flags.contains(&"1") {
+                            if header.is_none() && files.last().is_some_and(|file| file == "<stdin>") {
+                                header = Some(name.clone());
+                            }
+                            files.push(name);
+                        } else {
+                            if flags.contains(&"2") {
+                                files.pop();
+                            }
+                            match files.last_mut() {
+                                Some(file) => *file = name,
+                                None => files.push(name),
+                            }
+                        }
+                    }
+                }
+                continue;
+            }
+            // glibc splits its headers into pieces under bits/, which are part of
+            // whichever header includes them
+            let own: bool = header.is_some() && files.iter().rev().find(|file| !file.contains("/bits/")) == header.as_ref();
+            let characters: Vec<char> = line.chars().collect();
+            let mut i: usize = 0;
+            while i < characters.len() {
+                let c: char = characters[i];
+                let start: usize = i;
+                if c.is_whitespace() {
+                    i += 1;
+                    continue;
+                } else if c.is_ascii_alphanumeric() || c == '_' {
+                    while i < characters.len() && (characters[i].is_ascii_alphanumeric() || characters[i] == '_' || (characters[i] == '.' && characters[start].is_ascii_digit())) {
+                        i += 1;
+                    }
+                } else if c == '"' || c == '\'' {
+                    i += 1;
+                    while i < characters.len() && characters[i] != c {
+                        i += if characters[i] == '\\' { 2 } else { 1 };
+                    }
+                    i = (i + 1).min(characters.len());
+                } else if characters[i..].starts_with(&['.', '.', '.']) {
+                    i += 3;
+                } else {
+                    i += 1;
+                }
+                tokens.push(CToken { value: characters[start..i].iter().collect(), own });
+            }
+        }
+        tokens
+    }
+    pub fn current(&self) -> &str {
+        self.tokens.get(self.current).map(|token| token.value.as_str()).unwrap_or("")
+    }
+    pub fn peek(&self) -> &str {
+        self.tokens.get(self.current + 1).map(|token| token.value.as_str()).unwrap_or("")
+    }
+    pub fn advance(&mut self) {
+        self.current += 1;
+    }
+    pub fn at_end(&self) -> bool {
+        self.current >= self.tokens.len()
+    }
+    pub fn is_identifier(word: &str) -> bool {
+        word.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+    }
+    // Steps over a bracketed group, from its opening bracket to just past the
+    // one that closes it
+    pub fn skip_group(&mut self) {
+        let mut depth: usize = 0;
+        while !self.at_end() {
+            match self.current() {
+                "(" | "[" | "{" => depth += 1,
+                ")" | "]" | "}" => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            self.advance();
+            if depth == 0 {
+                break;
+            }
+        }
+    }
+    // Steps over anything of C's that only matters to the compiler, such as
+    // `__attribute__((nothrow))` or an `__asm__("name")`
+    pub fn skip_attributes(&mut self) {
+        while ATTRIBUTES.contains(&self.current()) {
+            self.advance();
+            if self.current() == "(" {
+                self.skip_group();
+            }
+        }
+    }
+    // Steps to where the next of `stops` is, outside any brackets, without going
+    // past the `}` of a struct this is inside
+    pub fn skip_to(&mut self, stops: &[&str]) {
+        while !self.at_end() && !stops.contains(&self.current()) {
+            match self.current() {
+                "(" | "[" | "{" => self.skip_group(),
+                ")" | "]" | "}" => return,
+                _ => self.advance(),
+            }
+        }
+    }
+    // After something that isn't understood, the rest of its declaration goes
+    pub fn recover(&mut self) {
+        self.skip_to(&[";"]);
+        if self.current() == ";" {
+            self.advance();
+        }
+    }
+    pub fn parse(&mut self) {
+        while !self.at_end() {
+            let start: usize = self.current;
+            self.parse_declaration();
+            if self.current == start {
+                self.advance();
+            }
+        }
+    }
+    pub fn parse_declaration(&mut self) {
+        let own: bool = self.tokens[self.current].own;
+        if self.current() == ";" {
+            self.advance();
+            return;
+        }
+        let Some(specifiers) = self.parse_specifiers() else {
+            self.recover();
+            return;
+        };
+        loop {
+            if self.current() == ";" {
+                self.advance();
+                return;
+            }
+            let Some((name, t)) = self.parse_declarator(specifiers.t.clone()) else {
+                self.recover();
+                return;
+            };
+            self.skip_attributes();
+            // The body of a function defined in the header, which is usually
+            // static inline and so not bound
+            let definition: bool = self.current() == "{";
+            if definition {
+                self.skip_group();
+            } else if self.current() == "=" {
+                self.skip_to(&[",", ";"]);
+            }
+            if let Some(name) = name {
+                if specifiers.typedef {
+                    self.typedefs.insert(name.clone());
+                }
+                if own && !specifiers.static_ {
+                    self.declarations.push(match t {
+                        _ if specifiers.typedef => Declaration::Typedef(name),
+                        CType::Function(..) => Declaration::Function(name, t),
+                        t => Declaration::Variable(name, t),
+                    });
+                }
+            }
+            match self.current() {
+                _ if definition => return,
+                "," => self.advance(),
+                ";" => {
+                    self.advance();
+                    return;
+                }
+                _ => {
+                    self.recover();
+                    return;
+                }
+            }
+        }
+    }
+    // Storage, qualifiers and a type, or nothing if there's no type
+    pub fn parse_specifiers(&mut self) -> Option<Specifiers> {
+        let mut typedef: bool = false;
+        let mut static_: bool = false;
+        let mut constant: bool = false;
+        let mut volatile: bool = false;
+        let mut words: Vec<String> = vec![];
+        let mut t: Option<CType> = None;
+        loop {
+            let word: String = self.current().to_string();
+            if STORAGE.contains(&word.as_str()) {
+                typedef |= word == "typedef";
+                static_ |= word == "static";
+                self.advance();
+            } else if QUALIFIERS.contains(&word.as_str()) {
+                constant |= word.ends_with("const");
+                volatile |= word.contains("volatile");
+                self.advance();
+            } else if ATTRIBUTES.contains(&word.as_str()) {
+                self.skip_attributes();
+            } else if BUILT_IN.contains(&word.as_str()) && t.is_none() {
+                words.push(word);
+                self.advance();
+            } else if (word == "struct" || word == "union" || word == "enum") && t.is_none() && words.is_empty() {
+                t = Some(self.parse_tagged());
+            } else if Bindgen::is_identifier(&word) && t.is_none() && words.is_empty() {
+                // Any other name where the type goes is a typedef's, even one
+                // the compiler has built in such as `__builtin_va_list`
+                t = Some(CType::Named(word));
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        let mut t: CType = match t {
+            Some(t) => t,
+            None if !words.is_empty() => CType::Named(Bindgen::built_in(&words)),
+            None => return None,
+        };
+        if constant {
+            t = CType::Const(Box::new(t));
+        }
+        if volatile {
+            t = CType::Volatile(Box::new(t));
+        }
+        Some(Specifiers { typedef, static_, t })
+    }
+    // The one way C writes a built-in type, as in `unsigned long` for
+    // `long unsigned int`
+    pub fn built_in(words: &[String]) -> String {
+        let has = |word: &str| words.iter().any(|w| w == word);
+        let longs: usize = words.iter().filter(|word| *word == "long").count();
+        let sign: &str = if has("unsigned") { "unsigned " } else { "" };
+        if has("_Complex") || has("__int128") {
+            words.join(" ")
+        } else if has("void") {
+            "void".to_string()
+        } else if has("_Bool") || has("bool") {
+            "_Bool".to_string()
+        } else if has("char") {
+            // A plain char is neither of the other two
+            let sign: &str = if has("signed") || has("__signed__") { "signed " } else { sign };
+            format!("{}char", sign)
+        } else if has("float") {
+            "float".to_string()
+        } else if has("double") {
+            if longs > 0 { "long double" } else { "double" }.to_string()
+        } else if has("short") {
+            format!("{}short", sign)
+        } else if longs == 1 {
+            format!("{}long", sign)
+        } else if longs > 1 {
+            format!("{}long long", sign)
+        } else if has("unsigned") {
+            "unsigned".to_string()
+        } else {
+            "int".to_string()
+        }
+    }
+    // `struct name`, `union name` or `enum name`, each maybe with a body. A
+    // struct's fields are kept, while an enum's constants are passed over.
+    pub fn parse_tagged(&mut self) -> CType {
+        let own: bool = self.tokens[self.current].own;
+        let keyword: String = self.current().to_string();
+        self.advance();
+        self.skip_attributes();
+        let mut name: String = String::new();
+        if Bindgen::is_identifier(self.current()) {
+            name = self.current().to_string();
+            self.advance();
+        }
+        self.skip_attributes();
+        if self.current() == "{" {
+            if keyword == "struct" {
+                let fields: Vec<Declarator> = self.parse_fields();
+                if !name.is_empty() {
+                    self.structs.insert(name.clone(), fields);
+                    if own {
+                        self.declarations.push(Declaration::Struct(name.clone()));
+                    }
+                }
+            } else {
+                self.skip_group();
+            }
+            self.skip_attributes();
+        }
+        match keyword.as_str() {
+            "struct" => CType::Struct(name),
+            "union" => CType::Union(name),
+            _ => CType::Enum(name),
+        }
+    }
+    pub fn parse_fields(&mut self) -> Vec<Declarator> {
+        let mut fields: Vec<Declarator> = vec![];
+        self.advance();
+        while !self.at_end() && self.current() != "}" {
+            let start: usize = self.current;
+            if let Some(specifiers) = self.parse_specifiers() {
+                loop {
+                    // A nested struct or union without a name of its own
+                    if self.current() == ";" {
+                        break;
+                    }
+                    let Some((name, t)) = self.parse_declarator(specifiers.t.clone()) else {
+                        break;
+                    };
+                    // A bit-field has no .sl type
+                    if self.current() == ":" {
+                        self.skip_to(&[",", ";"]);
+                    } else {
+                        fields.push((name, t));
+                    }
+                    self.skip_attributes();
+                    if self.current() != "," {
+                        break;
+                    }
+                    self.advance();
+                }
+            }
+            self.skip_to(&[";"]);
+            if self.current() == ";" || self.current == start {
+                self.advance();
+            }
+        }
+        self.advance();
+        fields
+    }
+    // Whether the `(` here starts a declarator in parentheses, as in
+    // `void (*handler)(int)`, rather than a function's parameters
+    pub fn is_nested_declarator(&self) -> bool {
+        match self.peek() {
+            "*" | "(" | "^" => true,
+            word => Bindgen::is_identifier(word) && !self.typedefs.contains(word)
+                && !STORAGE.contains(&word) && !QUALIFIERS.contains(&word) && !BUILT_IN.contains(&word) && !ATTRIBUTES.contains(&word)
+                && !matches!(word, "struct" | "union" | "enum"),
+        }
+    }
+    // The name being declared, if there is one, and its type built around the
+    // one the declaration starts with
+    pub fn parse_declarator(&mut self, mut t: CType) -> Option<Declarator> {
+        loop {
+            self.skip_attributes();
+            match self.current() {
+                "*" => {
+                    self.advance();
+                    t = CType::Pointer(Box::new(t));
+                }
+                "const" | "__const" => {
+                    self.advance();
+                    t = CType::Const(Box::new(t));
+                }
+                "volatile" | "__volatile" | "__volatile__" => {
+                    self.advance();
+                    t = CType::Volatile(Box::new(t));
+                }
+                "restrict" | "__restrict" | "__restrict__" | "_Atomic" | "_Nonnull" | "_Nullable" | "_Null_unspecified" => self.advance(),
+                _ => break,
+            }
+        }
+        let mut name: Option<String> = None;
+        // What's in parentheses is declared with the type the suffixes after
+        // them make, so it's come back to once they're read
+        let mut nested: Option<usize> = None;
+        if self.current() == "(" && self.is_nested_declarator() {
+            nested = Some(self.current + 1);
+            self.skip_group();
+        } else if Bindgen::is_identifier(self.current()) && !BUILT_IN.contains(&self.current()) {
+            name = Some(self.current().to_string());
+            self.advance();
+        }
+        // Array sizes, or parameters with whether more can follow them
+        let mut suffixes: Vec<Result<Option<i64>, Parameters>> = vec![];
+        loop {
+            self.skip_attributes();
+            match self.current() {
+                "[" => {
+                    let size: Option<i64> = Bindgen::integer(self.peek()).filter(|_| self.tokens.get(self.current + 2).is_some_and(|token| token.value == "]"));
+                    self.skip_group();
+                    suffixes.push(Ok(size));
+                }
+                "(" => suffixes.push(Err(self.parse_parameters()?)),
+                _ => break,
+            }
+        }
+        // `int grid[2][3]` is two arrays of three, so the last suffix is the innermost
+        for suffix in suffixes.into_iter().rev() {
+            t = match suffix {
+                Ok(size) => CType::Array(Box::new(t), size),
+                Err((parameters, variadic)) => CType::Function(parameters, variadic, Box::new(t)),
+            };
+        }
+        if let Some(start) = nested {
+            let end: usize = self.current;
+            self.current = start;
+            let declared: Option<Declarator> = self.parse_declarator(t);
+            let closed: bool = self.current() == ")";
+            self.current = end;
+            return declared.filter(|_| closed);
+        }
+        Some((name, t))
+    }
+    // A function's parameters, with arrays and functions passed as pointers,
+    // and whether it takes more after them
+    pub fn parse_parameters(&mut self) -> Option<Parameters> {
+        self.advance();
+        let mut parameters: Vec<Declarator> = vec![];
+        let mut variadic: bool = false;
+        if self.current() == "void" && self.peek() == ")" {
+            self.advance();
+        }
+        while self.current() != ")" {
+            if self.current() == "..." {
+                variadic = true;
+                self.advance();
+            } else {
+                let specifiers: Specifiers = self.parse_specifiers()?;
+                let (name, t): Declarator = self.parse_declarator(specifiers.t)?;
+                let t: CType = match t {
+                    CType::Array(of, _) => CType::Pointer(of),
+                    t @ CType::Function(..) => CType::Pointer(Box::new(t)),
+                    t => t,
+                };
+                parameters.push((name, t));
+            }
+            match self.current() {
+                "," => self.advance(),
+                ")" => {}
+                _ => return None,
+            }
+        }
+        self.advance();
+        Some((parameters, variadic))
+    }
+    pub fn integer(word: &str) -> Option<i64> {
+        let digits: &str = word.trim_end_matches(['u', 'U', 'l', 'L']);
+        match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+            Some(hex) => i64::from_str_radix(hex, 16).ok(),
+            None => digits.parse().ok(),
+        }
+    }
+    // Whether .sl can say the name as it is, which it can't for one of its
+    // keywords or the names it keeps for what it generates
+    pub fn is_name(name: &str) -> bool {
+        let tokens: Vec<Token> = Lexer::new(name.to_string()).lex();
+        matches!(tokens.as_slice(), [token] if token.kind == TokenKind::Identifier && token.value == name) && !name.starts_with(RESERVED_PREFIX)
+    }
+    // Names C keeps for itself and its library's insides, which aren't bound
+    pub fn is_reserved(name: &str) -> bool {
+        name.starts_with("__") || (name.starts_with('_') && name[1..].starts_with(|c: char| c.is_ascii_uppercase()))
+    }
+    // The .sl type for a C one, or why there isn't one
+    pub fn bind(&self, t: &CType, needs: &mut Needs) -> Result<Type, String> {
+        match t {
+            CType::Named(name) => match name.as_str() {
+                "void" => Ok(Type::void()),
+                "char" => Ok(Type::char()),
+                "int" => Ok(Type::int()),
+                "_Bool" => Ok(Type::bool()),
+                "size_t" => Ok(Type::usize()),
+                name if SPELLED_TYPES.iter().any(|(_, spelled)| *spelled == name) => {
+                    let word: String = name.replace(' ', "_");
+                    needs.add(Needs { types: vec![word.clone()], structs: vec![] });
+                    Ok(Type::named(&word))
+                }
+                name if Bindgen::is_name(name) => {
+                    needs.add(Needs { types: vec![name.to_string()], structs: vec![] });
+                    Ok(Type::named(name))
+                }
+                name => Err(format!("`{}` has no .sl type", name)),
+            },
+            CType::Struct(name) if name.is_empty() => Err("a struct without a name has no .sl type".to_string()),
+            CType::Struct(name) if Bindgen::is_name(name) => {
+                needs.add(Needs { types: vec![], structs: vec![name.clone()] });
+                Ok(Type::named(name))
+            }
+            CType::Struct(name) => Err(format!("`struct {}` has no .sl type", name)),
+            CType::Union(name) => Err(format!("`{}` has no .sl type", format!("union {}", name).trim_end())),
+            CType::Enum(name) => Err(format!("`{}` has no .sl type", format!("enum {}", name).trim_end())),
+            CType::Pointer(to) => match &**to {
+                CType::Named(name) if name == "char" => Ok(Type::cstring()),
+                CType::Function(..) => Err("a function pointer can only be a parameter or a field".to_string()),
+                to => Ok(Type::pointer(self.bind(to, needs)?)),
+            },
+            CType::Const(t) | CType::Volatile(t) if matches!(**t, CType::Pointer(_)) => Err("a pointer that's const or volatile itself has no .sl type".to_string()),
+            CType::Const(t) => Ok(Type::constant(self.bind(t, needs)?)),
+            CType::Volatile(t) => Ok(Type::volatile(self.bind(t, needs)?)),
+            CType::Array(of, Some(size)) => Ok(Type::array(self.bind(of, needs)?, *size)),
+            CType::Array(_, None) => Err("an array without a size has no .sl type".to_string()),
+            CType::Function(..) => Err("a function can only be passed as a pointer".to_string()),
+        }
+    }
+    // A parameter or field, which can be a function pointer
+    pub fn bind_slot(&self, t: &CType, needs: &mut Needs) -> Result<Type, String> {
+        match t.unqualified() {
+            CType::Pointer(to) => match &**to {
+                CType::Function(_, true, _) => Err("a function pointer that takes a variable number of arguments has no .sl type".to_string()),
+                CType::Function(parameters, false, return_type) => {
+                    let args: Vec<Type> = parameters.iter().map(|(_, t)| self.bind(t.unqualified(), needs)).collect::<Result<Vec<Type>, String>>()?;
+                    Ok(Type::function(args, self.bind(return_type.unqualified(), needs)?))
+                }
+                _ => self.bind(t.unqualified(), needs),
+            },
+            _ => self.bind(t.unqualified(), needs),
+        }
+    }
+    // Each parameter gets a name .sl can say, in place of one it can't or none
+    pub fn parameter_name(name: &Option<String>, index: usize) -> String {
+        let name: &str = name.as_deref().unwrap_or("").trim_start_matches('_');
+        if Bindgen::is_name(name) {
+            return name.to_string();
+        }
+        let renamed: String = format!("{}_", name);
+        if !name.is_empty() && Bindgen::is_name(&renamed) {
+            return renamed;
+        }
+        format!("arg{}", index + 1)
+    }
+    // The declarations in groups: the import, the external types, the structs,
+    // and the functions and variables in the header's order. Along with them
+    // comes a line for each declaration that was left out, saying why.
+    pub fn bindings(&self) -> (Vec<Vec<Statement>>, Vec<String>) {
+        let mut needs: Needs = Needs::default();
+        let mut declared: Vec<Statement> = vec![];
+        let mut skipped: Vec<String> = vec![];
+        let mut seen: HashSet<&str> = HashSet::new();
+        for declaration in self.declarations.iter() {
+            let name: &str = match declaration {
+                Declaration::Struct(name) | Declaration::Typedef(name) | Declaration::Function(name, _) | Declaration::Variable(name, _) => name,
+            };
+            // A function or variable may be declared more than once
+            let repeated: bool = matches!(declaration, Declaration::Function(..) | Declaration::Variable(..)) && !seen.insert(name);
+            if Bindgen::is_reserved(name) || repeated {
+                continue;
+            }
+            if !Bindgen::is_name(name) {
+                skipped.push(format!("{}: `{}` can't be a name in .sl", name, name));
+                continue;
+            }
+            let mut wanted: Needs = Needs::default();
+            let bound: Result<Option<Statement>, String> = match declaration {
+                Declaration::Struct(name) => {
+                    wanted.structs.push(name.clone());
+                    Ok(None)
+                }
+                Declaration::Typedef(name) => {
+                    wanted.types.push(name.clone());
+                    Ok(None)
+                }
+                Declaration::Function(_, CType::Function(_, true, _)) => Err("it takes a variable number of arguments".to_string()),
+                Declaration::Function(name, CType::Function(parameters, false, return_type)) => {
+                    let mut params: Vec<(String, Type)> = vec![];
+                    let mut error: Option<String> = None;
+                    for (index, (parameter, t)) in parameters.iter().enumerate() {
+                        match self.bind_slot(t, &mut wanted) {
+                            Ok(t) => params.push((Bindgen::parameter_name(parameter, index), t)),
+                            Err(reason) => {
+                                error = Some(reason);
+                                break;
+                            }
+                        }
+                    }
+                    match (error, self.bind(return_type.unqualified(), &mut wanted)) {
+                        (Some(reason), _) | (None, Err(reason)) => Err(reason),
+                        (None, Ok(return_type)) => {
+                            let params = params.iter().map(|(name, t)| (name.as_str(), t.clone()));
+                            Ok(Some(Statement::external(Statement::function(name, params, return_type, []))))
+                        }
+                    }
+                }
+                Declaration::Variable(name, t) => self.bind(t, &mut wanted).map(|t| Some(Statement::external(Statement::declare(name, t)))),
+                Declaration::Function(_, _) => Ok(None),
+            };
+            match bound {
+                Ok(statement) => {
+                    needs.add(wanted);
+                    declared.extend(statement);
+                }
+                Err(reason) => skipped.push(format!("{}: {}", name, reason)),
+            }
+        }
+        // Structs bring in what their fields need, which may be more structs
+        let mut structs: Vec<Statement> = vec![];
+        let mut index: usize = 0;
+        while index < needs.structs.len() {
+            let name: String = needs.structs[index].clone();
+            let mut fields: Vec<(String, Type)> = vec![];
+            for (field, t) in self.structs.get(&name).into_iter().flatten() {
+                let mut wanted: Needs = Needs::default();
+                if let (Some(field), Ok(t)) = (field, self.bind_slot(t, &mut wanted)) {
+                    if Bindgen::is_name(field) {
+                        fields.push((field.clone(), t));
+                        needs.add(wanted);
+                    }
+                }
+            }
+            structs.push(Statement::external(Statement::structure(&name, fields.iter().map(|(name, t)| (name.as_str(), t.clone())))));
+            index += 1;
+        }
+        // A typedef that names a struct of the same name is the struct
+        let types: Vec<Statement> = needs.types.iter()
+            .filter(|name| !needs.structs.contains(name))
+            .map(|name| Statement::external(Statement::type_alias(name, [])))
+            .collect();
+        (vec![vec![Statement::import(&self.header)], types, structs, declared], skipped)
+    }
+    // The bindings as a .sl file, with a blank line between each group
+    pub fn source(&self) -> String {
+        let (groups, skipped): (Vec<Vec<Statement>>, Vec<String>) = self.bindings();
+        let mut code: String = format!("// Declarations from {}, written by `sl bindgen`\n", self.header);
+        let groups: Vec<String> = groups.iter().filter(|group| !group.is_empty()).map(|group| Unparser::unparse(group)).collect();
+        code.push_str(&groups.join("\n"));
+        if !skipped.is_empty() {
+            code.push_str("\n// Left out, since .sl can't declare them, though they can still be\n// called through the import:\n");
+            for line in skipped.iter() {
+                code.push_str(&format!("//   {}\n", line));
+            }
+        }
+        code
+    }
+}
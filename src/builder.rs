@@ -0,0 +1,194 @@
+use crate::ast::{Expression, Statement, Type};
+use crate::lexer::{TokenKind, TokenLocation};
+use crate::symbol::Symbol;
+// Where a node that wasn't parsed from any source is said to be. Diagnostics
+// about it point at the start of the first file.
+pub const NOWHERE: TokenLocation = TokenLocation { start: 0, end: 0 };
+// Constructors for building a syntax tree in code, for generators and tests
+// that would otherwise spell out every tuple variant and location:
+//
+//     let main: Statement = Statement::function("main", [], Type::int(), [
+//         Statement::expression(Expression::call("printf", [Expression::string("hi\n")])),
+//         Statement::ret(Expression::number(0)),
+//     ]);
+//
+// Every node is placed at NOWHERE.
+impl Expression {
+    pub fn number(value: i64) -> Self {
+        Expression::Number(value, NOWHERE)
+    }
+    // The string's contents themselves, which the unparser and codegen escape
+    pub fn string(value: &str) -> Self {
+        Expression::String(value.to_string(), NOWHERE)
+    }
+    pub fn char(value: &str) -> Self {
+        Expression::Char(value.to_string(), NOWHERE)
+    }
+    pub fn boolean(value: bool) -> Self {
+        Expression::Boolean(value, NOWHERE)
+    }
+    pub fn identifier(name: &str) -> Self {
+        Expression::Identifier(Symbol::intern(name), NOWHERE)
+    }
+    pub fn call(name: &str, args: impl IntoIterator<Item = Expression>) -> Self {
+        Expression::Call(Symbol::intern(name), args.into_iter().collect(), NOWHERE)
+    }
+    pub fn generic_call(name: &str, types: impl IntoIterator<Item = Type>, args: impl IntoIterator<Item = Expression>) -> Self {
+        Expression::GenericCall(Symbol::intern(name), types.into_iter().collect(), args.into_iter().collect(), NOWHERE)
+    }
+    // `base.field`, or a method call when `member` is a call
+    pub fn member(base: Expression, member: Expression) -> Self {
+        Expression::Member(Box::new(base), Box::new(member), NOWHERE)
+    }
+    pub fn field(base: Expression, field: &str) -> Self {
+        Expression::member(base, Expression::identifier(field))
+    }
+    pub fn named_argument(name: &str, value: Expression) -> Self {
+        Expression::NamedArgument(Symbol::intern(name), Box::new(value), NOWHERE)
+    }
+    pub fn cast(value: Expression, t: Type) -> Self {
+        Expression::Cast(Box::new(value), t, NOWHERE)
+    }
+    pub fn unsafe_cast(value: Expression, t: Type) -> Self {
+        Expression::UnsafeCast(Box::new(value), t, NOWHERE)
+    }
+    pub fn size_of(t: Type) -> Self {
+        Expression::SizeOf(t, NOWHERE)
+    }
+    pub fn index(base: Expression, index: Expression) -> Self {
+        Expression::Index(Box::new(base), Box::new(index), NOWHERE)
+    }
+    pub fn array(elements: impl IntoIterator<Item = Expression>) -> Self {
+        Expression::Array(elements.into_iter().collect(), NOWHERE)
+    }
+    pub fn map(entries: impl IntoIterator<Item = (Expression, Expression)>) -> Self {
+        Expression::Map(entries.into_iter().collect(), NOWHERE)
+    }
+    pub fn new_struct(name: &str, args: impl IntoIterator<Item = Expression>) -> Self {
+        Expression::New(Symbol::intern(name), args.into_iter().collect(), NOWHERE)
+    }
+    pub fn ternary(condition: Expression, then: Expression, otherwise: Expression) -> Self {
+        Expression::Ternary(Box::new(condition), Box::new(then), Box::new(otherwise), NOWHERE)
+    }
+    pub fn assign(target: Expression, value: Expression) -> Self {
+        Expression::Assignment(Box::new(target), Box::new(value), NOWHERE)
+    }
+    // `operator` is the token that would be between the operands, such as
+    // TokenKind::Plus
+    pub fn binary(operator: TokenKind, left: Expression, right: Expression) -> Self {
+        Expression::Binary(operator, Box::new(left), Box::new(right), NOWHERE)
+    }
+    pub fn unary(operator: TokenKind, operand: Expression) -> Self {
+        Expression::Unary(operator, Box::new(operand), NOWHERE)
+    }
+    pub fn grouping(inner: Expression) -> Self {
+        Expression::Grouping(Box::new(inner), NOWHERE)
+    }
+    pub fn address_of(value: Expression) -> Self {
+        Expression::AddressOf(Box::new(value), NOWHERE)
+    }
+    pub fn dereference(value: Expression) -> Self {
+        Expression::Dereference(Box::new(value), NOWHERE)
+    }
+    pub fn range(start: Expression, end: Expression) -> Self {
+        Expression::Range(Box::new(start), Box::new(end), NOWHERE)
+    }
+}
+impl Statement {
+    pub fn function<'a>(name: &str, params: impl IntoIterator<Item = (&'a str, Type)>, return_type: Type, body: impl IntoIterator<Item = Statement>) -> Self {
+        Statement::Function(Symbol::intern(name), Statement::fields(params), return_type, body.into_iter().collect(), NOWHERE)
+    }
+    // `func Struct.name(...)`, which takes `self` when its first parameter is
+    // named that
+    pub fn struct_function<'a>(struct_name: &str, name: &str, params: impl IntoIterator<Item = (&'a str, Type)>, return_type: Type, body: impl IntoIterator<Item = Statement>) -> Self {
+        Statement::StructFunction(Symbol::intern(struct_name), Symbol::intern(name), Statement::fields(params), return_type, body.into_iter().collect(), NOWHERE)
+    }
+    pub fn structure<'a>(name: &str, fields: impl IntoIterator<Item = (&'a str, Type)>) -> Self {
+        Statement::Struct(Symbol::intern(name), Statement::fields(fields), NOWHERE)
+    }
+    pub fn variable(name: &str, t: Type, value: Expression) -> Self {
+        Statement::Variable(Symbol::intern(name), t, value, NOWHERE)
+    }
+    // `var name: t` without a value
+    pub fn declare(name: &str, t: Type) -> Self {
+        Statement::variable(name, t, Expression::Empty)
+    }
+    // `type name = a | b`, or with no types, an external type only C knows
+    pub fn type_alias(name: &str, types: impl IntoIterator<Item = Type>) -> Self {
+        Statement::TypeAlias(Symbol::intern(name), types.into_iter().collect(), NOWHERE)
+    }
+    // A declaration of something C defines, such as a function without a body
+    pub fn external(statement: Statement) -> Self {
+        Statement::External(Box::new(statement), NOWHERE)
+    }
+    pub fn constant(name: &str, t: Type, value: Expression) -> Self {
+        Statement::Constant(Symbol::intern(name), t, value, NOWHERE)
+    }
+    pub fn ret(value: Expression) -> Self {
+        Statement::Return(value, NOWHERE)
+    }
+    pub fn while_loop(condition: Expression, body: impl IntoIterator<Item = Statement>) -> Self {
+        Statement::While(condition, body.into_iter().collect(), NOWHERE)
+    }
+    pub fn if_else(condition: Expression, body: impl IntoIterator<Item = Statement>, else_body: impl IntoIterator<Item = Statement>) -> Self {
+        Statement::If(condition, body.into_iter().collect(), else_body.into_iter().collect(), NOWHERE)
+    }
+    pub fn import(path: &str) -> Self {
+        Statement::Import(path.to_string(), NOWHERE)
+    }
+    pub fn expression(expression: Expression) -> Self {
+        Statement::Expression(expression, NOWHERE)
+    }
+    fn fields<'a>(fields: impl IntoIterator<Item = (&'a str, Type)>) -> Vec<(Symbol, Type)> {
+        fields.into_iter().map(|(name, t)| (Symbol::intern(name), t)).collect()
+    }
+}
+impl Type {
+    pub fn int() -> Self {
+        Type::Int(NOWHERE)
+    }
+    pub fn usize() -> Self {
+        Type::Usize(NOWHERE)
+    }
+    pub fn string() -> Self {
+        Type::String(NOWHERE)
+    }
+    pub fn cstring() -> Self {
+        Type::CString(NOWHERE)
+    }
+    pub fn char() -> Self {
+        Type::Char(NOWHERE)
+    }
+    pub fn bool() -> Self {
+        Type::Bool(NOWHERE)
+    }
+    pub fn void() -> Self {
+        Type::Void(NOWHERE)
+    }
+    // A struct, enum or type alias by its name, which the checker resolves as
+    // it does a name written in source
+    pub fn named(name: &str) -> Self {
+        Type::Unknown(Symbol::intern(name), NOWHERE)
+    }
+    pub fn function(args: impl IntoIterator<Item = Type>, return_type: Type) -> Self {
+        Type::Function(args.into_iter().collect(), Box::new(return_type), NOWHERE)
+    }
+    pub fn pointer(to: Type) -> Self {
+        Type::Pointer(Box::new(to), NOWHERE)
+    }
+    pub fn array(of: Type, size: i64) -> Self {
+        Type::Array(Box::new(of), Box::new(Expression::number(size)), NOWHERE)
+    }
+    pub fn dynamic_array(of: Type) -> Self {
+        Type::DynamicArray(Box::new(of), NOWHERE)
+    }
+    pub fn map(key: Type, value: Type) -> Self {
+        Type::Map(Box::new(key), Box::new(value), NOWHERE)
+    }
+    pub fn constant(t: Type) -> Self {
+        Type::Const(Box::new(t), NOWHERE)
+    }
+    pub fn volatile(t: Type) -> Self {
+        Type::Volatile(Box::new(t), NOWHERE)
+    }
+}
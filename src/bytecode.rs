@@ -0,0 +1,984 @@
+use std::collections::HashMap;
+use crate::ast::{Expression, Statement, Type};
+use crate::checker::{format_pieces, PrintConversion};
+use crate::codegen::{mangle, Codegen};
+use crate::diagnostics::Diagnostic;
+use crate::lexer::{TokenKind, TokenLocation};
+use crate::symbol::Symbol;
+// The instructions of the bytecode VM. Each is an opcode byte followed by its
+// operands, which are little endian.
+#[derive(Debug, Clone, Copy, PartialEq)] pub enum Op {
+    // u16 constant
+    Constant,
+    Null,
+    Pop,
+    Dup,
+    // u16 slot
+    GetLocal,
+    SetLocal,
+    GetGlobal,
+    SetGlobal,
+    // u16 function
+    Function,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Negate,
+    Not,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    // i32 offset from the end of the instruction
+    Jump,
+    JumpIfFalse,
+    // u16 function, u8 argument count
+    Call,
+    // u8 argument count, calls the function value below the arguments
+    CallValue,
+    // u16 native, u8 argument count
+    CallNative,
+    Return,
+    // u16 field count
+    Object,
+    // u16 field
+    GetField,
+    SetField,
+    // A struct value is copied whenever it's stored somewhere
+    Copy,
+    // u16 element count, taken off the stack
+    Array,
+    // u32 length, filled with zeroes
+    ArrayOf,
+    Index,
+    SetIndex,
+    ArrayPush,
+    ArrayPop,
+    ArrayLength,
+}
+impl Op {
+    // In the order of their bytes
+    pub const ALL: [Op; 39] = [
+        Op::Constant, Op::Null, Op::Pop, Op::Dup, Op::GetLocal, Op::SetLocal, Op::GetGlobal, Op::SetGlobal, Op::Function,
+        Op::Add, Op::Subtract, Op::Multiply, Op::Divide, Op::Remainder, Op::Negate, Op::Not,
+        Op::Equal, Op::NotEqual, Op::Less, Op::LessEqual, Op::Greater, Op::GreaterEqual,
+        Op::Jump, Op::JumpIfFalse, Op::Call, Op::CallValue, Op::CallNative, Op::Return,
+        Op::Object, Op::GetField, Op::SetField, Op::Copy, Op::Array, Op::ArrayOf, Op::Index, Op::SetIndex,
+        Op::ArrayPush, Op::ArrayPop, Op::ArrayLength,
+    ];
+    pub fn from_byte(byte: u8) -> Option<Op> {
+        Op::ALL.get(byte as usize).copied()
+    }
+}
+// The C functions a program can call when it runs on the VM, by their index in
+// the bytecode
+pub const NATIVES: [&str; 7] = ["printf", "puts", "putchar", "exit", "strlen", "free", "abs"];
+#[derive(Debug, Clone, PartialEq)] pub enum Constant {
+    Int(i64),
+    String(String),
+}
+#[derive(Debug, Clone, PartialEq)] pub struct BytecodeFunction {
+    pub name: String,
+    pub arity: u8,
+    // Every slot the function uses, parameters first
+    pub locals: u16,
+    pub code: Vec<u8>,
+}
+// A whole program as the VM runs it, and as it's stored in a .slbc file:
+//
+//     "SLBC" version:u8
+//     constants:u32 { tag:u8 (0 int:i64 | 1 length:u32 bytes) }
+//     functions:u32 { name length:u32 bytes, arity:u8, locals:u16, code length:u32 bytes }
+//     globals:u16 entry:u16
+#[derive(Debug, Clone, PartialEq)] pub struct Bytecode {
+    pub constants: Vec<Constant>,
+    pub functions: Vec<BytecodeFunction>,
+    pub globals: u16,
+    // Sets up the globals and calls main
+    pub entry: u16,
+}
+impl Bytecode {
+    pub const MAGIC: &'static [u8; 4] = b"SLBC";
+    pub const VERSION: u8 = 1;
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Bytecode::MAGIC.to_vec();
+        bytes.push(Bytecode::VERSION);
+        bytes.extend((self.constants.len() as u32).to_le_bytes());
+        for constant in self.constants.iter() {
+            match constant {
+                Constant::Int(value) => {
+                    bytes.push(0);
+                    bytes.extend(value.to_le_bytes());
+                }
+                Constant::String(value) => {
+                    bytes.push(1);
+                    Bytecode::write_bytes(&mut bytes, value.as_bytes());
+                }
+            }
+        }
+        bytes.extend((self.functions.len() as u32).to_le_bytes());
+        for function in self.functions.iter() {
+            Bytecode::write_bytes(&mut bytes, function.name.as_bytes());
+            bytes.push(function.arity);
+            bytes.extend(function.locals.to_le_bytes());
+            Bytecode::write_bytes(&mut bytes, &function.code);
+        }
+        bytes.extend(self.globals.to_le_bytes());
+        bytes.extend(self.entry.to_le_bytes());
+        bytes
+    }
+    pub fn write_bytes(bytes: &mut Vec<u8>, value: &[u8]) {
+        bytes.extend((value.len() as u32).to_le_bytes());
+        bytes.extend(value);
+    }
+    pub fn deserialize(bytes: &[u8]) -> Result<Bytecode, String> {
+        let mut reader: BytecodeReader = BytecodeReader { bytes, position: 0 };
+        if reader.take(4)? != Bytecode::MAGIC {
+            return Err("not a bytecode file".to_string());
+        }
+        let version: u8 = reader.u8()?;
+        if version != Bytecode::VERSION {
+            return Err(format!("bytecode version {} isn't supported, expected {}", version, Bytecode::VERSION));
+        }
+        let mut constants: Vec<Constant> = vec![];
+        for _ in 0..reader.u32()? {
+            constants.push(match reader.u8()? {
+                0 => Constant::Int(i64::from_le_bytes(reader.take(8)?.try_into().map_err(|_| "truncated bytecode")?)),
+                1 => Constant::String(reader.string()?),
+                tag => return Err(format!("unknown constant tag {}", tag)),
+            });
+        }
+        let mut functions: Vec<BytecodeFunction> = vec![];
+        for _ in 0..reader.u32()? {
+            let name: String = reader.string()?;
+            let arity: u8 = reader.u8()?;
+            let locals: u16 = reader.u16()?;
+            let length: usize = reader.u32()? as usize;
+            let code: Vec<u8> = reader.take(length)?.to_vec();
+            functions.push(BytecodeFunction { name, arity, locals, code });
+        }
+        let globals: u16 = reader.u16()?;
+        let entry: u16 = reader.u16()?;
+        if entry as usize >= functions.len() {
+            return Err("the entry point is missing".to_string());
+        }
+        Ok(Bytecode { constants, functions, globals, entry })
+    }
+}
+pub struct BytecodeReader<'a> {
+    pub bytes: &'a [u8],
+    pub position: usize,
+}
+impl<'a> BytecodeReader<'a> {
+    pub fn take(&mut self, length: usize) -> Result<&'a [u8], String> {
+        let end: usize = self.position.checked_add(length).filter(|end| *end <= self.bytes.len()).ok_or("truncated bytecode")?;
+        let bytes: &'a [u8] = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+    pub fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+    pub fn u16(&mut self) -> Result<u16, String> {
+        let bytes: &[u8] = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+    pub fn u32(&mut self) -> Result<u32, String> {
+        let bytes: &[u8] = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+    pub fn string(&mut self) -> Result<String, String> {
+        let length: usize = self.u32()? as usize;
+        String::from_utf8(self.take(length)?.to_vec()).map_err(|_| "a string in the bytecode isn't UTF-8".to_string())
+    }
+}
+// Where a loop's `continue` goes back to, or None when it goes forward to a
+// `for` loop's step, and the jumps its `continue`s and `break`s leave to be
+// patched
+pub struct LoopJumps {
+    pub start: Option<usize>,
+    pub continues: Vec<usize>,
+    pub breaks: Vec<usize>,
+}
+// Compiles a checked program to bytecode. It covers the part of the language
+// that doesn't need real memory: pointers are references to objects, and only
+// the C functions in NATIVES can be called.
+pub struct BytecodeCompiler {
+    pub constants: Vec<Constant>,
+    pub functions: Vec<BytecodeFunction>,
+    pub function_indexes: HashMap<Symbol, u16>,
+    pub function_types: HashMap<Symbol, (Vec<(Symbol, Type)>, Type)>,
+    pub globals: HashMap<Symbol, (u16, Type)>,
+    pub struct_fields: HashMap<Symbol, Vec<(Symbol, Type)>>,
+    pub enums: HashMap<Symbol, Vec<(Symbol, Expression, TokenLocation)>>,
+    pub type_aliases: HashMap<Symbol, Type>,
+    pub member_types: HashMap<TokenLocation, Type>,
+    pub methods: HashMap<TokenLocation, (Symbol, Type)>,
+    // Calls of the built-in `print` and `println`, which become printf
+    pub prints: HashMap<TokenLocation, Vec<PrintConversion>>,
+    // What each `for` loop goes over, as the checker found
+    pub iterated: HashMap<TokenLocation, Type>,
+    // The function marked @entry, which is called instead of `main`
+    pub entry: Option<Symbol>,
+    // The function being compiled: its code, the names in each of its blocks,
+    // how many slots it needs, and the loops around the current statement
+    pub code: Vec<u8>,
+    pub scopes: Vec<Vec<(Symbol, u16, Type)>>,
+    pub locals: u16,
+    pub loops: Vec<LoopJumps>,
+    pub errors: Vec<Diagnostic>,
+}
+impl Default for BytecodeCompiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl BytecodeCompiler {
+    pub fn new() -> Self {
+        Self {
+            constants: vec![],
+            functions: vec![],
+            function_indexes: HashMap::new(),
+            function_types: HashMap::new(),
+            globals: HashMap::new(),
+            struct_fields: HashMap::new(),
+            enums: HashMap::new(),
+            type_aliases: HashMap::new(),
+            member_types: HashMap::new(),
+            methods: HashMap::new(),
+            prints: HashMap::new(),
+            iterated: HashMap::new(),
+            entry: None,
+            code: vec![],
+            scopes: vec![],
+            locals: 0,
+            loops: vec![],
+            errors: vec![],
+        }
+    }
+    pub fn compile(&mut self, statements: &[Statement]) -> Bytecode {
+        let statements: Vec<Statement> = statements.iter().map(BytecodeCompiler::unwrap_statement).cloned().collect();
+        for statement in statements.iter() {
+            self.collect(statement);
+        }
+        for statement in statements.iter() {
+            match statement {
+                Statement::Function(name, args, _, body, _) => self.compile_function(name, args, body),
+                Statement::StructFunction(struct_name, name, args, _, body, _) => self.compile_function(&Symbol::intern(&format!("{}.{}", struct_name, name)), args, body),
+                _ => {}
+            }
+        }
+        let entry: u16 = self.compile_entry(&statements);
+        Bytecode { constants: self.constants.clone(), functions: self.functions.clone(), globals: self.globals.len() as u16, entry }
+    }
+    pub fn unwrap_statement(statement: &Statement) -> &Statement {
+        match statement {
+            Statement::Annotated(statement, _, _) | Statement::Inline(statement, _) => BytecodeCompiler::unwrap_statement(statement),
+            statement => statement,
+        }
+    }
+    // Functions get their index up front so calls can come before definitions
+    pub fn collect(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Function(name, args, return_type, _, _) => {
+                self.function_indexes.insert(*name, self.functions.len() as u16);
+                self.function_types.insert(*name, (args.clone(), return_type.clone()));
+                self.functions.push(BytecodeFunction { name: name.to_string(), arity: args.len() as u8, locals: 0, code: vec![] });
+            }
+            Statement::StructFunction(struct_name, name, args, return_type, _, _) => {
+                let name: Symbol = Symbol::intern(&format!("{}.{}", struct_name, name));
+                self.function_indexes.insert(name, self.functions.len() as u16);
+                self.function_types.insert(name, (args.clone(), return_type.clone()));
+                self.functions.push(BytecodeFunction { name: name.to_string(), arity: args.len() as u8, locals: 0, code: vec![] });
+            }
+            Statement::Struct(name, fields, _) => {
+                self.struct_fields.insert(*name, fields.clone());
+            }
+            Statement::Enum(name, _, variants, _) => {
+                self.enums.insert(*name, variants.clone());
+            }
+            Statement::TypeAlias(name, types, _) if types.len() == 1 => {
+                self.type_aliases.insert(*name, types[0].clone());
+            }
+            Statement::Variable(name, t, _, _) | Statement::Constant(name, t, _, _) => {
+                self.globals.insert(*name, (self.globals.len() as u16, t.clone()));
+            }
+            Statement::Generic(_, _, location) | Statement::StructEnum(_, _, location) | Statement::JavaEnum(_, _, _, location) | Statement::Annotation(_, _, location) => {
+                self.unsupported("this declaration", location);
+            }
+            _ => {}
+        }
+    }
+    pub fn unsupported(&mut self, what: &str, location: &TokenLocation) {
+        self.errors.push(Diagnostic::type_error("E0028", format!("{} can't be compiled to bytecode", what), location.clone()));
+    }
+    pub fn compile_function(&mut self, name: &Symbol, args: &[(Symbol, Type)], body: &[Statement]) {
+        self.code = vec![];
+        self.locals = 0;
+        self.scopes = vec![vec![]];
+        for (arg_name, arg_type) in args.iter() {
+            self.declare_local(arg_name, arg_type);
+        }
+        for statement in body.iter() {
+            self.compile_statement(statement);
+        }
+        self.emit(Op::Null);
+        self.emit(Op::Return);
+        let index: usize = self.function_indexes[name] as usize;
+        self.functions[index].locals = self.locals;
+        self.functions[index].code = std::mem::take(&mut self.code);
+    }
+    // The globals are set in order, then main or the @entry function is called
+    // with the program's arguments
+    pub fn compile_entry(&mut self, statements: &[Statement]) -> u16 {
+        self.code = vec![];
+        self.locals = 0;
+        self.scopes = vec![vec![]];
+        for statement in statements.iter() {
+            if let Statement::Variable(name, t, value, _) | Statement::Constant(name, t, value, _) = statement {
+                self.compile_value(t, value);
+                let slot: u16 = self.globals[name].0;
+                self.emit(Op::SetGlobal);
+                self.emit_u16(slot);
+                self.emit(Op::Pop);
+            }
+        }
+        let entry: Symbol = self.entry.unwrap_or_else(|| Symbol::intern("main"));
+        match (self.function_indexes.get(&entry).copied(), self.function_types.get(&entry).cloned()) {
+            (Some(main), Some((args, _))) => {
+                // argc and argv are the only arguments main can take
+                let arity: u8 = args.len().min(2) as u8;
+                for index in 0..arity {
+                    self.emit(Op::GetLocal);
+                    self.emit_u16(index as u16);
+                }
+                self.emit(Op::Call);
+                self.emit_u16(main);
+                self.code.push(arity);
+            }
+            _ => self.errors.push(Diagnostic::type_error("E0028", "a program compiled to bytecode needs a `main` function".to_string(), TokenLocation { start: 0, end: 0 })),
+        }
+        self.emit(Op::Return);
+        self.functions.push(BytecodeFunction { name: "<entry>".to_string(), arity: 2, locals: 2, code: std::mem::take(&mut self.code) });
+        (self.functions.len() - 1) as u16
+    }
+    pub fn declare_local(&mut self, name: &Symbol, t: &Type) -> u16 {
+        let slot: u16 = self.locals;
+        self.locals += 1;
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push((*name, slot, t.clone()));
+        }
+        slot
+    }
+    pub fn lookup_local(&self, name: &Symbol) -> Option<(u16, Type)> {
+        self.scopes.iter().rev().flat_map(|scope| scope.iter().rev()).find(|(local, _, _)| local == name).map(|(_, slot, t)| (*slot, t.clone()))
+    }
+    pub fn emit(&mut self, op: Op) {
+        self.code.push(op as u8);
+    }
+    pub fn emit_u16(&mut self, value: u16) {
+        self.code.extend(value.to_le_bytes());
+    }
+    pub fn emit_constant(&mut self, constant: Constant) {
+        let index: usize = match self.constants.iter().position(|existing| *existing == constant) {
+            Some(index) => index,
+            None => {
+                self.constants.push(constant);
+                self.constants.len() - 1
+            }
+        };
+        self.emit(Op::Constant);
+        self.emit_u16(index as u16);
+    }
+    // Emits a jump to be patched later, returning where its offset is
+    pub fn emit_jump(&mut self, op: Op) -> usize {
+        self.emit(op);
+        self.code.extend(0i32.to_le_bytes());
+        self.code.len() - 4
+    }
+    pub fn patch_jump(&mut self, at: usize) {
+        let offset: i32 = (self.code.len() - (at + 4)) as i32;
+        self.code[at..at + 4].copy_from_slice(&offset.to_le_bytes());
+    }
+    pub fn emit_loop(&mut self, start: usize) {
+        self.emit(Op::Jump);
+        let offset: i32 = start as i32 - (self.code.len() + 4) as i32;
+        self.code.extend(offset.to_le_bytes());
+    }
+    pub fn compile_block(&mut self, body: &[Statement]) {
+        self.scopes.push(vec![]);
+        for statement in body.iter() {
+            self.compile_statement(statement);
+        }
+        self.scopes.pop();
+    }
+    pub fn compile_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Variable(name, t, value, _) | Statement::Constant(name, t, value, _) => {
+                // A constructor's object is the only thing malloc can make here
+                if let (true, Some(struct_name)) = (Codegen::is_malloc(value), self.pointed_struct(t)) {
+                    self.compile_default(&Type::Struct(struct_name, value.location()));
+                } else {
+                    self.compile_value(t, value);
+                }
+                let slot: u16 = self.declare_local(name, t);
+                self.emit(Op::SetLocal);
+                self.emit_u16(slot);
+                self.emit(Op::Pop);
+            }
+            Statement::Return(value, _) => {
+                match value {
+                    Expression::Empty => self.emit(Op::Null),
+                    value => self.compile_expression(value),
+                }
+                self.emit(Op::Return);
+            }
+            Statement::If(condition, body, else_body, _) => {
+                self.compile_expression(condition);
+                let to_else: usize = self.emit_jump(Op::JumpIfFalse);
+                self.compile_block(body);
+                let to_end: usize = self.emit_jump(Op::Jump);
+                self.patch_jump(to_else);
+                self.compile_block(else_body);
+                self.patch_jump(to_end);
+            }
+            Statement::While(condition, body, _) => {
+                let start: usize = self.code.len();
+                self.compile_expression(condition);
+                let to_end: usize = self.emit_jump(Op::JumpIfFalse);
+                self.loops.push(LoopJumps { start: Some(start), continues: vec![], breaks: vec![] });
+                self.compile_block(body);
+                self.emit_loop(start);
+                self.patch_jump(to_end);
+                if let Some(jumps) = self.loops.pop() {
+                    for at in jumps.breaks {
+                        self.patch_jump(at);
+                    }
+                }
+            }
+            Statement::Break(location) => {
+                if self.loops.is_empty() {
+                    self.unsupported("`break` outside of a loop", location);
+                    return;
+                }
+                let at: usize = self.emit_jump(Op::Jump);
+                if let Some(jumps) = self.loops.last_mut() {
+                    jumps.breaks.push(at);
+                }
+            }
+            Statement::Continue(location) => match self.loops.last().map(|jumps| jumps.start) {
+                Some(Some(start)) => self.emit_loop(start),
+                Some(None) => {
+                    let at: usize = self.emit_jump(Op::Jump);
+                    if let Some(jumps) = self.loops.last_mut() {
+                        jumps.continues.push(at);
+                    }
+                }
+                None => self.unsupported("`continue` outside of a loop", location),
+            },
+            Statement::For(name, iterable, body, location) => self.compile_for(name, iterable, body, location),
+            Statement::Expression(expression, _) => {
+                self.compile_expression(expression);
+                self.emit(Op::Pop);
+            }
+            Statement::Annotated(statement, _, _) | Statement::Inline(statement, _) => self.compile_statement(statement),
+            Statement::Import(_, _) | Statement::External(_, _) | Statement::TypeAlias(_, _, _) => {}
+            statement => self.unsupported("this statement", &statement.location()),
+        }
+    }
+    pub fn resolve(&self, t: &Type) -> Type {
+        match t {
+            Type::Unknown(name, _) => match self.type_aliases.get(name) {
+                Some(t) => self.resolve(t),
+                None => t.clone(),
+            },
+            Type::Const(t, _) | Type::Volatile(t, _) | Type::Restrict(t, _) => self.resolve(t),
+            t => t.clone(),
+        }
+    }
+    pub fn struct_value(&self, t: &Type) -> Option<Symbol> {
+        match self.resolve(t) {
+            Type::Unknown(name, _) | Type::Struct(name, _) if self.struct_fields.contains_key(&name) => Some(name),
+            _ => None,
+        }
+    }
+    pub fn pointed_struct(&self, t: &Type) -> Option<Symbol> {
+        match self.resolve(t) {
+            Type::Pointer(t, _) => self.struct_value(&t),
+            _ => None,
+        }
+    }
+    // The struct a member access goes through, whether by value or by pointer
+    pub fn member_struct(&self, location: &TokenLocation) -> Option<Symbol> {
+        let t: Type = self.member_types.get(location)?.clone();
+        self.struct_value(&t).or_else(|| self.pointed_struct(&t))
+    }
+    // What an uninitialized variable of a type holds
+    pub fn compile_default(&mut self, t: &Type) {
+        match self.resolve(t) {
+            Type::DynamicArray(_, _) => {
+                self.emit(Op::Array);
+                self.emit_u16(0);
+            }
+            Type::Array(_, size, location) => match *size {
+                Expression::Number(size, _) => {
+                    self.emit(Op::ArrayOf);
+                    self.code.extend((size as u32).to_le_bytes());
+                }
+                _ => self.unsupported("an array without a constant size", &location),
+            },
+            Type::Pointer(_, _) | Type::String(_) | Type::CString(_) | Type::Function(_, _, _) | Type::Map(_, _, _) => self.emit(Op::Null),
+            t => match self.struct_value(&t) {
+                Some(name) => {
+                    let fields: Vec<(Symbol, Type)> = self.struct_fields[&name].clone();
+                    self.emit(Op::Object);
+                    self.emit_u16(fields.len() as u16);
+                    for (index, (_, field_type)) in fields.iter().enumerate() {
+                        if matches!(self.resolve(field_type), Type::DynamicArray(_, _) | Type::Array(_, _, _)) || self.struct_value(field_type).is_some() {
+                            self.emit(Op::Dup);
+                            self.compile_default(field_type);
+                            self.emit(Op::SetField);
+                            self.emit_u16(index as u16);
+                            self.emit(Op::Pop);
+                        }
+                    }
+                }
+                None => self.emit_constant(Constant::Int(0)),
+            },
+        }
+    }
+    // A value being stored somewhere of type t
+    // A range's end, or the array and the index into it, are kept in slots
+    // named as C names them, which no name in a program can be
+    pub fn compile_for(&mut self, name: &Symbol, iterable: &Expression, body: &[Statement], location: &TokenLocation) {
+        let iterated: Type = self.iterated.get(location).cloned().unwrap_or(Type::Int(location.clone()));
+        self.scopes.push(vec![]);
+        // A range's variable counts up to its end, and an array's index up to
+        // its length, each item being copied into the variable
+        let (counter, bound, item): (u16, u16, Option<(u16, Type)>) = match iterable {
+            Expression::Range(from, to, _) => {
+                self.compile_expression(from);
+                let variable: u16 = self.declare_local(name, &iterated);
+                self.set_local(variable);
+                self.compile_expression(to);
+                let end: u16 = self.declare_local(&Symbol::intern(&mangle("for", &[name, "end"])), &iterated);
+                self.set_local(end);
+                (variable, end, None)
+            }
+            _ => {
+                let element: Type = match &iterated {
+                    Type::Array(element, _, _) | Type::DynamicArray(element, _) => (**element).clone(),
+                    t => t.clone(),
+                };
+                self.compile_expression(iterable);
+                let items: u16 = self.declare_local(&Symbol::intern(&mangle("for", &[name, "items"])), &iterated);
+                self.set_local(items);
+                self.emit_constant(Constant::Int(0));
+                let index: u16 = self.declare_local(&Symbol::intern(&mangle("for", &[name, "index"])), &Type::Usize(location.clone()));
+                self.set_local(index);
+                self.compile_default(&element);
+                let variable: u16 = self.declare_local(name, &element);
+                self.set_local(variable);
+                (index, items, Some((variable, element)))
+            }
+        };
+        let start: usize = self.code.len();
+        self.get_local(counter);
+        self.get_local(bound);
+        if item.is_some() {
+            self.emit(Op::ArrayLength);
+        }
+        self.emit(Op::Less);
+        let to_end: usize = self.emit_jump(Op::JumpIfFalse);
+        if let Some((variable, element)) = &item {
+            self.get_local(bound);
+            self.get_local(counter);
+            self.emit(Op::Index);
+            if self.struct_value(element).is_some() {
+                self.emit(Op::Copy);
+            }
+            self.set_local(*variable);
+        }
+        self.loops.push(LoopJumps { start: None, continues: vec![], breaks: vec![] });
+        self.compile_block(body);
+        let jumps: Option<LoopJumps> = self.loops.pop();
+        for at in jumps.iter().flat_map(|jumps| jumps.continues.iter()) {
+            self.patch_jump(*at);
+        }
+        self.get_local(counter);
+        self.emit_constant(Constant::Int(1));
+        self.emit(Op::Add);
+        self.set_local(counter);
+        self.emit_loop(start);
+        self.patch_jump(to_end);
+        for at in jumps.iter().flat_map(|jumps| jumps.breaks.iter()) {
+            self.patch_jump(*at);
+        }
+        self.scopes.pop();
+    }
+    pub fn get_local(&mut self, slot: u16) {
+        self.emit(Op::GetLocal);
+        self.emit_u16(slot);
+    }
+    // Stores what's on top of the stack and takes it off
+    pub fn set_local(&mut self, slot: u16) {
+        self.emit(Op::SetLocal);
+        self.emit_u16(slot);
+        self.emit(Op::Pop);
+    }
+    pub fn compile_value(&mut self, t: &Type, value: &Expression) {
+        if let Expression::Empty = value {
+            self.compile_default(t);
+            return;
+        }
+        self.compile_expression(value);
+        if self.struct_value(t).is_some() {
+            self.emit(Op::Copy);
+        }
+    }
+    pub fn compile_arguments(&mut self, params: &[Type], args: &[Expression]) {
+        for (index, arg) in args.iter().enumerate() {
+            match params.get(index) {
+                Some(t) => self.compile_value(t, arg),
+                None => self.compile_expression(arg),
+            }
+        }
+    }
+    pub fn compile_call(&mut self, name: &Symbol, args: &[Expression], location: &TokenLocation) {
+        if let Some(index) = self.function_indexes.get(name).copied() {
+            let params: Vec<Type> = self.function_types[name].0.iter().map(|(_, t)| t.clone()).collect();
+            self.compile_arguments(&params, args);
+            self.emit(Op::Call);
+            self.emit_u16(index);
+            self.code.push(args.len() as u8);
+        } else if let Some(native) = NATIVES.iter().position(|native| *name == *native) {
+            self.compile_arguments(&[], args);
+            self.emit(Op::CallNative);
+            self.emit_u16(native as u16);
+            self.code.push(args.len() as u8);
+        } else {
+            self.unsupported(&format!("a call to `{}`", name), location);
+        }
+    }
+    // `print` and `println` call the native printf, with each `bool` turned
+    // into `true` or `false` first
+    pub fn compile_print(&mut self, name: &Symbol, args: &[Expression], conversions: &[PrintConversion]) {
+        let pieces: Vec<String> = match args.first() {
+            Some(Expression::String(format, _)) => format_pieces(format).unwrap_or_default(),
+            _ => vec![],
+        };
+        let mut format: String = String::new();
+        for (i, piece) in pieces.iter().enumerate() {
+            format.push_str(&piece.replace('%', "%%"));
+            if let Some(conversion) = conversions.get(i) {
+                format.push_str(conversion.specifier());
+            }
+        }
+        if name == "println" {
+            format.push('\n');
+        }
+        self.emit_constant(Constant::String(format));
+        for (arg, conversion) in args.iter().skip(1).zip(conversions.iter()) {
+            self.compile_expression(arg);
+            if *conversion == PrintConversion::Bool {
+                let to_false: usize = self.emit_jump(Op::JumpIfFalse);
+                self.emit_constant(Constant::String("true".to_string()));
+                let to_end: usize = self.emit_jump(Op::Jump);
+                self.patch_jump(to_false);
+                self.emit_constant(Constant::String("false".to_string()));
+                self.patch_jump(to_end);
+            }
+        }
+        let printf: usize = NATIVES.iter().position(|native| *native == "printf").unwrap_or_default();
+        self.emit(Op::CallNative);
+        self.emit_u16(printf as u16);
+        self.code.push(args.len() as u8);
+    }
+    pub fn compile_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Number(value, _) => self.emit_constant(Constant::Int(*value)),
+            Expression::Boolean(value, _) => self.emit_constant(Constant::Int(*value as i64)),
+            Expression::Char(value, _) => {
+                let value: i64 = value.bytes().next().unwrap_or(0) as i64;
+                self.emit_constant(Constant::Int(value));
+            }
+            Expression::String(value, _) => self.emit_constant(Constant::String(value.clone())),
+            Expression::Null => self.emit(Op::Null),
+            Expression::Identifier(name, location) => {
+                if let Some((slot, _)) = self.lookup_local(name) {
+                    self.emit(Op::GetLocal);
+                    self.emit_u16(slot);
+                } else if let Some((slot, _)) = self.globals.get(name).cloned() {
+                    self.emit(Op::GetGlobal);
+                    self.emit_u16(slot);
+                } else if let Some(index) = self.function_indexes.get(name).copied() {
+                    self.emit(Op::Function);
+                    self.emit_u16(index);
+                } else {
+                    self.unsupported(&format!("`{}`", name), location);
+                }
+            }
+            Expression::Call(name, args, location) => {
+                if let Some(conversions) = self.prints.get(location).cloned() {
+                    self.compile_print(name, args, &conversions);
+                } else if let Some(fields) = self.struct_fields.get(name).cloned() {
+                    self.compile_struct_literal(name, &fields, args);
+                } else if let Some((slot, t)) = self.lookup_local(name).or_else(|| self.globals.get(name).cloned()) {
+                    let params: Vec<Type> = match self.resolve(&t) {
+                        Type::Function(params, _, _) => params,
+                        _ => vec![],
+                    };
+                    self.emit(if self.lookup_local(name).is_some() { Op::GetLocal } else { Op::GetGlobal });
+                    self.emit_u16(slot);
+                    self.compile_arguments(&params, args);
+                    self.emit(Op::CallValue);
+                    self.code.push(args.len() as u8);
+                } else {
+                    self.compile_call(name, args, location);
+                }
+            }
+            Expression::Member(base, member, location) => self.compile_member(base, member, location),
+            Expression::Cast(value, _, _) | Expression::UnsafeCast(value, _, _) | Expression::Grouping(value, _) => self.compile_expression(value),
+            Expression::Index(base, index, location) => {
+                if let Some(Type::Map(_, _, _)) = self.member_types.get(location) {
+                    self.unsupported("a map", location);
+                }
+                self.compile_expression(base);
+                self.compile_expression(index);
+                self.emit(Op::Index);
+            }
+            Expression::Array(elements, _) => {
+                for element in elements.iter() {
+                    self.compile_expression(element);
+                }
+                self.emit(Op::Array);
+                self.emit_u16(elements.len() as u16);
+            }
+            Expression::New(name, args, location) => {
+                let name: Symbol = Symbol::intern(&format!("{}.constructor", name));
+                self.compile_call(&name, args, location);
+            }
+            Expression::Ternary(condition, then, otherwise, _) => {
+                self.compile_expression(condition);
+                let to_else: usize = self.emit_jump(Op::JumpIfFalse);
+                self.compile_expression(then);
+                let to_end: usize = self.emit_jump(Op::Jump);
+                self.patch_jump(to_else);
+                self.compile_expression(otherwise);
+                self.patch_jump(to_end);
+            }
+            Expression::Assignment(target, value, location) => self.compile_assignment(target, value, location),
+            Expression::Binary(op, left, right, location) => {
+                self.compile_expression(left);
+                self.compile_expression(right);
+                let op: Op = match op {
+                    TokenKind::Plus => Op::Add,
+                    TokenKind::Minus => Op::Subtract,
+                    TokenKind::Star => Op::Multiply,
+                    TokenKind::Slash => Op::Divide,
+                    TokenKind::Percent => Op::Remainder,
+                    TokenKind::EqualEqual => Op::Equal,
+                    TokenKind::BangEqual => Op::NotEqual,
+                    TokenKind::Less => Op::Less,
+                    TokenKind::LessEqual => Op::LessEqual,
+                    TokenKind::Greater => Op::Greater,
+                    TokenKind::GreaterEqual => Op::GreaterEqual,
+                    _ => return self.unsupported("this operator", location),
+                };
+                self.emit(op);
+            }
+            Expression::Unary(op, value, location) => {
+                self.compile_expression(value);
+                match op {
+                    TokenKind::Minus => self.emit(Op::Negate),
+                    TokenKind::Bang => self.emit(Op::Not),
+                    _ => self.unsupported("this operator", location),
+                }
+            }
+            Expression::Error(error) => self.errors.push(error.clone()),
+            expression => self.unsupported("this expression", &expression.location()),
+        }
+    }
+    // `S(a, b)` or `S(x: a, y: b)` makes an object, with any field not given left zero
+    pub fn compile_struct_literal(&mut self, name: &Symbol, fields: &[(Symbol, Type)], args: &[Expression]) {
+        self.compile_default(&Type::Struct(*name, TokenLocation { start: 0, end: 0 }));
+        for (position, arg) in args.iter().enumerate() {
+            let (index, value): (Option<usize>, &Expression) = match arg {
+                Expression::NamedArgument(field, value, _) => (fields.iter().position(|(name, _)| name == field), value),
+                arg => (Some(position), arg),
+            };
+            let Some(index) = index.filter(|index| *index < fields.len()) else {
+                self.unsupported("this field", &arg.location());
+                continue;
+            };
+            self.emit(Op::Dup);
+            let field_type: Type = fields[index].1.clone();
+            self.compile_value(&field_type, value);
+            self.emit(Op::SetField);
+            self.emit_u16(index as u16);
+            self.emit(Op::Pop);
+        }
+    }
+    pub fn field_index(&mut self, location: &TokenLocation, field: &str) -> Option<(u16, Type)> {
+        let struct_name: Symbol = self.member_struct(location)?;
+        let fields: &Vec<(Symbol, Type)> = &self.struct_fields[&struct_name];
+        fields.iter().position(|(name, _)| name == field).map(|index| (index as u16, fields[index].1.clone()))
+    }
+    pub fn compile_member(&mut self, base: &Expression, member: &Expression, location: &TokenLocation) {
+        if let Some((function, self_type)) = self.methods.get(location).cloned() {
+            let params: Vec<Type> = self.function_types.get(&function).map(|(args, _)| args.iter().map(|(_, t)| t.clone()).collect()).unwrap_or_default();
+            self.compile_value(&self_type, base);
+            if let Expression::Call(_, args, _) = member {
+                self.compile_arguments(&params[1.min(params.len())..], args);
+                self.emit(Op::Call);
+                self.emit_u16(self.function_indexes[&function]);
+                self.code.push(args.len() as u8 + 1);
+            }
+            return;
+        }
+        if let Expression::Identifier(name, _) = base {
+            if self.lookup_local(name).is_none() && !self.globals.contains_key(name) {
+                if let Some(variants) = self.enums.get(name).cloned() {
+                    return self.compile_enum_member(&variants, member, location);
+                }
+                if let (true, Expression::Call(function, args, call_location)) = (self.struct_fields.contains_key(name), member) {
+                    return self.compile_call(&Symbol::intern(&format!("{}.{}", name, function)), args, call_location);
+                }
+            }
+        }
+        match (self.member_types.get(location).map(|t| self.resolve(t)), member) {
+            (Some(Type::DynamicArray(element, _)), Expression::Call(method, args, _)) => {
+                self.compile_expression(base);
+                match method.as_str() {
+                    "push" if args.len() == 1 => {
+                        self.compile_value(&element, &args[0]);
+                        self.emit(Op::ArrayPush);
+                    }
+                    "pop" => self.emit(Op::ArrayPop),
+                    _ => self.emit(Op::ArrayLength),
+                }
+            }
+            (Some(Type::DynamicArray(_, _)), _) => {
+                self.compile_expression(base);
+                self.emit(Op::ArrayLength);
+            }
+            (Some(Type::Map(_, _, _)), _) => self.unsupported("a map", location),
+            // `data` is the string itself, there being no memory to point into
+            (Some(Type::String(_)), Expression::Identifier(field, _)) => {
+                self.compile_expression(base);
+                if field == "length" {
+                    self.emit(Op::CallNative);
+                    self.emit_u16(NATIVES.iter().position(|native| *native == "strlen").unwrap_or_default() as u16);
+                    self.code.push(1);
+                }
+            }
+            (_, Expression::Identifier(field, field_location)) => match self.field_index(location, field) {
+                Some((index, _)) => {
+                    self.compile_expression(base);
+                    self.emit(Op::GetField);
+                    self.emit_u16(index);
+                }
+                None => self.unsupported(&format!("the field `{}`", field), field_location),
+            },
+            // A call through a function pointer field, which like in C gets the
+            // variable it's called on first
+            (_, Expression::Call(field, args, field_location)) => match self.field_index(location, field) {
+                Some((index, t)) => {
+                    self.compile_expression(base);
+                    self.emit(Op::GetField);
+                    self.emit_u16(index);
+                    let receiver: bool = matches!(base, Expression::Identifier(name, _) if self.lookup_local(name).is_some());
+                    if receiver {
+                        self.compile_expression(base);
+                    }
+                    let params: Vec<Type> = match self.resolve(&t) {
+                        Type::Function(params, _, _) => params,
+                        _ => vec![],
+                    };
+                    self.compile_arguments(&params[(receiver as usize).min(params.len())..], args);
+                    self.emit(Op::CallValue);
+                    self.code.push(args.len() as u8 + receiver as u8);
+                }
+                None => self.unsupported(&format!("the field `{}`", field), field_location),
+            },
+            _ => self.unsupported("this member", location),
+        }
+    }
+    // An enum's variant is its value, the variants without one counting up
+    // from the last that has one
+    pub fn compile_enum_member(&mut self, variants: &[(Symbol, Expression, TokenLocation)], member: &Expression, location: &TokenLocation) {
+        let (variant, args): (&Symbol, Option<&Vec<Expression>>) = match member {
+            Expression::Identifier(variant, _) => (variant, None),
+            Expression::Call(variant, args, _) => (variant, Some(args)),
+            _ => return self.unsupported("this member", location),
+        };
+        let Some(position) = variants.iter().position(|(name, _, _)| name == variant) else {
+            return self.unsupported(&format!("the variant `{}`", variant), location);
+        };
+        let mut offset: i64 = 0;
+        let mut base: Option<&Expression> = None;
+        for (_, value, _) in variants[..=position].iter() {
+            if let Expression::Empty = value {
+                offset += 1;
+            } else {
+                base = Some(value);
+                offset = 0;
+            }
+        }
+        match base {
+            Some(value) => {
+                self.compile_expression(value);
+                if offset > 0 {
+                    self.emit_constant(Constant::Int(offset));
+                    self.emit(Op::Add);
+                }
+            }
+            None => self.emit_constant(Constant::Int(offset - 1)),
+        }
+        if let Some(args) = args {
+            self.compile_arguments(&[], args);
+            self.emit(Op::CallValue);
+            self.code.push(args.len() as u8);
+        }
+    }
+    pub fn compile_assignment(&mut self, target: &Expression, value: &Expression, location: &TokenLocation) {
+        match target {
+            Expression::Identifier(name, _) => {
+                if let Some((slot, t)) = self.lookup_local(name) {
+                    self.compile_value(&t, value);
+                    self.emit(Op::SetLocal);
+                    self.emit_u16(slot);
+                } else if let Some((slot, t)) = self.globals.get(name).cloned() {
+                    self.compile_value(&t, value);
+                    self.emit(Op::SetGlobal);
+                    self.emit_u16(slot);
+                } else {
+                    self.unsupported(&format!("`{}`", name), location);
+                }
+            }
+            Expression::Member(base, member, member_location) => match &**member {
+                Expression::Identifier(field, _) => match self.field_index(member_location, field) {
+                    Some((index, t)) => {
+                        self.compile_expression(base);
+                        self.compile_value(&t, value);
+                        self.emit(Op::SetField);
+                        self.emit_u16(index);
+                    }
+                    None => self.unsupported(&format!("the field `{}`", field), member_location),
+                },
+                _ => self.unsupported("this assignment", location),
+            },
+            Expression::Index(base, index, _) => {
+                self.compile_expression(base);
+                self.compile_expression(index);
+                self.compile_expression(value);
+                self.emit(Op::SetIndex);
+            }
+            _ => self.unsupported("this assignment", location),
+        }
+    }
+}
@@ -0,0 +1,1703 @@
+use std::collections::{HashMap, HashSet};
+use crate::ast::{Annotation, Expression, Statement, Type, type_graph};
+use crate::codegen::RESERVED_PREFIX;
+use crate::diagnostics::{closest, Diagnostic, Label, Suggestion};
+use crate::fold::{Folder, Substitution};
+use crate::lexer::{TokenKind, TokenLocation};
+use crate::lint::{Lint, LintConfig};
+use crate::symbol::Symbol;
+use crate::target::TargetMachine;
+// Parameters and return type of a function the checker knows about
+pub type Signature = (Vec<(Symbol, Type)>, Type);
+// The C library's most used functions, by the header that declares them. A
+// call of one that the program neither declares nor imports the header of
+// gets -Wmissing-import.
+pub const C_LIBRARY: [(&str, &[&str]); 6] = [
+    ("stdio.h", &["printf", "fprintf", "sprintf", "snprintf", "puts", "putchar", "getchar", "scanf", "sscanf", "fopen", "fclose", "fread", "fwrite", "fgets", "fputs", "fflush", "perror", "remove", "rename"]),
+    ("stdlib.h", &["malloc", "calloc", "realloc", "free", "exit", "abort", "atoi", "atof", "strtol", "strtoul", "strtod", "rand", "srand", "qsort", "bsearch", "getenv", "system", "abs", "labs"]),
+    ("string.h", &["strlen", "strcmp", "strncmp", "strcpy", "strncpy", "strcat", "strncat", "strchr", "strrchr", "strstr", "strdup", "strerror", "memcpy", "memmove", "memset", "memcmp"]),
+    ("ctype.h", &["isalpha", "isdigit", "isalnum", "isspace", "isupper", "islower", "ispunct", "toupper", "tolower"]),
+    ("math.h", &["sqrt", "pow", "sin", "cos", "tan", "atan2", "floor", "ceil", "fabs", "fmod", "log", "exp", "round"]),
+    ("time.h", &["time", "clock", "difftime", "localtime", "strftime"]),
+];
+// Headers whose functions are in a library of their own rather than the C
+// library, with the flag that links it
+pub const LINKED_HEADERS: [(&str, &str); 1] = [("math.h", "-lm")];
+// What codegen has to do for an expression involving the `string` runtime
+#[derive(Debug, Clone, PartialEq)]
+pub enum StringConversion {
+    // A literal that becomes a `string` rather than staying a C string
+    Literal,
+    Equals,
+    NotEquals,
+    ToCString,
+    FromCString,
+}
+// How `print` and `println` hand each value to printf, by its type
+#[derive(Debug, Clone, PartialEq)]
+pub enum PrintConversion {
+    Int,
+    Usize,
+    // C's `double` or `float`, declared with `external type`
+    Double,
+    Char,
+    // As `true` or `false`
+    Bool,
+    CString,
+    String,
+    Pointer,
+}
+impl PrintConversion {
+    // What the value's `{}` becomes in printf's format
+    pub fn specifier(&self) -> &'static str {
+        match self {
+            PrintConversion::Int => "%d",
+            PrintConversion::Usize => "%zu",
+            PrintConversion::Double => "%g",
+            PrintConversion::Char => "%c",
+            PrintConversion::Bool | PrintConversion::CString | PrintConversion::String => "%s",
+            PrintConversion::Pointer => "%p",
+        }
+    }
+}
+// The text of a `print` format around its `{}`s, one more piece than there are
+// of them, with `{{` and `}}` read as braces
+pub fn format_pieces(format: &str) -> Result<Vec<String>, String> {
+    let mut pieces: Vec<String> = vec![String::new()];
+    let mut characters = format.chars().peekable();
+    while let Some(character) = characters.next() {
+        match (character, characters.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                characters.next();
+                pieces.last_mut().unwrap().push(character);
+            }
+            ('{', Some('}')) => {
+                characters.next();
+                pieces.push(String::new());
+            }
+            ('{', _) => return Err("a `{` has to be closed right away, as `{}`, or be written `{{`".to_string()),
+            ('}', _) => return Err("a `}` has to be written `}}`".to_string()),
+            _ => pieces.last_mut().unwrap().push(character),
+        }
+    }
+    Ok(pieces)
+}
+// A name declared in one of the checker's scopes
+#[derive(Debug, Clone)] pub struct Binding {
+    pub t: Type,
+    pub constant: bool,
+    // False for a local declared without a value until something assigns it
+    pub initialized: bool,
+    // Only locals start out unused; parameters and globals never get reported
+    pub used: bool,
+    // Whether anything assigns it after its declaration
+    pub assigned: bool,
+    pub location: TokenLocation,
+}
+// Semantic checks that run between the parser and codegen. Anything the checker
+// can't see into (C externs, generics, ...) gets the empty Type::Unknown and is
+// never reported, so only mistakes that are certain in .sl terms become errors.
+#[derive(Debug, Clone)] pub struct Checker {
+    pub structs: HashMap<Symbol, Vec<(Symbol, Type)>>,
+    pub enums: HashMap<Symbol, Type>,
+    pub struct_enums: HashMap<Symbol, Vec<Symbol>>,
+    pub java_enums: HashMap<Symbol, Vec<(Symbol, Type)>>,
+    pub type_aliases: HashMap<Symbol, Vec<Type>>,
+    pub annotations: HashMap<Symbol, Vec<(Symbol, Type)>>,
+    pub functions: HashMap<Symbol, Signature>,
+    // The type parameters of generic functions
+    pub generics: HashMap<Symbol, Vec<Symbol>>,
+    // Names marked @deprecated, with the message and where they're declared
+    pub deprecated: HashMap<Symbol, (Option<String>, TokenLocation)>,
+    // The function being checked, so a deprecated item may refer to itself
+    pub current_function: Symbol,
+    // Names given with @extern_name, and the function each belongs to
+    pub extern_names: HashMap<String, Symbol>,
+    pub scopes: Vec<HashMap<Symbol, Binding>>,
+    // Resolved type of the value on the left of each `.`, keyed by the dot's location
+    pub member_types: HashMap<TokenLocation, Type>,
+    // Expressions codegen lowers to the string runtime, keyed by their location
+    pub strings: HashMap<TokenLocation, StringConversion>,
+    // Indexes into a `T[]`, which codegen bounds checks
+    pub array_indexes: HashSet<TokenLocation>,
+    // Calls of the built-in `assert`
+    pub asserts: HashSet<TokenLocation>,
+    // What each `for` loop goes over, by the loop's location: the type its
+    // variable counts in for a range, or the array
+    pub loops: HashMap<TokenLocation, Type>,
+    // Structs the program points at without `new`, by taking one's address,
+    // casting another pointer to one or allocating through a generic, which
+    // --arc leaves uncounted since those objects have no count
+    pub uncounted: HashSet<Symbol>,
+    // Calls of the built-in `print` and `println`, with how each value after
+    // the format is printed
+    pub prints: HashMap<TokenLocation, Vec<PrintConversion>>,
+    // The map type of every map literal and map index, which codegen needs to
+    // size keys and values
+    pub maps: HashMap<TokenLocation, Type>,
+    // Array literals that make a `T[]`, with that type, which codegen builds
+    // through the array runtime
+    pub arrays: HashMap<TokenLocation, Type>,
+    // Calls of struct functions as methods: the function and the type of its `self`
+    pub methods: HashMap<TokenLocation, (Symbol, Type)>,
+    // Where each use of a local or parameter, and each declaration of a
+    // variable, was declared and its type, keyed by the name's location
+    pub references: HashMap<TokenLocation, (TokenLocation, Type)>,
+    pub errors: Vec<Diagnostic>,
+    pub warnings: Vec<Diagnostic>,
+    pub lints: LintConfig,
+    // Decides which literals fit in an `int` or `usize`
+    pub machine: TargetMachine,
+    // Without a C library there's no `main` to generate for an @entry function
+    pub freestanding: bool,
+    // The function marked @entry
+    pub entry: Option<Symbol>,
+    // Every import in the program, by path
+    pub imports: Vec<(String, TokenLocation)>,
+}
+impl Default for Checker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Checker {
+    pub fn new() -> Self {
+        Self {
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            struct_enums: HashMap::new(),
+            java_enums: HashMap::new(),
+            type_aliases: HashMap::new(),
+            annotations: HashMap::new(),
+            functions: HashMap::new(),
+            generics: HashMap::new(),
+            deprecated: HashMap::new(),
+            current_function: Symbol::default(),
+            extern_names: HashMap::new(),
+            scopes: vec![HashMap::new()],
+            member_types: HashMap::new(),
+            strings: HashMap::new(),
+            array_indexes: HashSet::new(),
+            asserts: HashSet::new(),
+            loops: HashMap::new(),
+            uncounted: HashSet::new(),
+            prints: HashMap::new(),
+            maps: HashMap::new(),
+            arrays: HashMap::new(),
+            methods: HashMap::new(),
+            references: HashMap::new(),
+            errors: vec![],
+            warnings: vec![],
+            lints: LintConfig::new(),
+            machine: TargetMachine::host(),
+            freestanding: false,
+            entry: None,
+            imports: vec![],
+        }
+    }
+    pub fn check(&mut self, statements: &[Statement]) {
+        // Declarations are collected up front so bodies can refer to anything in the program
+        let mut defined: HashMap<Symbol, TokenLocation> = HashMap::new();
+        for statement in statements.iter() {
+            if let Some((name, location)) = Checker::definition(statement) {
+                if let Some(first) = defined.get(&name) {
+                    self.errors.push(Diagnostic::type_error("E0009", format!("`{}` is defined more than once", name), location.clone())
+                        .with_label(first.clone(), "first defined here".to_string()));
+                }
+                if !name.contains('.') {
+                    self.check_reserved(&name, &location);
+                }
+                defined.entry(name).or_insert(location);
+            }
+            self.collect_statement(statement);
+        }
+        self.check_type_cycles(statements);
+        for statement in statements.iter() {
+            self.check_statement(statement);
+        }
+    }
+    // Codegen orders type definitions by what they contain, which only works if
+    // no type ends up containing itself by value
+    pub fn check_type_cycles(&mut self, statements: &[Statement]) {
+        let graph: HashMap<Symbol, (usize, Vec<Symbol>)> = type_graph(statements);
+        let mut reported: Vec<Symbol> = vec![];
+        for statement in statements.iter() {
+            let name: &Symbol = match statement.declared_type() {
+                Some(name) => name,
+                None => continue,
+            };
+            let mut path: Vec<Symbol> = vec![*name];
+            if !Checker::find_cycle(&graph, name, &mut path) || path.iter().any(|name| reported.contains(name)) {
+                continue;
+            }
+            self.errors.push(Diagnostic::type_error("E0024", format!("`{}` contains itself through {}; put one of them behind a pointer", name, path.iter().map(Symbol::as_str).collect::<Vec<&str>>().join(" -> ")), statement.location()));
+            reported.extend(path);
+        }
+    }
+    pub fn find_cycle(graph: &HashMap<Symbol, (usize, Vec<Symbol>)>, start: &Symbol, path: &mut Vec<Symbol>) -> bool {
+        let current: Symbol = *path.last().unwrap();
+        for dependency in graph[&current].1.iter() {
+            if dependency == start {
+                path.push(*dependency);
+                return true;
+            }
+            if path.contains(dependency) || !graph.contains_key(dependency) {
+                continue;
+            }
+            path.push(*dependency);
+            if Checker::find_cycle(graph, start, path) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+    // The name a top-level statement defines; external declarations don't count
+    // since they may repeat across modules and precede a definition
+    pub fn definition(statement: &Statement) -> Option<(Symbol, TokenLocation)> {
+        match statement {
+            Statement::Generic(statement, _, _) | Statement::Annotated(statement, _, _) | Statement::Inline(statement, _) => Checker::definition(statement),
+            Statement::Struct(name, _, location)
+            | Statement::Enum(name, _, _, location)
+            | Statement::StructEnum(name, _, location)
+            | Statement::JavaEnum(name, _, _, location)
+            | Statement::TypeAlias(name, _, location)
+            | Statement::Function(name, _, _, _, location)
+            | Statement::Annotation(name, _, location) => Some((*name, location.clone())),
+            Statement::StructFunction(struct_name, name, _, _, _, location) => Some((Symbol::intern(&format!("{}.{}", struct_name, name)), location.clone())),
+            _ => None,
+        }
+    }
+    pub fn collect_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Generic(statement, type_parameters, _) => {
+                if let Statement::Function(name, _, _, _, _) = &**statement {
+                    self.generics.insert(*name, type_parameters.iter().map(|(name, _)| *name).collect());
+                }
+                self.collect_statement(statement)
+            }
+            Statement::Annotated(statement, annotations, _) => {
+                if let Some(annotation) = annotations.iter().find(|annotation| annotation.name == "deprecated") {
+                    if let Some((name, location)) = Checker::definition(statement) {
+                        let message: Option<String> = match annotation.arguments.first() {
+                            Some(Expression::String(message, _)) => Some(message.clone()),
+                            _ => None,
+                        };
+                        self.deprecated.insert(name, (message, location));
+                    }
+                }
+                self.collect_statement(statement);
+            }
+            Statement::External(statement, _) => self.collect_statement(statement),
+            Statement::Inline(statement, _) => self.collect_statement(statement),
+            Statement::Struct(name, fields, _) => {
+                self.structs.insert(*name, fields.clone());
+            }
+            Statement::Enum(name, enum_type, _, _) => {
+                self.enums.insert(*name, enum_type.clone());
+            }
+            Statement::StructEnum(name, values, _) => {
+                self.struct_enums.insert(*name, values.iter().map(|(variant_name, _)| *variant_name).collect());
+            }
+            Statement::JavaEnum(name, constructor, _, _) => {
+                self.java_enums.insert(*name, constructor.clone());
+            }
+            Statement::TypeAlias(name, types, _) => {
+                self.type_aliases.insert(*name, types.clone());
+            }
+            Statement::Annotation(name, fields, _) => {
+                self.annotations.insert(*name, fields.clone());
+            }
+            Statement::Function(name, args, return_type, _, _) => {
+                self.functions.insert(*name, (args.clone(), return_type.clone()));
+            }
+            Statement::StructFunction(struct_name, name, args, return_type, _, _) => {
+                self.functions.insert(Symbol::intern(&format!("{}.{}", struct_name, name)), (args.clone(), return_type.clone()));
+            }
+            Statement::Import(path, location) => {
+                self.imports.push((path.clone(), location.clone()));
+            }
+            _ => {}
+        }
+    }
+    pub fn check_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Generic(statement, _, _) => self.check_statement(statement),
+            Statement::Annotated(statement, annotations, _) => {
+                for annotation in annotations.iter() {
+                    self.check_annotation(annotation);
+                }
+                self.check_linkage(statement, annotations);
+                self.check_statement(statement);
+            }
+            Statement::Inline(statement, _) => self.check_statement(statement),
+            Statement::External(statement, _) => {
+                // The same extern may be declared by several modules
+                if let Statement::Variable(name, t, _, location) = &**statement {
+                    if self.lookup_binding(name).is_none() {
+                        self.declare(name, Binding { t: t.clone(), constant: false, initialized: true, used: true, assigned: false, location: location.clone() }, location);
+                    }
+                }
+            }
+            Statement::Enum(name, enum_type, variants, _) => {
+                let value_type: Type = self.resolve(enum_type);
+                let countable: bool = self.is_numeric(&value_type) || self.is_unknown(&value_type);
+                let mut seen: Vec<&Symbol> = vec![];
+                for (variant, value, location) in variants.iter() {
+                    if seen.contains(&variant) {
+                        self.errors.push(Diagnostic::type_error("E0013", format!("variant `{}` is defined more than once in `{}`", variant, name), location.clone()));
+                    }
+                    seen.push(variant);
+                    self.check_reserved(variant, location);
+                    if *value == Expression::Empty && !countable {
+                        self.errors.push(Diagnostic::type_error("E0014", format!("variant `{}` needs a value, only integer enums count up automatically", variant), location.clone()));
+                    }
+                    self.type_of(value);
+                }
+            }
+            Statement::StructEnum(name, variants, location) => {
+                let mut seen: Vec<&Symbol> = vec![];
+                for (variant, _) in variants.iter() {
+                    if seen.contains(&variant) {
+                        self.errors.push(Diagnostic::type_error("E0013", format!("variant `{}` is defined more than once in `{}`", variant, name), location.clone()));
+                    }
+                    seen.push(variant);
+                }
+            }
+            Statement::JavaEnum(name, _, values, location) => {
+                let mut seen: Vec<&Symbol> = vec![];
+                for (variant, _) in values.iter() {
+                    if seen.contains(&variant) {
+                        self.errors.push(Diagnostic::type_error("E0013", format!("variant `{}` is defined more than once in `{}`", variant, name), location.clone()));
+                    }
+                    seen.push(variant);
+                    self.check_reserved(variant, location);
+                }
+                for (_, arguments) in values.iter() {
+                    for argument in arguments.iter() {
+                        self.type_of(argument);
+                    }
+                }
+            }
+            Statement::Function(name, args, return_type, body, _) => {
+                // A `main` taking one argument gets them as an array
+                if let (true, [(_, t)]) = (name == "main", args.as_slice()) {
+                    if !self.startable(args, return_type) {
+                        self.errors.push(Diagnostic::type_error("E0032", "`main` takes its arguments as `argc` and `argv` or as one `string[]` or `cstring[]`".to_string(), t.location()));
+                    }
+                }
+                self.check_function(name, args, return_type, body)
+            }
+            Statement::StructFunction(struct_name, name, args, return_type, body, _) => {
+                self.check_function(&Symbol::intern(&format!("{}.{}", struct_name, name)), args, return_type, body)
+            }
+            Statement::Variable(name, t, value, location) | Statement::Constant(name, t, value, location) => {
+                self.check_type_use(t);
+                let value_type: Type = self.type_of(value);
+                self.coerce(t, value);
+                let t: Type = if self.is_unknown(t) { value_type } else { t.clone() };
+                let constant: bool = matches!(statement, Statement::Constant(_, _, _, _));
+                // Globals are zeroed by C, so only locals can be read uninitialized
+                let initialized: bool = *value != Expression::Empty || self.scopes.len() == 1 || !self.is_scalar(&self.resolve(&t));
+                let used: bool = self.scopes.len() == 1;
+                self.references.insert(location.clone(), (location.clone(), t.clone()));
+                self.check_reserved(name, location);
+                self.declare(name, Binding { t, constant, initialized, used, assigned: false, location: location.clone() }, location);
+            }
+            Statement::Return(value, _) => {
+                self.type_of(value);
+                if let Some((_, return_type)) = self.functions.get(&self.current_function).cloned() {
+                    self.coerce(&return_type, value);
+                }
+            }
+            Statement::While(condition, body, _) => {
+                self.type_of(condition);
+                // The body may not run at all, so nothing it assigns counts afterwards
+                let before: Vec<(usize, Symbol)> = self.uninitialized();
+                self.check_block(body);
+                self.set_uninitialized(&before);
+            }
+            Statement::For(name, iterable, body, location) => {
+                let t: Type = self.iterated(iterable, location);
+                self.references.insert(location.clone(), (location.clone(), t.clone()));
+                self.check_reserved(name, location);
+                let before: Vec<(usize, Symbol)> = self.uninitialized();
+                self.scopes.push(HashMap::new());
+                self.declare(name, Binding { t, constant: false, initialized: true, used: false, assigned: false, location: location.clone() }, location);
+                self.check_block(body);
+                self.pop_scope();
+                self.set_uninitialized(&before);
+            }
+            Statement::If(condition, body, else_body, _) => {
+                self.type_of(condition);
+                // A variable is only initialized after the if when every branch that
+                // falls through assigns it
+                let before: Vec<(usize, Symbol)> = self.uninitialized();
+                self.check_block(body);
+                let mut after: Vec<(usize, Symbol)> = if Checker::terminates(body) { vec![] } else { self.uninitialized() };
+                self.set_uninitialized(&before);
+                self.check_block(else_body);
+                if !Checker::terminates(else_body) {
+                    after.extend(self.uninitialized());
+                }
+                self.set_uninitialized(&after);
+            }
+            Statement::Expression(expression, _) => {
+                self.type_of(expression);
+            }
+            _ => {}
+        }
+    }
+    // The type of a `for` loop's variable: what a range's ends count in, or
+    // the items of an array
+    pub fn iterated(&mut self, iterable: &Expression, location: &TokenLocation) -> Type {
+        if let Expression::Range(from, to, range_location) = iterable {
+            let from_type: Type = self.type_of(from);
+            let to_type: Type = self.type_of(to);
+            for (end, t) in [(from, &from_type), (to, &to_type)] {
+                let resolved: Type = self.resolve(t);
+                if !self.is_unknown(&resolved) && !self.is_numeric(&resolved) {
+                    self.errors.push(Diagnostic::type_error("E0025", format!("a range counts through integers, not `{}`", t.name()), end.location()));
+                }
+            }
+            let t: Type = self.promote(&self.resolve(&from_type), &self.resolve(&to_type), range_location);
+            self.loops.insert(location.clone(), t.clone());
+            return t;
+        }
+        // A literal has no type of its own, so its first item gives it one
+        let t: Type = match iterable {
+            Expression::Array(elements, array_location) => {
+                let types: Vec<Type> = elements.iter().map(|element| self.type_of(element)).collect();
+                match types.into_iter().next() {
+                    Some(element) => {
+                        let t: Type = Type::DynamicArray(Box::new(element), array_location.clone());
+                        self.coerce(&t, iterable);
+                        t
+                    }
+                    None => self.unknown(array_location.clone()),
+                }
+            }
+            _ => self.type_of(iterable),
+        };
+        match self.resolve(&t) {
+            Type::DynamicArray(element, _) | Type::Array(element, _, _) => {
+                self.loops.insert(location.clone(), self.resolve(&t));
+                *element
+            }
+            resolved if self.is_unknown(&resolved) => {
+                self.errors.push(Diagnostic::type_error("E0025", "the type of this isn't known, so it can't be iterated over".to_string(), iterable.location()));
+                self.unknown(location.clone())
+            }
+            Type::Map(_, _, _) => {
+                self.errors.push(Diagnostic::type_error("E0025", format!("`{}` can't be iterated over itself, go over its `keys()` or `values()`", t.name()), iterable.location()));
+                self.unknown(location.clone())
+            }
+            _ => {
+                self.errors.push(Diagnostic::type_error("E0025", format!("`{}` can't be iterated over, a `for` loop goes over a range or an array", t.name()), iterable.location()));
+                self.unknown(location.clone())
+            }
+        }
+    }
+    // @static, @export, @entry, @test and @bench only mean something for plain
+    // functions. A struct function can be given a C name with @extern_name in
+    // place of its mangled one.
+    pub fn check_linkage(&mut self, statement: &Statement, annotations: &[Annotation]) {
+        let linkage: Vec<&Annotation> = annotations.iter().filter(|annotation| matches!(annotation.name.as_str(), "static" | "export" | "extern_name" | "entry" | "test" | "bench")).collect();
+        let Some(first) = linkage.first() else {
+            return;
+        };
+        let name: &Symbol = match statement {
+            Statement::Function(name, _, _, _, _) => name,
+            Statement::StructFunction(struct_name, name, _, _, _, _) => {
+                for annotation in linkage.iter().filter(|annotation| annotation.name != "extern_name") {
+                    self.errors.push(Diagnostic::type_error("E0020", format!("`@{}` can only be applied to functions outside of structs", annotation.name), annotation.location.clone()));
+                }
+                let extern_names: Vec<&&Annotation> = linkage.iter().filter(|annotation| annotation.name == "extern_name").collect();
+                if let Some(repeated) = extern_names.get(1) {
+                    self.errors.push(Diagnostic::type_error("E0029", "`@extern_name` is given more than once".to_string(), repeated.location.clone()));
+                }
+                if let Some(annotation) = extern_names.first() {
+                    self.check_extern_name(&Symbol::intern(&format!("{}.{}", struct_name, name)), annotation);
+                }
+                return;
+            }
+            _ => {
+                for annotation in linkage.iter() {
+                    self.errors.push(Diagnostic::type_error("E0020", format!("`@{}` can only be applied to functions", annotation.name), annotation.location.clone()));
+                }
+                return;
+            }
+        };
+        if let (true, Some(entry)) = (name == "main", linkage.iter().find(|annotation| annotation.name == "entry")) {
+            self.errors.push(Diagnostic::type_error("E0029", "`main` is already the entry point".to_string(), entry.location.clone()));
+            return;
+        }
+        if name == "main" {
+            self.errors.push(Diagnostic::type_error("E0029", "`main` always keeps its name and external linkage".to_string(), first.location.clone()));
+            return;
+        }
+        for (i, annotation) in linkage.iter().enumerate() {
+            if linkage[..i].iter().any(|other| other.name == annotation.name) {
+                self.errors.push(Diagnostic::type_error("E0029", format!("`@{}` is given more than once", annotation.name), annotation.location.clone()));
+            }
+        }
+        let static_linkage: bool = linkage.iter().any(|annotation| annotation.name == "static");
+        if let (true, Some(export)) = (static_linkage, linkage.iter().find(|annotation| annotation.name == "export")) {
+            self.errors.push(Diagnostic::type_error("E0029", format!("`{}` can't be both `@static` and `@export`", name), export.location.clone()));
+        }
+        if let Some(entry) = linkage.iter().find(|annotation| annotation.name == "entry") {
+            self.check_entry(statement, static_linkage, entry);
+        }
+        for harnessed in linkage.iter().filter(|annotation| annotation.name == "test" || annotation.name == "bench") {
+            let Statement::Function(_, args, return_type, _, _) = statement else {
+                continue;
+            };
+            if !args.is_empty() || !matches!(self.resolve(return_type), Type::Void(_)) {
+                let kind: &str = if harnessed.name == "test" { "test" } else { "benchmark" };
+                self.errors.push(Diagnostic::type_error("E0034", format!("the {} `{}` can't take arguments or return anything", kind, name), harnessed.location.clone()));
+            }
+        }
+        for annotation in linkage.iter().filter(|annotation| annotation.name == "extern_name") {
+            self.check_extern_name(name, annotation);
+        }
+    }
+    // No two functions may share a C name, and none may take a mangled one
+    pub fn check_extern_name(&mut self, name: &Symbol, annotation: &Annotation) {
+        let Some(Expression::String(symbol, _)) = annotation.arguments.first() else {
+            return;
+        };
+        self.check_reserved(symbol, &annotation.location);
+        if symbol != name && self.functions.contains_key(&Symbol::intern(symbol)) {
+            self.errors.push(Diagnostic::type_error("E0029", format!("`{}` is already the name of another function", symbol), annotation.location.clone()));
+        } else if let Some(other) = self.extern_names.insert(symbol.clone(), *name).filter(|other| other != name) {
+            self.errors.push(Diagnostic::type_error("E0029", format!("`{}` is already the C name of `{}`", symbol, other), annotation.location.clone()));
+        }
+    }
+    // There's one entry point. Unless it's freestanding, the program still
+    // starts in a generated `main`, which hands it the arguments if it wants them
+    pub fn check_entry(&mut self, statement: &Statement, static_linkage: bool, entry: &Annotation) {
+        let Statement::Function(name, args, return_type, _, _) = statement else {
+            return;
+        };
+        if let Some(other) = &self.entry {
+            self.errors.push(Diagnostic::type_error("E0029", format!("`{}` is already the entry point", other), entry.location.clone()));
+            return;
+        }
+        self.entry = Some(*name);
+        if static_linkage {
+            self.errors.push(Diagnostic::type_error("E0029", format!("`{}` can't be both `@static` and `@entry`", name), entry.location.clone()));
+        }
+        if self.freestanding {
+            return;
+        }
+        if self.functions.contains_key(&Symbol::intern("main")) {
+            self.errors.push(Diagnostic::type_error("E0029", format!("`main` is generated to call `{}`, so the program can't define its own", name), entry.location.clone()));
+        }
+        if !self.startable(args, return_type) {
+            self.errors.push(Diagnostic::type_error("E0032", "an entry point takes no arguments, `argc` and `argv`, or a `string[]`, and returns `int` or nothing".to_string(), entry.location.clone()));
+        }
+    }
+    // What a generated `main` can call: nothing, `argc` and `argv`, or the
+    // arguments as an array, returning an exit code or nothing
+    pub fn startable(&self, args: &[(Symbol, Type)], return_type: &Type) -> bool {
+        let arguments: bool = match args {
+            [] => true,
+            [(_, argc), (_, argv)] => matches!(self.resolve(argc), Type::Int(_)) && matches!(self.resolve(argv), Type::Pointer(_, _)),
+            [(_, array)] => match self.resolve(array) {
+                Type::DynamicArray(element, _) => matches!(self.resolve(&element), Type::String(_) | Type::CString(_)),
+                _ => false,
+            },
+            _ => false,
+        };
+        arguments && matches!(self.resolve(return_type), Type::Int(_) | Type::Void(_))
+    }
+    // Arguments are matched to the annotation's fields by position, then by name
+    pub fn check_annotation(&mut self, annotation: &Annotation) {
+        if annotation.name == "deprecated" {
+            if annotation.arguments.len() > 1 || !matches!(annotation.arguments.first(), None | Some(Expression::String(_, _))) {
+                self.errors.push(Diagnostic::type_error("E0012", "`@deprecated` takes an optional message string".to_string(), annotation.location.clone()));
+            }
+            return;
+        }
+        if matches!(annotation.name.as_str(), "static" | "export" | "entry" | "test" | "bench") {
+            if !annotation.arguments.is_empty() {
+                self.errors.push(Diagnostic::type_error("E0012", format!("`@{}` takes no arguments", annotation.name), annotation.location.clone()));
+            }
+            return;
+        }
+        if annotation.name == "extern_name" {
+            let valid: bool = match annotation.arguments.as_slice() {
+                [Expression::String(name, _)] => name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+                _ => false,
+            };
+            if !valid {
+                self.errors.push(Diagnostic::type_error("E0012", "`@extern_name` takes the C name as a string".to_string(), annotation.location.clone()));
+            }
+            return;
+        }
+        let fields: Vec<(Symbol, Type)> = match self.annotations.get(&annotation.name) {
+            Some(fields) => fields.clone(),
+            None => {
+                self.errors.push(Diagnostic::type_error("E0011", format!("unknown annotation `{}`", annotation.name), annotation.location.clone()));
+                return;
+            }
+        };
+        let mut given: Vec<&Symbol> = vec![];
+        let mut named: bool = false;
+        for (i, argument) in annotation.arguments.iter().enumerate() {
+            let (field, value): (Option<&(Symbol, Type)>, &Expression) = match argument {
+                Expression::NamedArgument(name, value, location) => {
+                    named = true;
+                    let field: Option<&(Symbol, Type)> = fields.iter().find(|(field_name, _)| field_name == name);
+                    if field.is_none() {
+                        self.errors.push(Diagnostic::type_error("E0012", format!("`@{}` has no field `{}`", annotation.name, name), location.clone()));
+                    }
+                    (field, value)
+                }
+                _ => {
+                    if named {
+                        self.errors.push(Diagnostic::type_error("E0012", "positional arguments must come before named ones".to_string(), argument.location()));
+                    } else if i >= fields.len() {
+                        self.errors.push(Diagnostic::type_error("E0012", format!("`@{}` takes {} arguments but {} were given", annotation.name, fields.len(), annotation.arguments.len()), argument.location()));
+                    }
+                    (if named { None } else { fields.get(i) }, argument)
+                }
+            };
+            let value_type: Type = self.type_of(value);
+            let (field_name, field_type): &(Symbol, Type) = match field {
+                Some(field) => field,
+                None => continue,
+            };
+            if given.contains(&field_name) {
+                self.errors.push(Diagnostic::type_error("E0012", format!("`{}` is given more than once", field_name), argument.location()));
+            }
+            given.push(field_name);
+            if !self.compatible(field_type, &value_type) {
+                self.errors.push(Diagnostic::type_error("E0012", format!("`{}` expects `{}`, found `{}`", field_name, field_type.name(), value_type.name()), value.location()));
+            }
+        }
+        for (field_name, _) in fields.iter() {
+            if !given.contains(&field_name) {
+                self.errors.push(Diagnostic::type_error("E0012", format!("`@{}` is missing `{}`", annotation.name, field_name), annotation.location.clone()));
+            }
+        }
+    }
+    pub fn check_function(&mut self, name: &Symbol, args: &[(Symbol, Type)], return_type: &Type, body: &[Statement]) {
+        self.current_function = *name;
+        self.check_type_use(return_type);
+        self.scopes.push(HashMap::new());
+        for (arg_name, arg_type) in args.iter() {
+            self.check_type_use(arg_type);
+            self.check_reserved(arg_name, &arg_type.location());
+            self.declare(arg_name, Binding { t: arg_type.clone(), constant: false, initialized: true, used: true, assigned: false, location: arg_type.location() }, &arg_type.location());
+        }
+        for statement in body.iter() {
+            self.check_statement(statement);
+        }
+        self.pop_scope();
+        self.current_function = Symbol::default();
+    }
+    pub fn check_block(&mut self, body: &[Statement]) {
+        self.scopes.push(HashMap::new());
+        for statement in body.iter() {
+            self.check_statement(statement);
+        }
+        self.pop_scope();
+    }
+    // A name may shadow one from an enclosing scope (with a warning under
+    // -Wshadow) but can't be declared twice in the same scope
+    pub fn declare(&mut self, name: &Symbol, binding: Binding, location: &TokenLocation) {
+        if let Some(first) = self.scopes.last().unwrap().get(name) {
+            self.errors.push(Diagnostic::type_error("E0010", format!("`{}` is already declared in this scope", name), location.clone())
+                .with_label(first.location.clone(), "declared here first".to_string()));
+            return;
+        }
+        if self.lookup_binding(name).is_some() {
+            self.lint(Lint::Shadow, format!("`{}` shadows a declaration from an outer scope", name), location.clone());
+        }
+        self.scopes.last_mut().unwrap().insert(*name, binding);
+    }
+    // Names starting with `__sl` are kept for the ones codegen makes up
+    pub fn check_reserved(&mut self, name: &str, location: &TokenLocation) {
+        if name.starts_with(RESERVED_PREFIX) {
+            self.errors.push(Diagnostic::type_error("E0033", format!("`{}` starts with `{}`, which is kept for generated names", name, RESERVED_PREFIX), location.clone()));
+        }
+    }
+    pub fn lint(&mut self, lint: Lint, message: String, location: TokenLocation) -> Option<&mut Diagnostic> {
+        self.lints.report(lint, message, location, &mut self.warnings, &mut self.errors)
+    }
+    pub fn pop_scope(&mut self) {
+        let scope: HashMap<Symbol, Binding> = self.scopes.pop().unwrap();
+        let mut unused: Vec<(&Symbol, &Binding)> = scope.iter().filter(|(name, binding)| !binding.used && !name.starts_with('_')).collect();
+        unused.sort_by_key(|(_, binding)| binding.location.start);
+        for (name, binding) in unused {
+            // Renaming is only a fix when nothing assigns it under the old name
+            if let (Some(diagnostic), false) = (self.lint(Lint::Unused, format!("`{}` is never used", name), binding.location.clone()), binding.assigned) {
+                diagnostic.suggestions.push(Suggestion { message: format!("rename it to `_{}`", name), location: binding.location.clone(), replacement: format!("_{}", name) });
+            }
+        }
+    }
+    // A call of a C library function the program doesn't know of, whose header
+    // isn't imported. The fix imports it ahead of the last import before the
+    // call, which is one of its own file's unless that file imports nothing, or
+    // else at the start of the program.
+    pub fn check_import(&mut self, name: &Symbol, location: &TokenLocation) {
+        let Some((header, _)) = C_LIBRARY.iter().find(|(_, functions)| functions.contains(&name.as_str())) else {
+            return;
+        };
+        let path: String = format!("std/{}", header);
+        if self.freestanding || self.imports.iter().any(|(import, _)| *import == path) {
+            return;
+        }
+        let start: usize = self.imports.iter().map(|(_, import)| import.start).filter(|start| *start < location.start).max().unwrap_or(0);
+        if let Some(diagnostic) = self.lint(Lint::MissingImport, format!("`{}` is declared in {}, which isn't imported", name, header), location.clone()) {
+            diagnostic.suggestions.push(Suggestion { message: format!("import `{}`", path), location: TokenLocation { start, end: start }, replacement: format!("import \"{}\"\n", path) });
+        }
+    }
+    // What the program has to be linked with for the headers it imports
+    pub fn link_flags(&self) -> Vec<String> {
+        let mut flags: Vec<String> = vec![];
+        for (header, flag) in LINKED_HEADERS.iter() {
+            let path: String = format!("std/{}", header);
+            if !self.freestanding && self.imports.iter().any(|(import, _)| *import == path) {
+                flags.push(flag.to_string());
+            }
+        }
+        flags
+    }
+    pub fn check_deprecated(&mut self, name: &Symbol, location: &TokenLocation) {
+        let (message, declared): (Option<String>, TokenLocation) = match self.deprecated.get(name) {
+            Some(deprecated) => deprecated.clone(),
+            None => return,
+        };
+        if self.current_function == *name || self.current_function.starts_with(&format!("{}.", name)) {
+            return;
+        }
+        let message: String = match message {
+            Some(message) => format!("`{}` is deprecated: {}", name, message),
+            None => format!("`{}` is deprecated", name),
+        };
+        // Only the first use points at the declaration as well
+        let labeled: bool = self.warnings.iter().chain(self.errors.iter()).any(|diagnostic| diagnostic.labels.iter().any(|label| label.location == declared));
+        if let (Some(diagnostic), false) = (self.lint(Lint::Deprecated, message, location.clone()), labeled) {
+            diagnostic.labels.push(Label { location: declared, message: format!("`{}` was marked deprecated here", name) });
+        }
+    }
+    // Reports deprecated structs named anywhere in a type
+    pub fn check_type_use(&mut self, t: &Type) {
+        match t {
+            Type::Unknown(name, location) | Type::Struct(name, location) => self.check_deprecated(name, location),
+            Type::Pointer(t, _) | Type::Array(t, _, _) | Type::DynamicArray(t, _) | Type::Const(t, _) | Type::Volatile(t, _) | Type::Restrict(t, _) => self.check_type_use(t),
+            Type::Function(args, return_type, _) => {
+                for arg in args.iter() {
+                    self.check_type_use(arg);
+                }
+                self.check_type_use(return_type);
+            }
+            Type::Map(key, value, _) => {
+                self.check_type_use(key);
+                self.check_type_use(value);
+            }
+            _ => {}
+        }
+    }
+    pub fn lookup_binding(&self, name: &Symbol) -> Option<&Binding> {
+        for scope in self.scopes.iter().rev() {
+            if let Some(binding) = scope.get(name) {
+                return Some(binding);
+            }
+        }
+        None
+    }
+    pub fn uninitialized(&self) -> Vec<(usize, Symbol)> {
+        let mut names: Vec<(usize, Symbol)> = vec![];
+        for (depth, scope) in self.scopes.iter().enumerate() {
+            for (name, binding) in scope.iter() {
+                if !binding.initialized {
+                    names.push((depth, *name));
+                }
+            }
+        }
+        names
+    }
+    pub fn set_uninitialized(&mut self, names: &[(usize, Symbol)]) {
+        for (depth, scope) in self.scopes.iter_mut().enumerate() {
+            for (name, binding) in scope.iter_mut() {
+                binding.initialized = !names.contains(&(depth, *name));
+            }
+        }
+    }
+    pub fn initialize(&mut self, name: &Symbol) {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(binding) = scope.get_mut(name) {
+                binding.initialized = true;
+                return;
+            }
+        }
+    }
+    pub fn mark_used(&mut self, name: &Symbol) {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(binding) = scope.get_mut(name) {
+                binding.used = true;
+                return;
+            }
+        }
+    }
+    pub fn terminates(body: &[Statement]) -> bool {
+        matches!(body.last(), Some(Statement::Return(_, _)) | Some(Statement::Break(_)) | Some(Statement::Continue(_)))
+    }
+    pub fn lookup(&self, name: &Symbol) -> Option<Type> {
+        self.lookup_binding(name).map(|binding| binding.t.clone())
+    }
+    pub fn type_of(&mut self, expression: &Expression) -> Type {
+        let location: TokenLocation = expression.location();
+        match expression {
+            Expression::Number(_, _) => Type::Int(location),
+            Expression::String(_, _) => Type::String(location),
+            Expression::Char(_, _) => Type::Char(location),
+            Expression::Boolean(_, _) => Type::Bool(location),
+            Expression::Null => Type::Pointer(Box::new(Type::Void(location.clone())), location),
+            Expression::Identifier(name, _) => {
+                if let Some(binding) = self.lookup_binding(name) {
+                    let t: Type = binding.t.clone();
+                    let declared: TokenLocation = binding.location.clone();
+                    if !binding.initialized {
+                        self.lint(Lint::Uninitialized, format!("`{}` is used before being initialized", name), location.clone());
+                        // Only report the first use
+                        self.initialize(name);
+                    }
+                    self.mark_used(name);
+                    self.references.insert(location.clone(), (declared, t.clone()));
+                    t
+                } else if let Some((args, return_type)) = self.functions.get(name).cloned() {
+                    self.check_deprecated(name, &location);
+                    Type::Function(args.iter().map(|(_, t)| t.clone()).collect(), Box::new(return_type), location)
+                } else {
+                    self.unknown(location)
+                }
+            }
+            Expression::Call(name, args, call_location) => {
+                let arg_types: Vec<Type> = args.iter().map(|arg| self.type_of(arg)).collect();
+                // The built-ins, unless the program has its own
+                if (name == "print" || name == "println") && self.lookup(name).is_none() && !self.functions.contains_key(name) {
+                    self.check_print(name, args, &arg_types, call_location);
+                    return Type::Void(location);
+                }
+                if name == "assert" && self.lookup(name).is_none() && !self.functions.contains_key(name) {
+                    if !matches!(args.as_slice(), [_] | [_, Expression::String(_, _)]) {
+                        self.errors.push(Diagnostic::type_error("E0035", "`assert` takes a condition and an optional message string".to_string(), location.clone()));
+                    }
+                    self.asserts.insert(call_location.clone());
+                    return Type::Void(location);
+                }
+                if let Some(fields) = self.structs.get(name).cloned() {
+                    self.coerce_arguments(&fields.iter().map(|(_, t)| t.clone()).collect::<Vec<Type>>(), args);
+                    self.check_deprecated(name, &location);
+                    Type::Pointer(Box::new(Type::Unknown(*name, location.clone())), location)
+                } else if let Some(t) = self.lookup(name) {
+                    self.mark_used(name);
+                    match self.resolve(&t) {
+                        Type::Function(_, return_type, _) => *return_type,
+                        _ => self.unknown(location),
+                    }
+                } else if let Some((params, return_type)) = self.functions.get(name).cloned() {
+                    self.coerce_arguments(&params.iter().map(|(_, t)| t.clone()).collect::<Vec<Type>>(), args);
+                    self.check_deprecated(name, &location);
+                    return_type
+                } else {
+                    self.check_import(name, &location);
+                    self.unknown(location)
+                }
+            }
+            Expression::GenericCall(name, types, args, _) => {
+                for arg in args.iter() {
+                    self.type_of(arg);
+                }
+                // The return type with the types given for the type parameters
+                match (self.functions.get(name), self.generics.get(name)) {
+                    (Some((_, return_type)), Some(type_parameters)) if type_parameters.len() == types.len() => {
+                        let mut substitution: Substitution = Substitution(type_parameters.iter().copied().zip(types.iter().cloned()).collect());
+                        let t: Type = substitution.fold_type(return_type.clone());
+                        // Such as `alloc[Point](4)`, which is only malloc
+                        if let Some(pointed) = self.pointed_struct_name(&t).filter(|pointed| types.iter().any(|given| matches!(self.resolve(given), Type::Unknown(name, _) if name == *pointed))) {
+                            self.uncounted.insert(pointed);
+                        }
+                        t
+                    }
+                    _ => self.unknown(location),
+                }
+            }
+            Expression::Member(base, member, location) => self.type_of_member(base, member, location),
+            Expression::NamedArgument(_, value, _) => self.type_of(value),
+            Expression::Cast(value, t, location) => {
+                let value_type: Type = self.type_of(value);
+                self.check_cast(&value_type, t, location);
+                self.convert_string(value, &value_type, t, location);
+                self.cast_pointer(value, &value_type, t);
+                t.clone()
+            }
+            Expression::UnsafeCast(value, t, location) => {
+                let value_type: Type = self.type_of(value);
+                self.check_unsafe_cast(&value_type, t, location);
+                self.cast_pointer(value, &value_type, t);
+                t.clone()
+            }
+            Expression::SizeOf(_, _) => Type::Usize(location),
+            Expression::Index(base, index, _) => {
+                let base_type: Type = self.type_of(base);
+                let index_type: Type = self.type_of(index);
+                match self.resolve(&base_type) {
+                    Type::DynamicArray(_, _) => {
+                        self.array_indexes.insert(location.clone());
+                    }
+                    Type::Map(key, value, map_location) => {
+                        if !self.compatible(&key, &index_type) {
+                            self.errors.push(Diagnostic::type_error("E0026", format!("`{}` is indexed by `{}`, not `{}`", base_type.name(), key.name(), index_type.name()), index.location()));
+                        }
+                        self.coerce(&key, index);
+                        self.maps.insert(location.clone(), Type::Map(key, value.clone(), map_location));
+                        return *value;
+                    }
+                    _ => {}
+                }
+                match self.pointee(&base_type, &location) {
+                    Some(t) => t,
+                    None => self.unknown(location),
+                }
+            }
+            Expression::Array(elements, _) => {
+                for element in elements.iter() {
+                    self.type_of(element);
+                }
+                self.unknown(location)
+            }
+            Expression::Map(entries, _) => {
+                // Without a type to go by, the first entry decides the map's type
+                let mut inferred: Option<(Type, Type)> = None;
+                for (key, value) in entries.iter() {
+                    let key_type: Type = self.type_of(key);
+                    let value_type: Type = self.type_of(value);
+                    match &inferred {
+                        Some((first_key, first_value)) => {
+                            if !self.compatible(first_key, &key_type) || !self.compatible(first_value, &value_type) {
+                                self.errors.push(Diagnostic::type_error("E0026", format!("expected an entry of `{}: {}`, found `{}: {}`", first_key.name(), first_value.name(), key_type.name(), value_type.name()), key.location()));
+                            }
+                        }
+                        None => inferred = Some((key_type, value_type)),
+                    }
+                }
+                match inferred {
+                    Some((key, value)) => {
+                        let t: Type = Type::Map(Box::new(key), Box::new(value), location);
+                        self.coerce_map(&t, expression);
+                        t
+                    }
+                    None => self.unknown(location),
+                }
+            }
+            Expression::New(name, args, _) => {
+                for arg in args.iter() {
+                    self.type_of(arg);
+                }
+                let constructor: Option<Type> = self.structs.get(name).and_then(|fields| fields.iter().find(|(field, _)| field == "constructor")).map(|(_, t)| t.clone());
+                if let Some(Type::Function(params, _, _)) = constructor {
+                    self.coerce_arguments(&params, args);
+                }
+                self.check_deprecated(name, &location);
+                Type::Pointer(Box::new(Type::Unknown(*name, location.clone())), location)
+            }
+            Expression::Ternary(condition, then, otherwise, _) => {
+                self.type_of(condition);
+                let t: Type = self.type_of(then);
+                self.type_of(otherwise);
+                t
+            }
+            Expression::Assignment(left, right, location) => {
+                self.type_of(right);
+                // Storing into a variable isn't a use of it
+                let t: Type = match &**left {
+                    Expression::Identifier(name, name_location) if self.lookup_binding(name).is_some() => {
+                        self.initialize(name);
+                        if let Some(binding) = self.scopes.iter_mut().rev().find_map(|scope| scope.get_mut(name)) {
+                            binding.assigned = true;
+                        }
+                        let binding: Binding = self.lookup_binding(name).unwrap().clone();
+                        self.references.insert(name_location.clone(), (binding.location, binding.t.clone()));
+                        binding.t
+                    }
+                    _ => self.type_of(left),
+                };
+                self.check_assignable(left, &t, location);
+                self.coerce(&t, right);
+                t
+            }
+            Expression::Binary(op, left, right, location) => self.check_binary(op, left, right, location),
+            Expression::Unary(op, value, location) => self.check_unary(op, value, location),
+            Expression::Grouping(value, _) => self.type_of(value),
+            Expression::AddressOf(value, _) => {
+                // Taking the address hands the variable to code that may fill it in
+                if let Expression::Identifier(name, _) = &**value {
+                    self.initialize(name);
+                }
+                let t: Type = Type::Pointer(Box::new(self.type_of(value)), location);
+                if let Some(name) = self.pointed_struct_name(&t) {
+                    self.uncounted.insert(name);
+                }
+                t
+            }
+            Expression::Dereference(value, _) => {
+                let t: Type = self.type_of(value);
+                match self.pointee(&t, &location) {
+                    Some(t) => t,
+                    None => {
+                        if !self.is_unknown(&self.resolve(&t)) {
+                            self.errors.push(Diagnostic::type_error("E0015", format!("cannot dereference a value of type `{}`", t.name()), location.clone()));
+                        }
+                        self.unknown(location)
+                    }
+                }
+            }
+            Expression::Range(from, to, _) => {
+                self.type_of(from);
+                self.type_of(to);
+                // C has nothing a range could become outside of a loop header
+                self.errors.push(Diagnostic::type_error("E0025", "a range can only be iterated over by a `for` loop".to_string(), location.clone()));
+                self.unknown(location)
+            }
+            Expression::Type(_, _) | Expression::Error(_) | Expression::Empty => self.unknown(location),
+        }
+    }
+    pub fn type_of_member(&mut self, base: &Expression, member: &Expression, location: &TokenLocation) -> Type {
+        if let Expression::Identifier(name, _) = base {
+            if self.lookup(name).is_none() {
+                if let Some(enum_type) = self.enums.get(name).cloned() {
+                    return match (member, self.resolve(&enum_type)) {
+                        (Expression::Identifier(_, _), _) => enum_type,
+                        (Expression::Call(_, args, _), Type::Function(_, return_type, _)) => {
+                            for arg in args.iter() {
+                                self.type_of(arg);
+                            }
+                            *return_type
+                        }
+                        _ => self.unknown(location.clone()),
+                    };
+                }
+                if self.java_enums.contains_key(name) || self.struct_enums.contains_key(name) {
+                    return Type::Unknown(*name, location.clone());
+                }
+                if let (true, Expression::Call(function, args, _)) = (self.structs.contains_key(name), member) {
+                    return self.type_of_static_call(name, function, args, location);
+                }
+            }
+        }
+        let base_type: Type = self.type_of(base);
+        let resolved: Type = self.resolve(&base_type);
+        // Codegen picks between `.` and `->` based on this
+        self.member_types.insert(location.clone(), resolved.clone());
+        let mut arg_types: Vec<Type> = vec![];
+        let (field, is_call): (&Symbol, bool) = match member {
+            Expression::Identifier(field, _) => (field, false),
+            Expression::Call(field, args, _) => {
+                for arg in args.iter() {
+                    arg_types.push(self.type_of(arg));
+                }
+                (field, true)
+            }
+            _ => return self.unknown(location.clone()),
+        };
+        if let Type::DynamicArray(element, _) = &resolved {
+            return self.type_of_array_method(&base_type, element, field, is_call, &arg_types, location);
+        }
+        if let Type::Map(key, value, _) = &resolved {
+            return self.type_of_map_method(&base_type, (key, value), member, &arg_types, location);
+        }
+        let target: Type = match &resolved {
+            Type::Pointer(t, _) => self.resolve(t),
+            t => t.clone(),
+        };
+        let struct_name: Symbol = match &target {
+            Type::Unknown(name, _) => *name,
+            _ => Symbol::default(),
+        };
+        if let (Type::String(_), false) = (&target, is_call) {
+            match field.as_str() {
+                "length" => return Type::Usize(location.clone()),
+                "data" => return Type::Const(Box::new(Type::Char(location.clone())), location.clone()),
+                _ => {}
+            }
+        }
+        let fields: Vec<(Symbol, Type)> = if let Some(fields) = self.structs.get(&struct_name) {
+            fields.clone()
+        } else if let Some(fields) = self.java_enums.get(&struct_name) {
+            fields.clone()
+        } else {
+            if !self.is_unknown(&target) && !self.struct_enums.contains_key(&struct_name) {
+                self.errors.push(Diagnostic::type_error("E0016", format!("cannot access `{}` on a value of type `{}`", field, base_type.name()), location.clone()));
+            }
+            return self.unknown(location.clone());
+        };
+        if let (true, Expression::Call(_, args, _)) = (is_call, member) {
+            let name: Symbol = Symbol::intern(&format!("{}.{}", struct_name, field));
+            if let Some((params, return_type)) = self.functions.get(&name).cloned() {
+                self.check_method_call(&name, &params, args, location);
+                return return_type;
+            }
+        }
+        for (field_name, field_type) in fields.iter() {
+            if field_name != field {
+                continue;
+            }
+            if !is_call {
+                return field_type.clone();
+            }
+            return match self.resolve(field_type) {
+                Type::Function(_, return_type, _) => *return_type,
+                _ => self.unknown(location.clone()),
+            };
+        }
+        let mut error: Diagnostic = Diagnostic::type_error("E0016", format!("`{}` has no field `{}`", struct_name, field), location.clone());
+        if let Some(similar) = closest(field, fields.iter().map(|(name, _)| name.as_str())) {
+            error = error.with_note(format!("`{}` has a field named `{}`", struct_name, similar));
+        }
+        self.errors.push(error);
+        self.unknown(location.clone())
+    }
+    // `print` and `println` take a format string literal with a `{}` for each
+    // value after it, which has to be one printf can print
+    pub fn check_print(&mut self, name: &Symbol, args: &[Expression], arg_types: &[Type], location: &TokenLocation) {
+        let (format, format_location): (&String, &TokenLocation) = match args.first() {
+            Some(Expression::String(format, format_location)) => (format, format_location),
+            _ => {
+                self.errors.push(Diagnostic::type_error("E0038", format!("`{}` takes a format string literal, then a value for each `{{}}` in it", name), location.clone()));
+                return;
+            }
+        };
+        let pieces: Vec<String> = match format_pieces(format) {
+            Ok(pieces) => pieces,
+            Err(message) => {
+                self.errors.push(Diagnostic::type_error("E0038", message, format_location.clone()));
+                return;
+            }
+        };
+        if pieces.len() != args.len() {
+            self.errors.push(Diagnostic::type_error("E0038", format!("the format has {} `{{}}` but {} value(s) were given", pieces.len() - 1, args.len() - 1), location.clone()));
+            return;
+        }
+        let mut conversions: Vec<PrintConversion> = vec![];
+        for (arg, t) in args.iter().zip(arg_types.iter()).skip(1) {
+            match self.print_conversion(t) {
+                Some(conversion) => conversions.push(conversion),
+                None if self.is_unknown(t) => self.errors.push(Diagnostic::type_error("E0038", "the type of this value isn't known, so it can't be printed".to_string(), arg.location())
+                    .with_note("give it one with `as`, as in `strlen(s) as usize`".to_string())),
+                None => self.errors.push(Diagnostic::type_error("E0038", format!("a value of type `{}` can't be printed", t.name()), arg.location())),
+            }
+        }
+        if conversions.len() == args.len() - 1 {
+            self.prints.insert(location.clone(), conversions);
+        }
+    }
+    pub fn print_conversion(&self, t: &Type) -> Option<PrintConversion> {
+        match t {
+            Type::Const(inner, _) | Type::Volatile(inner, _) => return self.print_conversion(inner),
+            // External types are left to C, but printf knows its floating point ones
+            Type::Unknown(name, _) if (name == "double" || name == "float") && self.type_aliases.get(name).is_some_and(|types| types.is_empty()) => return Some(PrintConversion::Double),
+            _ => {}
+        }
+        match self.resolve(t) {
+            Type::Int(_) => Some(PrintConversion::Int),
+            Type::Usize(_) => Some(PrintConversion::Usize),
+            Type::Char(_) => Some(PrintConversion::Char),
+            Type::Bool(_) => Some(PrintConversion::Bool),
+            Type::CString(_) => Some(PrintConversion::CString),
+            Type::String(_) => Some(PrintConversion::String),
+            Type::Pointer(inner, _) if matches!(self.resolve(&inner), Type::Char(_)) => Some(PrintConversion::CString),
+            Type::Pointer(_, _) => Some(PrintConversion::Pointer),
+            _ => None,
+        }
+    }
+    // A struct function called on a value, which is passed as its `self`
+    pub fn check_method_call(&mut self, name: &Symbol, params: &[(Symbol, Type)], args: &[Expression], location: &TokenLocation) {
+        self.check_deprecated(name, location);
+        let self_type: Type = match params.first() {
+            Some((_, t)) => t.clone(),
+            None => {
+                self.errors.push(Diagnostic::type_error("E0016", format!("`{}` has no `self` parameter, call it as `{}(...)`", name, name), location.clone()));
+                return;
+            }
+        };
+        if args.len() != params.len() - 1 {
+            self.errors.push(Diagnostic::type_error("E0016", format!("`{}` takes {} argument(s) besides `self` but {} were given", name, params.len() - 1, args.len()), location.clone()));
+        }
+        self.coerce_arguments(&params.iter().map(|(_, t)| t.clone()).collect::<Vec<Type>>(), args);
+        self.methods.insert(location.clone(), (*name, self_type));
+    }
+    // `Struct.function(args)` calls a struct function like any other function
+    pub fn type_of_static_call(&mut self, struct_name: &str, function: &str, args: &[Expression], location: &TokenLocation) -> Type {
+        for arg in args.iter() {
+            self.type_of(arg);
+        }
+        let name: Symbol = Symbol::intern(&format!("{}.{}", struct_name, function));
+        match self.functions.get(&name).cloned() {
+            Some((params, return_type)) => {
+                self.check_deprecated(&name, location);
+                if args.len() != params.len() {
+                    self.errors.push(Diagnostic::type_error("E0016", format!("`{}` takes {} argument(s) but {} were given", name, params.len(), args.len()), location.clone()));
+                }
+                self.coerce_arguments(&params.iter().map(|(_, t)| t.clone()).collect::<Vec<Type>>(), args);
+                return_type
+            }
+            None => {
+                self.errors.push(Diagnostic::type_error("E0016", format!("`{}` has no function `{}`", struct_name, function), location.clone()));
+                self.unknown(location.clone())
+            }
+        }
+    }
+    // `T[]` has `push(value)`, `pop()`, and `len()`, which the array runtime implements
+    pub fn type_of_array_method(&mut self, base_type: &Type, element: &Type, method: &str, is_call: bool, arg_types: &[Type], location: &TokenLocation) -> Type {
+        let (arity, t): (usize, Type) = match method {
+            "push" => (1, Type::Void(location.clone())),
+            "pop" => (0, element.clone()),
+            "len" => (0, Type::Usize(location.clone())),
+            _ => {
+                self.errors.push(Diagnostic::type_error("E0016", format!("`{}` has no method `{}`", base_type.name(), method), location.clone()));
+                return self.unknown(location.clone());
+            }
+        };
+        if !is_call {
+            self.errors.push(Diagnostic::type_error("E0016", format!("`{}` is a method of `{}` and has to be called", method, base_type.name()), location.clone()));
+        } else if arg_types.len() != arity {
+            self.errors.push(Diagnostic::type_error("E0016", format!("`{}` takes {} argument(s) but {} were given", method, arity, arg_types.len()), location.clone()));
+        } else if let Some(value_type) = arg_types.first() {
+            if !self.compatible(element, value_type) {
+                self.errors.push(Diagnostic::type_error("E0016", format!("cannot push `{}` onto `{}`", value_type.name(), base_type.name()), location.clone()));
+            }
+        }
+        t
+    }
+    // `map[K, V]` has `len()`, `contains(key)`, `remove(key)`, and `keys()` and
+    // `values()`, which copy out a `T[]` to iterate over. A map has no order of
+    // its own, so that's how a `for` loop goes over one
+    pub fn type_of_map_method(&mut self, base_type: &Type, (key, value): (&Type, &Type), member: &Expression, arg_types: &[Type], location: &TokenLocation) -> Type {
+        let (method, args): (&Symbol, &Vec<Expression>) = match member {
+            Expression::Call(method, args, _) => (method, args),
+            Expression::Identifier(method, _) => {
+                self.errors.push(Diagnostic::type_error("E0016", format!("`{}` has no field `{}`", base_type.name(), method), location.clone()));
+                return self.unknown(location.clone());
+            }
+            _ => return self.unknown(location.clone()),
+        };
+        let (takes_key, t): (bool, Type) = match method.as_str() {
+            "len" => (false, Type::Usize(location.clone())),
+            "contains" | "remove" => (true, Type::Bool(location.clone())),
+            "keys" => (false, Type::DynamicArray(Box::new(key.clone()), location.clone())),
+            "values" => (false, Type::DynamicArray(Box::new(value.clone()), location.clone())),
+            _ => {
+                self.errors.push(Diagnostic::type_error("E0016", format!("`{}` has no method `{}`", base_type.name(), method), location.clone()));
+                return self.unknown(location.clone());
+            }
+        };
+        let arity: usize = if takes_key { 1 } else { 0 };
+        if arg_types.len() != arity {
+            self.errors.push(Diagnostic::type_error("E0016", format!("`{}` takes {} argument(s) but {} were given", method, arity, arg_types.len()), location.clone()));
+        } else if let (Some(arg_type), Some(arg)) = (arg_types.first(), args.first()) {
+            if !self.compatible(key, arg_type) {
+                self.errors.push(Diagnostic::type_error("E0026", format!("`{}` is indexed by `{}`, not `{}`", base_type.name(), key.name(), arg_type.name()), arg.location()));
+            }
+            self.coerce(key, arg);
+        }
+        t
+    }
+    // Arithmetic follows C's usual conversions restricted to the types the language
+    // has: char and int (and enum values) promote to int, and anything combined with
+    // usize becomes usize. Pointers and cstrings support `ptr +/- integer` and
+    // `ptr - ptr`; string, bool, and struct values take part in no arithmetic at all.
+    pub fn check_binary(&mut self, op: &TokenKind, left: &Expression, right: &Expression, location: &TokenLocation) -> Type {
+        let left_type: Type = self.type_of(left);
+        let right_type: Type = self.type_of(right);
+        let left_resolved: Type = self.resolve(&left_type);
+        let right_resolved: Type = self.resolve(&right_type);
+        let is_comparison: bool = matches!(op, TokenKind::EqualEqual | TokenKind::BangEqual | TokenKind::Less | TokenKind::LessEqual | TokenKind::Greater | TokenKind::GreaterEqual);
+        if self.is_unknown(&left_resolved) || self.is_unknown(&right_resolved) {
+            return if is_comparison { Type::Bool(location.clone()) } else { self.unknown(location.clone()) };
+        }
+        let both_numeric: bool = self.is_numeric(&left_resolved) && self.is_numeric(&right_resolved);
+        let result: Option<Type> = match op {
+            TokenKind::Plus => {
+                if both_numeric {
+                    Some(self.promote(&left_resolved, &right_resolved, location))
+                } else if self.is_pointer(&left_resolved) && self.is_numeric(&right_resolved) {
+                    Some(left_type.clone())
+                } else if self.is_numeric(&left_resolved) && self.is_pointer(&right_resolved) {
+                    Some(right_type.clone())
+                } else {
+                    None
+                }
+            }
+            TokenKind::Minus => {
+                if both_numeric {
+                    Some(self.promote(&left_resolved, &right_resolved, location))
+                } else if self.is_pointer(&left_resolved) && self.is_numeric(&right_resolved) {
+                    Some(left_type.clone())
+                } else if self.is_pointer(&left_resolved) && self.is_pointer(&right_resolved) {
+                    Some(Type::Int(location.clone()))
+                } else {
+                    None
+                }
+            }
+            TokenKind::Star | TokenKind::Slash | TokenKind::Percent => {
+                if both_numeric {
+                    Some(self.promote(&left_resolved, &right_resolved, location))
+                } else {
+                    None
+                }
+            }
+            TokenKind::Less | TokenKind::LessEqual | TokenKind::Greater | TokenKind::GreaterEqual => {
+                let both_pointers: bool = matches!(left_resolved, Type::Pointer(_, _)) && matches!(right_resolved, Type::Pointer(_, _));
+                if both_numeric || both_pointers {
+                    Some(Type::Bool(location.clone()))
+                } else {
+                    None
+                }
+            }
+            TokenKind::EqualEqual | TokenKind::BangEqual => {
+                let both_bool: bool = matches!(left_resolved, Type::Bool(_)) && matches!(right_resolved, Type::Bool(_));
+                let both_pointers: bool = self.is_pointer_like_value(left, &left_resolved) && self.is_pointer_like_value(right, &right_resolved);
+                let both_strings: bool = matches!(left_resolved, Type::String(_)) && matches!(right_resolved, Type::String(_));
+                if both_strings && !both_pointers {
+                    let conversion: StringConversion = if *op == TokenKind::EqualEqual { StringConversion::Equals } else { StringConversion::NotEquals };
+                    self.strings.insert(location.clone(), conversion);
+                    self.coerce(&left_resolved, left);
+                    self.coerce(&right_resolved, right);
+                    Some(Type::Bool(location.clone()))
+                } else if both_numeric || both_bool || both_pointers {
+                    Some(Type::Bool(location.clone()))
+                } else {
+                    None
+                }
+            }
+            _ => Some(self.unknown(location.clone())),
+        };
+        match result {
+            Some(t) => t,
+            None => {
+                self.errors.push(Diagnostic::type_error("E0017", format!("cannot apply `{}` to `{}` and `{}`", Checker::operator(op), left_type.name(), right_type.name()), location.clone()));
+                self.unknown(location.clone())
+            }
+        }
+    }
+    pub fn check_unary(&mut self, op: &TokenKind, value: &Expression, location: &TokenLocation) -> Type {
+        let t: Type = self.type_of(value);
+        let resolved: Type = self.resolve(&t);
+        if self.is_unknown(&resolved) {
+            return self.unknown(location.clone());
+        }
+        match op {
+            TokenKind::Minus if self.is_numeric(&resolved) => self.promote(&resolved, &resolved, location),
+            TokenKind::Bang if matches!(resolved, Type::Bool(_)) => Type::Bool(location.clone()),
+            _ => {
+                self.errors.push(Diagnostic::type_error("E0017", format!("cannot apply unary `{}` to `{}`", Checker::operator(op), t.name()), location.clone()));
+                self.unknown(location.clone())
+            }
+        }
+    }
+    // Constants can never be reassigned. Otherwise a `const` type only protects
+    // the value itself when it isn't a pointer: like the emitted C, `const T*`
+    // is a mutable pointer to const data, which `pointee` takes care of.
+    pub fn check_assignable(&mut self, target: &Expression, t: &Type, location: &TokenLocation) {
+        if let Expression::Identifier(name, _) = target {
+            if let Some(binding) = self.lookup_binding(name) {
+                if binding.constant {
+                    self.errors.push(Diagnostic::type_error("E0018", format!("cannot assign to constant `{}`", name), location.clone()));
+                    return;
+                }
+            }
+        }
+        if self.is_const(t) && !self.is_pointer_like(&self.resolve(t)) {
+            let message: String = match target {
+                Expression::Identifier(name, _) => format!("cannot assign to `{}` of type `{}`", name, t.name()),
+                _ => format!("cannot assign to a value of type `{}`", t.name()),
+            };
+            self.errors.push(Diagnostic::type_error("E0018", message, location.clone()));
+        }
+    }
+    // The type reached by indexing or dereferencing `t`, if it can be
+    pub fn pointee(&self, t: &Type, location: &TokenLocation) -> Option<Type> {
+        let element: Type = match self.resolve(t) {
+            Type::Pointer(t, _) | Type::Array(t, _, _) | Type::DynamicArray(t, _) => *t,
+            Type::CString(_) => Type::Char(location.clone()),
+            Type::String(_) => return Some(Type::Const(Box::new(Type::Char(location.clone())), location.clone())),
+            _ => return None,
+        };
+        if self.is_const(t) {
+            Some(Type::Const(Box::new(element), location.clone()))
+        } else {
+            Some(element)
+        }
+    }
+    pub fn is_const(&self, t: &Type) -> bool {
+        match t {
+            Type::Const(_, _) => true,
+            Type::Volatile(t, _) | Type::Restrict(t, _) => self.is_const(t),
+            _ => false,
+        }
+    }
+    // `as` converts between numbers (widening or narrowing), between pointer
+    // types, and between a pointer and usize. Struct values never convert;
+    // `unsafe_cast[T](x)` emits the same C cast without any of these checks
+    // but that one.
+    pub fn check_cast(&mut self, value_type: &Type, t: &Type, location: &TokenLocation) {
+        let from: Type = self.resolve(value_type);
+        let to: Type = self.resolve(t);
+        if self.is_unknown(&from) || self.is_unknown(&to) || matches!(to, Type::Void(_)) {
+            return;
+        }
+        let is_scalar = |checker: &Checker, t: &Type| checker.is_numeric(t) || matches!(t, Type::Bool(_));
+        let message: Option<String> = if self.is_struct_value(&from) || self.is_struct_value(&to) {
+            if from.name() == to.name() {
+                None
+            } else {
+                Some(Checker::struct_cast_help(t))
+            }
+        } else if (is_scalar(self, &from) && is_scalar(self, &to)) || (self.is_pointer_like(&from) && self.is_pointer_like(&to)) {
+            None
+        } else if matches!(from, Type::String(_)) || matches!(to, Type::String(_)) {
+            // The runtime converts between `string` and C strings
+            let is_text = |checker: &Checker, t: &Type| matches!(t, Type::String(_)) || checker.is_pointer_like(t);
+            if is_text(self, &from) && is_text(self, &to) {
+                None
+            } else {
+                Some(String::new())
+            }
+        } else if (self.is_pointer_like(&from) && self.is_numeric(&to)) || (self.is_numeric(&from) && self.is_pointer_like(&to)) {
+            if matches!(from, Type::Usize(_)) || matches!(to, Type::Usize(_)) {
+                None
+            } else {
+                Some("only `usize` can hold a pointer".to_string())
+            }
+        } else {
+            Some(String::new())
+        };
+        if let Some(message) = message {
+            let mut error: String = format!("cannot cast `{}` to `{}`", value_type.name(), t.name());
+            if !message.is_empty() {
+                error.push_str(&format!(" ({})", message));
+            }
+            self.errors.push(Diagnostic::type_error("E0019", error, location.clone()));
+        }
+    }
+    // C only casts to and from scalars, so not even `unsafe_cast` can turn a
+    // struct value into anything but itself
+    pub fn check_unsafe_cast(&mut self, value_type: &Type, t: &Type, location: &TokenLocation) {
+        let from: Type = self.resolve(value_type);
+        let to: Type = self.resolve(t);
+        if (self.is_struct_value(&from) || self.is_struct_value(&to)) && from.name() != to.name() {
+            let error: String = format!("cannot cast `{}` to `{}` ({})", value_type.name(), t.name(), Checker::struct_cast_help(t));
+            self.errors.push(Diagnostic::type_error("E0019", error, location.clone()));
+        }
+    }
+    // The struct a pointer type points to
+    pub fn pointed_struct_name(&self, t: &Type) -> Option<Symbol> {
+        match self.resolve(t) {
+            Type::Pointer(t, _) => match self.resolve(&t) {
+                Type::Unknown(name, _) if self.structs.contains_key(&name) => Some(name),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+    // A pointer to a struct made from anything but another one. A constructor
+    // allocating its own object with `malloc` is still where `new` gets it.
+    pub fn cast_pointer(&mut self, value: &Expression, value_type: &Type, t: &Type) {
+        let Some(name) = self.pointed_struct_name(t) else {
+            return;
+        };
+        let allocation: bool = matches!(value, Expression::Call(function, _, _) if function == "malloc" || function == "calloc") && *self.current_function == format!("{}.constructor", name);
+        if !allocation && !matches!(value, Expression::Null) && self.pointed_struct_name(value_type) != Some(name) {
+            self.uncounted.insert(name);
+        }
+    }
+    pub fn struct_cast_help(t: &Type) -> String {
+        format!("struct values cannot be converted, reinterpret one through a pointer with `*unsafe_cast[{}*](&value)`", t.name())
+    }
+    pub fn promote(&self, left: &Type, right: &Type, location: &TokenLocation) -> Type {
+        if matches!(left, Type::Usize(_)) || matches!(right, Type::Usize(_)) {
+            Type::Usize(location.clone())
+        } else {
+            Type::Int(location.clone())
+        }
+    }
+    pub fn operator(op: &TokenKind) -> &'static str {
+        match op {
+            TokenKind::Plus => "+",
+            TokenKind::Minus => "-",
+            TokenKind::Star => "*",
+            TokenKind::Slash => "/",
+            TokenKind::Percent => "%",
+            TokenKind::Bang => "!",
+            TokenKind::EqualEqual => "==",
+            TokenKind::BangEqual => "!=",
+            TokenKind::Less => "<",
+            TokenKind::LessEqual => "<=",
+            TokenKind::Greater => ">",
+            TokenKind::GreaterEqual => ">=",
+            _ => "?",
+        }
+    }
+    // Strips qualifiers and single-type aliases; names that aren't a known
+    // struct or enum (C types, generic parameters) resolve to the unknown type
+    pub fn resolve(&self, t: &Type) -> Type {
+        let mut t: Type = t.clone();
+        for _ in 0..32 {
+            t = match t {
+                Type::Const(t, _) | Type::Volatile(t, _) | Type::Restrict(t, _) => *t,
+                Type::Unknown(name, location) => {
+                    match self.type_aliases.get(&name) {
+                        Some(types) if types.len() == 1 => types[0].clone(),
+                        // An external type is whatever C says it is
+                        Some(types) if types.is_empty() => return self.unknown(location),
+                        Some(_) => return Type::Unknown(name, location),
+                        None => {
+                            if self.structs.contains_key(&name) || self.enums.contains_key(&name) || self.struct_enums.contains_key(&name) || self.java_enums.contains_key(&name) {
+                                return Type::Unknown(name, location);
+                            }
+                            return self.unknown(location);
+                        }
+                    }
+                }
+                Type::GenericType(_, location) | Type::Error(_, location) => return self.unknown(location),
+                t => return t,
+            };
+        }
+        self.unknown(t.location())
+    }
+    pub fn unknown(&self, location: TokenLocation) -> Type {
+        Type::Unknown(Symbol::default(), location)
+    }
+    pub fn is_unknown(&self, t: &Type) -> bool {
+        match t {
+            Type::Unknown(name, _) => name.is_empty(),
+            Type::Error(_, _) => true,
+            _ => false,
+        }
+    }
+    pub fn is_struct_value(&self, t: &Type) -> bool {
+        match t {
+            Type::Unknown(name, _) => self.structs.contains_key(name) || self.struct_enums.contains_key(name) || self.java_enums.contains_key(name),
+            _ => false,
+        }
+    }
+    // Loose agreement between a declared type and a value, in the same families
+    // the operator checks use
+    pub fn compatible(&self, expected: &Type, actual: &Type) -> bool {
+        let expected: Type = self.resolve(expected);
+        let actual: Type = self.resolve(actual);
+        if self.is_unknown(&expected) || self.is_unknown(&actual) {
+            return true;
+        }
+        (self.is_numeric(&expected) && self.is_numeric(&actual))
+            || (matches!(expected, Type::Bool(_)) && matches!(actual, Type::Bool(_)))
+            || (self.is_pointer_like(&expected) && self.is_pointer_like(&actual))
+            || (matches!(expected, Type::String(_)) && matches!(actual, Type::String(_)))
+            || (self.is_pointer_like(&expected) && matches!(actual, Type::String(_)))
+            || (self.is_struct_value(&expected) && expected.name() == actual.name())
+            || match (&expected, &actual) {
+                (Type::Map(expected_key, expected_value, _), Type::Map(key, value, _)) => self.compatible(expected_key, key) && self.compatible(expected_value, value),
+                _ => false,
+            }
+    }
+    // Values C leaves as garbage when declared without an initializer
+    pub fn is_scalar(&self, t: &Type) -> bool {
+        self.is_numeric(t) || matches!(t, Type::Bool(_) | Type::Pointer(_, _) | Type::CString(_) | Type::String(_) | Type::Function(_, _, _))
+    }
+    pub fn is_numeric(&self, t: &Type) -> bool {
+        match t {
+            Type::Int(_) | Type::Usize(_) | Type::Char(_) => true,
+            Type::Unknown(name, _) => self.enums.contains_key(name),
+            _ => false,
+        }
+    }
+    // Types that are a C pointer underneath and so support pointer arithmetic
+    pub fn is_pointer(&self, t: &Type) -> bool {
+        matches!(t, Type::Pointer(_, _) | Type::CString(_) | Type::DynamicArray(_, _) | Type::Array(_, _, _))
+    }
+    pub fn is_pointer_like(&self, t: &Type) -> bool {
+        self.is_pointer(t) || matches!(t, Type::Function(_, _, _))
+    }
+    // A string literal is still a plain C string unless it's stored into a `string`
+    pub fn is_pointer_like_value(&self, value: &Expression, t: &Type) -> bool {
+        matches!(value, Expression::String(_, _)) || self.is_pointer_like(t)
+    }
+    // Turns a literal into a runtime string when it's stored somewhere of type
+    // `string`, and checks an integer literal fits where it's stored
+    pub fn coerce(&mut self, expected: &Type, value: &Expression) {
+        let literal: Option<i128> = match value {
+            Expression::Number(value, _) => Some(*value as i128),
+            Expression::Unary(TokenKind::Minus, value, _) => match **value {
+                Expression::Number(value, _) => Some(-(value as i128)),
+                _ => None,
+            },
+            _ => None,
+        };
+        let resolved: Type = self.resolve(expected);
+        if let (Some(literal), Some((min, max))) = (literal, self.machine.range(&resolved)) {
+            if literal < min || literal > max {
+                self.errors.push(Diagnostic::type_error("E0030", format!("`{}` doesn't fit in `{}` on {} (from {} to {})", literal, resolved.name(), self.machine.name, min, max), value.location()));
+            }
+        }
+        match (resolved, value) {
+            (Type::String(_), Expression::String(_, location)) => {
+                self.strings.insert(location.clone(), StringConversion::Literal);
+            }
+            (Type::Map(_, _, _), Expression::Map(_, location)) => {
+                if let Some(Type::Map(key, value, _)) = self.maps.get(location).cloned() {
+                    if !self.compatible(expected, &Type::Map(key.clone(), value.clone(), location.clone())) {
+                        self.errors.push(Diagnostic::type_error("E0026", format!("expected `{}`, found `map[{}, {}]`", expected.name(), key.name(), value.name()), location.clone()));
+                    }
+                }
+                self.coerce_map(&self.resolve(expected), value);
+            }
+            (t @ Type::DynamicArray(_, _), Expression::Array(elements, location)) => {
+                if let Type::DynamicArray(element, _) = &t {
+                    for value in elements.iter() {
+                        self.coerce(element, value);
+                    }
+                }
+                self.arrays.insert(location.clone(), t);
+            }
+            _ => {}
+        }
+    }
+    // Records the type a map literal ends up with, so its entries are built to match
+    pub fn coerce_map(&mut self, t: &Type, literal: &Expression) {
+        if let (Type::Map(key, value, _), Expression::Map(entries, location)) = (t, literal) {
+            self.maps.insert(location.clone(), t.clone());
+            for (entry_key, entry_value) in entries.iter() {
+                // An inferred type may have turned literals into strings already
+                self.strings.remove(&entry_key.location());
+                self.strings.remove(&entry_value.location());
+                self.coerce(key, entry_key);
+                self.coerce(value, entry_value);
+            }
+        }
+    }
+    // Parameters line up with the arguments from the end, as a method's `self` isn't passed
+    pub fn coerce_arguments(&mut self, params: &[Type], args: &[Expression]) {
+        for (t, arg) in params.iter().rev().zip(args.iter().rev()) {
+            self.coerce(t, arg);
+        }
+    }
+    // `as` between `string` and `cstring` goes through the runtime, except for
+    // a literal which is already a C string
+    pub fn convert_string(&mut self, value: &Expression, value_type: &Type, t: &Type, location: &TokenLocation) {
+        let from_string: bool = matches!(self.resolve(value_type), Type::String(_));
+        let to_string: bool = matches!(self.resolve(t), Type::String(_));
+        let literal: bool = matches!(value, Expression::String(_, _));
+        if from_string && !to_string && !literal {
+            self.strings.insert(location.clone(), StringConversion::ToCString);
+        } else if to_string && literal {
+            self.coerce(t, value);
+        } else if to_string && !from_string {
+            self.strings.insert(location.clone(), StringConversion::FromCString);
+        }
+    }
+}
@@ -0,0 +1,1953 @@
+use std::collections::HashSet;
+use colored::Colorize;
+use crate::ast::Statement;
+use crate::bindgen::Bindgen;
+use crate::bytecode::{Bytecode, BytecodeCompiler};
+use crate::codegen::Codegen;
+use crate::compile::{self, CompileOptions, Program};
+use crate::completions::{COMPLETIONS_USAGE, completions};
+use crate::dap::{DAP_USAGE, debug_adapter};
+use crate::diagnostics::{Diagnostic, SourceMap};
+use crate::doc::DocItem;
+use crate::format::Formatter;
+use crate::ide::Ide;
+use crate::importer::{Importer, read_source, source_error};
+use crate::lexer::Lexer;
+use crate::lint::{Level, Lint, LintConfig};
+use crate::lsp::{LSP_USAGE, language_server};
+use crate::optimize::Optimization;
+use crate::parser::Parser;
+use crate::target::TargetMachine;
+use crate::unparse::Unparser;
+use crate::vm::Vm;
+pub(crate) const USAGE: &str = "Usage: sl <command> [options] [<file>...]
+
+Commands:
+  check    Check a program without writing anything
+  emit     Write the generated C, or bytecode with --bytecode
+  build    Compile a program into an executable with the C compiler
+  run      Build a program and run it, or run a .slbc file
+  test     Build a program's @test functions and run them
+  bench    Build a program's @bench functions optimized and time them
+  new      Create a project in a new directory
+  init     Create a project in the current directory
+  fmt      Format source files, or check that they're formatted
+  doc      Write Markdown or HTML documentation for a program's modules
+  bindgen  Print external declarations for what a C header declares
+  lsp      Run a language server for editors over stdin and stdout
+  dap      Run a debug adapter for editors over stdin and stdout
+  explain  Describe an error or warning code
+  completions
+           Print a completion script for bash, zsh, fish or powershell
+
+Several files are compiled together as one program, which is named
+after the first. Without a file, the files and settings come from an
+sl.toml in the current directory, such as:
+
+  name = \"game\"
+  sources = [\"src/main.sl\", \"src/physics.sl\"]
+  target = \"c++\"
+  cflags = [\"-Wall\"]
+  lflags = [\"-lm\"]
+  features = [\"arc\", \"debug\"]
+  deny = [\"warnings\"]
+
+with features from arc, gc, debug, release, hidden, freestanding and
+bytecode, and `deny` making all warnings or the lints it names errors.
+
+Defaults for every program can be set in ~/.config/sl/config.toml, and
+for a project in a .sl/config.toml in the current directory or one above
+it, which wins over the user's:
+
+  color = \"always\"
+  cc = \"clang\"
+  target = \"c++\"
+  warn = [\"shadow\"]
+  allow = [\"naming\"]
+  deny = [\"warnings\"]
+
+sl.toml and the command line win over both.
+
+A file of `-` reads the program from stdin, and `emit` and `build` then
+print its C unless they're given --output.
+
+Any command takes --color=<when>: always, never, or auto to color its
+output only on a terminal and when NO_COLOR isn't set.
+
+Exit status is 0 on success, 1 if the program has errors, 2 if the
+command line is wrong, 3 if the C compiler failed, 4 if a file couldn't
+be read or written and 70 if sl crashed. Once a program is built, `run`,
+`test` and `bench` exit as it does.
+
+Run `sl <command> --help` for a command's options.";
+pub(crate) const NEW_USAGE: &str = "Usage: sl new [--name <name>] <directory>
+
+Create a directory with a project in it: an sl.toml, a src/main.sl that
+prints a greeting, and a .gitignore for what sl generates. `sl run` in
+the directory builds and runs it.
+
+Options:
+  --name <name>          What the project and its executable are called,
+                         the directory's name by default
+  -h, --help             Show this help";
+pub(crate) const INIT_USAGE: &str = "Usage: sl init [--name <name>]
+
+Create a project in the current directory, as `sl new` does in a new one.
+A src/main.sl or .gitignore that's already there is kept.
+
+Options:
+  --name <name>          What the project and its executable are called,
+                         the directory's name by default
+  -h, --help             Show this help";
+pub(crate) const FORMAT_USAGE: &str = "Usage: sl fmt [--check] [<file>...]
+
+Format files in place: a tab per level of block, `else` and `end` lined up
+with what they close, a space on either side of binary operators and after
+commas and colons, and no more than one blank line in a row. Line breaks
+and comments are kept. Without a file, the sources in sl.toml are
+formatted, and a file of `-` is read from stdin and printed.
+
+Options:
+  --check                Change nothing, but list the files that aren't
+                         formatted and exit with 1 if there are any
+  -h, --help             Show this help";
+pub(crate) const DOC_USAGE: &str = "Usage: sl doc [options] [<file>...]
+
+Write a page for each module of a program, and one that lists them, with
+its functions, structs, enums, type aliases, constants and annotations,
+how each is declared and the `///` comment lines above it:
+
+  /// How far apart two points are, rounded down
+  func distance(a: Point*, b: Point*): int
+
+Functions marked @static are left out. Without a file, the sources in
+sl.toml are documented.
+
+Options:
+  -o, --output <path>    The directory to write into, doc by default
+  --html                 Write HTML in place of Markdown
+  -h, --help             Show this help";
+pub(crate) const EXPLAIN_USAGE: &str = "Usage: sl explain <code>
+
+Describe what an error or warning code, such as E0001 or W0002, means and
+how to fix what it's reported for.
+
+Options:
+  -h, --help             Show this help";
+pub(crate) const BINDGEN_USAGE: &str = "Usage: sl bindgen [options] <header>
+
+Print `external` declarations for the functions, variables, structs and
+typedefs a C header declares, to use in place of writing them by hand:
+
+  sl bindgen std/time.h -o time.sl
+
+The header is named as `import` names it, std/time.h for <time.h> or a
+path for \"mylib.h\", and read through the C preprocessor. Its typedefs,
+and C's types that .sl has no word for such as `double`, are declared as
+`external type`s, which aren't checked. What .sl can't declare, such as a
+function taking a variable number of arguments, is listed in a comment
+at the end, and can still be called through the header's import.
+The declarations are the header as C sees it, so they're for --target=c.
+
+Options:
+  -o, --output <path>    Write the declarations there in place of printing
+                         them
+  --cc=<compiler>        The C compiler to preprocess the header with, $CC
+                         or cc by default
+  --cflag <flag>         Pass a flag to the preprocessor, such as
+                         -I<directory> or -D<macro>
+  -h, --help             Show this help";
+pub(crate) const COMPILE_OPTIONS: &str = "  -W<lint>, -Wno-<lint>, -Werror=<lint>
+                         Warn about, allow or deny a lint
+  -Werror                Make every warning an error
+  -O, -O0, -O<pass>, -Ono-<pass>
+                         Turn all, none or one of the optimizations on or off
+  --target=<target>      c or c++, a machine such as avr, or key=value
+                         settings for its widths and endianness
+  --arc                  Count references to objects and free them
+  --gc                   Collect objects with the Boehm GC (link with -lgc)
+  --release              Leave out the null and bounds checks and #line
+                         directives, and compile with -O2 -DNDEBUG in place
+                         of -g -O0
+  --debug                Check pointers for null even with --release
+  --hidden               Hide functions from outside a shared library
+  --freestanding         Leave out the C library
+  --bytecode             Compile for the virtual machine in place of C
+  --emit-layout=<layout> single, or per-module for a .c/.h pair per module
+  --message-format=<format>
+                         human, or json for one object per diagnostic with
+                         its code, severity, message, file, span in bytes,
+                         line and column
+  --timings              Print how long parsing (lexing included), checking,
+                         codegen and the C compiler took, and how many tokens
+                         and statements there were
+  -h, --help             Show this help";
+// The subcommands, which share how a program is read and checked
+#[derive(Debug, Clone, Copy, PartialEq)] pub(crate) enum Command {
+    Check,
+    Emit,
+    Build,
+    Run,
+    Test,
+    Bench,
+}
+impl Command {
+    pub(crate) fn from_name(name: &str) -> Option<Command> {
+        match name {
+            "check" => Some(Command::Check),
+            "emit" => Some(Command::Emit),
+            "build" => Some(Command::Build),
+            "run" => Some(Command::Run),
+            "test" => Some(Command::Test),
+            "bench" => Some(Command::Bench),
+            _ => None,
+        }
+    }
+    pub(crate) fn help(&self) -> String {
+        let (usage, description, options): (&str, &str, &str) = match self {
+            Command::Check => (
+                "check [options] [<file>...]",
+                "Read and check a program, and print its diagnostics without generating\nor writing anything. Exits with 1 if there were errors.",
+                "",
+            ),
+            Command::Emit => (
+                "emit [options] [<file>...]",
+                "Write the C or C++ generated from a program, or its bytecode as a\n.slbc file with --bytecode.",
+                "  -o, --output <path>    The file to write, with the header next to it, or\n                         a directory to write into. Next to the program by\n                         default, and a directory with --emit-layout=per-module\n  --emit=<output>        Print the program's tokens, its syntax tree, its\n                         C, what each token is for highlighting, or each\n                         file as parsed and written back out (tokens, ast,\n                         c, semantic-tokens or source) in place of writing\n                         files\n",
+            ),
+            Command::Build => (
+                "build [options] [<file>...]",
+                "Compile a program into an executable with $CC, or $CXX with\n--target=c++. Without them, the first of cc, gcc, clang and zig cc, or\nc++, g++, clang++ and zig c++, that's installed is used.",
+                "  -o, --output <path>    The executable, or a directory to write it into.\n                         The program's name without its extension by default\n  --cc=<compiler>        The C or C++ compiler to use\n  --cflag <flag>         Pass a flag to the compiler, such as -I<directory>\n                         or -fsanitize=address\n  --lflag <flag>         Pass a flag to the compiler after the generated\n                         files, such as -lm\n  --watch                Build again whenever the program or a file it\n                         imports changes, until interrupted\n",
+            ),
+            Command::Run => (
+                "run [options] [<file>...] [-- <arguments>]",
+                "Build a program in a temporary directory and run it with the\narguments after `--`, exiting as it does. A .slbc file, or a program\nwith --bytecode, is run in the virtual machine.",
+                "  -o, --output <path>    Keep the executable there\n  --cc=<compiler>        The C or C++ compiler to use\n  --cflag <flag>         Pass a flag to the compiler, such as -I<directory>\n                         or -fsanitize=address\n  --lflag <flag>         Pass a flag to the compiler after the generated\n                         files, such as -lm\n",
+            ),
+            Command::Test => (
+                "test [options] [<file>...] [-- <filter>...]",
+                "Build the functions marked @test into an executable with a `main` of its\nown and run each of them, or those whose names contain one of the\nfilters. A failed `assert` fails the test it's in. Exits with 1 if any\ntest failed.",
+                "  -o, --output <path>    Keep the test executable there\n  --cc=<compiler>        The C or C++ compiler to use\n  --cflag <flag>         Pass a flag to the compiler, such as -I<directory>\n                         or -fsanitize=address\n  --lflag <flag>         Pass a flag to the compiler after the generated\n                         files, such as -lm\n",
+            ),
+            Command::Bench => (
+                "bench [options] [<file>...] [-- <filter>...]",
+                "Build the functions marked @bench into an executable with a `main` of\nits own and time each of them, or those whose names contain one of the\nfilters. They're built with every optimization and --release, which -O\nflags can change. Each is run in batches until one takes 10ms, and the\nmedian, fastest and slowest time per call of 11 batches is reported.",
+                "  -o, --output <path>    Keep the benchmark executable there\n  --cc=<compiler>        The C or C++ compiler to use\n  --cflag <flag>         Pass a flag to the compiler, such as -I<directory>\n                         or -march=native\n  --lflag <flag>         Pass a flag to the compiler after the generated\n                         files, such as -lm\n",
+            ),
+        };
+        format!("Usage: sl {}\n\n{}\n\nOptions:\n{}{}", usage, description, options, COMPILE_OPTIONS)
+    }
+}
+// What `emit --emit=` prints in place of writing files
+#[derive(Debug, Clone, Copy, PartialEq)] pub(crate) enum Dump {
+    Tokens,
+    Ast,
+    C,
+    SemanticTokens,
+    Source,
+}
+// An sl.toml in the directory a command is run from, which stands in for the
+// command line when no file is given:
+//
+//     name = "game"
+//     sources = ["src/main.sl", "src/physics.sl"]
+//     target = "c++,avr"
+//     cflags = ["-Wall", "-I/usr/local/include"]
+//     lflags = ["-lm"]
+//     features = ["arc", "debug"]
+//     deny = ["warnings"]
+//
+// Flags given on the command line win over the manifest's.
+#[derive(Debug, Clone)] pub(crate) struct Manifest {
+    pub(crate) name: Option<String>,
+    pub(crate) sources: Vec<String>,
+    pub(crate) target: Option<String>,
+    pub(crate) cflags: Vec<String>,
+    pub(crate) lflags: Vec<String>,
+    pub(crate) features: Vec<String>,
+    // Lints that are errors, or `warnings` for all of them
+    pub(crate) deny: Vec<String>,
+}
+impl Manifest {
+    pub(crate) const FILENAME: &'static str = "sl.toml";
+    // Flags that can be turned on by name in `features`
+    pub(crate) const FEATURES: [&'static str; 7] = ["arc", "gc", "debug", "release", "hidden", "freestanding", "bytecode"];
+    pub fn parse(contents: &str) -> Result<Manifest, String> {
+        let mut manifest: Manifest = Manifest { name: None, sources: vec![], target: None, cflags: vec![], lflags: vec![], features: vec![], deny: vec![] };
+        for (number, line) in Manifest::lines(contents)? {
+            let line: &str = line.as_str();
+            let (key, value): (&str, &str) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => return Err(format!("line {}: expected `name = value`", number + 1)),
+            };
+            let values: Vec<String> = Manifest::values(value).ok_or(format!("line {}: expected a string or a list of strings", number + 1))?;
+            let single = || -> Result<String, String> {
+                match (value.starts_with('"'), values.first()) {
+                    (true, Some(value)) => Ok(value.clone()),
+                    _ => Err(format!("line {}: `{}` is a string", number + 1, key)),
+                }
+            };
+            match key {
+                "name" => manifest.name = Some(single()?),
+                "target" => manifest.target = Some(single()?),
+                "sources" => manifest.sources = values,
+                "cflags" => manifest.cflags = values,
+                "lflags" => manifest.lflags = values,
+                "features" => {
+                    if let Some(feature) = values.iter().find(|feature| !Manifest::FEATURES.contains(&feature.as_str())) {
+                        return Err(format!("line {}: unknown feature `{}`, expected one of {}", number + 1, feature, Manifest::FEATURES.join(", ")));
+                    }
+                    manifest.features = values;
+                }
+                "deny" => {
+                    if let Some(lint) = values.iter().find(|lint| *lint != "warnings" && Lint::from_name(lint).is_none()) {
+                        return Err(format!("line {}: unknown lint `{}`", number + 1, lint));
+                    }
+                    manifest.deny = values;
+                }
+                _ => return Err(format!("line {}: unknown key `{}`", number + 1, key)),
+            }
+        }
+        if manifest.sources.is_empty() {
+            return Err("`sources` has to name at least one file".to_string());
+        }
+        Ok(manifest)
+    }
+    // The settings in a file without their comments and with the index of the
+    // line each starts on. A list can go over several lines:
+    //
+    //     sources = [
+    //         "src/main.sl",  # where it starts
+    //         "src/physics.sl",
+    //     ]
+    pub(crate) fn lines(contents: &str) -> Result<Vec<(usize, String)>, String> {
+        let mut lines: Vec<(usize, String)> = vec![];
+        let mut open: Option<(usize, String)> = None;
+        let mut depth: usize = 0;
+        for (number, line) in contents.lines().enumerate() {
+            let mut quoted: bool = false;
+            let mut end: usize = line.len();
+            for (index, c) in line.char_indices() {
+                match c {
+                    '"' => quoted = !quoted,
+                    '#' if !quoted => {
+                        end = index;
+                        break;
+                    }
+                    '[' if !quoted => depth += 1,
+                    ']' if !quoted => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+            }
+            let text: &str = line[..end].trim();
+            match &mut open {
+                Some((_, joined)) => {
+                    joined.push(' ');
+                    joined.push_str(text);
+                }
+                None => open = Some((number, text.to_string())),
+            }
+            if depth == 0 {
+                if let Some((start, joined)) = open.take().filter(|(_, joined)| !joined.is_empty()) {
+                    lines.push((start, joined));
+                }
+            }
+        }
+        match open {
+            Some((number, _)) => Err(format!("line {}: the list is never closed with `]`", number + 1)),
+            None => Ok(lines),
+        }
+    }
+    // A "string" or a ["list", "of", "strings"], with an optional # comment after it
+    pub(crate) fn values(value: &str) -> Option<Vec<String>> {
+        let (list, mut rest): (bool, &str) = match value.strip_prefix('[') {
+            Some(rest) => (true, rest),
+            None => (false, value),
+        };
+        let mut values: Vec<String> = vec![];
+        loop {
+            rest = rest.trim_start();
+            if list && rest.starts_with(']') {
+                rest = &rest[1..];
+                break;
+            }
+            let end: usize = rest.strip_prefix('"')?.find('"')? + 1;
+            values.push(rest[1..end].to_string());
+            rest = rest[end + 1..].trim_start();
+            if !list {
+                break;
+            }
+            match rest.strip_prefix(',') {
+                Some(after) => rest = after,
+                None if rest.starts_with(']') => {}
+                None => return None,
+            }
+        }
+        let rest: &str = rest.trim();
+        (rest.is_empty() || rest.starts_with('#')).then_some(values)
+    }
+    // The manifest's settings as the flags that ask for them, and its files
+    pub(crate) fn args(&self, command: Command) -> Vec<String> {
+        let mut args: Vec<String> = self.features.iter().map(|feature| format!("--{}", feature)).collect();
+        args.extend(self.deny.iter().map(|lint| if lint == "warnings" { "-Werror".to_string() } else { format!("-Werror={}", lint) }));
+        if let Some(target) = &self.target {
+            args.push(format!("--target={}", target));
+        }
+        if matches!(command, Command::Build | Command::Run | Command::Test | Command::Bench) {
+            args.extend(self.cflags.iter().map(|flag| format!("--cflag={}", flag)));
+            args.extend(self.lflags.iter().map(|flag| format!("--lflag={}", flag)));
+        }
+        args.extend(self.sources.iter().cloned());
+        args
+    }
+}
+// Defaults for every command, from the user's ~/.config/sl/config.toml and
+// then each .sl/config.toml from the filesystem's root down to the current
+// directory, so that a project's settings win over the user's:
+//
+//     color = "always"
+//     cc = "clang"
+//     target = "c++"
+//     warn = ["shadow"]
+//     allow = ["naming"]
+//     deny = ["warnings"]
+//
+// They go before sl.toml's settings and the command line's flags, which win.
+#[derive(Debug, Clone, Default)] pub(crate) struct Config {
+    pub(crate) color: Option<String>,
+    pub(crate) cc: Option<String>,
+    pub(crate) target: Option<String>,
+    pub(crate) warn: Vec<String>,
+    pub(crate) allow: Vec<String>,
+    // Lints that are errors, or `warnings` for all of them
+    pub(crate) deny: Vec<String>,
+}
+impl Config {
+    // Every config file there is, least important first
+    pub fn load() -> Result<Config, String> {
+        let mut config: Config = Config::default();
+        let user: Option<std::path::PathBuf> = match (std::env::var_os("XDG_CONFIG_HOME"), std::env::var_os("HOME"), std::env::var_os("APPDATA")) {
+            (Some(directory), _, _) if !directory.is_empty() => Some(std::path::PathBuf::from(directory)),
+            (_, Some(home), _) => Some(std::path::Path::new(&home).join(".config")),
+            (_, None, Some(directory)) => Some(std::path::PathBuf::from(directory)),
+            _ => None,
+        };
+        let mut paths: Vec<std::path::PathBuf> = user.map(|directory| directory.join("sl").join("config.toml")).into_iter().collect();
+        if let Ok(directory) = std::env::current_dir() {
+            let mut projects: Vec<std::path::PathBuf> = directory.ancestors().map(|directory| directory.join(".sl").join("config.toml")).collect();
+            projects.reverse();
+            paths.extend(projects);
+        }
+        for path in paths {
+            if let Ok(contents) = std::fs::read_to_string(&path) {
+                config.parse(&contents).map_err(|error| format!("{}: {}", path.display(), error))?;
+            }
+        }
+        Ok(config)
+    }
+    // Reads a file's settings over the ones so far
+    pub fn parse(&mut self, contents: &str) -> Result<(), String> {
+        for (number, line) in Manifest::lines(contents)? {
+            let line: &str = line.as_str();
+            let (key, value): (&str, &str) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => return Err(format!("line {}: expected `name = value`", number + 1)),
+            };
+            let values: Vec<String> = Manifest::values(value).ok_or(format!("line {}: expected a string or a list of strings", number + 1))?;
+            let single = || -> Result<String, String> {
+                match (value.starts_with('"'), values.first()) {
+                    (true, Some(value)) => Ok(value.clone()),
+                    _ => Err(format!("line {}: `{}` is a string", number + 1, key)),
+                }
+            };
+            // `deny` can also name every warning at once
+            let lints = |warnings: bool| -> Result<Vec<String>, String> {
+                match values.iter().find(|lint| !(warnings && *lint == "warnings") && Lint::from_name(lint).is_none()) {
+                    Some(lint) => Err(format!("line {}: unknown lint `{}`", number + 1, lint)),
+                    None => Ok(values.clone()),
+                }
+            };
+            match key {
+                "color" => {
+                    let color: String = single()?;
+                    if !["always", "auto", "never"].contains(&color.as_str()) {
+                        return Err(format!("line {}: unknown color setting `{}`, expected `always`, `auto` or `never`", number + 1, color));
+                    }
+                    self.color = Some(color);
+                }
+                "cc" => self.cc = Some(single()?),
+                "target" => self.target = Some(single()?),
+                "warn" => self.warn.extend(lints(false)?),
+                "allow" => self.allow.extend(lints(false)?),
+                "deny" => self.deny.extend(lints(true)?),
+                _ => return Err(format!("line {}: unknown key `{}`", number + 1, key)),
+            }
+        }
+        Ok(())
+    }
+    // The settings as the flags that ask for them
+    pub(crate) fn args(&self, command: Command) -> Vec<String> {
+        let mut args: Vec<String> = self.warn.iter().map(|lint| format!("-W{}", lint)).collect();
+        args.extend(self.allow.iter().map(|lint| format!("-Wno-{}", lint)));
+        args.extend(self.deny.iter().map(|lint| if lint == "warnings" { "-Werror".to_string() } else { format!("-Werror={}", lint) }));
+        if let Some(target) = &self.target {
+            args.push(format!("--target={}", target));
+        }
+        if let (Some(cc), true) = (&self.cc, matches!(command, Command::Build | Command::Run | Command::Test | Command::Bench)) {
+            args.push(format!("--cc={}", cc));
+        }
+        args
+    }
+}
+// How long each phase of a compile took and how much it went through, kept
+// with --timings and printed to stderr once the command is done
+#[derive(Debug, Clone, Default)] pub(crate) struct Timings {
+    pub(crate) phases: std::cell::RefCell<Vec<(&'static str, std::time::Duration)>>,
+    pub(crate) counts: std::cell::RefCell<Vec<(&'static str, usize)>>,
+}
+impl Timings {
+    pub fn add(&self, phase: &'static str, duration: std::time::Duration) {
+        let mut phases = self.phases.borrow_mut();
+        match phases.iter_mut().find(|(name, _)| *name == phase) {
+            Some((_, total)) => *total += duration,
+            None => phases.push((phase, duration)),
+        }
+    }
+    pub fn count(&self, name: &'static str, count: usize) {
+        self.counts.borrow_mut().push((name, count));
+    }
+    // Prints what's been recorded and starts over
+    pub fn report(&self) {
+        let phases: Vec<(&'static str, std::time::Duration)> = self.phases.take();
+        let counts: Vec<(&'static str, usize)> = self.counts.take();
+        if phases.is_empty() {
+            return;
+        }
+        let total: std::time::Duration = phases.iter().map(|(_, duration)| *duration).sum();
+        let mut report: String = String::from("timings:\n");
+        for (name, duration) in phases.iter().chain([("total", total)].iter()) {
+            let share: f64 = if total.is_zero() { 0.0 } else { duration.as_secs_f64() / total.as_secs_f64() * 100.0 };
+            report.push_str(&format!("  {:<12}{:>10.3}ms {:>5.1}%\n", name, duration.as_secs_f64() * 1000.0, share));
+        }
+        if !counts.is_empty() {
+            report.push_str(&format!("  {}\n", counts.iter().map(|(name, count)| format!("{} {}", count, name)).collect::<Vec<String>>().join(", ")));
+        }
+        eprint!("{}", report);
+    }
+}
+// What the command line asks for
+#[derive(Debug, Clone)] pub(crate) struct Options {
+    pub(crate) command: Command,
+    // The first file, which the generated files are named after
+    pub(crate) filename: String,
+    // The rest of the program's files, loaded after the first
+    pub(crate) others: Vec<String>,
+    pub(crate) output: Option<String>,
+    // The C compiler given with --cc, in place of $CC or $CXX
+    pub(crate) compiler: Option<String>,
+    // What `run` passes on to the program
+    pub(crate) arguments: Vec<String>,
+    pub(crate) dump: Option<Dump>,
+    // Diagnostics as JSON, one object per line
+    pub(crate) json: bool,
+    // The program, when it's read from stdin with a file of `-`
+    pub(crate) stdin: Option<String>,
+    pub(crate) levels: Vec<(Lint, Level)>,
+    // Every warning is an error, with -Werror
+    pub(crate) deny_warnings: bool,
+    pub(crate) optimizations: HashSet<Optimization>,
+    pub(crate) machine: Option<TargetMachine>,
+    pub(crate) arc: bool,
+    pub(crate) gc: bool,
+    pub(crate) debug: bool,
+    pub(crate) cpp: bool,
+    pub(crate) bytecode: bool,
+    pub(crate) hidden: bool,
+    pub(crate) freestanding: bool,
+    pub(crate) per_module: bool,
+    // Build again whenever one of the program's files changes
+    pub(crate) watch: bool,
+    // The release profile in place of the debug one
+    pub(crate) release: bool,
+    // What else the C compiler is given, before the generated files and after
+    pub(crate) cflags: Vec<String>,
+    pub(crate) lflags: Vec<String>,
+    // What sl.toml calls the executable
+    pub(crate) package: Option<String>,
+    // Where --timings keeps how long each phase took
+    pub(crate) timings: Option<Timings>,
+}
+impl Options {
+    // Flags may come before or after the file. Ok(None) means help was asked for.
+    // Reads the defaults, such as the config's and the manifest's, and then
+    // the command line over them
+    pub(crate) fn parse(command: Command, defaults: &[String], args: &[String]) -> Result<Option<Options>, String> {
+        let mut options: Options = Options {
+            command,
+            filename: String::new(),
+            others: vec![],
+            output: None,
+            compiler: None,
+            arguments: vec![],
+            dump: None,
+            json: false,
+            stdin: None,
+            levels: vec![],
+            deny_warnings: false,
+            optimizations: HashSet::new(),
+            machine: None,
+            arc: false,
+            gc: false,
+            debug: false,
+            cpp: false,
+            bytecode: false,
+            hidden: false,
+            freestanding: false,
+            per_module: false,
+            watch: false,
+            release: false,
+            cflags: vec![],
+            lflags: vec![],
+            package: None,
+            timings: None,
+        };
+        // Benchmarks are of the optimized program
+        if command == Command::Bench {
+            options.optimizations.extend(Optimization::all());
+            options.release = true;
+        }
+        let mut filenames: Vec<String> = vec![];
+        let command_line: &[String] = args;
+        let args: Vec<String> = [defaults, command_line].concat();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg == "-h" || arg == "--help" {
+                return Ok(None);
+            } else if arg == "--" && matches!(command, Command::Run | Command::Test | Command::Bench) {
+                options.arguments = args.cloned().collect();
+                break;
+            } else if (arg == "-o" || arg == "--output") && command != Command::Check {
+                match args.next() {
+                    Some(output) => options.output = Some(output.clone()),
+                    None => return Err(format!("`{}` needs a file or directory to write to", arg)),
+                }
+            } else if let (Some(output), false) = (arg.strip_prefix("--output="), command == Command::Check) {
+                options.output = Some(output.to_string());
+            } else if let (Some(compiler), true) = (arg.strip_prefix("--cc="), matches!(command, Command::Build | Command::Run | Command::Test | Command::Bench)) {
+                options.compiler = Some(compiler.to_string());
+            } else if (arg == "--cflag" || arg == "--lflag") && matches!(command, Command::Build | Command::Run | Command::Test | Command::Bench) {
+                let flags: &mut Vec<String> = if arg == "--cflag" { &mut options.cflags } else { &mut options.lflags };
+                match args.next() {
+                    Some(flag) => flags.push(flag.clone()),
+                    None => return Err(format!("`{}` needs a flag to pass on", arg)),
+                }
+            } else if let (Some(flag), true) = (arg.strip_prefix("--cflag="), matches!(command, Command::Build | Command::Run | Command::Test | Command::Bench)) {
+                options.cflags.push(flag.to_string());
+            } else if let (Some(flag), true) = (arg.strip_prefix("--lflag="), matches!(command, Command::Build | Command::Run | Command::Test | Command::Bench)) {
+                options.lflags.push(flag.to_string());
+            } else if let (Some(dump), Command::Emit) = (arg.strip_prefix("--emit="), command) {
+                options.dump = Some(match dump {
+                    "tokens" => Dump::Tokens,
+                    "ast" => Dump::Ast,
+                    "c" => Dump::C,
+                    "semantic-tokens" => Dump::SemanticTokens,
+                    "source" => Dump::Source,
+                    _ => return Err(format!("unknown output `{}`, expected `tokens`, `ast`, `c`, `semantic-tokens` or `source`", dump)),
+                });
+            } else if let Some(format) = arg.strip_prefix("--message-format=") {
+                options.json = match format {
+                    "human" => false,
+                    "json" => true,
+                    _ => return Err(format!("unknown message format `{}`, expected `human` or `json`", format)),
+                };
+            } else if arg == "-Werror" {
+                options.deny_warnings = true;
+            } else if arg.starts_with("-W") {
+                match Lint::from_flag(arg) {
+                    Some(level) => options.levels.push(level),
+                    None => return Err(format!("unknown warning option `{}`", arg)),
+                }
+            } else if arg.starts_with("-O") {
+                if !Optimization::apply_flag(arg, &mut options.optimizations) {
+                    return Err(format!("unknown optimization option `{}`", arg));
+                }
+            } else if arg == "--timings" {
+                options.timings = Some(Timings::default());
+            } else if arg == "--arc" {
+                options.arc = true;
+            } else if arg == "--gc" {
+                options.gc = true;
+            } else if arg == "--release" {
+                options.release = true;
+            } else if arg == "--debug" {
+                options.debug = true;
+            } else if arg == "--bytecode" {
+                options.bytecode = true;
+            } else if arg == "--hidden" {
+                options.hidden = true;
+            } else if arg == "--freestanding" {
+                options.freestanding = true;
+            } else if arg == "--watch" && command == Command::Build {
+                options.watch = true;
+            } else if let Some(layout) = arg.strip_prefix("--emit-layout=") {
+                match layout {
+                    "single" => options.per_module = false,
+                    "per-module" => options.per_module = true,
+                    _ => return Err(format!("unknown layout `{}`, expected `single` or `per-module`", layout)),
+                }
+            } else if let Some(target) = arg.strip_prefix("--target=") {
+                // The language and the machine can be given together, as in `c++,avr`
+                let mut spec: Vec<&str> = vec![];
+                for part in target.split(',') {
+                    match part {
+                        "c" => options.cpp = false,
+                        "c++" => options.cpp = true,
+                        part => spec.push(part),
+                    }
+                }
+                if !spec.is_empty() {
+                    options.machine = Some(TargetMachine::parse(&spec.join(","))?);
+                }
+            } else if arg.starts_with('-') && arg != "-" {
+                return Err(format!("unknown option `{}`, see `sl {} --help`", arg, Options::name(command)));
+            } else {
+                filenames.push(arg.clone());
+            }
+        }
+        if filenames.is_empty() {
+            let contents: String = match std::fs::read_to_string(Manifest::FILENAME) {
+                Ok(contents) => contents,
+                Err(_) => return Err(format!("no file given, and no {} here, see `sl {} --help`", Manifest::FILENAME, Options::name(command))),
+            };
+            let manifest: Manifest = Manifest::parse(&contents).map_err(|error| format!("{}: {}", Manifest::FILENAME, error))?;
+            // The command line comes after the manifest so its flags win
+            let Some(mut options) = Options::parse(command, &[defaults, &manifest.args(command)].concat(), command_line)? else {
+                return Ok(None);
+            };
+            options.package = manifest.name;
+            return Ok(Some(options));
+        }
+        options.filename = filenames.remove(0);
+        options.others = filenames;
+        // Without anywhere to write, what's built from stdin is printed
+        if options.filename == "-" && options.output.is_none() && matches!(command, Command::Emit | Command::Build) {
+            if options.bytecode {
+                return Err("bytecode read from stdin needs `--output` to say where it goes".to_string());
+            }
+            options.dump.get_or_insert(Dump::C);
+        }
+        options.validate()?;
+        if options.filename == "-" {
+            use std::io::Read;
+            let mut contents: String = String::new();
+            if let Err(error) = std::io::stdin().read_to_string(&mut contents) {
+                return Err(format!("can't read stdin: {}", error));
+            }
+            options.stdin = Some(contents);
+        }
+        Ok(Some(options))
+    }
+    // The program's file, which the generated files are named after. A
+    // program from stdin is named `stdin`.
+    pub(crate) fn path(&self) -> &str {
+        if self.stdin.is_some() {
+            "stdin.sl"
+        } else {
+            &self.filename
+        }
+    }
+    // The executable built from the program: next to its file without the
+    // extension, or what sl.toml names it
+    pub(crate) fn executable(&self) -> std::path::PathBuf {
+        match &self.package {
+            Some(package) => std::path::PathBuf::from(package),
+            None => std::path::Path::new(self.path()).with_extension(""),
+        }
+    }
+    // Prints what's found along the way. When stdout carries what's emitted,
+    // that goes to stderr instead so the two don't end up interleaved.
+    pub(crate) fn say(&self, message: impl std::fmt::Display) {
+        if self.dump.is_some() {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    }
+    // Prints a diagnostic the way --message-format asks for
+    pub(crate) fn report(&self, sources: &SourceMap, error: &Diagnostic) {
+        if self.json {
+            self.say(sources.render_json(error));
+        } else {
+            self.say(sources.render(error));
+        }
+    }
+    // How diagnostics name the root file
+    pub(crate) fn source_name(&self) -> &str {
+        if self.stdin.is_some() {
+            "<stdin>"
+        } else {
+            &self.filename
+        }
+    }
+    pub(crate) fn name(command: Command) -> &'static str {
+        match command {
+            Command::Check => "check",
+            Command::Emit => "emit",
+            Command::Build => "build",
+            Command::Run => "run",
+            Command::Test => "test",
+            Command::Bench => "bench",
+        }
+    }
+    // The flags the library's pipeline is run with, which `sl` shares with `compile`
+    pub(crate) fn compile_options(&self) -> CompileOptions {
+        CompileOptions {
+            filename: self.source_name().to_string(),
+            lints: self.levels.clone(),
+            deny_warnings: self.deny_warnings,
+            optimizations: self.optimizations.clone(),
+            machine: self.machine.clone(),
+            arc: self.arc,
+            gc: self.gc,
+            debug: self.debug,
+            release: self.release,
+            cpp: self.cpp,
+            hidden: self.hidden,
+            freestanding: self.freestanding,
+            ast: false,
+        }
+    }
+    // Runs a phase, adding how long it took to --timings
+    pub(crate) fn time<T>(&self, phase: &'static str, run: impl FnOnce() -> T) -> T {
+        let started: std::time::Instant = std::time::Instant::now();
+        let result: T = run();
+        if let Some(timings) = &self.timings {
+            timings.add(phase, started.elapsed());
+        }
+        result
+    }
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if self.arc && self.gc {
+            return Err("`--arc` and `--gc` can't be used together".to_string());
+        }
+        // Both manage memory through malloc, and the VM is its own C library
+        for (used, flag) in [(self.arc, "--arc"), (self.gc, "--gc"), (self.bytecode, "--bytecode")] {
+            if self.freestanding && used {
+                return Err(format!("`{}` can't be used with `--freestanding`", flag));
+            }
+        }
+        if self.freestanding && matches!(self.command, Command::Build | Command::Run | Command::Test | Command::Bench) {
+            return Err("a --freestanding program is linked for its platform, `emit` its C instead".to_string());
+        }
+        // The harness catches failed assertions in the one file it's in
+        if matches!(self.command, Command::Test | Command::Bench) && (self.bytecode || self.per_module) {
+            return Err(format!("{} are built as a single C file, so `{}` can't be used", if self.command == Command::Test { "tests" } else { "benchmarks" }, if self.bytecode { "--bytecode" } else { "--emit-layout=per-module" }));
+        }
+        if self.per_module && self.command == Command::Emit && self.output.as_deref().is_some_and(|output| output_directory(output).is_none()) {
+            return Err("--emit-layout=per-module writes a pair of files per module, so `--output` has to be a directory".to_string());
+        }
+        if self.dump == Some(Dump::C) && (self.bytecode || self.per_module) {
+            return Err(format!("the C is printed as a single file, so it can't be used with `{}`", if self.bytecode { "--bytecode" } else { "--emit-layout=per-module" }));
+        }
+        // A program from stdin or a compiled one is all there is to run
+        let alone: Option<&String> = [&self.filename].into_iter().chain(self.others.iter()).find(|filename| *filename == "-" || filename.ends_with(".slbc"));
+        if let (Some(filename), false) = (alone, self.others.is_empty()) {
+            return Err(format!("`{}` can't be given along with other files", filename));
+        }
+        if self.watch && (self.filename == "-" || self.filename.ends_with(".slbc")) {
+            return Err(format!("there's nothing to watch in `{}`", self.filename));
+        }
+        if self.filename.ends_with(".slbc") && self.command != Command::Run {
+            return Err(format!("`{}` is compiled already, and only `run` takes it", self.filename));
+        }
+        let filenames = [&self.filename].into_iter().chain(self.others.iter());
+        if let Some(filename) = filenames.filter(|filename| *filename != "-" && !filename.ends_with(".slbc")).find(|filename| !filename.ends_with(".sl")) {
+            return Err(format!("`{}` isn't a program, which ends in .sl", filename));
+        }
+        Ok(())
+    }
+    // Whether every file given can be read, before anything is done with them
+    pub(crate) fn check_files(&self) -> Result<(), String> {
+        if self.stdin.is_some() {
+            return Ok(());
+        }
+        for filename in [&self.filename].into_iter().chain(self.others.iter()) {
+            // Compiled programs are bytes rather than text
+            if filename.ends_with(".slbc") {
+                std::fs::File::open(filename).map_err(|error| source_error(filename, &error))?;
+            } else {
+                read_source(filename)?;
+            }
+        }
+        Ok(())
+    }
+}
+// How sl exits, so that scripts can tell failures apart. Once a program is
+// built, `run`, `test` and `bench` exit as it does instead.
+#[derive(Debug, Clone, Copy, PartialEq)] pub(crate) enum Exit {
+    // The program has errors, or isn't formatted for `fmt --check`
+    Diagnostics = 1,
+    // The command line is wrong
+    Usage = 2,
+    // The C compiler couldn't be found, couldn't be run or failed
+    Compiler = 3,
+    // A file couldn't be read or written
+    Io = 4,
+    // sl itself crashed
+    Internal = 70,
+}
+pub fn main() {
+    // A panic is a bug in sl rather than in the program being compiled
+    std::panic::set_hook(Box::new(|info| {
+        // Output piped into something that stopped reading, such as `head`, is
+        // the only panic that isn't a bug
+        let message: &str = info.payload().downcast_ref::<String>().map(String::as_str).unwrap_or_default();
+        if message.starts_with("failed printing to stdout") {
+            std::process::exit(Exit::Io as i32);
+        }
+        eprintln!("{}", format!("internal compiler error: {}", info).red());
+        eprintln!("note: this is a bug in sl {}, please report it with the program that caused it and the command that was run", env!("CARGO_PKG_VERSION"));
+        if std::env::var_os("RUST_BACKTRACE").is_none() {
+            eprintln!("note: run with RUST_BACKTRACE=1 to see where it happened");
+        }
+        std::process::exit(Exit::Internal as i32);
+    }));
+    let config: Result<Config, String> = Config::load();
+    let args: Vec<String> = match set_color(std::env::args().skip(1).collect(), config.as_ref().ok().and_then(|config| config.color.as_deref())) {
+        Ok(args) => args,
+        Err(error) => {
+            println!("{}", error.red());
+            std::process::exit(Exit::Usage as i32);
+        }
+    };
+    let config: Config = match config {
+        Ok(config) => config,
+        Err(error) => {
+            println!("{}", error.red());
+            std::process::exit(Exit::Usage as i32);
+        }
+    };
+    let Some(first) = args.first() else {
+        println!("{}", USAGE);
+        std::process::exit(Exit::Usage as i32);
+    };
+    if first == "-h" || first == "--help" || first == "help" {
+        match args.get(1).and_then(|name| Command::from_name(name)) {
+            Some(command) => println!("{}", command.help()),
+            None if args.get(1).is_some_and(|name| name == "new") => println!("{}", NEW_USAGE),
+            None if args.get(1).is_some_and(|name| name == "init") => println!("{}", INIT_USAGE),
+            None if args.get(1).is_some_and(|name| name == "fmt") => println!("{}", FORMAT_USAGE),
+            None if args.get(1).is_some_and(|name| name == "doc") => println!("{}", DOC_USAGE),
+            None if args.get(1).is_some_and(|name| name == "bindgen") => println!("{}", BINDGEN_USAGE),
+            None if args.get(1).is_some_and(|name| name == "lsp") => println!("{}", LSP_USAGE),
+            None if args.get(1).is_some_and(|name| name == "dap") => println!("{}", DAP_USAGE),
+            None if args.get(1).is_some_and(|name| name == "completions") => println!("{}", COMPLETIONS_USAGE),
+            None if args.get(1).is_some_and(|name| name == "explain") => println!("{}", EXPLAIN_USAGE),
+            None => println!("{}", USAGE),
+        }
+        return;
+    }
+    if first == "new" || first == "init" {
+        std::process::exit(create_project(&args[1..], first == "init"));
+    }
+    // Formatting only reads the files' tokens, so it takes none of the compiler's options
+    if first == "fmt" {
+        std::process::exit(format_files(&args[1..]));
+    }
+    if first == "doc" {
+        std::process::exit(document_files(&args[1..]));
+    }
+    if first == "bindgen" {
+        std::process::exit(bind_header(&args[1..]));
+    }
+    if first == "lsp" {
+        std::process::exit(language_server(&args[1..]));
+    }
+    if first == "dap" {
+        std::process::exit(debug_adapter(&args[1..]));
+    }
+    if first == "completions" {
+        std::process::exit(completions(&args[1..]));
+    }
+    if first == "explain" || first == "--explain" {
+        let Some(code) = args.get(1) else {
+            println!("{}", "`explain` needs an error or warning code, such as E0001".red());
+            std::process::exit(Exit::Usage as i32);
+        };
+        if code == "-h" || code == "--help" {
+            println!("{}", EXPLAIN_USAGE);
+            return;
+        }
+        match Diagnostic::explain(code) {
+            Some(explanation) => println!("{}", explanation),
+            None => {
+                println!("{}", format!("no explanation for `{}`", code).red());
+                std::process::exit(Exit::Usage as i32);
+            }
+        }
+        return;
+    }
+    let Some(command) = Command::from_name(first) else {
+        println!("{}", format!("unknown command `{}`, see `sl --help`", first).red());
+        std::process::exit(Exit::Usage as i32);
+    };
+    let mut options: Options = match Options::parse(command, &config.args(command), &args[1..]) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", command.help());
+            return;
+        }
+        Err(error) => {
+            println!("{}", error.red());
+            std::process::exit(Exit::Usage as i32);
+        }
+    };
+    if let Err(error) = options.check_files() {
+        println!("{}", error.red());
+        std::process::exit(Exit::Io as i32);
+    }
+    // A compiled program runs with the arguments after it
+    if options.filename.ends_with(".slbc") {
+        run_bytecode(&options.filename, &[vec![options.filename.clone()], options.arguments.clone()].concat());
+        return;
+    }
+    // The tokens, what they are and the tree are printed without generating anything
+    if let Some(dump @ (Dump::Tokens | Dump::Ast | Dump::SemanticTokens | Dump::Source)) = options.dump {
+        if !print_syntax(&options, dump) {
+            std::process::exit(Exit::Diagnostics as i32);
+        }
+        return;
+    }
+    // A missing compiler is better found before the program is read than after
+    if matches!(options.command, Command::Build | Command::Run | Command::Test | Command::Bench) && !options.bytecode && options.dump.is_none() {
+        match find_compiler(&options) {
+            Ok(compiler) => options.compiler = Some(compiler),
+            Err(error) => {
+                println!("{}", error.red());
+                std::process::exit(Exit::Compiler as i32);
+            }
+        }
+    }
+    if options.watch {
+        watch(&options);
+    }
+    // What a program does when it runs isn't part of its timings
+    let report_timings = || {
+        if let Some(timings) = &options.timings {
+            timings.report();
+        }
+    };
+    let Some(program) = front_end(&options) else {
+        report_timings();
+        std::process::exit(Exit::Diagnostics as i32);
+    };
+    let result: Result<(), Exit> = match options.command {
+        Command::Check => Ok(()),
+        Command::Run if options.bytecode => {
+            let compiled: Option<Bytecode> = compile_bytecode(&options, &program);
+            report_timings();
+            let Some(bytecode) = compiled else {
+                std::process::exit(Exit::Diagnostics as i32);
+            };
+            std::process::exit(execute(&bytecode, &[vec![options.filename.clone()], options.arguments.clone()].concat()));
+        }
+        Command::Run | Command::Test | Command::Bench => std::process::exit(run(&options, program)),
+        Command::Emit => emit(&options, program, options.output.as_deref()).map(|_| ()),
+        Command::Build => build_program(&options, program),
+    };
+    report_timings();
+    if let Err(exit) = result {
+        std::process::exit(exit as i32);
+    }
+}
+// Turns colored output on or off from --color, which any command takes and is
+// taken out of the arguments, then the config's `color`, or else from NO_COLOR
+// and whether stdout is a terminal
+pub(crate) fn set_color(args: Vec<String>, default: Option<&str>) -> Result<Vec<String>, String> {
+    use std::io::IsTerminal;
+    let mut color: Option<bool> = match default {
+        Some("always") => Some(true),
+        Some("never") => Some(false),
+        _ => None,
+    };
+    let mut rest: Vec<String> = vec![];
+    for (index, arg) in args.iter().enumerate() {
+        // What's after `--` belongs to the program being run
+        if arg == "--" {
+            rest.extend(args[index..].iter().cloned());
+            break;
+        }
+        match arg.strip_prefix("--color=") {
+            Some("always") => color = Some(true),
+            Some("never") => color = Some(false),
+            Some("auto") => color = None,
+            Some(when) => return Err(format!("unknown color setting `{}`, expected `always`, `auto` or `never`", when)),
+            None => rest.push(arg.clone()),
+        }
+    }
+    let color: bool = color.unwrap_or_else(|| std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) && std::io::stdout().is_terminal());
+    colored::control::set_override(color);
+    Ok(rest)
+}
+// Formats each file in place, or prints what's read from stdin. With --check
+// nothing is written, and the files that would change are listed instead.
+// Returns the exit code.
+pub(crate) fn format_files(args: &[String]) -> i32 {
+    let mut check: bool = false;
+    let mut filenames: Vec<String> = vec![];
+    for arg in args.iter() {
+        if arg == "-h" || arg == "--help" {
+            println!("{}", FORMAT_USAGE);
+            return 0;
+        } else if arg == "--check" {
+            check = true;
+        } else if arg.starts_with('-') && arg != "-" {
+            println!("{}", format!("unknown option `{}`, see `sl fmt --help`", arg).red());
+            return Exit::Usage as i32;
+        } else {
+            filenames.push(arg.clone());
+        }
+    }
+    if filenames.is_empty() {
+        match manifest_sources("fmt") {
+            Ok(sources) => filenames = sources,
+            Err(error) => {
+                println!("{}", error.red());
+                return Exit::Usage as i32;
+            }
+        }
+    }
+    let mut exit: i32 = 0;
+    for filename in filenames.iter() {
+        let name: &str = if filename == "-" { "<stdin>" } else { filename };
+        let contents: std::io::Result<String> = if filename == "-" {
+            use std::io::Read;
+            let mut contents: String = String::new();
+            std::io::stdin().read_to_string(&mut contents).map(|_| contents)
+        } else {
+            std::fs::read_to_string(filename)
+        };
+        let contents: String = match contents {
+            Ok(contents) => contents,
+            Err(error) => {
+                println!("{}", source_error(name, &error).red());
+                exit = Exit::Io as i32;
+                continue;
+            }
+        };
+        let formatted: String = match Formatter::format(&contents) {
+            Ok(formatted) => formatted,
+            Err(errors) => {
+                let mut sources: SourceMap = SourceMap::new();
+                sources.add(name.to_string(), contents);
+                for error in errors.iter() {
+                    report(&sources, error, false);
+                }
+                exit = exit.max(Exit::Diagnostics as i32);
+                continue;
+            }
+        };
+        // Only whitespace is meant to change, so anything else is left alone
+        if Formatter::tokens(&formatted) != Formatter::tokens(&contents) {
+            println!("{}", format!("`{}` can't be formatted without changing its tokens, so it's left as it is", name).red());
+            exit = exit.max(Exit::Diagnostics as i32);
+            continue;
+        }
+        if check {
+            if formatted != contents {
+                println!("{}", name);
+                exit = exit.max(Exit::Diagnostics as i32);
+            }
+        } else if filename == "-" {
+            print_dump(&formatted);
+        } else if formatted != contents {
+            if let Err(error) = std::fs::write(filename, &formatted) {
+                println!("{}", format!("can't write `{}`: {}", filename, error).red());
+                exit = Exit::Io as i32;
+            }
+        }
+    }
+    exit
+}
+// The files sl.toml lists, for a command that's given none
+pub(crate) fn manifest_sources(command: &str) -> Result<Vec<String>, String> {
+    match std::fs::read_to_string(Manifest::FILENAME) {
+        Ok(contents) => Manifest::parse(&contents).map(|manifest| manifest.sources).map_err(|error| format!("{}: {}", Manifest::FILENAME, error)),
+        Err(_) => Err(format!("no file given, and no {} here, see `sl {} --help`", Manifest::FILENAME, command)),
+    }
+}
+// The files a new project starts with, named after it
+pub(crate) fn project_files(name: &str) -> [(&'static str, String); 3] {
+    [
+        (Manifest::FILENAME, format!("name = \"{}\"\nsources = [\"src/main.sl\"]\n# cflags = [\"-Wall\"]\n# lflags = [\"-lm\"]\n# features = [\"arc\"]\n", name)),
+        ("src/main.sl", "func main(argc: int, argv: const cstring*): int\n\tprintln(\"Hello, world!\")\n\treturn 0\nend\n".to_string()),
+        // What `sl build`, `sl emit` and `sl doc` write
+        (".gitignore", format!("/{}\n/src/*.c\n/src/*.h\n*.slbc\n/doc/\n", name)),
+    ]
+}
+// Creates a project in a new directory for `sl new`, or in the current one for
+// `sl init`, leaving any of its files that are already there alone. Returns the
+// exit code.
+pub(crate) fn create_project(args: &[String], init: bool) -> i32 {
+    let (usage, command): (&str, &str) = if init { (INIT_USAGE, "init") } else { (NEW_USAGE, "new") };
+    let mut name: Option<String> = None;
+    let mut directories: Vec<String> = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            println!("{}", usage);
+            return 0;
+        } else if arg == "--name" {
+            match args.next() {
+                Some(given) => name = Some(given.clone()),
+                None => {
+                    println!("{}", "`--name` needs a name".red());
+                    return Exit::Usage as i32;
+                }
+            }
+        } else if let Some(given) = arg.strip_prefix("--name=") {
+            name = Some(given.to_string());
+        } else if arg.starts_with('-') {
+            println!("{}", format!("unknown option `{}`, see `sl {} --help`", arg, command).red());
+            return Exit::Usage as i32;
+        } else {
+            directories.push(arg.clone());
+        }
+    }
+    let directory: std::path::PathBuf = match (init, directories.as_slice()) {
+        (true, []) => std::path::PathBuf::from("."),
+        (false, [directory]) => std::path::PathBuf::from(directory),
+        (false, []) => {
+            println!("{}", "`new` needs a directory to create, such as `sl new game`".red());
+            return Exit::Usage as i32;
+        }
+        (_, [_, extra, ..]) | (true, [extra, ..]) => {
+            println!("{}", format!("unexpected `{}`, see `sl {} --help`", extra, command).red());
+            return Exit::Usage as i32;
+        }
+    };
+    if !init && directory.read_dir().is_ok_and(|mut entries| entries.next().is_some()) {
+        println!("{}", format!("`{}` already exists and isn't empty, use `sl init` in it instead", directory.display()).red());
+        return Exit::Io as i32;
+    }
+    if init && directory.join(Manifest::FILENAME).exists() {
+        println!("{}", format!("there's already an {} here", Manifest::FILENAME).red());
+        return Exit::Io as i32;
+    }
+    // The project is named after its directory unless it's given a name
+    let name: String = match name {
+        Some(name) => name,
+        None => {
+            let path: std::path::PathBuf = std::fs::canonicalize(&directory).unwrap_or_else(|_| directory.clone());
+            path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default()
+        }
+    };
+    if name.is_empty() || name.starts_with('-') || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        println!("{}", format!("`{}` can't name a project, which takes letters, digits, `_` and `-`, see `--name`", name).red());
+        return Exit::Usage as i32;
+    }
+    for (filename, contents) in project_files(&name) {
+        let path: std::path::PathBuf = directory.join(filename);
+        if path.exists() {
+            println!("{}", format!("kept the `{}` that's already there", path.display()).yellow());
+            continue;
+        }
+        if let Err(error) = path.parent().map_or(Ok(()), std::fs::create_dir_all).and_then(|_| std::fs::write(&path, contents)) {
+            println!("{}", format!("can't write `{}`: {}", path.display(), error).red());
+            return Exit::Io as i32;
+        }
+    }
+    println!("{}", format!("created `{}` in {}, build and run it with `sl run`", name, directory.display()).green());
+    0
+}
+// Prints or writes the declarations for a C header, which the C compiler
+// preprocesses from an `#include` of it. Returns the exit code.
+pub(crate) fn bind_header(args: &[String]) -> i32 {
+    let mut output: Option<String> = None;
+    let mut compiler: Option<String> = None;
+    let mut cflags: Vec<String> = vec![];
+    let mut headers: Vec<String> = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            println!("{}", BINDGEN_USAGE);
+            return 0;
+        } else if arg == "-o" || arg == "--output" || arg == "--cflag" {
+            let Some(value) = args.next() else {
+                println!("{}", format!("`{}` needs a value, see `sl bindgen --help`", arg).red());
+                return Exit::Usage as i32;
+            };
+            if arg == "--cflag" {
+                cflags.push(value.clone());
+            } else {
+                output = Some(value.clone());
+            }
+        } else if let Some(path) = arg.strip_prefix("--output=") {
+            output = Some(path.to_string());
+        } else if let Some(flag) = arg.strip_prefix("--cflag=") {
+            cflags.push(flag.to_string());
+        } else if let Some(given) = arg.strip_prefix("--cc=") {
+            compiler = Some(given.to_string());
+        } else if arg.starts_with('-') {
+            println!("{}", format!("unknown option `{}`, see `sl bindgen --help`", arg).red());
+            return Exit::Usage as i32;
+        } else {
+            headers.push(arg.clone());
+        }
+    }
+    let header: String = match headers.as_slice() {
+        [header] => header.clone(),
+        [] => {
+            println!("{}", "`bindgen` needs a header, such as `sl bindgen std/time.h`".red());
+            return Exit::Usage as i32;
+        }
+        [_, extra, ..] => {
+            println!("{}", format!("unexpected `{}`, `bindgen` takes one header", extra).red());
+            return Exit::Usage as i32;
+        }
+    };
+    let compiler: String = compiler
+        .or_else(|| std::env::var("CC").ok().filter(|compiler| !compiler.trim().is_empty()))
+        .unwrap_or_else(|| "cc".to_string());
+    let words: Vec<&str> = compiler.split_whitespace().collect();
+    if words.is_empty() {
+        println!("{}", "--cc doesn't name a compiler".red());
+        return Exit::Usage as i32;
+    }
+    let mut command: std::process::Command = std::process::Command::new(words[0]);
+    command.args(&words[1..]).args(["-E", "-x", "c"]).args(&cflags).arg("-");
+    command.stdin(std::process::Stdio::piped()).stdout(std::process::Stdio::piped());
+    let preprocessed: std::io::Result<std::process::Output> = command.spawn().and_then(|mut child| {
+        use std::io::Write;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(Bindgen::include(&header).as_bytes())?;
+        }
+        child.wait_with_output()
+    });
+    let preprocessed: String = match preprocessed {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout).to_string(),
+        // The preprocessor has said what's wrong on stderr
+        Ok(_) => {
+            println!("{}", format!("`{}` couldn't preprocess `{}`", compiler, header).red());
+            return Exit::Compiler as i32;
+        }
+        Err(error) => {
+            println!("{}", format!("can't run `{}`: {}", compiler, error).red());
+            return Exit::Compiler as i32;
+        }
+    };
+    let mut bindgen: Bindgen = Bindgen::new(&header, &preprocessed);
+    bindgen.parse();
+    let source: String = bindgen.source();
+    match output {
+        Some(path) => {
+            if let Err(error) = std::fs::write(&path, source) {
+                println!("{}", format!("can't write `{}`: {}", path, error).red());
+                return Exit::Io as i32;
+            }
+        }
+        None => print_dump(&source),
+    }
+    0
+}
+// Writes a page for each module of the program, and an index of them, into the
+// output directory. Returns the exit code.
+pub(crate) fn document_files(args: &[String]) -> i32 {
+    let mut html: bool = false;
+    let mut output: String = "doc".to_string();
+    let mut filenames: Vec<String> = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            println!("{}", DOC_USAGE);
+            return 0;
+        } else if arg == "--html" {
+            html = true;
+        } else if arg == "-o" || arg == "--output" {
+            match args.next() {
+                Some(directory) => output = directory.clone(),
+                None => {
+                    println!("{}", format!("`{}` needs a directory to write to", arg).red());
+                    return Exit::Usage as i32;
+                }
+            }
+        } else if let Some(directory) = arg.strip_prefix("--output=") {
+            output = directory.to_string();
+        } else if arg.starts_with('-') {
+            println!("{}", format!("unknown option `{}`, see `sl doc --help`", arg).red());
+            return Exit::Usage as i32;
+        } else {
+            filenames.push(arg.clone());
+        }
+    }
+    if filenames.is_empty() {
+        match manifest_sources("doc") {
+            Ok(sources) => filenames = sources,
+            Err(error) => {
+                println!("{}", error.red());
+                return Exit::Usage as i32;
+            }
+        }
+    }
+    let mut importer: Importer = Importer::new();
+    for filename in filenames.iter() {
+        if let Err(error) = importer.load(filename) {
+            println!("{}", error.red());
+            return Exit::Io as i32;
+        }
+    }
+    for error in importer.errors.iter() {
+        report(&importer.sources, error, false);
+    }
+    if !importer.errors.is_empty() {
+        return Exit::Diagnostics as i32;
+    }
+    let extension: &str = if html { "html" } else { "md" };
+    let mut pages: Vec<(String, String)> = vec![];
+    for module in importer.modules.iter() {
+        let items: Vec<DocItem> = DocItem::collect(module, &importer.sources);
+        let page: String = if html { DocItem::html(&module.name, &items) } else { DocItem::markdown(&module.name, &items) };
+        let stem: String = std::path::Path::new(&module.name).file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        // Modules from different directories can share a name
+        let mut filename: String = format!("{}.{}", stem, extension);
+        let mut count: usize = 1;
+        while pages.iter().any(|(existing, _)| *existing == filename) || filename == format!("index.{}", extension) {
+            count += 1;
+            filename = format!("{}-{}.{}", stem, count, extension);
+        }
+        pages.push((filename, page));
+    }
+    let index: String = if html {
+        let links: Vec<String> = importer.modules.iter().zip(pages.iter()).map(|(module, (filename, _))| format!("<li><a href=\"{}\">{}</a></li>\n", DocItem::escape_html(filename), DocItem::escape_html(&module.name))).collect();
+        format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Modules</title>\n</head>\n<body>\n<h1>Modules</h1>\n<ul>\n{}</ul>\n</body>\n</html>\n", links.concat())
+    } else {
+        let links: Vec<String> = importer.modules.iter().zip(pages.iter()).map(|(module, (filename, _))| format!("- [{}]({})\n", module.name, filename)).collect();
+        format!("# Modules\n\n{}", links.concat())
+    };
+    pages.push((format!("index.{}", extension), index));
+    if let Err(error) = std::fs::create_dir_all(&output) {
+        println!("{}", format!("can't create `{}`: {}", output, error).red());
+        return Exit::Io as i32;
+    }
+    for (filename, page) in pages.iter() {
+        let path: std::path::PathBuf = std::path::Path::new(&output).join(filename);
+        if let Err(error) = std::fs::write(&path, page) {
+            println!("{}", format!("can't write `{}`: {}", path.display(), error).red());
+            return Exit::Io as i32;
+        }
+    }
+    println!("{}", format!("documented {} module{} in {}", importer.modules.len(), if importer.modules.len() == 1 { "" } else { "s" }, output).green());
+    0
+}
+pub(crate) fn build_program(options: &Options, program: Program) -> Result<(), Exit> {
+    if options.bytecode || options.dump.is_some() {
+        return emit(options, program, options.output.as_deref()).map(|_| ());
+    }
+    let link_flags: Vec<String> = program.checker.link_flags();
+    let sources: Vec<std::path::PathBuf> = emit(options, program, None)?;
+    build(options, &sources, &link_flags, &[], &executable_name(options))
+}
+// Builds the program, then polls the files it was read from and builds it
+// again when any of them changes, until the process is interrupted. A file
+// stays watched after it's deleted, so putting it back builds again.
+pub(crate) fn watch(options: &Options) -> ! {
+    let mut files: Vec<std::path::PathBuf> = vec![];
+    loop {
+        let succeeded: bool = match options.validate() {
+            Ok(()) => front_end(options).is_some_and(|program| build_program(options, program).is_ok()),
+            Err(error) => {
+                println!("{}", error.red());
+                false
+            }
+        };
+        if let Some(timings) = &options.timings {
+            timings.report();
+        }
+        for file in watched_files(options) {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+        let status: &str = if succeeded { "built" } else { "failed" };
+        println!("{}", format!("{}, watching {} file{} for changes", status, files.len(), if files.len() == 1 { "" } else { "s" }).yellow());
+        let modified = |files: &[std::path::PathBuf]| -> Vec<Option<std::time::SystemTime>> {
+            files.iter().map(|file| std::fs::metadata(file).and_then(|metadata| metadata.modified()).ok()).collect()
+        };
+        let before: Vec<Option<std::time::SystemTime>> = modified(&files);
+        while modified(&files) == before {
+            std::thread::sleep(std::time::Duration::from_millis(250));
+        }
+    }
+}
+// The files given, everything they import and the lint config next to the
+// first, whether or not they exist yet
+pub(crate) fn watched_files(options: &Options) -> Vec<std::path::PathBuf> {
+    let mut files: Vec<std::path::PathBuf> = vec![std::path::Path::new(&options.filename).with_file_name("sl.lints")];
+    let mut importer: Importer = Importer::new();
+    for filename in [&options.filename].into_iter().chain(options.others.iter()) {
+        files.push(std::fs::canonicalize(filename).unwrap_or_else(|_| filename.into()));
+        // One that's gone is still watched for coming back
+        let _ = importer.load(filename);
+    }
+    for module in importer.modules.iter() {
+        if !files.contains(&module.path) {
+            files.push(module.path.clone());
+        }
+    }
+    files
+}
+// Builds the program in a directory of its own under the system's temporary
+// one and runs it there, returning its exit code. Only an executable asked for
+// with --output is kept.
+pub(crate) fn run(options: &Options, program: Program) -> i32 {
+    let directory: std::path::PathBuf = std::env::temp_dir().join(format!("sl-run-{}", std::process::id()));
+    let executable: std::path::PathBuf = match &options.output {
+        Some(_) => executable_name(options),
+        None => directory.join(options.executable().file_name().unwrap_or_default()),
+    };
+    let link_flags: Vec<String> = program.checker.link_flags();
+    // The generated files aren't next to the program's, so the headers it
+    // imports are looked for where its files are and where it's built from
+    let mut includes: Vec<std::path::PathBuf> = vec![std::path::PathBuf::from(".")];
+    for module in program.modules.iter() {
+        if let Some(parent) = module.path.parent().filter(|parent| !parent.as_os_str().is_empty() && !includes.iter().any(|include| include == parent)) {
+            includes.push(parent.to_path_buf());
+        }
+    }
+    let built: Result<(), Exit> = emit(options, program, Some(&format!("{}/", directory.display()))).and_then(|sources| build(options, &sources, &link_flags, &includes, &executable));
+    let code: i32 = match built {
+        Ok(()) => {
+            if let Some(timings) = &options.timings {
+                timings.report();
+            }
+            // A bare name would be looked for on the PATH
+            let executable: std::path::PathBuf = std::path::Path::new(".").join(&executable);
+            match std::process::Command::new(&executable).args(&options.arguments).status() {
+                Ok(status) => exit_code(status),
+                Err(error) => {
+                    println!("{}", format!("can't run `{}`: {}", executable.display(), error).red());
+                    Exit::Io as i32
+                }
+            }
+        }
+        Err(exit) => exit as i32,
+    };
+    let _ = std::fs::remove_dir_all(&directory);
+    code
+}
+// A program killed by a signal exits with 128 and the signal, as in a shell
+pub(crate) fn exit_code(status: std::process::ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(1)
+}
+// Prints the tokens of a file, or what each is for highlighting, one per line
+// with where it starts, the file written back out from its syntax tree, or
+// the syntax tree of the program it's the start of
+pub(crate) fn print_syntax(options: &Options, dump: Dump) -> bool {
+    let filename: &str = options.source_name();
+    if dump == Dump::Ast {
+        let mut importer: Importer = Importer::new();
+        let loaded: Result<usize, String> = match &options.stdin {
+            Some(contents) => Ok(importer.load_contents(filename, contents.clone())),
+            None => importer.load(filename),
+        };
+        if let Err(error) = loaded.and_then(|_| options.others.iter().try_for_each(|filename| importer.load(filename).map(|_| ()))) {
+            options.say(error.red());
+            return false;
+        }
+        for error in importer.errors.iter() {
+            options.report(&importer.sources, error);
+        }
+        if importer.errors.is_empty() {
+            print_dump(&format!("{:#?}\n", importer.program()));
+        }
+        return importer.errors.is_empty();
+    }
+    let semantic: bool = dump == Dump::SemanticTokens;
+    let source: bool = dump == Dump::Source;
+    let mut success: bool = true;
+    for filename in [filename].into_iter().chain(options.others.iter().map(String::as_str)) {
+        let contents: String = match options.stdin.clone().map_or_else(|| std::fs::read_to_string(filename), Ok) {
+            Ok(contents) => contents,
+            Err(error) => {
+                options.say(source_error(filename, &error).red());
+                return false;
+            }
+        };
+        let mut sources: SourceMap = SourceMap::new();
+        sources.add(filename.to_string(), contents.clone());
+        // Each file's tokens get a header once there's more than one file
+        let mut dump: String = if options.others.is_empty() { String::new() } else { format!("==> {} <==\n", filename) };
+        // Telling names apart takes checking the file, but only its errors stop the dump from succeeding
+        if semantic {
+            let ide: Ide = Ide::new(filename.to_string(), contents.clone());
+            for (location, kind) in ide.semantic_tokens() {
+                let (_, line, column): (String, usize, usize) = sources.position(&location);
+                dump.push_str(&format!("{}:{} {} {:?}\n", line, column, kind, contents.get(location.start..location.end).unwrap_or_default()));
+            }
+            print_dump(&dump);
+            let errors: Vec<&Diagnostic> = ide.diagnostics().into_iter().filter(|error| !error.is_warning()).collect();
+            for error in errors.iter() {
+                options.report(&ide.sources, error);
+            }
+            success &= errors.is_empty();
+            continue;
+        }
+        // The file as the parser read it, without its comments or layout
+        if source {
+            let mut parser: Parser = Parser::new(Lexer::new(contents), 0);
+            let statements: Vec<Statement> = parser.parse();
+            let errors: Vec<Diagnostic> = [parser.lexer.errors, parser.errors].concat();
+            if errors.is_empty() {
+                dump.push_str(&Unparser::unparse(&statements));
+                print_dump(&dump);
+            }
+            for error in errors.iter() {
+                options.report(&sources, error);
+            }
+            success &= errors.is_empty();
+            continue;
+        }
+        let mut lexer: Lexer = Lexer::new(contents);
+        for token in lexer.lex().iter() {
+            let (_, line, column): (String, usize, usize) = sources.position(&token.location);
+            dump.push_str(&format!("{}:{} {:?} {:?}\n", line, column, token.kind, token.value));
+        }
+        print_dump(&dump);
+        for error in lexer.errors.iter() {
+            options.report(&sources, error);
+        }
+        success &= lexer.errors.is_empty();
+    }
+    success
+}
+// Prints a diagnostic the way --message-format asks for
+pub(crate) fn report(sources: &SourceMap, error: &Diagnostic, json: bool) {
+    if json {
+        println!("{}", sources.render_json(error));
+    } else {
+        println!("{}", sources.render(error));
+    }
+}
+// Dumps are often piped into something that stops reading early, which isn't an error
+pub(crate) fn print_dump(dump: &str) {
+    use std::io::Write;
+    let _ = std::io::stdout().lock().write_all(dump.as_bytes());
+}
+// Reads, checks, lints and optimizes a program, printing what's found along the
+// way. Nothing comes back if there were errors.
+pub(crate) fn front_end(options: &Options) -> Option<Program> {
+    let mut lints: LintConfig = LintConfig::new();
+    let config_filename: std::path::PathBuf = std::path::Path::new(&options.filename).with_file_name("sl.lints");
+    if let Ok(contents) = std::fs::read_to_string(&config_filename) {
+        if let Err(error) = lints.parse(&contents) {
+            options.say(format!("{}: {}", config_filename.display(), error).red());
+            return None;
+        }
+    }
+    lints.levels.extend(options.levels.iter().cloned());
+    lints.deny_warnings |= options.deny_warnings;
+
+    let mut importer: Importer = Importer::new();
+    let loaded: Result<usize, String> = match &options.stdin {
+        Some(contents) => Ok(importer.load_contents(options.source_name(), contents.clone())),
+        None => importer.load(&options.filename),
+    };
+    // A file can go away after it's checked for, as while watching
+    if let Err(error) = loaded.and_then(|_| options.others.iter().try_for_each(|filename| importer.load(filename).map(|_| ()))) {
+        options.say(error.red());
+        return None;
+    }
+    if let Some(timings) = &options.timings {
+        timings.add("parsing", importer.parsing);
+        timings.count("tokens", importer.tokens);
+        timings.count("statements", importer.modules.iter().map(|module| Statement::count(&module.statements)).sum());
+    }
+    let compile_options: CompileOptions = options.compile_options();
+    let time = |phase: &'static str, duration: std::time::Duration| {
+        if let Some(timings) = &options.timings {
+            timings.add(phase, duration);
+        }
+    };
+    let program: Program = match compile::front_end(importer, lints, &compile_options, time) {
+        Ok(program) => program,
+        Err(error) => {
+            for diagnostic in error.diagnostics.iter() {
+                options.report(&error.sources, diagnostic);
+            }
+            return None;
+        }
+    };
+    for warning in program.warnings.iter() {
+        options.report(&program.sources, warning);
+    }
+    // Nothing is generated from a check, so there's nothing to optimize
+    match options.command {
+        Command::Check => Some(program),
+        _ => Some(options.time("optimizing", || compile::optimize(program, &compile_options))),
+    }
+}
+pub(crate) fn compile_bytecode(options: &Options, program: &Program) -> Option<Bytecode> {
+    let mut compiler: BytecodeCompiler = BytecodeCompiler::new();
+    compiler.member_types = program.checker.member_types.clone();
+    compiler.methods = program.checker.methods.clone();
+    compiler.prints = program.checker.prints.clone();
+    compiler.iterated = program.checker.loops.clone();
+    compiler.entry = program.checker.entry;
+    let bytecode: Bytecode = options.time("bytecode", || compiler.compile(&program.statements));
+    if !compiler.errors.is_empty() {
+        for error in compiler.errors.iter() {
+            options.report(&program.sources, error);
+        }
+        return None;
+    }
+    Some(bytecode)
+}
+// Writes the generated files, returning the sources to compile: the C or C++
+// of each, or nothing for bytecode. They go next to the program's unless
+// `output` names a file or a directory for them.
+pub(crate) fn emit(options: &Options, program: Program, output: Option<&str>) -> Result<Vec<std::path::PathBuf>, Exit> {
+    let directory: Option<std::path::PathBuf> = output.and_then(output_directory);
+    let sources_read: Vec<std::path::PathBuf> = program.modules.iter().map(|module| module.path.clone()).collect();
+    // A file of the program, given another extension and put in the output directory
+    let generated = |filename: &str, extension: &str| -> std::path::PathBuf {
+        let path: std::path::PathBuf = std::path::Path::new(filename).with_extension(extension);
+        match (&directory, path.file_name()) {
+            (Some(directory), Some(name)) => directory.join(name),
+            _ => path,
+        }
+    };
+    if options.bytecode {
+        let bytecode: Bytecode = compile_bytecode(options, &program).ok_or(Exit::Diagnostics)?;
+        let path: std::path::PathBuf = match (output, &directory) {
+            (Some(output), None) => std::path::PathBuf::from(output),
+            _ => generated(options.path(), "slbc"),
+        };
+        return write_output(&path, &bytecode.serialize(), &sources_read).then(Vec::new).ok_or(Exit::Io);
+    }
+    let compile_options: CompileOptions = options.compile_options();
+    let mut codegen: Codegen = compile_options.codegen(&program);
+    codegen.testing = options.command == Command::Test;
+    codegen.benchmarking = options.command == Command::Bench;
+    let (source_extension, header_extension): (&str, &str) = if options.cpp { ("cpp", "hpp") } else { ("c", "h") };
+    if options.per_module {
+        let files: Vec<(String, String)> = options.time("codegen", || codegen.codegen_modules(&program.modules));
+        if !codegen.errors.is_empty() {
+            for error in codegen.errors.iter() {
+                options.report(&program.sources, error);
+            }
+            return Err(Exit::Diagnostics);
+        }
+        // The modules keep their places relative to each other, so that the
+        // headers they include are still where they say
+        let root: std::path::PathBuf = common_directory(&sources_read);
+        let mut written: Vec<std::path::PathBuf> = vec![];
+        for (module, (code, header)) in program.modules.iter().zip(files) {
+            let base: std::path::PathBuf = match &directory {
+                Some(directory) => directory.join(module.path.strip_prefix(&root).unwrap_or(&module.path)),
+                None => std::path::PathBuf::from(&module.name),
+            };
+            let source_path: std::path::PathBuf = base.with_extension(source_extension);
+            if !write_output(&source_path, code.as_bytes(), &sources_read) || !write_output(&base.with_extension(header_extension), header.as_bytes(), &sources_read) {
+                return Err(Exit::Io);
+            }
+            written.push(source_path);
+        }
+        return Ok(written);
+    }
+    if options.dump == Some(Dump::C) {
+        let code: String = options.time("codegen", || codegen.codegen());
+        if !codegen.errors.is_empty() {
+            for error in codegen.errors.iter() {
+                options.report(&program.sources, error);
+            }
+            return Err(Exit::Diagnostics);
+        }
+        print_dump(&code);
+        return Ok(vec![]);
+    }
+
+    let output_filename: std::path::PathBuf = match (output, &directory) {
+        (Some(output), None) => std::path::PathBuf::from(output),
+        _ => generated(options.path(), source_extension),
+    };
+    // The C goes straight into its file as it's generated rather than being
+    // built up in memory first
+    let mut out: std::io::BufWriter<std::fs::File> = match create_output(&output_filename, &sources_read) {
+        Some(file) => std::io::BufWriter::new(file),
+        None => return Err(Exit::Io),
+    };
+    let written: std::io::Result<()> = options.time("codegen", || codegen.codegen_to(&mut out).and_then(|_| std::io::Write::flush(&mut out)));
+    if !codegen.errors.is_empty() {
+        drop(out);
+        let _ = std::fs::remove_file(&output_filename);
+        for error in codegen.errors.iter() {
+            options.report(&program.sources, error);
+        }
+        return Err(Exit::Diagnostics);
+    }
+    if let Err(error) = written {
+        println!("{}", format!("can't write `{}`: {}", output_filename.display(), error).red());
+        return Err(Exit::Io);
+    }
+    let header_filename: std::path::PathBuf = output_filename.with_extension(header_extension);
+    let mut header: Codegen = compile_options.codegen(&program);
+    let header: String = options.time("codegen", || header.header());
+    if !write_output(&header_filename, header.as_bytes(), &sources_read) {
+        return Err(Exit::Io);
+    }
+    Ok(vec![output_filename])
+}
+// Writes a generated file, making its directory if needed, but never over one
+// of the program's own sources
+pub(crate) fn write_output(path: &std::path::Path, contents: &[u8], sources: &[std::path::PathBuf]) -> bool {
+    use std::io::Write;
+    let mut file: std::fs::File = match create_output(path, sources) {
+        Some(file) => file,
+        None => return false,
+    };
+    if let Err(error) = file.write_all(contents) {
+        println!("{}", format!("can't write `{}`: {}", path.display(), error).red());
+        return false;
+    }
+    true
+}
+// Opens a generated file to be written as it's made, on the same terms
+pub(crate) fn create_output(path: &std::path::Path, sources: &[std::path::PathBuf]) -> Option<std::fs::File> {
+    if std::fs::canonicalize(path).is_ok_and(|path| sources.contains(&path)) {
+        println!("{}", format!("`{}` is a source of the program, so it won't be written over", path.display()).red());
+        return None;
+    }
+    let created: std::io::Result<std::fs::File> = match path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        Some(parent) => std::fs::create_dir_all(parent).and_then(|_| std::fs::File::create(path)),
+        None => std::fs::File::create(path),
+    };
+    match created {
+        Ok(file) => Some(file),
+        Err(error) => {
+            println!("{}", format!("can't write `{}`: {}", path.display(), error).red());
+            None
+        }
+    }
+}
+// The deepest directory holding every one of these files
+pub(crate) fn common_directory(paths: &[std::path::PathBuf]) -> std::path::PathBuf {
+    let mut root: std::path::PathBuf = paths.first().and_then(|path| path.parent()).map(|parent| parent.to_path_buf()).unwrap_or_default();
+    while !paths.iter().all(|path| path.starts_with(&root)) && root.pop() {}
+    root
+}
+// The directory an --output names, if it's one: it ends in a slash or exists
+pub(crate) fn output_directory(output: &str) -> Option<std::path::PathBuf> {
+    (output.ends_with('/') || std::path::Path::new(output).is_dir()).then(|| std::path::PathBuf::from(output))
+}
+// The executable `build` writes: the one given with --output, in the directory
+// it names, or the program's name without its extension
+pub(crate) fn executable_name(options: &Options) -> std::path::PathBuf {
+    let name: std::path::PathBuf = options.executable();
+    match (&options.output, options.output.as_deref().and_then(output_directory)) {
+        (_, Some(directory)) => directory.join(name.file_name().unwrap_or_default()),
+        (Some(output), None) => std::path::PathBuf::from(output),
+        (None, None) => name,
+    }
+}
+// The compiler from --cc, $CC or $CXX, or else the first of the usual ones that
+// answers --version. A compiler can be a command with arguments, as in `zig cc`.
+pub(crate) fn find_compiler(options: &Options) -> Result<String, String> {
+    let variable: &str = if options.cpp { "CXX" } else { "CC" };
+    let given: Option<(String, String)> = match (&options.compiler, std::env::var(variable)) {
+        (Some(compiler), _) => Some((compiler.clone(), "--cc".to_string())),
+        (None, Ok(compiler)) if !compiler.trim().is_empty() => Some((compiler, format!("${}", variable))),
+        _ => None,
+    };
+    let runs = |compiler: &str| -> Result<bool, std::io::Error> {
+        let words: Vec<&str> = compiler.split_whitespace().collect();
+        let output: std::process::Output = std::process::Command::new(words[0]).args(&words[1..]).arg("--version").output()?;
+        Ok(output.status.success())
+    };
+    // What's asked for only has to run, since not every compiler knows --version
+    if let Some((compiler, source)) = given {
+        if compiler.trim().is_empty() {
+            return Err(format!("{} doesn't name a compiler", source));
+        }
+        return match runs(&compiler) {
+            Ok(_) => Ok(compiler),
+            Err(error) => Err(format!("can't run `{}` from {}: {}", compiler, source, error)),
+        };
+    }
+    let candidates: [&str; 4] = if options.cpp { ["c++", "g++", "clang++", "zig c++"] } else { ["cc", "gcc", "clang", "zig cc"] };
+    match candidates.iter().find(|compiler| runs(compiler).unwrap_or(false)) {
+        Some(compiler) => Ok(compiler.to_string()),
+        None => Err(format!(
+            "no {} compiler found, tried {}. Install one, or name it with --cc=<compiler> or ${}",
+            if options.cpp { "C++" } else { "C" },
+            candidates.map(|compiler| format!("`{}`", compiler)).join(", "),
+            variable
+        )),
+    }
+}
+// Compiles the generated sources into an executable with the C or C++ compiler,
+// linked with what the program's imports need as well as any --lflag. Headers
+// are looked for in `includes` after any --cflag's directories.
+pub(crate) fn build(options: &Options, sources: &[std::path::PathBuf], link_flags: &[String], includes: &[std::path::PathBuf], executable: &std::path::Path) -> Result<(), Exit> {
+    if std::fs::canonicalize(&options.filename).is_ok_and(|program| std::fs::canonicalize(executable).is_ok_and(|executable| executable == program)) {
+        println!("{}", format!("`{}` is the program itself, give the executable another name with --output", executable.display()).red());
+        return Err(Exit::Usage);
+    }
+    if let Some(parent) = executable.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        if let Err(error) = std::fs::create_dir_all(parent) {
+            println!("{}", format!("can't write `{}`: {}", executable.display(), error).red());
+            return Err(Exit::Io);
+        }
+    }
+    let compiler: String = options.compiler.clone().unwrap_or_else(|| (if options.cpp { "c++" } else { "cc" }).to_string());
+    let words: Vec<&str> = compiler.split_whitespace().collect();
+    let mut command: std::process::Command = std::process::Command::new(words[0]);
+    command.args(&words[1..]);
+    command.args(if options.release { ["-O2", "-DNDEBUG"] } else { ["-g", "-O0"] });
+    command.args(&options.cflags).args(includes.iter().map(|include| format!("-I{}", include.display()))).arg("-o").arg(executable).args(sources).args(&options.lflags);
+    command.args(link_flags.iter().filter(|flag| !options.lflags.contains(flag)));
+    if options.gc {
+        command.arg("-lgc");
+    }
+    match options.time("cc", || command.status()) {
+        Ok(status) if status.success() => Ok(()),
+        Ok(_) => {
+            println!("{}", format!("`{}` couldn't compile the generated code", compiler).red());
+            Err(Exit::Compiler)
+        }
+        Err(error) => {
+            println!("{}", format!("can't run `{}`: {}", compiler, error).red());
+            Err(Exit::Compiler)
+        }
+    }
+}
+pub(crate) fn run_bytecode(filename: &str, args: &[String]) {
+    let program: Bytecode = match std::fs::read(filename).map_err(|error| error.to_string()).and_then(|bytes| Bytecode::deserialize(&bytes)) {
+        Ok(program) => program,
+        Err(error) => {
+            println!("{}", format!("{}: {}", filename, error).red());
+            std::process::exit(Exit::Io as i32);
+        }
+    };
+    std::process::exit(execute(&program, args));
+}
+// Runs a program in the VM, returning its exit code
+pub(crate) fn execute(program: &Bytecode, args: &[String]) -> i32 {
+    let result: Result<i64, String> = Vm::new(program).run(args);
+    use std::io::Write;
+    let _ = std::io::stdout().flush();
+    match result {
+        Ok(code) => code as i32,
+        Err(error) => {
+            eprintln!("{}", format!("runtime error: {}", error).red());
+            1
+        }
+    }
+}
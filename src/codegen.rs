@@ -0,0 +1,2746 @@
+use std::collections::{HashMap, HashSet};
+use crate::ast::{Annotation, Expression, Statement, Type, type_graph};
+use crate::checker::{format_pieces, Checker, PrintConversion, StringConversion, LINKED_HEADERS};
+use crate::diagnostics::{Diagnostic, SourceFile, SourceMap};
+use crate::fold::{Folder, Substitution};
+use crate::importer::Module;
+use crate::lexer::{TokenKind, TokenLocation};
+use crate::symbol::Symbol;
+use crate::target::TargetMachine;
+use crate::visit::{self, Visitor};
+// Where the runtimes below get their memory from
+pub const ALLOCATOR: &str = "#define SL_MALLOC(size) malloc(size)
+#define SL_CALLOC(count, size) calloc(count, size)
+#define SL_REALLOC(pointer, size) realloc(pointer, size)
+#define SL_FREE(pointer) free(pointer)
+";
+// With --gc everything is left to the Boehm collector
+pub const GC_ALLOCATOR: &str = "// Link with -lgc
+#include <gc.h>
+#define SL_MALLOC(size) GC_MALLOC(size)
+#define SL_CALLOC(count, size) GC_MALLOC((count) * (size))
+#define SL_REALLOC(pointer, size) GC_REALLOC(pointer, size)
+#define SL_FREE(pointer) GC_FREE(pointer)
+";
+// The `string` type: a pointer and a length, so neither a length query nor a
+// slice has to go looking for a terminator
+pub const STRING_RUNTIME: &str = "#include <string.h>
+typedef struct { const char* data; size_t length; } sl_string;
+#define SL_STRING(literal) ((sl_string){ (literal), sizeof(literal) - 1 })
+static inline sl_string sl_string_from_cstring(const char* data) {
+    return (sl_string){ data, strlen(data) };
+}
+static inline bool sl_string_equals(sl_string left, sl_string right) {
+    return left.length == right.length && memcmp(left.data, right.data, left.length) == 0;
+}
+static inline char* sl_string_to_cstring(sl_string string) {
+    char* data = (char*)SL_MALLOC(string.length + 1);
+    memcpy(data, string.data, string.length);
+    data[string.length] = '\\0';
+    return data;
+}
+";
+// With --target=c++ a `string` is a `std::string`; turning one into a C string
+// still hands back a copy the caller owns
+pub const CPP_STRING_RUNTIME: &str = "#include <string>
+#include <string.h>
+static inline char* sl_string_to_cstring(const std::string& string) {
+    char* data = (char*)SL_MALLOC(string.size() + 1);
+    memcpy(data, string.c_str(), string.size() + 1);
+    return data;
+}
+";
+// printf would need a `string`'s length and pointer as two arguments, so
+// `print` writes one on its own
+pub const PRINT_STRING_RUNTIME: &str = "static inline void sl_print_string(sl_string string) {
+    fwrite(string.data, 1, string.length, stdout);
+}
+";
+// `T[]` stays a `T*` so it can be indexed like any other pointer, with its
+// length and capacity kept in a header just before the first element
+pub const ARRAY_RUNTIME: &str = "#include <stdio.h>
+#include <string.h>
+typedef struct { size_t length; size_t capacity; } sl_array_header;
+#define SL_ARRAY_HEADER(array) ((sl_array_header*)(array) - 1)
+static inline size_t sl_array_length(const void* array) {
+    return array ? SL_ARRAY_HEADER(array)->length : 0;
+}
+static inline void* sl_array_grow(void* array, size_t element_size) {
+    size_t length = sl_array_length(array);
+    if (array && length < SL_ARRAY_HEADER(array)->capacity) {
+        return array;
+    }
+    size_t capacity = length ? length * 2 : 4;
+    sl_array_header* header = (sl_array_header*)SL_REALLOC(array ? SL_ARRAY_HEADER(array) : NULL, sizeof(sl_array_header) + capacity * element_size);
+    if (!header) {
+        abort();
+    }
+    header->length = length;
+    header->capacity = capacity;
+    return header + 1;
+}
+static inline void* sl_array_from(size_t count, const void* items, size_t element_size) {
+    sl_array_header* header = (sl_array_header*)SL_MALLOC(sizeof(sl_array_header) + count * element_size);
+    if (!header) {
+        abort();
+    }
+    header->length = count;
+    header->capacity = count;
+    memcpy(header + 1, items, count * element_size);
+    return header + 1;
+}
+static inline size_t sl_array_check(const void* array, size_t index) {
+    if (index >= sl_array_length(array)) {
+        fprintf(stderr, \"index %zu is out of bounds for an array of length %zu\\n\", index, sl_array_length(array));
+        abort();
+    }
+    return index;
+}
+static inline size_t sl_array_pop_index(void* array) {
+    if (sl_array_length(array) == 0) {
+        fprintf(stderr, \"pop from an empty array\\n\");
+        abort();
+    }
+    return --SL_ARRAY_HEADER(array)->length;
+}
+#define sl_array_push(array, value) ((array) = (__typeof__(array))sl_array_grow((array), sizeof(*(array))), (array)[SL_ARRAY_HEADER(array)->length++] = (value))
+";
+// Keys are hashed with FNV-1a at the width of the target's `size_t`, or 32 bits
+// where that's narrower
+pub const MAP_HASH_64: &str = "#include <stdint.h>
+typedef uint64_t sl_map_hash_t;
+#define SL_MAP_FNV_OFFSET 14695981039346656037ULL
+#define SL_MAP_FNV_PRIME 1099511628211ULL
+";
+pub const MAP_HASH_32: &str = "#include <stdint.h>
+typedef uint32_t sl_map_hash_t;
+#define SL_MAP_FNV_OFFSET 2166136261UL
+#define SL_MAP_FNV_PRIME 16777619UL
+";
+// `map[K, V]` is an open-addressing hash table behind a pointer. C string keys
+// are stored as the pointer, so the characters have to outlive the map
+pub const MAP_RUNTIME: &str = "typedef struct {
+    size_t key_size;
+    size_t value_size;
+    // 0 compares keys bytewise, 1 as C strings, 2 as strings
+    int key_kind;
+    size_t length;
+    // Slots that are full or were removed, which probing has to step over
+    size_t used;
+    size_t capacity;
+    char* states;
+    char* keys;
+    char* values;
+} sl_map;
+enum { SL_MAP_EMPTY, SL_MAP_FULL, SL_MAP_REMOVED };
+static inline size_t sl_map_hash(const sl_map* map, const void* key) {
+    const unsigned char* bytes = (const unsigned char*)key;
+    size_t size = map->key_size;
+    if (map->key_kind == 1) {
+        bytes = *(const unsigned char* const*)key;
+        size = strlen((const char*)bytes);
+    } else if (map->key_kind == 2) {
+        bytes = (const unsigned char*)((const sl_string*)key)->data;
+        size = ((const sl_string*)key)->length;
+    }
+    sl_map_hash_t hash = SL_MAP_FNV_OFFSET;
+    for (size_t i = 0; i < size; i++) {
+        hash = (hash ^ bytes[i]) * SL_MAP_FNV_PRIME;
+    }
+    return (size_t)hash;
+}
+static inline bool sl_map_key_equals(const sl_map* map, const void* left, const void* right) {
+    if (map->key_kind == 1) {
+        return strcmp(*(const char* const*)left, *(const char* const*)right) == 0;
+    } else if (map->key_kind == 2) {
+        return sl_string_equals(*(const sl_string*)left, *(const sl_string*)right);
+    }
+    return memcmp(left, right, map->key_size) == 0;
+}
+static inline size_t sl_map_find(const sl_map* map, const void* key) {
+    if (!map || map->capacity == 0) {
+        return (size_t)-1;
+    }
+    size_t mask = map->capacity - 1;
+    for (size_t slot = sl_map_hash(map, key) & mask;; slot = (slot + 1) & mask) {
+        if (map->states[slot] == SL_MAP_EMPTY) {
+            return (size_t)-1;
+        }
+        if (map->states[slot] == SL_MAP_FULL && sl_map_key_equals(map, map->keys + slot * map->key_size, key)) {
+            return slot;
+        }
+    }
+}
+static inline void* sl_map_insert(sl_map* map, const void* key) {
+    size_t mask = map->capacity - 1;
+    size_t slot = sl_map_hash(map, key) & mask;
+    while (map->states[slot] == SL_MAP_FULL) {
+        slot = (slot + 1) & mask;
+    }
+    if (map->states[slot] == SL_MAP_EMPTY) {
+        map->used++;
+    }
+    map->states[slot] = SL_MAP_FULL;
+    map->length++;
+    memcpy(map->keys + slot * map->key_size, key, map->key_size);
+    return map->values + slot * map->value_size;
+}
+static inline void sl_map_resize(sl_map* map, size_t capacity) {
+    sl_map old = *map;
+    map->length = 0;
+    map->used = 0;
+    map->capacity = capacity;
+    map->states = (char*)SL_CALLOC(capacity, 1);
+    map->keys = (char*)SL_MALLOC(capacity * map->key_size);
+    map->values = (char*)SL_MALLOC(capacity * map->value_size);
+    if (!map->states || !map->keys || !map->values) {
+        abort();
+    }
+    for (size_t slot = 0; slot < old.capacity; slot++) {
+        if (old.states[slot] == SL_MAP_FULL) {
+            memcpy(sl_map_insert(map, old.keys + slot * old.key_size), old.values + slot * old.value_size, old.value_size);
+        }
+    }
+    SL_FREE(old.states);
+    SL_FREE(old.keys);
+    SL_FREE(old.values);
+}
+// The value stored under key, inserted as zeroes if it isn't there yet. A map
+// is created on its first insert, so a null map is an empty one
+static inline void* sl_map_put(sl_map** map, const void* key, size_t key_size, size_t value_size, int key_kind) {
+    if (!*map) {
+        *map = (sl_map*)SL_CALLOC(1, sizeof(sl_map));
+        if (!*map) {
+            abort();
+        }
+        (*map)->key_size = key_size;
+        (*map)->value_size = value_size;
+        (*map)->key_kind = key_kind;
+    }
+    size_t slot = sl_map_find(*map, key);
+    if (slot != (size_t)-1) {
+        return (*map)->values + slot * (*map)->value_size;
+    }
+    if (((*map)->used + 1) * 4 > (*map)->capacity * 3) {
+        // Only grow when the map is actually full rather than full of removed slots
+        size_t capacity = (*map)->capacity;
+        sl_map_resize(*map, capacity == 0 ? 8 : ((*map)->length + 1) * 2 > capacity ? capacity * 2 : capacity);
+    }
+    void* value = sl_map_insert(*map, key);
+    memset(value, 0, value_size);
+    return value;
+}
+static inline void* sl_map_at(const sl_map* map, const void* key) {
+    size_t slot = sl_map_find(map, key);
+    if (slot == (size_t)-1) {
+        fprintf(stderr, \"key not found in map\\n\");
+        abort();
+    }
+    return map->values + slot * map->value_size;
+}
+static inline bool sl_map_contains(const sl_map* map, const void* key) {
+    return sl_map_find(map, key) != (size_t)-1;
+}
+static inline bool sl_map_remove(sl_map* map, const void* key) {
+    size_t slot = sl_map_find(map, key);
+    if (slot == (size_t)-1) {
+        return false;
+    }
+    map->states[slot] = SL_MAP_REMOVED;
+    map->length--;
+    return true;
+}
+static inline size_t sl_map_length(const sl_map* map) {
+    return map ? map->length : 0;
+}
+static inline sl_map* sl_map_from(size_t count, const void* keys, const void* values, size_t key_size, size_t value_size, int key_kind) {
+    sl_map* map = NULL;
+    for (size_t i = 0; i < count; i++) {
+        memcpy(sl_map_put(&map, (const char*)keys + i * key_size, key_size, value_size, key_kind), (const char*)values + i * value_size, value_size);
+    }
+    return map;
+}
+// The keys or the values as a T[], in no particular order but in the same
+// one for both while the map is unchanged, so keys()[i] goes with values()[i]
+static inline void* sl_map_collect(const sl_map* map, bool keys) {
+    void* array = NULL;
+    if (!map) {
+        return NULL;
+    }
+    size_t size = keys ? map->key_size : map->value_size;
+    for (size_t slot = 0; slot < map->capacity; slot++) {
+        if (map->states[slot] != SL_MAP_FULL) {
+            continue;
+        }
+        array = sl_array_grow(array, size);
+        memcpy((char*)array + SL_ARRAY_HEADER(array)->length++ * size, keys ? map->keys + slot * map->key_size : map->values + slot * map->value_size, size);
+    }
+    return array;
+}
+";
+// With --arc, objects made by `new` carry a reference count that the generated
+// code keeps up to date, and are freed once nothing refers to them. Only
+// pointers to structs the program never allocates any other way are counted,
+// so every object these are given has a header.
+pub const ARC_RUNTIME: &str = "typedef struct {
+    size_t count;
+    void (*drop)(void*);
+} sl_rc_header;
+#define SL_RC_HEADER(object) ((sl_rc_header*)(object) - 1)
+static inline void* sl_rc_alloc(size_t size, void (*drop)(void*)) {
+    sl_rc_header* header = (sl_rc_header*)calloc(1, sizeof(sl_rc_header) + size);
+    if (!header) {
+        abort();
+    }
+    header->count = 1;
+    header->drop = drop;
+    return header + 1;
+}
+static inline void* sl_retain(void* object) {
+    if (object) {
+        SL_RC_HEADER(object)->count++;
+    }
+    return object;
+}
+static inline void sl_release(void* object) {
+    if (!object) {
+        return;
+    }
+    sl_rc_header* header = SL_RC_HEADER(object);
+    if (--header->count > 0) {
+        return;
+    }
+    if (header->drop) {
+        header->drop(object);
+    }
+    free(header);
+}
+// Stores value, which the slot now owns, and lets go of what was there
+static inline void* sl_rc_replace(void** slot, void* value) {
+    void* old = *slot;
+    *slot = value;
+    sl_release(old);
+    return value;
+}
+";
+// Without --release, or with --debug, pointers are checked before `->` and `*`
+// go through them
+pub const NULL_CHECK_RUNTIME: &str = "#include <stdio.h>
+static inline void* sl_check_null(const void* pointer, const char* location) {
+    if (!pointer) {
+        fprintf(stderr, \"%s: null pointer dereference\\n\", location);
+        abort();
+    }
+    return (void*)pointer;
+}
+#define SL_CHECK_NULL(pointer, location) ((__typeof__(pointer))sl_check_null((pointer), (location)))
+";
+// `assert` says where it failed and stops the program, unless it's built with
+// -DNDEBUG. Under `sl test` it jumps back to the harness, which moves on to the
+// next test.
+pub const ASSERT_RUNTIME: &str = "#include <stdio.h>
+#include <setjmp.h>
+static jmp_buf* sl_test_jump = NULL;
+static inline void sl_assert_failed(const char* location, const char* message) {
+    fflush(stdout);
+    fprintf(stderr, \"%s: assertion failed%s%s\\n\", location, message ? \": \" : \"\", message ? message : \"\");
+    if (sl_test_jump) {
+        longjmp(*sl_test_jump, 1);
+    }
+    abort();
+}
+#if defined(NDEBUG) && !defined(SL_TEST)
+#define SL_ASSERT(condition, location, message) ((void)0)
+#else
+#define SL_ASSERT(condition, location, message) ((condition) ? (void)0 : sl_assert_failed((location), (message)))
+#endif
+";
+pub const FREESTANDING_ASSERT_RUNTIME: &str = "#define SL_ASSERT(condition, location, message) ((condition) ? (void)0 : __builtin_trap())
+";
+// Where the generated `main` keeps its arguments for a program that declares
+// these, as std.os does. They're weak so that every file including them shares
+// the one.
+pub const ARGUMENTS_RUNTIME: &str = "__attribute__((weak)) int sl_argc = 0;
+__attribute__((weak)) char** sl_argv = NULL;
+";
+pub const ARGUMENT_VARIABLES: [&str; 2] = ["sl_argc", "sl_argv"];
+// The generated `main` of `sl test` and `sl bench` runs what the arguments name
+// a part of, or everything without any
+pub const HARNESS_RUNTIME: &str = "#include <string.h>
+static inline bool sl_harness_selected(int argc, char** argv, const char* name) {
+    for (int i = 1; i < argc; i++) {
+        if (strstr(name, argv[i])) {
+            return true;
+        }
+    }
+    return argc < 2;
+}
+";
+// A benchmark runs in batches doubled until one takes 10ms, then 11 batches of
+// that size are timed and the fastest, median and slowest time per call are
+// reported. The call goes through a volatile pointer so it can't be optimized
+// out of the loop.
+pub const BENCH_RUNTIME: &str = "#include <stdio.h>
+#include <time.h>
+static inline double sl_bench_now(void) {
+    struct timespec now;
+    timespec_get(&now, TIME_UTC);
+    return (double)now.tv_sec * 1e9 + (double)now.tv_nsec;
+}
+static inline double sl_bench_batch(void (*volatile function)(void), long iterations) {
+    double start = sl_bench_now();
+    for (long i = 0; i < iterations; i++) {
+        function();
+    }
+    return sl_bench_now() - start;
+}
+static void sl_bench(const char* name, void (*function)(void)) {
+    long iterations = 1;
+    while (sl_bench_batch(function, iterations) < 1e7 && iterations < (1L << 30)) {
+        iterations *= 2;
+    }
+    double samples[11];
+    for (int i = 0; i < 11; i++) {
+        double sample = sl_bench_batch(function, iterations) / (double)iterations;
+        int j = i;
+        for (; j > 0 && samples[j - 1] > sample; j--) {
+            samples[j] = samples[j - 1];
+        }
+        samples[j] = sample;
+    }
+    printf(\"bench %s ... %.1f ns/iter (min %.1f, max %.1f, 11 x %ld iterations)\\n\", name, samples[5], samples[0], samples[10], iterations);
+    fflush(stdout);
+}
+";
+// Without a C library there's nothing to report with, so a null dereference
+// just stops the program
+pub const FREESTANDING_NULL_CHECK_RUNTIME: &str = "static inline void* sl_check_null(const void* pointer, const char* location) {
+    (void)location;
+    if (!pointer) {
+        __builtin_trap();
+    }
+    return (void*)pointer;
+}
+#define SL_CHECK_NULL(pointer, location) ((__typeof__(pointer))sl_check_null((pointer), (location)))
+";
+// C's types that take more than one word to write, which an `external type`
+// names with underscores in place of the spaces, as `sl bindgen` does
+pub const SPELLED_TYPES: [(&str, &str); 7] = [
+    ("signed_char", "signed char"),
+    ("unsigned_char", "unsigned char"),
+    ("unsigned_short", "unsigned short"),
+    ("unsigned_long", "unsigned long"),
+    ("long_long", "long long"),
+    ("unsigned_long_long", "unsigned long long"),
+    ("long_double", "long double"),
+];
+// The headers C guarantees even without a C library
+pub const FREESTANDING_HEADERS: [&str; 9] = ["float.h", "iso646.h", "limits.h", "stdalign.h", "stdarg.h", "stdbool.h", "stddef.h", "stdint.h", "stdnoreturn.h"];
+// Names the generated C makes up are `__sl_`, what the name is for, then each
+// name it's made from with its length in front: `Point.area` is
+// `__sl_fn_5Point4area`, so `A_b.c` and `A.b_c` stay apart. No name in a program
+// may start with `__sl`, so these can't collide with one either. Functions
+// outside of structs and `external` declarations keep their names, and
+// `@extern_name` gives a function whatever C name it's given instead.
+//
+//   fn       a struct function, `Struct.function`, or a `main` that's wrapped
+//   values   the table of an enum's values
+//   tag      a struct enum's tag type, and the tag of each variant
+//   variant  the struct holding a struct enum variant's fields
+//   drop     what releases a counted struct's fields with --arc
+//   inline   a temporary holding an inlined function's argument
+//   for      a `for` loop's end, or the array it goes over and the index
+//            into it, by the loop variable's name
+//   generic  a generic function's copy for the types after its name, written
+//            as in .sl source with anything C can't take in a name as `_` and
+//            its code in hex
+pub fn mangle(kind: &str, names: &[&str]) -> String {
+    let mut symbol: String = format!("__sl_{}_", kind);
+    for name in names.iter() {
+        symbol.push_str(&format!("{}{}", name.len(), name));
+    }
+    symbol
+}
+// The prefix no name in a program may start with, kept for mangled names
+pub const RESERVED_PREFIX: &str = "__sl";
+pub fn generic_symbol(name: &Symbol, types: &[Type]) -> String {
+    let mut names: Vec<String> = vec![name.to_string()];
+    for t in types.iter() {
+        names.push(t.name().chars().map(|c| if c.is_ascii_alphanumeric() { c.to_string() } else { format!("_{:x}", c as u32) }).collect());
+    }
+    mangle("generic", &names.iter().map(|name| name.as_str()).collect::<Vec<&str>>())
+}
+// How many copies of generic functions a program may make, which only a
+// function calling itself with ever larger types comes near
+pub const SPECIALIZATION_LIMIT: usize = 1024;
+// Copies each generic function of a program for every list of types some
+// statements call it with, those types standing in for its type parameters.
+// The copies' own calls are followed too, so a generic function can call
+// another with its parameters.
+pub fn specialize(statements: &[Statement], callers: &[&Statement]) -> HashMap<Symbol, Vec<Statement>> {
+    let mut generics: HashMap<Symbol, (&Statement, Vec<Symbol>)> = HashMap::new();
+    for statement in statements.iter() {
+        if let Statement::Generic(inner, type_parameters, _) = statement {
+            if let Statement::Function(name, _, _, _, _) = &**inner {
+                generics.insert(*name, (&**inner, type_parameters.iter().map(|(name, _)| *name).collect()));
+            }
+        }
+    }
+    let mut calls: GenericCalls = GenericCalls(vec![]);
+    for statement in callers.iter() {
+        calls.visit_statement(statement);
+    }
+    let mut specializations: HashMap<Symbol, Vec<Statement>> = HashMap::new();
+    let mut symbols: HashSet<String> = HashSet::new();
+    let mut next: usize = 0;
+    while next < calls.0.len() && symbols.len() < SPECIALIZATION_LIMIT {
+        let (name, types): (Symbol, Vec<Type>) = calls.0[next].clone();
+        next += 1;
+        let Some((function, type_parameters)) = generics.get(&name) else {
+            continue;
+        };
+        let symbol: String = generic_symbol(&name, &types);
+        if types.len() != type_parameters.len() || !symbols.insert(symbol.clone()) {
+            continue;
+        }
+        let mut substitution: Substitution = Substitution(type_parameters.iter().copied().zip(types).collect());
+        let specialization: Statement = match substitution.fold_statement((*function).clone()) {
+            Statement::Function(_, args, return_type, body, location) => Statement::Function(Symbol::intern(&symbol), args, return_type, body, location),
+            statement => statement,
+        };
+        calls.visit_statement(&specialization);
+        specializations.entry(name).or_default().push(specialization);
+    }
+    specializations
+}
+// The generic functions a program calls and the types it calls them with,
+// leaving out calls within generic functions, whose types aren't known yet
+pub struct GenericCalls(pub Vec<(Symbol, Vec<Type>)>);
+impl Visitor for GenericCalls {
+    fn visit_statement(&mut self, statement: &Statement) {
+        if !matches!(statement, Statement::Generic(_, _, _)) {
+            visit::walk_statement(self, statement);
+        }
+    }
+    fn visit_expression(&mut self, expression: &Expression) {
+        if let Expression::GenericCall(name, types, _, _) = expression {
+            self.0.push((*name, types.clone()));
+        }
+        visit::walk_expression(self, expression);
+    }
+}
+#[derive(Debug)] pub struct Codegen<'a> {
+    pub statements: &'a [Statement],
+    pub structs: Vec<Symbol>,
+    pub struct_fields: HashMap<Symbol, Vec<(Symbol, Type)>>,
+    pub struct_functions: HashMap<Symbol, Vec<Symbol>>,
+    pub enums: Vec<Symbol>,
+    pub struct_enums: HashMap<Symbol, Vec<Symbol>>,
+    pub java_enums: Vec<Symbol>,
+    pub variable_struct_enum_variant: HashMap<Symbol, Symbol>,
+    pub current_variable_struct_enum_variant: Option<Symbol>,
+    pub type_aliases: Vec<Symbol>,
+    // `external type`s, which C already has
+    pub external_types: Vec<Symbol>,
+    pub variable_types: HashMap<Symbol, Type>,
+    pub parameter_types: HashMap<Symbol, Type>,
+    // What the checker found, looked up by the location of what it's about
+    pub member_types: &'a HashMap<TokenLocation, Type>,
+    // Set when #line directives should be emitted
+    pub sources: Option<std::sync::Arc<SourceMap>>,
+    pub annotations: HashMap<Symbol, Vec<(Symbol, Type)>>,
+    pub errors: Vec<Diagnostic>,
+    // The copies of each generic function made for the types it's called with
+    pub specializations: HashMap<Symbol, Vec<Statement>>,
+    // Set once `new` is generated, which needs malloc
+    pub uses_new: bool,
+    pub strings: &'a HashMap<TokenLocation, StringConversion>,
+    // Set once anything uses `string`, which pulls in its runtime
+    pub uses_strings: bool,
+    pub array_indexes: &'a HashSet<TokenLocation>,
+    // Set once anything uses `T[]`, which pulls in its runtime
+    pub uses_arrays: bool,
+    pub maps: &'a HashMap<TokenLocation, Type>,
+    pub arrays: &'a HashMap<TokenLocation, Type>,
+    // Set once anything uses `map[K, V]`, whose runtime builds on the other two
+    pub uses_maps: bool,
+    pub methods: &'a HashMap<TokenLocation, (Symbol, Type)>,
+    // Reference count objects made by `new` (--arc)
+    pub arc: bool,
+    // Allocate objects made by `new` and the runtimes' memory from the Boehm
+    // collector (--gc)
+    pub gc: bool,
+    // Check pointers for null before they're dereferenced (--debug, or
+    // without --release)
+    pub debug: bool,
+    pub uses_null_checks: bool,
+    // Left out with --release, along with the null checks
+    pub bounds_checks: bool,
+    pub lines: bool,
+    // Counted locals of each block in the current function, and whether the
+    // block is a loop body
+    pub arc_scopes: Vec<(bool, Vec<Symbol>)>,
+    pub return_type: Option<Type>,
+    // The struct whose constructor is being generated
+    pub constructing: Option<Symbol>,
+    // Emit C++ rather than C (--target=c++)
+    pub cpp: bool,
+    // Constructors generated as real C++ constructors: their parameters and the
+    // local their body allocates the object into
+    pub constructors: HashMap<Symbol, (Vec<(Symbol, Type)>, Symbol)>,
+    // That local while such a constructor is being generated
+    pub constructor_self: Option<Symbol>,
+    // C names given with @extern_name, by function
+    pub symbols: HashMap<Symbol, String>,
+    // Functions marked @static and @export
+    pub static_functions: HashSet<Symbol>,
+    pub exported: HashSet<Symbol>,
+    // Already `static inline`, so they get no other linkage
+    pub inline_functions: HashSet<Symbol>,
+    // Give functions hidden visibility unless they're exported (--hidden)
+    pub hidden: bool,
+    // The machine given with --target, whose layout the generated code checks for
+    pub machine: Option<TargetMachine>,
+    // Leave out the C library (--freestanding)
+    pub freestanding: bool,
+    // The function marked @entry, which the program starts in instead of `main`
+    pub entry: Option<Symbol>,
+    // Set when the program declares the runtime's copy of `main`'s arguments,
+    // so `main` is generated to fill it in
+    pub keeps_arguments: bool,
+    // Structs --arc doesn't count, as the checker found them
+    pub uncounted: &'a HashSet<Symbol>,
+    // What each `for` loop goes over
+    pub loops: &'a HashMap<TokenLocation, Type>,
+    // Calls of the built-in `assert`, and whether there were any
+    pub asserts: &'a HashSet<TokenLocation>,
+    pub uses_asserts: bool,
+    // Calls of the built-in `print` and `println`, and whether there were any
+    pub prints: &'a HashMap<TokenLocation, Vec<PrintConversion>>,
+    pub uses_prints: bool,
+    // Generate a `main` that runs the @test functions in place of the program's
+    // own (`sl test`), or times the @bench functions (`sl bench`)
+    pub testing: bool,
+    pub tests: Vec<(Symbol, TokenLocation)>,
+    pub benchmarking: bool,
+    pub benches: Vec<(Symbol, TokenLocation)>,
+}
+impl<'a> Codegen<'a> {
+    pub fn new(statements: &'a [Statement], checker: &'a Checker) -> Self {
+        Self {
+            statements,
+            structs: vec![],
+            struct_fields: HashMap::new(),
+            struct_functions: HashMap::new(),
+            enums: vec![],
+            struct_enums: HashMap::new(),
+            java_enums: vec![],
+            variable_struct_enum_variant: HashMap::new(),
+            current_variable_struct_enum_variant: None,
+            type_aliases: vec![],
+            external_types: vec![],
+            variable_types: HashMap::new(),
+            parameter_types: HashMap::new(),
+            member_types: &checker.member_types,
+            sources: None,
+            annotations: HashMap::new(),
+            errors: vec![],
+            specializations: specialize(statements, &statements.iter().collect::<Vec<&Statement>>()),
+            uses_new: false,
+            strings: &checker.strings,
+            uses_strings: false,
+            array_indexes: &checker.array_indexes,
+            uses_arrays: false,
+            maps: &checker.maps,
+            arrays: &checker.arrays,
+            uses_maps: false,
+            methods: &checker.methods,
+            arc: false,
+            gc: false,
+            debug: false,
+            uses_null_checks: false,
+            bounds_checks: true,
+            lines: true,
+            arc_scopes: vec![],
+            return_type: None,
+            constructing: None,
+            cpp: false,
+            constructors: HashMap::new(),
+            constructor_self: None,
+            symbols: HashMap::new(),
+            static_functions: HashSet::new(),
+            exported: HashSet::new(),
+            inline_functions: HashSet::new(),
+            hidden: false,
+            machine: None,
+            freestanding: false,
+            entry: None,
+            keeps_arguments: Codegen::declares_arguments(statements),
+            uncounted: &checker.uncounted,
+            loops: &checker.loops,
+            asserts: &checker.asserts,
+            uses_asserts: false,
+            prints: &checker.prints,
+            uses_prints: false,
+            testing: false,
+            tests: vec![],
+            benchmarking: false,
+            benches: vec![],
+        }
+    }
+    // Includes and type definitions come first, then a prototype for every
+    // function so that definitions can call each other in any order
+    pub fn codegen(&mut self) -> String {
+        let mut code: Vec<u8> = vec![];
+        // Writing into memory can't fail
+        let _ = self.codegen_to(&mut code);
+        String::from_utf8(code).unwrap_or_default()
+    }
+    // Generates the program once. Which runtime the prologue carries depends on
+    // what the whole program uses, so the definitions are held until they're
+    // all generated and then written out after it.
+    pub fn codegen_to(&mut self, out: &mut impl std::io::Write) -> std::io::Result<()> {
+        let mut definitions: Vec<u8> = vec![];
+        self.codegen_definitions(&mut definitions)?;
+        out.write_all(self.prologue().as_bytes())?;
+        out.write_all(&definitions)
+    }
+    pub fn codegen_definitions(&mut self, out: &mut impl std::io::Write) -> std::io::Result<()> {
+        let mut declarations: Vec<String> = vec![];
+        let (types, rest): (Vec<&Statement>, Vec<&Statement>) = self.ordered_statements().into_iter().partition(|statement| Codegen::is_type_definition(statement));
+        for statement in types.iter() {
+            let mut code: String = String::new();
+            self.codegen_top_level(statement, &mut code, &mut declarations);
+            out.write_all(code.as_bytes())?;
+        }
+        if self.arc {
+            out.write_all(self.codegen_drops().as_bytes())?;
+        }
+        for statement in rest.iter() {
+            if let Some(prototype) = self.codegen_prototype(statement) {
+                out.write_all(self.codegen_namespaced(statement, prototype).as_bytes())?;
+            }
+        }
+        for statement in rest.iter() {
+            let mut code: String = String::new();
+            self.codegen_top_level(statement, &mut code, &mut declarations);
+            out.write_all(code.as_bytes())?;
+        }
+        if !self.freestanding {
+            out.write_all(self.codegen_entry().as_bytes())?;
+        }
+        Ok(())
+    }
+    // With --emit-layout=per-module each module gets a header with its types and
+    // declarations and a source with its definitions, returned in module order.
+    // Every header carries the runtime, behind a guard so it's only seen once.
+    pub fn codegen_modules(&mut self, modules: &[Module]) -> Vec<(String, String)> {
+        let indexes: HashMap<String, usize> = modules.iter().enumerate().map(|(i, module)| (module.name.clone(), i)).collect();
+        let mut headers: Vec<String> = vec![String::new(); modules.len()];
+        let mut sources: Vec<String> = vec![String::new(); modules.len()];
+        let mut declarations: Vec<Vec<String>> = vec![vec![]; modules.len()];
+        let (types, rest): (Vec<&Statement>, Vec<&Statement>) = self.ordered_statements().into_iter().partition(|statement| Codegen::is_type_definition(statement));
+        for statement in types.iter() {
+            let module: usize = self.module_index(statement, &indexes);
+            self.codegen_top_level(statement, &mut headers[module], &mut declarations[module]);
+        }
+        // Each module makes its own copies of the generic functions it calls,
+        // since it may call them with types only it can see
+        let mut copies: Vec<Vec<Statement>> = vec![vec![]; modules.len()];
+        for (module, copies) in copies.iter_mut().enumerate() {
+            let callers: Vec<&Statement> = types.iter().chain(rest.iter()).copied().filter(|statement| self.module_index(statement, &indexes) == module).collect();
+            let mut specializations: HashMap<Symbol, Vec<Statement>> = specialize(self.statements, &callers);
+            for statement in self.statements.iter() {
+                if let Statement::Generic(inner, _, _) = statement {
+                    if let Some(specializations) = Checker::definition(inner).and_then(|(name, _)| specializations.remove(&name)) {
+                        copies.extend(specializations);
+                    }
+                }
+            }
+            for copy in copies.iter() {
+                if let Statement::Function(name, _, _, _, _) = copy {
+                    self.static_functions.insert(*name);
+                }
+                if let Some(prototype) = self.codegen_prototype(copy) {
+                    sources[module].push_str(&self.codegen_namespaced(copy, prototype));
+                }
+            }
+        }
+        for statement in rest.iter() {
+            let module: usize = self.module_index(statement, &indexes);
+            let declaration: Option<String> = match Codegen::unannotated(statement) {
+                Statement::Generic(_, _, _) => None,
+                Statement::Variable(name, t, _, _) => Some(format!("extern {} {};\n", self.codegen_type(t), name)),
+                Statement::Constant(name, t, _, _) => Some(format!("extern const {} {};\n", self.codegen_type(t), name)),
+                // Static functions can't be called from outside the file
+                Statement::Function(name, _, _, _, _) if self.static_functions.contains(name) => {
+                    if let Some(prototype) = self.codegen_prototype(statement) {
+                        sources[module].push_str(&self.codegen_namespaced(statement, prototype));
+                    }
+                    None
+                }
+                _ => self.codegen_prototype(statement),
+            };
+            if let Some(declaration) = declaration {
+                headers[module].push_str(&self.codegen_namespaced(statement, declaration));
+            }
+        }
+        for statement in rest.iter() {
+            let module: usize = self.module_index(statement, &indexes);
+            // Every file that uses an external, an inline function or an enum of
+            // functions needs to see all of it
+            if matches!(statement, Statement::Inline(_, _) | Statement::External(_, _) | Statement::Enum(_, Type::Function(_, _, _), _, _)) {
+                self.codegen_top_level(statement, &mut headers[module], &mut declarations[module]);
+            } else if !matches!(Codegen::unannotated(statement), Statement::Generic(_, _, _)) {
+                self.codegen_top_level(statement, &mut sources[module], &mut declarations[module]);
+            }
+        }
+        for (module, copies) in copies.iter().enumerate() {
+            for copy in copies.iter() {
+                self.codegen_top_level(copy, &mut sources[module], &mut declarations[module]);
+            }
+        }
+        let entry_name: Option<Symbol> = self.entry_name();
+        let entry: Option<usize> = rest.iter().find(|statement| matches!(Codegen::unannotated(statement), Statement::Function(name, _, _, _, _) if entry_name.as_ref() == Some(name))).map(|statement| self.module_index(statement, &indexes));
+        if let (false, Some(module)) = (self.freestanding, entry) {
+            sources[module].push_str(&self.codegen_entry());
+        }
+        let drops: String = if self.arc { self.codegen_drops() } else { String::new() };
+        let runtime: String = format!("#ifndef SL_RUNTIME_H\n#define SL_RUNTIME_H\n{}#endif\n", self.prologue());
+        let extension: &str = if self.cpp { "hpp" } else { "h" };
+        let mut files: Vec<(String, String)> = vec![];
+        for (i, module) in modules.iter().enumerate() {
+            let directory: &std::path::Path = module.path.parent().unwrap_or(std::path::Path::new(""));
+            let mut header: String = format!("#pragma once\n{}", runtime);
+            for import in module.imports.iter() {
+                header.push_str(&format!("#include \"{}\"\n", relative_path(directory, &modules[*import].path.with_extension(extension))));
+            }
+            header.push_str(&headers[i]);
+            let own: std::path::PathBuf = module.path.with_extension(extension);
+            let source: String = format!("#include \"{}\"\n{}{}", relative_path(directory, &own), drops, sources[i]);
+            files.push((source, header));
+        }
+        files
+    }
+    pub fn module_index(&self, statement: &Statement, indexes: &HashMap<String, usize>) -> usize {
+        match &self.sources {
+            Some(sources) => indexes.get(&sources.file(&statement.location()).name).copied().unwrap_or(0),
+            None => 0,
+        }
+    }
+    // With --target=c++ the definitions of an imported module go in a namespace
+    // named after its file. The root module and `main` stay global.
+    pub fn namespace(&self, statement: &Statement) -> Option<String> {
+        if !self.cpp || matches!(statement, Statement::Import(_, _) | Statement::External(_, _)) {
+            return None;
+        }
+        let file: &SourceFile = self.sources.as_ref()?.file(&statement.location());
+        if file.start == 0 {
+            return None;
+        }
+        let stem: String = std::path::Path::new(&file.name).file_stem()?.to_string_lossy().to_string();
+        Some(stem.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect())
+    }
+    // Everything in the program can refer to everything else by its plain name,
+    // so each namespace is brought into scope again as soon as it's closed
+    pub fn codegen_namespaced(&self, statement: &Statement, code: String) -> String {
+        match self.namespace(statement) {
+            Some(namespace) if !code.is_empty() => format!("namespace {} {{\n{}}}\nusing namespace {};\n", namespace, code, namespace),
+            _ => code,
+        }
+    }
+    pub fn machine(&self) -> TargetMachine {
+        self.machine.clone().unwrap_or_else(TargetMachine::host)
+    }
+    // Headers the generated code relies on whatever the program imports
+    pub fn prologue(&self) -> String {
+        let mut code: String = String::new();
+        code.push_str("#include <stdbool.h>\n");
+        code.push_str("#include <stddef.h>\n");
+        if let Some(machine) = &self.machine {
+            code.push_str(&machine.assertions(self.cpp));
+        }
+        // Only structs are counted, so without any there's nothing for the runtime to do
+        let arc: bool = self.arc && !self.structs.is_empty();
+        if !self.freestanding && (self.uses_new || self.uses_strings || self.uses_arrays || self.uses_maps || arc || self.uses_null_checks || self.uses_asserts || self.testing || self.benchmarking) {
+            code.push_str("#include <stdlib.h>\n");
+        }
+        // C++ has its own strings, but maps still compare keys through the runtime's
+        let string_runtime: bool = (self.uses_strings && !self.cpp) || self.uses_maps;
+        if !self.constructors.is_empty() {
+            code.push_str("#include <new>\n");
+        }
+        // Which map and array literals are built from
+        if self.cpp && (self.uses_maps || self.uses_arrays) {
+            code.push_str("#include <initializer_list>\n");
+        }
+        if self.uses_prints {
+            code.push_str("#include <stdio.h>\n");
+        }
+        if self.gc {
+            code.push_str(GC_ALLOCATOR);
+        } else if self.uses_strings || self.uses_arrays || self.uses_maps {
+            code.push_str(ALLOCATOR);
+        }
+        if self.cpp && self.uses_strings {
+            code.push_str(CPP_STRING_RUNTIME);
+        }
+        if string_runtime {
+            code.push_str(STRING_RUNTIME);
+        }
+        if string_runtime && self.uses_prints {
+            code.push_str(PRINT_STRING_RUNTIME);
+        }
+        if self.uses_arrays || self.uses_maps {
+            code.push_str(ARRAY_RUNTIME);
+        }
+        if self.uses_maps {
+            code.push_str(if self.machine().pointer_width == 64 { MAP_HASH_64 } else { MAP_HASH_32 });
+            code.push_str(MAP_RUNTIME);
+        }
+        if arc {
+            code.push_str(ARC_RUNTIME);
+        }
+        if self.uses_null_checks {
+            code.push_str(if self.freestanding { FREESTANDING_NULL_CHECK_RUNTIME } else { NULL_CHECK_RUNTIME });
+        }
+        if self.testing {
+            code.push_str("#define SL_TEST\n");
+        }
+        if self.uses_asserts || self.testing {
+            code.push_str(if self.freestanding { FREESTANDING_ASSERT_RUNTIME } else { ASSERT_RUNTIME });
+        }
+        if self.testing || self.benchmarking {
+            code.push_str(HARNESS_RUNTIME);
+        }
+        if self.keeps_arguments && self.cpp {
+            code.push_str(&format!("extern \"C\" {{\n{}}}\n", ARGUMENTS_RUNTIME));
+        } else if self.keeps_arguments {
+            code.push_str(ARGUMENTS_RUNTIME);
+        }
+        if self.benchmarking {
+            code.push_str(BENCH_RUNTIME);
+        }
+        code
+    }
+    pub fn codegen_top_level(&mut self, statement: &Statement, code: &mut String, declarations: &mut Vec<String>) {
+        let statement_code: String = self.codegen_statement(statement);
+        // Several modules may include the same header or declare the same extern
+        if let Statement::Import(_, _) | Statement::External(_, _) = statement {
+            if declarations.contains(&statement_code) {
+                return;
+            }
+            declarations.push(statement_code.clone());
+        }
+        code.push_str(&self.codegen_line(statement));
+        code.push_str(&self.codegen_namespaced(statement, statement_code));
+    }
+    // Statements that go above the function prototypes. Enums holding functions
+    // stay below since their values name functions.
+    pub fn is_type_definition(statement: &Statement) -> bool {
+        match statement {
+            Statement::Import(_, _) | Statement::Annotation(_, _, _) => true,
+            Statement::Annotated(statement, _, _) => Codegen::is_type_definition(statement),
+            Statement::Enum(_, Type::Function(_, _, _), _, _) => false,
+            statement => statement.declared_type().is_some(),
+        }
+    }
+    pub fn codegen_prototype(&mut self, statement: &Statement) -> Option<String> {
+        let mut prototype: String = String::new();
+        let statement: &Statement = match statement {
+            Statement::Annotated(inner, annotations, _) if matches!(**inner, Statement::Function(_, _, _, _, _) | Statement::StructFunction(_, _, _, _, _, _)) => {
+                for annotation in annotations.iter().filter(|annotation| annotation.name == "deprecated") {
+                    prototype.push_str(&format!("{} ", self.codegen_deprecated(annotation)));
+                }
+                inner
+            }
+            statement => statement,
+        };
+        match statement {
+            Statement::Generic(inner, _, _) => {
+                let prototypes: String = self.specializations_of(inner).iter().filter_map(|specialization| self.codegen_prototype(specialization)).collect();
+                return (!prototypes.is_empty()).then_some(prototypes);
+            }
+            Statement::Function(name, args, return_type, _, _) if name != "main" => {
+                prototype.push_str(self.codegen_linkage(name));
+                let symbol: String = self.symbol(name);
+                prototype.push_str(&self.codegen_signature(&symbol, args, return_type))
+            }
+            Statement::StructFunction(struct_name, name, args, return_type, _, _) => {
+                prototype.push_str(self.codegen_linkage(&Symbol::intern(&format!("{}.{}", struct_name, name))));
+                prototype.push_str(&self.codegen_signature(&self.method_symbol(struct_name, name), args, return_type))
+            }
+            _ => return None,
+        }
+        prototype.push_str(";\n");
+        Some(prototype)
+    }
+    // The C name of a function
+    pub fn symbol(&self, name: &Symbol) -> String {
+        self.symbols.get(name).cloned().unwrap_or_else(|| name.to_string())
+    }
+    // The C name of a struct function, which is mangled unless it's given with @extern_name
+    pub fn method_symbol(&self, struct_name: &str, name: &str) -> String {
+        self.symbols.get(&Symbol::intern(&format!("{}.{}", struct_name, name))).cloned().unwrap_or_else(|| mangle("fn", &[struct_name, name]))
+    }
+    // A variable or parameter, which may hold a function but keeps its own name
+    pub fn is_variable(&self, name: &Symbol) -> bool {
+        self.variable_types.contains_key(name) || self.parameter_types.contains_key(name)
+    }
+    pub fn unannotated(statement: &Statement) -> &Statement {
+        match statement {
+            Statement::Annotated(statement, _, _) => statement,
+            statement => statement,
+        }
+    }
+    // What goes before a function's signature for @static, @export, @entry and --hidden
+    pub fn codegen_linkage(&self, name: &Symbol) -> &'static str {
+        let entry: bool = self.freestanding && self.entry.as_deref() == Some(name);
+        if entry && self.cpp {
+            // Whatever starts the program calls it by its C name
+            "extern \"C\" __attribute__((used, visibility(\"default\"))) "
+        } else if entry {
+            "__attribute__((used, visibility(\"default\"))) "
+        } else if self.static_functions.contains(name) {
+            "static "
+        } else if self.exported.contains(name) {
+            "__attribute__((visibility(\"default\"))) "
+        } else if self.hidden && name != "main" && !self.inline_functions.contains(name) {
+            "__attribute__((visibility(\"hidden\"))) "
+        } else {
+            ""
+        }
+    }
+    // The top-level statements in source order, except that a type definition is
+    // pulled up to just before the first definition that needs it. Every type
+    // name is known up front so pointers to later types still resolve.
+    pub fn ordered_statements(&mut self) -> Vec<&'a Statement> {
+        let statements: &'a [Statement] = self.statements;
+        let graph: HashMap<Symbol, (usize, Vec<Symbol>)> = type_graph(statements);
+        for statement in statements.iter() {
+            let inner: &Statement = match statement {
+                Statement::Annotated(statement, _, _) | Statement::External(statement, _) => statement,
+                statement => statement,
+            };
+            if let (Statement::Annotated(_, annotations, _), Statement::Function(name, _, _, _, _)) = (statement, inner) {
+                for annotation in annotations.iter() {
+                    match (annotation.name.as_str(), annotation.arguments.first()) {
+                        ("static", _) => {
+                            self.static_functions.insert(*name);
+                        }
+                        ("export", _) => {
+                            self.exported.insert(*name);
+                        }
+                        ("entry", _) => {
+                            self.entry = Some(*name);
+                        }
+                        ("test", _) => {
+                            self.tests.push((*name, annotation.location.clone()));
+                        }
+                        ("bench", _) => {
+                            self.benches.push((*name, annotation.location.clone()));
+                        }
+                        ("extern_name", Some(Expression::String(symbol, _))) => {
+                            self.symbols.insert(*name, symbol.clone());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            // A struct function's own C name is the one way around its mangled one
+            if let (Statement::Annotated(_, annotations, _), Statement::StructFunction(struct_name, name, _, _, _, _)) = (statement, inner) {
+                for annotation in annotations.iter().filter(|annotation| annotation.name == "extern_name") {
+                    if let Some(Expression::String(symbol, _)) = annotation.arguments.first() {
+                        self.symbols.insert(Symbol::intern(&format!("{}.{}", struct_name, name)), symbol.clone());
+                    }
+                }
+            }
+            if let Statement::Inline(function, _) = inner {
+                if let Statement::Function(name, _, _, _, _) = &**function {
+                    self.inline_functions.insert(*name);
+                }
+            }
+            // A `main` taking its arguments as an array, or in a program that
+            // keeps them, is called by a generated one, and a test or benchmark
+            // harness has its own
+            if let Statement::Function(name, args, _, _, _) = inner {
+                if name == "main" && (self.testing || self.benchmarking || (self.keeps_arguments && !self.freestanding) || matches!(args.as_slice(), [(_, Type::DynamicArray(_, _))])) {
+                    self.symbols.insert(*name, mangle("fn", &["main"]));
+                    self.static_functions.insert(*name);
+                }
+            }
+            match inner {
+                Statement::Struct(name, _, _) => self.structs.push(*name),
+                Statement::Enum(name, _, _, _) => self.enums.push(*name),
+                Statement::StructEnum(name, values, _) => {
+                    self.struct_enums.insert(*name, values.iter().map(|(variant, _)| *variant).collect());
+                }
+                Statement::JavaEnum(name, _, _, _) => self.java_enums.push(*name),
+                Statement::TypeAlias(name, types, _) => {
+                    self.type_aliases.push(*name);
+                    if types.is_empty() {
+                        self.external_types.push(*name);
+                    }
+                }
+                // Known up front so `Struct.function(...)` works before the definition
+                Statement::StructFunction(struct_name, name, _, _, _, _) => {
+                    let functions: &mut Vec<Symbol> = self.struct_functions.entry(*struct_name).or_default();
+                    if !functions.contains(name) {
+                        functions.push(*name);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if self.cpp && !self.arc && !self.gc {
+            self.find_constructors();
+        }
+        let mut order: Vec<usize> = vec![];
+        let mut visited: Vec<bool> = vec![false; statements.len()];
+        for index in 0..statements.len() {
+            Codegen::visit_statement(statements, &graph, index, &mut visited, &mut order);
+        }
+        order.into_iter().map(|index| &statements[index]).collect()
+    }
+    // A constructor that starts by allocating its object can become a real C++
+    // constructor, with `this` as the object. `__S_constructor` is kept as a
+    // function for anything that refers to it.
+    pub fn find_constructors(&mut self) {
+        let statements: &'a [Statement] = self.statements;
+        for statement in statements.iter() {
+            let inner: &Statement = match statement {
+                Statement::Annotated(statement, _, _) => statement,
+                statement => statement,
+            };
+            if let Statement::StructFunction(struct_name, name, args, _, body, _) = inner {
+                if let (true, Some(Statement::Variable(local, t, value, _))) = (name == "constructor", body.first()) {
+                    if Codegen::is_malloc(value) && self.pointed_struct(t).as_ref() == Some(struct_name) {
+                        self.constructors.insert(*struct_name, (args.clone(), *local));
+                    }
+                }
+            }
+        }
+    }
+    pub fn visit_statement(statements: &[Statement], graph: &HashMap<Symbol, (usize, Vec<Symbol>)>, index: usize, visited: &mut Vec<bool>, order: &mut Vec<usize>) {
+        if visited[index] {
+            return;
+        }
+        visited[index] = true;
+        if let Some(name) = statements[index].declared_type() {
+            for dependency in graph[name].1.iter() {
+                if let Some((dependency_index, _)) = graph.get(dependency) {
+                    Codegen::visit_statement(statements, graph, *dependency_index, visited, order);
+                }
+            }
+        }
+        order.push(index);
+    }
+    // A header to go with the generated C: the same includes and type
+    // definitions, plus prototypes and extern declarations for everything the
+    // .c file defines, so it can be used from hand-written C
+    pub fn header(&mut self) -> String {
+        let mut code: String = String::new();
+        let mut declarations: Vec<String> = vec![];
+        let (types, rest): (Vec<&Statement>, Vec<&Statement>) = self.ordered_statements().into_iter().partition(|statement| Codegen::is_type_definition(statement));
+        for statement in types.iter().chain(rest.iter()) {
+            let declaration: String = match statement {
+                Statement::External(_, _) => self.codegen_statement(statement),
+                Statement::Variable(name, t, _, _) => format!("extern {} {};\n", self.codegen_type(t), name),
+                Statement::Constant(name, t, _, _) => format!("extern const {} {};\n", self.codegen_type(t), name),
+                statement if Codegen::is_type_definition(statement) => self.codegen_statement(statement),
+                // Static functions can't be called from outside the file
+                statement if matches!(Codegen::unannotated(statement), Statement::Function(name, _, _, _, _) if self.static_functions.contains(name)) => continue,
+                statement => match self.codegen_prototype(statement) {
+                    Some(prototype) => prototype,
+                    None => continue,
+                },
+            };
+            if declarations.contains(&declaration) {
+                continue;
+            }
+            declarations.push(declaration.clone());
+            code.push_str(&self.codegen_namespaced(statement, declaration));
+        }
+        // Enums holding functions need the prototypes above
+        for statement in rest.iter() {
+            if let Statement::Enum(_, Type::Function(_, _, _), _, _) = statement {
+                let declaration: String = self.codegen_statement(statement);
+                code.push_str(&self.codegen_namespaced(statement, declaration));
+            }
+        }
+        // The prologue depends on which types the declarations used
+        format!("#pragma once\n{}{}", self.prologue(), code)
+    }
+    pub fn codegen_statement(&mut self, statement: &Statement) -> String {
+        match statement {
+            Statement::Generic(statement, _, _) => self.codegen_generic(statement),
+            Statement::Annotation(name, fields, _) => self.codegen_annotation_statement(name, fields),
+            Statement::Annotated(statement, annotations, _) => self.codegen_annotated(statement, annotations),
+            Statement::External(statement, _) => self.codegen_external(statement),
+            Statement::Inline(statement, _) => self.codegen_inline(statement),
+            Statement::Struct(name, fields, _) => self.codegen_struct(name, fields),
+            Statement::Enum(name, enum_type, variants, _) => self.codegen_enum(name, enum_type, variants),
+            Statement::StructEnum(name, values, _) => self.codegen_struct_enum(name, values),
+            Statement::JavaEnum(name, constructor, values, _) => self.codegen_java_enum(name, constructor, values),
+            Statement::TypeAlias(name, t, _) => self.codegen_type_alias(name, t),
+            Statement::Function(name, args, return_type, body, _) => self.codegen_function(name, args, return_type, body),
+            Statement::StructFunction(struct_name, name, args, return_type, body, _) => self.codegen_struct_function(struct_name, name, args, return_type, body),
+            Statement::Variable(name, t, value, _) => self.codegen_variable(name, t, value),
+            Statement::Constant(name, t, value, _) => self.codegen_constant(name, t, value),
+            Statement::Return(value, _) => self.codegen_return(value),
+            Statement::Import(path, location) => self.codegen_import(path, location),
+            Statement::While(condition, body, _) => self.codegen_while(condition, body),
+            Statement::For(name, iterable, body, location) => self.codegen_for(name, iterable, body, location),
+            Statement::Break(_) => self.codegen_break(),
+            Statement::Continue(_) => self.codegen_continue(),
+            Statement::If(condition, body, else_body, _) => self.codegen_if(condition, body, else_body),
+            Statement::Expression(expression, _) => {
+                let expression_code: String = self.codegen_expression(expression);
+                format!("{};\n", expression_code)
+            }
+        }
+    }
+    // Points the C compiler and debuggers back at the .sl line a statement came from
+    pub fn codegen_line(&self, statement: &Statement) -> String {
+        match &self.sources {
+            Some(sources) if self.lines => {
+                let (name, line): (String, usize) = sources.line(&statement.location());
+                format!("#line {} \"{}\"\n", line, name.replace('\\', "\\\\").replace('"', "\\\""))
+            }
+            _ => String::new(),
+        }
+    }
+    pub fn codegen_break(&mut self) -> String {
+        format!("{}break;\n", self.codegen_loop_releases())
+    }
+    pub fn codegen_continue(&mut self) -> String {
+        format!("{}continue;\n", self.codegen_loop_releases())
+    }
+    // A generic function is only generated as its copies
+    pub fn codegen_generic(&mut self, statement: &Statement) -> String {
+        let mut code: String = String::new();
+        for (i, specialization) in self.specializations_of(statement).iter().enumerate() {
+            // The first comes right after the generic function's own #line
+            if i > 0 {
+                code.push_str(&self.codegen_line(specialization));
+            }
+            code.push_str(&self.codegen_statement(specialization));
+        }
+        code
+    }
+    pub fn specializations_of(&self, statement: &Statement) -> Vec<Statement> {
+        match statement {
+            Statement::Function(name, _, _, _, _) => self.specializations.get(name).cloned().unwrap_or_default(),
+            _ => vec![],
+        }
+    }
+    pub fn codegen_annotation_statement(&mut self, name: &Symbol, fields: &Vec<(Symbol, Type)>) -> String {
+        self.annotations.insert(*name, fields.clone());
+        let mut code: String = String::new();
+        code.push_str(format!("#define {}(", name).as_str());
+        for (i, (field_name, _)) in fields.iter().enumerate() {
+            code.push_str(format!("{}", field_name).as_str());
+            if i != fields.len() - 1 {
+                code.push_str(", ");
+            }
+        }
+        code.push_str(format!(") __attribute__((annotate(\"{}\")))\n", name).as_str());
+        code
+    }
+    pub fn codegen_annotated(&mut self, statement: &Statement, annotations: &Vec<Annotation>) -> String {
+        let mut code: String = String::new();
+        for annotation in annotations.iter() {
+            if !Codegen::is_function_annotation(&annotation.name) {
+                code.push_str(&self.codegen_annotation(&annotation.name, &annotation.arguments, &annotation.location));
+            }
+        }
+
+        match statement {
+            Statement::Struct(name, fields, _) => {
+                code.push_str(&self.codegen_struct(name, fields));
+                code.pop();
+                code.pop();
+                for annotation in annotations.iter() {
+                    if annotation.name == "deprecated" {
+                        code.push_str(&format!(" {}", self.codegen_deprecated(annotation)));
+                        continue;
+                    }
+                    code.push_str(format!(" {}(", annotation.name).as_str());
+                    let arguments: Vec<Expression> = self.annotation_arguments(annotation);
+                    for (i, argument) in arguments.iter().enumerate() {
+                        code.push_str(&self.codegen_expression(argument));
+                        if i != arguments.len() - 1 {
+                            code.push_str(", ");
+                        }
+                    }
+                    code.push_str(")");
+                }
+                code.push_str(";\n");
+            }
+            Statement::Function(_, _, _, _, _) | Statement::StructFunction(_, _, _, _, _, _) if annotations.iter().all(|annotation| Codegen::is_function_annotation(&annotation.name)) => {
+                for annotation in annotations.iter().filter(|annotation| annotation.name == "deprecated") {
+                    code.push_str(&format!("{} ", self.codegen_deprecated(annotation)));
+                }
+                code.push_str(&self.codegen_statement(statement));
+            }
+            _ => self.errors.push(Diagnostic::type_error("E0020", "cannot annotate this statement".to_string(), statement.location())),
+        }
+        code
+    }
+    // The built-in annotations, which aren't C macros
+    pub fn is_function_annotation(name: &str) -> bool {
+        matches!(name, "deprecated" | "static" | "export" | "extern_name" | "entry" | "test" | "bench")
+    }
+    pub fn codegen_deprecated(&mut self, annotation: &Annotation) -> String {
+        match annotation.arguments.first() {
+            Some(message) => format!("__attribute__((deprecated({})))", self.codegen_expression(message)),
+            None => "__attribute__((deprecated))".to_string(),
+        }
+    }
+    // Puts named arguments back in the order the annotation declares its fields
+    pub fn annotation_arguments(&self, annotation: &Annotation) -> Vec<Expression> {
+        let fields: Vec<(Symbol, Type)> = self.annotations.get(&annotation.name).cloned().unwrap_or_default();
+        let mut arguments: Vec<Expression> = vec![];
+        for (i, (field_name, _)) in fields.iter().enumerate() {
+            let named: Option<&Expression> = annotation.arguments.iter().find_map(|argument| match argument {
+                Expression::NamedArgument(name, value, _) if name == field_name => Some(&**value),
+                _ => None,
+            });
+            match (named, annotation.arguments.get(i)) {
+                (Some(value), _) => arguments.push(value.clone()),
+                (None, Some(argument)) if !matches!(argument, Expression::NamedArgument(_, _, _)) => arguments.push(argument.clone()),
+                _ => {}
+            }
+        }
+        arguments
+    }
+    pub fn codegen_annotation(&mut self, name: &Symbol, _fields: &Vec<Expression>, location: &TokenLocation) -> String {
+        if !self.annotations.contains_key(name) {
+            self.errors.push(Diagnostic::type_error("E0011", format!("unknown annotation {}", name), location.clone()));
+        }
+        "".to_string()
+    }
+    pub fn codegen_external(&mut self, statement: &Statement) -> String {
+        match statement {
+            // C's headers define these, and only the struct's tag has to be
+            // known before a prototype names it
+            Statement::Struct(name, fields, _) => {
+                if !self.structs.contains(name) {
+                    self.structs.push(*name);
+                }
+                self.struct_fields.insert(*name, fields.clone());
+                return format!("struct {};\n", name);
+            }
+            Statement::TypeAlias(_, types, _) if types.is_empty() => return String::new(),
+            _ => {}
+        }
+        let mut code: String = String::new();
+        // C++ would otherwise mangle the names of what C code defines
+        code.push_str(if self.cpp { "extern \"C\" " } else { "extern " });
+        match statement {
+            // A function C defines is only declared, with its name in parentheses
+            // so that a macro the header defines in front of it, as C's library
+            // often does, isn't expanded
+            Statement::Function(name, args, return_type, body, _) if body.is_empty() => {
+                let symbol: String = format!("({})", self.symbol(name));
+                code.push_str(&format!("{};\n", self.codegen_signature(&symbol, args, return_type)));
+            }
+            statement => code.push_str(&self.codegen_statement(statement)),
+        }
+        code
+    }
+    pub fn codegen_inline(&mut self, statement: &Statement) -> String {
+        let mut code: String = String::new();
+        // A C99 inline definition isn't a symbol of its own, so a call the C
+        // compiler doesn't inline would have nothing to link against
+        code.push_str(if self.cpp { "inline " } else { "static inline " });
+        code.push_str(&self.codegen_statement(statement));
+        code
+    }
+    pub fn codegen_struct(&mut self, name: &Symbol, fields: &Vec<(Symbol, Type)>) -> String {
+        self.structs.push(*name);
+        self.struct_fields.insert(*name, fields.clone());
+        self.struct_functions.entry(*name).or_default();
+        let mut constructor: String = String::new();
+        // let mut has_constructor: bool = false;
+        let mut forward_declarations: String = String::new();
+        let mut code: String = String::new();
+        if fields.is_empty() {
+            return format!("struct {};\n", name);
+        }
+        code.push_str(&format!("struct {} {{\n", name));
+        for (field_name, field_type) in fields.iter() {
+            if let Type::Function(args, return_type, _) = field_type {
+                code.push_str(&format!("{} (*{})(", self.codegen_type(return_type), field_name));
+                for arg_type in args.iter() {
+                    code.push_str(&format!("{}, ", self.codegen_type(arg_type)));
+                }
+                if args.len() > 0 {
+                    code.pop();
+                    code.pop();
+                }
+                code.push_str(");\n");
+                if field_name == "constructor" {
+                    // has_constructor = true;
+                    constructor.push_str(&format!("static {} {}(", self.codegen_type(return_type), mangle("fn", &[name, "constructor"])));
+                    for (i, arg_type) in args.iter().enumerate() {
+                        constructor.push_str(&format!("{} __{}, ", self.codegen_type(arg_type), i));
+                    }
+                    if args.len() > 0 {
+                        constructor.pop();
+                        constructor.pop();
+                    }
+                    constructor.push_str(") {\n");
+                    constructor.push_str(&format!("{} self = ({})(malloc(sizeof({})));\n", self.codegen_type(return_type), self.codegen_type(return_type), self.codegen_type(return_type)));
+                    for (i, _) in args.iter().enumerate() {
+                        constructor.push_str(&format!("self->{} = __{};\n", fields[i].0, i));
+                    }
+                    for (field_name, field_type) in fields.iter() {
+                        if let Type::Function(_, _, _) = field_type {
+                            constructor.push_str(&format!("self->{} = {};\n", field_name, mangle("fn", &[name, field_name])));
+                        }
+                    }
+                    constructor.push_str(&format!("return self;\n"));
+                    constructor.push_str("}\n");
+                } else {
+                    forward_declarations.push_str(&format!("{} {}(", self.codegen_type(return_type), mangle("fn", &[name, field_name])));
+                    for arg_type in args.iter() {
+                        forward_declarations.push_str(&format!("{}, ", self.codegen_type(arg_type)));
+                    }
+                    if args.len() > 0 {
+                        forward_declarations.pop();
+                        forward_declarations.pop();
+                    }
+                    forward_declarations.push_str(");\n");
+                }
+                continue;
+            }
+            code.push_str(&format!("{} {};\n", self.codegen_type(field_type), field_name));
+        }
+        if let Some((args, _)) = self.constructors.get(name).cloned() {
+            // A default constructor as well, so the struct can still be a local
+            code.push_str(&format!("{}() = default;\n", name));
+            code.push_str(&format!("{}({});\n", name, self.codegen_parameters(&args)));
+        }
+        code.push_str("};\n");
+        // if has_constructor {
+        //     code.push_str(&forward_declarations);
+        // }
+        // code.push_str(&constructor);
+        code
+    }
+    pub fn codegen_enum(&mut self, name: &Symbol, enum_type: &Type, variants: &Vec<(Symbol, Expression, TokenLocation)>) -> String {
+        let mut code: String = String::new();
+        // C++ won't turn an int into an enum by itself, so there an enum of
+        // integers is backed by its type and its values are the enum
+        let integral: bool = self.cpp && matches!(enum_type, Type::Int(_) | Type::Usize(_) | Type::Char(_) | Type::Bool(_));
+        if integral {
+            code.push_str(&format!("enum {} : {} {{\n", name, self.codegen_type(enum_type)));
+        } else {
+            code.push_str(&format!("enum {} {{\n", name));
+        }
+        for (variant_name, _, _) in variants.iter() {
+            code.push_str(&format!("{},\n", variant_name));
+        }
+        code.push_str("};\n");
+        if integral {
+            code.push_str(&format!("static enum {} const {}[] = {{\n", name, mangle("values", &[name])));
+        } else if let Type::Function(args, return_type, _) = enum_type {
+            code.push_str(format!("static {} (*const {}[])(", self.codegen_type(return_type), mangle("values", &[name])).as_str());
+            for arg_type in args.iter() {
+                code.push_str(&format!("{}, ", self.codegen_type(arg_type)));
+            }
+            if args.len() > 0 {
+                code.pop();
+                code.pop();
+            }
+            code.push_str(") = {\n");
+        } else {
+            code.push_str(format!("static {} const {}[] = {{\n", self.codegen_type(enum_type), mangle("values", &[name])).as_str());
+        }
+        // Variants without a value are the last explicit value plus how far past it they are
+        let mut previous: (String, i64) = (String::new(), -1);
+        for (variant_name, variant_value, _) in variants.iter() {
+            let value: String = match variant_value {
+                Expression::Empty => {
+                    previous.1 += 1;
+                    if previous.0.is_empty() {
+                        previous.1.to_string()
+                    } else {
+                        format!("({}) + {}", previous.0, previous.1)
+                    }
+                }
+                Expression::Number(number, _) => {
+                    previous = (String::new(), *number);
+                    number.to_string()
+                }
+                _ => {
+                    let value: String = self.codegen_expression(variant_value);
+                    previous = (value.clone(), 0);
+                    value
+                }
+            };
+            if integral {
+                code.push_str(&format!("[{}] = (enum {})({}),\n", variant_name, name, value));
+            } else {
+                code.push_str(&format!("[{}] = {},\n", variant_name, value));
+            }
+        }
+        code.push_str("};\n");
+        self.enums.push(*name);
+        code
+    }
+    pub fn codegen_struct_enum(&mut self, name: &Symbol, values: &Vec<(Symbol, Vec<(Symbol, Type)>)>) -> String {
+        let mut enum_values: Vec<Symbol> = Vec::new();
+        for (variant_name, _) in values.iter() {
+            enum_values.push(*variant_name);
+        }
+        self.struct_enums.insert(*name, enum_values);
+        let mut code: String = String::new();
+        code.push_str(&format!("enum {} {{", mangle("tag", &[name])));
+        for (variant_name, _) in values.iter() {
+            code.push_str(&format!("{}, ", mangle("tag", &[name, variant_name])));
+        }
+        code.push_str("};\n");
+        for (variant_name, variant_fields) in values.iter() {
+            code.push_str(&format!("struct {} {{ ", mangle("variant", &[name, variant_name])));
+            for (field_name, field_type) in variant_fields.iter() {
+                code.push_str(&format!("{} {}; ", self.codegen_type(field_type), field_name));
+            }
+            code.push_str("};\n");
+        }
+        code.push_str(&format!("struct {} {{\n", name));
+        code.push_str(&format!("enum {} type;\n", mangle("tag", &[name])));
+        code.push_str(&format!("union {{ "));
+        for (variant_name, _) in values.iter() {
+            code.push_str(&format!("struct {} {}; ", mangle("variant", &[name, variant_name]), variant_name));
+        }
+        code.push_str(&format!("}};\n"));
+        code.push_str(&format!("}};\n"));
+        code
+    }
+    pub fn codegen_java_enum(&mut self, name: &Symbol, constructor: &Vec<(Symbol, Type)>, values: &Vec<(Symbol, Vec<Expression>)>) -> String {
+        self.java_enums.push(*name);
+        let mut code: String = String::new();
+        let mut variants: String = String::new();
+        for (variant_name, _) in values.iter() {
+            variants.push_str(&format!("{},\n", variant_name));
+        }
+        // C++ scopes an enum inside a struct to the struct, so it goes in front
+        if self.cpp {
+            code.push_str(&format!("enum {} {{\n{}}};\n", mangle("tag", &[name]), variants));
+        }
+        code.push_str(&format!("struct {} {{\n", name));
+        for (field_name, field_type) in constructor.iter() {
+            code.push_str(&format!("{} {}; ", self.codegen_type(field_type), field_name));
+        }
+        if self.cpp {
+            code.push_str(&format!("enum {} type;\n", mangle("tag", &[name])));
+        } else {
+            code.push_str(&format!("enum {{\n{}}} type;\n", variants));
+        }
+        code.push_str(&format!("}};\n"));
+
+        code.push_str(&format!("static struct {} {}[] = {{\n", name, mangle("values", &[name])));
+        for (variant_name, variant_values) in values.iter() {
+            code.push_str(&format!("[{}] = {{", variant_name));
+            for (_, value) in variant_values.iter().enumerate() {
+                code.push_str(&format!("{}, ", self.codegen_expression(value)));
+            }
+            code.push_str(&format!("{}", variant_name));
+            code.push_str(&format!("}},\n"));
+        }
+        code.push_str(&format!("}};\n"));
+        code
+    }
+    pub fn codegen_type_alias(&mut self, name: &Symbol, types: &Vec<Type>) -> String {
+        let mut code: String = String::new();
+        code.push_str(&format!("typedef "));
+        if types.len() == 1 {
+            code.push_str(&self.codegen_type(&types[0]));
+        } else {
+            code.push_str(&format!("union {{\n"));
+            for (i, t) in types.iter().enumerate() {
+                code.push_str(&format!("{} __{};\n", self.codegen_type(t), i));
+            }
+            code.push_str(&format!("}}"));
+        }
+        code.push_str(&format!(" {};\n", name));
+        self.type_aliases.push(*name);
+        code
+    }
+    pub fn codegen_member(&mut self, expression: &Expression, member: &Expression, location: &TokenLocation) -> String {
+        let base: String = self.codegen_expression(expression);
+        // Prefer the type the checker resolved; fall back to what we know about plain names
+        let pointer: bool = match self.member_types.get(location) {
+            Some(t) => matches!(t, Type::Pointer(_, _)),
+            None => match expression {
+                Expression::Identifier(name, _) => {
+                    matches!(self.variable_types.get(name).or(self.parameter_types.get(name)), Some(Type::Pointer(_, _)))
+                }
+                _ => false,
+            },
+        };
+        let operator: &str = if pointer { "->" } else { "." };
+        let target: String = if pointer { self.codegen_null_check(&base, location) } else { base.clone() };
+        // Calls through a local variable pass the variable along as the first argument
+        let receiver: bool = matches!(expression, Expression::Identifier(name, _) if self.variable_types.contains_key(name));
+        match member {
+            Expression::Call(callee, args, _) if receiver => {
+                let mut code: String = String::new();
+                code.push_str(&format!("{}{}{}({}, ", target, operator, callee, base));
+                for arg in args.iter() {
+                    code.push_str(&format!("{}, ", self.codegen_expression(arg)));
+                }
+                code.pop();
+                code.pop();
+                code.push_str(")");
+                code
+            }
+            // `std::string` has methods where `sl_string` has fields
+            Expression::Identifier(_, _) if self.cpp && matches!(self.member_types.get(location), Some(Type::String(_))) => {
+                format!("{}{}{}()", target, operator, self.codegen_expression(member))
+            }
+            _ => format!("{}{}{}", target, operator, self.codegen_expression(member)),
+        }
+    }
+    // `value.function(args)` calls `Struct.function(value, args)`, taking the
+    // address of value or dereferencing it to match what `self` expects
+    pub fn codegen_method_call(&mut self, receiver: &Expression, member: &Expression, location: &TokenLocation, function: &str, self_type: &Type) -> String {
+        let base: String = self.codegen_expression(receiver);
+        let is_pointer = |t: &Type| {
+            let mut t: &Type = t;
+            while let Type::Const(inner, _) | Type::Volatile(inner, _) | Type::Restrict(inner, _) = t {
+                t = inner;
+            }
+            matches!(t, Type::Pointer(_, _))
+        };
+        let base_is_pointer: bool = self.member_types.get(location).is_some_and(is_pointer);
+        let receiver: String = match (base_is_pointer, is_pointer(self_type)) {
+            (false, true) => format!("&({})", base),
+            (true, false) => format!("*{}", self.codegen_null_check(&base, location)),
+            _ => base,
+        };
+        let mut args: Vec<String> = vec![receiver];
+        if let Expression::Call(_, call_args, _) = member {
+            for arg in call_args.iter() {
+                args.push(self.codegen_expression(arg));
+            }
+        }
+        let (struct_name, name): (&str, &str) = function.split_once('.').unwrap_or_default();
+        format!("{}({})", self.method_symbol(struct_name, name), args.join(", "))
+    }
+    pub fn codegen_null_check(&mut self, pointer: &str, location: &TokenLocation) -> String {
+        let sources: std::sync::Arc<SourceMap> = match (&self.sources, self.debug) {
+            (Some(sources), true) => sources.clone(),
+            _ => return pointer.to_string(),
+        };
+        self.uses_null_checks = true;
+        let (name, line, column): (String, usize, usize) = sources.position(location);
+        let position: String = format!("{}:{}:{}", name, line, column);
+        format!("SL_CHECK_NULL({}, \"{}\")", pointer, position.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+    pub fn codegen_array_method(&mut self, array: &Expression, method: &Expression) -> String {
+        let array: String = self.codegen_expression(array);
+        match method {
+            Expression::Call(name, args, _) if name == "push" => format!("sl_array_push({}, {})", array, self.codegen_expression(&args[0])),
+            Expression::Call(name, _, _) if name == "pop" => format!("{}[sl_array_pop_index({})]", array, array),
+            _ => format!("sl_array_length({})", array),
+        }
+    }
+    // The type of an assignment's target if it holds a counted object: a counted
+    // local or a counted field
+    pub fn counted_target(&self, target: &Expression) -> Option<Type> {
+        match target {
+            Expression::Identifier(name, _) if self.is_counted_local(name) => self.variable_types.get(name).cloned(),
+            Expression::Member(_, field, location) => {
+                let struct_name: String = match self.member_types.get(location) {
+                    Some(Type::Pointer(t, _)) => t.name(),
+                    Some(t) => t.name(),
+                    None => return None,
+                };
+                let field: &Symbol = match &**field {
+                    Expression::Identifier(field, _) => field,
+                    _ => return None,
+                };
+                let (_, t): &(Symbol, Type) = self.struct_fields.get(&Symbol::intern(&struct_name))?.iter().find(|(name, _)| name == field)?;
+                if self.is_counted(t) {
+                    Some(t.clone())
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+    pub fn codegen_map_method(&mut self, t: &Type, map: &Expression, method: &Expression) -> String {
+        let map: String = self.codegen_expression(map);
+        let key: Type = match t {
+            Type::Map(key, _, _) => *key.clone(),
+            _ => return String::new(),
+        };
+        match method {
+            Expression::Call(name, args, _) if name == "contains" || name == "remove" => {
+                format!("sl_map_{}({}, {})", name, map, self.codegen_map_key(&key, &args[0]))
+            }
+            Expression::Call(name, _, _) if name == "keys" => format!("({}*)sl_map_collect({}, true)", self.codegen_type(&key), map),
+            Expression::Call(name, _, _) if name == "values" => format!("({}*)sl_map_collect({}, false)", self.codegen_map_value_type(t), map),
+            _ => format!("sl_map_length({})", map),
+        }
+    }
+    // Keys are passed to the runtime by address, through a one element array
+    // so that struct values work as well
+    pub fn codegen_map_key(&mut self, key: &Type, value: &Expression) -> String {
+        let value: String = self.codegen_expression(value);
+        self.codegen_items(key, &[value])
+    }
+    // A temporary array of items for the runtime to read. C++ has no compound
+    // literals, but an initializer list's array lives just as long.
+    pub fn codegen_items(&mut self, t: &Type, items: &[String]) -> String {
+        if self.cpp {
+            format!("std::initializer_list<{}>{{{}}}.begin()", self.codegen_type(t), items.join(", "))
+        } else {
+            format!("({}[]){{{}}}", self.codegen_type(t), items.join(", "))
+        }
+    }
+    pub fn codegen_map_value_type(&mut self, t: &Type) -> String {
+        match t {
+            Type::Map(_, value, _) => self.codegen_type(value),
+            _ => String::new(),
+        }
+    }
+    pub fn codegen_map_entry(&mut self, t: &Type, key: &Expression) -> String {
+        let key: String = match t {
+            Type::Map(key_type, _, _) => self.codegen_map_key(key_type, key),
+            _ => String::new(),
+        };
+        format!("{}, {}", key, self.codegen_map_layout(t))
+    }
+    // The key size, value size, and how keys compare, which a map is created with
+    pub fn codegen_map_layout(&mut self, t: &Type) -> String {
+        let (key, value): (&Type, &Type) = match t {
+            Type::Map(key, value, _) => (key, value),
+            _ => return String::new(),
+        };
+        let mut key_type: &Type = key;
+        while let Type::Const(t, _) | Type::Volatile(t, _) | Type::Restrict(t, _) = key_type {
+            key_type = t;
+        }
+        let kind: usize = match key_type {
+            Type::CString(_) => 1,
+            Type::Pointer(t, _) if matches!(**t, Type::Char(_)) || matches!(&**t, Type::Const(t, _) if matches!(**t, Type::Char(_))) => 1,
+            Type::String(_) => 2,
+            _ => 0,
+        };
+        format!("sizeof({}), sizeof({}), {}", self.codegen_type(key), self.codegen_type(value), kind)
+    }
+    pub fn codegen_signature(&mut self, name: &str, args: &[(Symbol, Type)], return_type: &Type) -> String {
+        let return_type: String = self.codegen_type(return_type);
+        format!("{} {}({})", return_type, name, self.codegen_parameters(args))
+    }
+    pub fn codegen_parameters(&mut self, args: &[(Symbol, Type)]) -> String {
+        let mut code: String = String::new();
+        for (arg_name, arg_type) in args.iter() {
+            if let Type::Function(func_args, return_type, _) = arg_type {
+                // return_type (*name)(args)
+                code.push_str(&format!("{} (*{})(", self.codegen_type(return_type), arg_name));
+                for func_arg_type in func_args.iter() {
+                    code.push_str(&format!("{}, ", self.codegen_type(func_arg_type)));
+                }
+                if func_args.len() > 0 {
+                    code.pop();
+                    code.pop();
+                }
+                code.push_str("), ");
+            } else {
+                code.push_str(&format!("{} {}, ", self.codegen_type(arg_type), arg_name));
+            }
+        }
+        if !args.is_empty() {
+            code.pop();
+            code.pop();
+        }
+        code
+    }
+    pub fn codegen_function(&mut self, name: &Symbol, args: &Vec<(Symbol, Type)>, return_type: &Type, body: &Vec<Statement>) -> String {
+        let symbol: String = self.symbol(name);
+        let mut code: String = self.codegen_linkage(name).to_string();
+        code.push_str(&match return_type {
+            // C++ insists on `int main`, and returns 0 from it by itself
+            Type::Void(location) if self.cpp && name == "main" => self.codegen_signature(&symbol, args, &Type::Int(location.clone())),
+            return_type => self.codegen_signature(&symbol, args, return_type),
+        });
+        for (arg_name, arg_type) in args.iter() {
+            self.parameter_types.insert(*arg_name, arg_type.clone());
+        }
+        code.push_str(" {\n");
+        if self.gc && symbol == "main" {
+            code.push_str("GC_INIT();\n");
+        }
+        self.return_type = Some(return_type.clone());
+        code.push_str(&self.codegen_block(body, false));
+        self.return_type = None;
+        code.push_str("}\n");
+        for (arg_name, _) in args.iter() {
+            self.parameter_types.remove(arg_name);
+        }
+        code
+    }
+    pub fn codegen_struct_function(&mut self, struct_name: &Symbol, name: &Symbol, args: &Vec<(Symbol, Type)>, return_type: &Type, body: &Vec<Statement>) -> String {
+        let functions: &mut Vec<Symbol> = self.struct_functions.entry(*struct_name).or_default();
+        if !functions.contains(name) {
+            functions.push(*name);
+        }
+        if let (true, Some((_, local))) = (name == "constructor", self.constructors.get(struct_name).cloned()) {
+            return self.codegen_constructor(struct_name, args, return_type, body, local);
+        }
+        let mut code: String = self.codegen_linkage(&Symbol::intern(&format!("{}.{}", struct_name, name))).to_string();
+        code.push_str(&self.codegen_signature(&self.method_symbol(struct_name, name), args, return_type));
+        for (arg_name, arg_type) in args.iter() {
+            self.parameter_types.insert(*arg_name, arg_type.clone());
+        }
+        code.push_str(" {\n");
+        self.return_type = Some(return_type.clone());
+        if name == "constructor" {
+            self.constructing = Some(*struct_name);
+        }
+        code.push_str(&self.codegen_block(body, false));
+        self.return_type = None;
+        self.constructing = None;
+        code.push_str("}\n");
+        for (arg_name, _) in args.iter() {
+            self.parameter_types.remove(arg_name);
+        }
+        code
+    }
+    // `S::S(args)`, where the object the body allocated is `this` and every
+    // return leaves the constructor
+    pub fn codegen_constructor(&mut self, struct_name: &Symbol, args: &[(Symbol, Type)], return_type: &Type, body: &[Statement], local: Symbol) -> String {
+        let mut code: String = format!("{}::{}({}) {{\n", struct_name, struct_name, self.codegen_parameters(args));
+        for (arg_name, arg_type) in args.iter() {
+            self.parameter_types.insert(*arg_name, arg_type.clone());
+        }
+        self.constructor_self = Some(local);
+        code.push_str(&self.codegen_block(body, false));
+        self.constructor_self = None;
+        code.push_str("}\n");
+        for (arg_name, _) in args.iter() {
+            self.parameter_types.remove(arg_name);
+        }
+        let names: Vec<String> = args.iter().map(|(arg_name, _)| arg_name.to_string()).collect();
+        code.push_str(&self.codegen_signature(&self.method_symbol(struct_name, "constructor"), args, return_type));
+        code.push_str(&format!(" {{\nreturn {};\n}}\n", Codegen::codegen_new(struct_name, &names)));
+        code
+    }
+    // A literal's text as it has to be written between quotes in C
+    pub fn escape(value: &str, quote: char) -> String {
+        let mut code: String = String::new();
+        let mut characters = value.chars().peekable();
+        while let Some(character) = characters.next() {
+            match character {
+                '\n' => code.push_str("\\n"),
+                '\t' => code.push_str("\\t"),
+                '\r' => code.push_str("\\r"),
+                '\\' => code.push_str("\\\\"),
+                // A digit after `\0` would be read as part of an octal escape
+                '\0' if characters.peek().is_some_and(|next| next.is_ascii_digit()) => code.push_str("\\000"),
+                '\0' => code.push_str("\\0"),
+                // `??` could start a trigraph
+                '?' if code.ends_with('?') => code.push_str("\\?"),
+                character if character == quote => {
+                    code.push('\\');
+                    code.push(character);
+                }
+                character if character.is_ascii_control() => code.push_str(&format!("\\{:03o}", character as u32)),
+                character => code.push(character),
+            }
+        }
+        code
+    }
+    // `print` and `println` become one printf call, or with a `string` among
+    // the values, printf calls around writing each of those on its own
+    pub fn codegen_print(&mut self, name: &Symbol, args: &[Expression], location: &TokenLocation) -> String {
+        self.require_hosted(&format!("`{}`", name), location);
+        self.uses_prints = true;
+        let pieces: Vec<String> = match args.first() {
+            Some(Expression::String(format, _)) => format_pieces(format).unwrap_or_default(),
+            _ => vec![],
+        };
+        let conversions: &'a Vec<PrintConversion> = &self.prints[location];
+        let mut calls: Vec<String> = vec![];
+        let mut format: String = String::new();
+        let mut values: Vec<String> = vec![];
+        let printf = |format: &str, values: &[String]| -> String {
+            format!("printf(\"{}\"{})", Codegen::escape(format, '"'), values.iter().map(|value| format!(", {}", value)).collect::<String>())
+        };
+        for (i, piece) in pieces.iter().enumerate() {
+            format.push_str(&piece.replace('%', "%%"));
+            let (Some(conversion), Some(arg)) = (conversions.get(i), args.get(i + 1)) else {
+                continue;
+            };
+            let value: String = self.codegen_expression(arg);
+            // A literal that wasn't made a `string` is still C's, and printf
+            // takes it as it is
+            let literal: bool = matches!(arg, Expression::String(_, location) if self.strings.get(location) != Some(&StringConversion::Literal));
+            match conversion {
+                PrintConversion::String if literal => values.push(value),
+                PrintConversion::String if self.cpp => values.push(format!("({}).c_str()", value)),
+                PrintConversion::String => {
+                    if !format.is_empty() {
+                        calls.push(printf(&format, &values));
+                    }
+                    // The helper comes with the string runtime
+                    self.uses_strings = true;
+                    calls.push(format!("sl_print_string({})", value));
+                    format.clear();
+                    values.clear();
+                    continue;
+                }
+                PrintConversion::Bool => values.push(format!("({}) ? \"true\" : \"false\"", value)),
+                PrintConversion::Pointer => values.push(format!("(void*)({})", value)),
+                _ => values.push(value),
+            }
+            format.push_str(conversion.specifier());
+        }
+        if name == "println" {
+            format.push('\n');
+        }
+        if !format.is_empty() {
+            calls.push(printf(&format, &values));
+        }
+        match calls.len() {
+            // An empty format prints nothing, which printf would be warned about
+            0 => "(void)0".to_string(),
+            1 => calls.remove(0),
+            _ => format!("({})", calls.join(", ")),
+        }
+    }
+    pub fn require_hosted(&mut self, what: &str, location: &TokenLocation) {
+        let error: Diagnostic = Diagnostic::type_error("E0031", format!("{} needs the C library, which --freestanding leaves out", what), location.clone());
+        // A type is generated again for each prototype
+        if self.freestanding && !self.errors.contains(&error) {
+            self.errors.push(error);
+        }
+    }
+    // The function the program starts in, if the C `main` has to be generated
+    // for it: one marked @entry, or a `main` taking its arguments as an array
+    pub fn entry_name(&self) -> Option<Symbol> {
+        if self.entry.is_some() {
+            return self.entry;
+        }
+        self.statements.iter().find_map(|statement| match Codegen::unannotated(statement) {
+            Statement::Function(name, args, _, _, _) if name == "main" && (self.keeps_arguments || matches!(args.as_slice(), [(_, Type::DynamicArray(_, _))])) => Some(*name),
+            _ => None,
+        })
+    }
+    pub fn declares_arguments(statements: &[Statement]) -> bool {
+        statements.iter().any(|statement| matches!(statement, Statement::External(inner, _) if matches!(&**inner, Statement::Variable(name, _, _, _) if ARGUMENT_VARIABLES.contains(&name.as_str()))))
+    }
+    pub fn codegen_keep_arguments(&self) -> &'static str {
+        if self.keeps_arguments {
+            "sl_argc = argc;\nsl_argv = argv;\n"
+        } else {
+            ""
+        }
+    }
+    // The `main` that starts the program in the entry function, passing the
+    // arguments along if it takes them, copied into an array if it wants one
+    pub fn codegen_entry(&mut self) -> String {
+        if self.testing {
+            return self.codegen_test_main();
+        }
+        if self.benchmarking {
+            return self.codegen_bench_main();
+        }
+        let Some(entry) = self.entry_name() else {
+            return String::new();
+        };
+        let Some((args, return_type)) = self.statements.iter().find_map(|statement| match Codegen::unannotated(statement) {
+            Statement::Function(name, args, return_type, _, _) if *name == entry => Some((args.clone(), return_type.clone())),
+            _ => None,
+        }) else {
+            return String::new();
+        };
+        let mut code: String = "int main(int argc, char** argv) {\n".to_string();
+        if self.gc {
+            code.push_str("GC_INIT();\n");
+        }
+        code.push_str(self.codegen_keep_arguments());
+        let call: String = match args.as_slice() {
+            [(_, array)] => {
+                let element: String = match array {
+                    Type::DynamicArray(element, _) if matches!(**element, Type::String(_)) => "sl_string_from_cstring(argv[__sl_i])".to_string(),
+                    _ => "argv[__sl_i]".to_string(),
+                };
+                code.push_str(&format!("{} __sl_args = NULL;\n", self.codegen_type(array)));
+                code.push_str(&format!("for (int __sl_i = 0; __sl_i < argc; __sl_i++) {{\nsl_array_push(__sl_args, {});\n}}\n", element));
+                format!("{}(__sl_args)", self.symbol(&entry))
+            }
+            [_, (_, argv)] => format!("{}(argc, ({})argv)", self.symbol(&entry), self.codegen_type(argv)),
+            _ => format!("{}()", self.symbol(&entry)),
+        };
+        match return_type {
+            Type::Void(_) => code.push_str(&format!("{};\nreturn 0;\n}}\n", call)),
+            _ => code.push_str(&format!("return {};\n}}\n", call)),
+        }
+        code
+    }
+    // Runs each test, or those whose names contain one of the arguments, with a
+    // failed assertion jumping back here. Exits with 1 if any of them failed.
+    pub fn codegen_test_main(&mut self) -> String {
+        let mut code: String = "int main(int argc, char** argv) {\n".to_string();
+        if self.gc {
+            code.push_str("GC_INIT();\n");
+        }
+        code.push_str(self.codegen_keep_arguments());
+        code.push_str("static int __sl_passed = 0, __sl_failed = 0;\njmp_buf __sl_jump;\nsl_test_jump = &__sl_jump;\n");
+        for (name, location) in self.tests.iter() {
+            let label: String = self.harness_label(name, location);
+            code.push_str(&format!("if (sl_harness_selected(argc, argv, \"{}\")) {{\n", Codegen::escape(name, '"')));
+            code.push_str(&format!("if (!setjmp(__sl_jump)) {{\n{}();\n__sl_passed++;\nprintf(\"test {} ... ok\\n\");\n}} ", self.symbol(name), label));
+            code.push_str(&format!("else {{\n__sl_failed++;\nprintf(\"test {} ... FAILED\\n\");\n}}\nfflush(stdout);\n}}\n", label));
+        }
+        code.push_str("printf(\"\\n%d passed, %d failed\\n\", __sl_passed, __sl_failed);\nreturn __sl_failed ? 1 : 0;\n}\n");
+        code
+    }
+    // Times each benchmark, or those whose names contain one of the arguments
+    pub fn codegen_bench_main(&mut self) -> String {
+        let mut code: String = "int main(int argc, char** argv) {\n".to_string();
+        if self.gc {
+            code.push_str("GC_INIT();\n");
+        }
+        code.push_str(self.codegen_keep_arguments());
+        for (name, location) in self.benches.iter() {
+            let label: String = self.harness_label(name, location);
+            code.push_str(&format!("if (sl_harness_selected(argc, argv, \"{}\")) {{\nsl_bench(\"{}\", {});\n}}\n", Codegen::escape(name, '"'), label, self.symbol(name)));
+        }
+        code.push_str("return 0;\n}\n");
+        code
+    }
+    // A test or benchmark's name and where it is, as a C string's contents
+    pub fn harness_label(&self, name: &str, location: &TokenLocation) -> String {
+        let position: String = match &self.sources {
+            Some(sources) => {
+                let (file, line, _): (String, usize, usize) = sources.position(location);
+                format!(" ({}:{})", file, line)
+            }
+            None => String::new(),
+        };
+        Codegen::escape(&format!("{}{}", name, position), '"')
+    }
+    // Objects are still allocated with malloc, so that they can be freed like any other
+    pub fn codegen_new(struct_name: &str, args: &[String]) -> String {
+        format!("new (malloc(sizeof(struct {}))) {}({})", struct_name, struct_name, args.join(", "))
+    }
+    pub fn codegen_variable(&mut self, name: &Symbol, t: &Type, value: &Expression) -> String {
+        self.variable_types.insert(*name, t.clone());
+        if self.is_counted(t) && !self.arc_scopes.is_empty() {
+            return self.codegen_counted_variable(name, t, value);
+        }
+        if self.gc && self.constructing.is_some() && Codegen::is_malloc(value) && self.pointed_struct(t) == self.constructing {
+            let t_code: String = self.codegen_type(t);
+            return format!("{} {} = ({})GC_MALLOC(sizeof(struct {}));\n", t_code, name, t_code, self.constructing.unwrap_or_default());
+        }
+        if self.constructor_self.as_ref() == Some(name) && Codegen::is_malloc(value) {
+            return format!("{} {} = this;\n", self.codegen_type(t), name);
+        }
+        self.current_variable_struct_enum_variant = Some(*name);
+        let mut code: String = String::new();
+        if let Type::Array(type_, size, _) = t {
+            code.push_str(&format!("{} {}[{}]", self.codegen_type(type_), name, self.codegen_expression(size)));
+        } else if let Type::Function(args, return_type, _) = t {
+            code.push_str(&format!("{} (*{})(", self.codegen_type(return_type), name));
+            for arg_type in args.iter() {
+                code.push_str(&format!("{}, ", self.codegen_type(arg_type)));
+            }
+            if args.len() > 0 {
+                code.pop();
+                code.pop();
+            }
+            code.push_str(")");
+        } else {
+            code.push_str(&format!("{} {}", self.codegen_type(t), name));
+        }
+        if let (Expression::Empty, Type::DynamicArray(_, _) | Type::Map(_, _, _)) = (value, t) {
+            // An array or map with nothing in it yet is a null pointer to the runtime
+            code.push_str(" = NULL;\n");
+        } else if let Expression::Empty = value {
+            code.push_str(";\n");
+        } else if self.cpp && Codegen::is_malloc(value) {
+            // C++ won't turn the `void*` into any other pointer by itself
+            code.push_str(&format!(" = ({}){};\n", self.codegen_type(t), self.codegen_expression(value)));
+        } else {
+            code.push_str(&format!(" = {};\n", self.codegen_expression(value)));
+        }
+        self.current_variable_struct_enum_variant = None;
+        code
+    }
+    pub fn codegen_counted_variable(&mut self, name: &Symbol, t: &Type, value: &Expression) -> String {
+        let t_code: String = self.codegen_type(t);
+        let value_code: String = match value {
+            Expression::Empty => "NULL".to_string(),
+            // The object a constructor allocates for itself is the counted one
+            value if self.constructing.is_some() && Codegen::is_malloc(value) && self.counted_struct(t) == self.constructing => {
+                let struct_name: Symbol = self.constructing.unwrap_or_default();
+                format!("({})sl_rc_alloc(sizeof(struct {}), {})", t_code, struct_name, mangle("drop", &[&struct_name]))
+            }
+            value => self.codegen_owned(t, value),
+        };
+        if let Some((_, names)) = self.arc_scopes.last_mut() {
+            names.push(*name);
+        }
+        format!("{} {} = {};\n", t_code, name, value_code)
+    }
+    pub fn is_malloc(value: &Expression) -> bool {
+        match value {
+            Expression::Call(name, _, _) => name == "malloc" || name == "calloc",
+            Expression::Cast(value, _, _) | Expression::UnsafeCast(value, _, _) | Expression::Grouping(value, _) => Codegen::is_malloc(value),
+            _ => false,
+        }
+    }
+    pub fn pointed_struct(&self, t: &Type) -> Option<Symbol> {
+        match t {
+            Type::Pointer(t, _) => match &**t {
+                Type::Unknown(name, _) | Type::Struct(name, _) if self.structs.contains(name) => Some(*name),
+                _ => None,
+            },
+            Type::Const(t, _) | Type::Volatile(t, _) | Type::Restrict(t, _) => self.pointed_struct(t),
+            _ => None,
+        }
+    }
+    // The struct a counted pointer points to. Whether a pointer is counted
+    // only depends on its type, so every one the runtime is handed has a count.
+    pub fn counted_struct(&self, t: &Type) -> Option<Symbol> {
+        if self.arc {
+            self.pointed_struct(t).filter(|name| !self.uncounted.contains(name))
+        } else {
+            None
+        }
+    }
+    pub fn is_counted(&self, t: &Type) -> bool {
+        self.counted_struct(t).is_some()
+    }
+    pub fn is_counted_local(&self, name: &Symbol) -> bool {
+        self.arc_scopes.iter().any(|(_, names)| names.iter().any(|local| local == name))
+    }
+    // `new` and calls hand back an object the caller owns; a value read from
+    // anywhere else has to be retained before it's stored
+    pub fn codegen_owned(&mut self, t: &Type, value: &Expression) -> String {
+        let code: String = self.codegen_expression(value);
+        let mut inner: &Expression = value;
+        while let Expression::Cast(value, _, _) | Expression::UnsafeCast(value, _, _) | Expression::Grouping(value, _) = inner {
+            inner = value;
+        }
+        let owned: bool = match inner {
+            Expression::New(_, _, _) | Expression::Call(_, _, _) | Expression::GenericCall(_, _, _, _) | Expression::Null => true,
+            Expression::Member(_, member, _) => matches!(**member, Expression::Call(_, _, _)),
+            _ => false,
+        };
+        if owned || !self.is_counted(t) {
+            code
+        } else {
+            format!("({})sl_retain({})", self.codegen_type(t), code)
+        }
+    }
+    // Releases the counted locals of every block from depth inwards
+    pub fn codegen_releases(&self, depth: usize, except: Option<&str>) -> String {
+        let mut code: String = String::new();
+        for (_, names) in self.arc_scopes.iter().skip(depth).rev() {
+            for name in names.iter().rev() {
+                if Some(name.as_str()) != except {
+                    code.push_str(&format!("sl_release({});\n", name));
+                }
+            }
+        }
+        code
+    }
+    pub fn codegen_loop_releases(&self) -> String {
+        match self.arc_scopes.iter().rposition(|(is_loop, _)| *is_loop) {
+            Some(depth) => self.codegen_releases(depth, None),
+            None => String::new(),
+        }
+    }
+    pub fn codegen_block(&mut self, body: &[Statement], is_loop: bool) -> String {
+        let mut code: String = String::new();
+        self.arc_scopes.push((is_loop, vec![]));
+        for statement in body.iter() {
+            code.push_str(&self.codegen_line(statement));
+            code.push_str(&self.codegen_statement(statement));
+        }
+        // Blocks that end in a jump have released everything on the way out
+        if !Checker::terminates(body) {
+            code.push_str(&self.codegen_releases(self.arc_scopes.len() - 1, None));
+        }
+        self.arc_scopes.pop();
+        code
+    }
+    // Called when the count of an object drops to zero, to let go of the
+    // objects its fields refer to
+    pub fn codegen_drops(&mut self) -> String {
+        let mut code: String = String::new();
+        let mut names: Vec<Symbol> = vec![];
+        for name in self.structs.iter() {
+            if !names.contains(name) {
+                names.push(*name);
+            }
+        }
+        for name in names.iter() {
+            code.push_str(&format!("static void {}(void* object) {{\n", mangle("drop", &[name])));
+            let fields: Vec<(Symbol, Type)> = self.struct_fields.get(name).cloned().unwrap_or_default();
+            let counted: Vec<&Symbol> = fields.iter().filter(|(_, t)| self.is_counted(t)).map(|(field, _)| field).collect();
+            if counted.is_empty() {
+                code.push_str("(void)object;\n");
+            } else {
+                code.push_str(&format!("struct {}* self = (struct {}*)object;\n", name, name));
+            }
+            for field in counted {
+                code.push_str(&format!("sl_release(self->{});\n", field));
+            }
+            code.push_str("}\n");
+        }
+        code
+    }
+    pub fn codegen_constant(&mut self, name: &Symbol, t: &Type, value: &Expression) -> String {
+        self.variable_types.insert(*name, t.clone());
+        let mut code: String = String::new();
+        code.push_str(&format!("const {} {} = {};\n", self.codegen_type(t), name, self.codegen_expression(value)));
+        code
+    }
+    pub fn codegen_return(&mut self, value: &Expression) -> String {
+        let mut code: String = String::new();
+        if self.constructor_self.is_some() {
+            return "return;\n".to_string();
+        }
+        if !self.arc {
+            code.push_str(&format!("return {};\n", self.codegen_expression(value)));
+            return code;
+        }
+        // A counted local being returned is handed over to the caller as is;
+        // anything else counted is retained so every call returns an owned object
+        let returned: Option<Symbol> = match value {
+            Expression::Identifier(name, _) if self.is_counted_local(name) => Some(*name),
+            _ => None,
+        };
+        let releases: String = self.codegen_releases(0, returned.as_deref());
+        if returned.is_some() || matches!(value, Expression::Empty) {
+            code.push_str(&format!("{}return {};\n", releases, self.codegen_expression(value)));
+            return code;
+        }
+        let return_type: Type = self.return_type.clone().unwrap_or(Type::Void(value.location()));
+        let value: String = self.codegen_owned(&return_type, value);
+        if releases.is_empty() {
+            code.push_str(&format!("return {};\n", value));
+        } else {
+            code.push_str(&format!("{{\n{} __sl_result = {};\n{}return __sl_result;\n}}\n", self.codegen_type(&return_type), value, releases));
+        }
+        code
+    }
+    pub fn codegen_import(&mut self, path: &String, location: &TokenLocation) -> String {
+        let mut code: String = String::new();
+        if let Some(header) = path.strip_prefix("std/").filter(|header| !FREESTANDING_HEADERS.contains(header)) {
+            self.require_hosted(&format!("`{}`", header), location);
+        }
+        if let Some((_, flag)) = LINKED_HEADERS.iter().find(|(header, _)| path.strip_prefix("std/") == Some(header)) {
+            code.push_str(&format!("// Link with {}\n", flag));
+        }
+        if path.starts_with("std/") {
+            code.push_str(&format!("#include <{}>\n", path.trim_start_matches("std/")));
+        } else {
+            code.push_str(&format!("#include \"{}\"\n", path));
+        }
+        code
+    }
+    pub fn codegen_if(&mut self, condition: &Expression, body: &Vec<Statement>, else_body: &Vec<Statement>) -> String {
+        let mut code: String = String::new();
+        code.push_str(&format!("if ({}) {{\n", self.codegen_expression(condition)));
+        code.push_str(&self.codegen_block(body, false));
+        code.push_str("}\n");
+        if else_body.len() > 0 {
+            code.push_str("else {\n");
+            code.push_str(&self.codegen_block(else_body, false));
+            code.push_str("}\n");
+        }
+        code
+    }
+    pub fn codegen_type(&mut self, t: &Type) -> String {
+        match t {
+            Type::Int(_) => "int".to_string(),
+            Type::Usize(_) => "size_t".to_string(),
+            Type::String(location) => {
+                self.uses_strings = true;
+                self.require_hosted("`string`", location);
+                if self.cpp { "std::string" } else { "sl_string" }.to_string()
+            }
+            Type::CString(_) => "char*".to_string(),
+            Type::Char(_) => "char".to_string(),
+            Type::Bool(_) => "bool".to_string(),
+            Type::Void(_) => "void".to_string(),
+            Type::Struct(name, _) => format!("struct {}", name),
+            Type::Enum(name, _) => format!("enum {}", name),
+            Type::Function(_, _, _) => {
+                self.errors.push(Diagnostic::type_error("E0021", "Function type is not allowed here".to_string(), t.location().clone()));
+                "".to_string()
+            }
+            Type::Pointer(t, _) => format!("{}*", self.codegen_type(t)),
+            Type::Array(t, _, _) => format!("{}", self.codegen_type(t)), // The size is generated in the declarations because C is stupid
+            Type::DynamicArray(element, location) => {
+                self.uses_arrays = true;
+                self.require_hosted("a dynamic array", location);
+                self.check_runtime_element(element);
+                format!("{}*", self.codegen_type(element))
+            }
+            Type::Map(key, value, location) => {
+                self.uses_maps = true;
+                self.require_hosted("a map", location);
+                self.check_runtime_element(key);
+                self.check_runtime_element(value);
+                "sl_map*".to_string()
+            }
+            Type::Restrict(t, _) if self.cpp => format!("{} __restrict", self.codegen_type(t)),
+            Type::Restrict(t, _) => format!("{} restrict", self.codegen_type(t)),
+            Type::Const(t, _) => format!("const {}", self.codegen_type(t)),
+            Type::Volatile(t, _) => format!("volatile {}", self.codegen_type(t)),
+            Type::GenericType(name, _) => name.to_string(),
+            Type::Unknown(name, location) => {
+                // This type is only for checking if it's a struct, enum, or type alias
+                if self.structs.contains(name) {
+                    format!("struct {}", name)
+                } else if self.enums.contains(name) {
+                    format!("enum {}", name)
+                } else if self.struct_enums.contains_key(name) {
+                    format!("struct {}", name)
+                } else if self.java_enums.contains(name) {
+                    format!("struct {}", name)
+                } else if self.external_types.contains(name) {
+                    SPELLED_TYPES.iter().find(|(word, _)| name == word).map_or(name.to_string(), |(_, spelled)| spelled.to_string())
+                } else if self.type_aliases.contains(name) {
+                    name.to_string()
+                } else {
+                    self.errors.push(Diagnostic::type_error("E0022", format!("Unknown type {}", name), location.clone()));
+                    "ERROR".to_string()
+                }
+            }
+            Type::Error(error, _) => {
+                self.errors.push(error.clone());
+                "ERROR".to_string()
+            }
+        }
+    }
+    // The runtimes move elements around with memcpy, which a `std::string` can't survive
+    pub fn check_runtime_element(&mut self, t: &Type) {
+        let mut element: &Type = t;
+        while let Type::Const(inner, _) | Type::Volatile(inner, _) | Type::Restrict(inner, _) = element {
+            element = inner;
+        }
+        if let (true, Type::String(location)) = (self.cpp, element) {
+            self.errors.push(Diagnostic::type_error("E0027", "`string` can't be stored in an array or map with --target=c++".to_string(), location.clone()));
+        }
+    }
+    pub fn codegen_while(&mut self, condition: &Expression, body: &Vec<Statement>) -> String {
+        let mut code: String = String::new();
+        code.push_str(&format!("while ({}) {{\n", self.codegen_expression(condition)));
+        code.push_str(&self.codegen_block(body, true));
+        code.push_str("}\n");
+        code
+    }
+    // A range's end and an array are worked out once, before the first time
+    // around. The loop variable is a copy of each item.
+    pub fn codegen_for(&mut self, name: &Symbol, iterable: &Expression, body: &[Statement], location: &TokenLocation) -> String {
+        let mut code: String = String::new();
+        let iterated: Type = self.loops.get(location).cloned().unwrap_or(Type::Int(location.clone()));
+        if let Expression::Range(from, to, _) = iterable {
+            let t: String = self.codegen_type(&iterated);
+            let end: String = mangle("for", &[name, "end"]);
+            code.push_str(&format!("for ({} {} = {}, {} = {}; {} < {}; {}++) {{\n", t, name, self.codegen_expression(from), end, self.codegen_expression(to), name, end, name));
+            self.variable_types.insert(*name, iterated);
+            code.push_str(&self.codegen_block(body, true));
+            code.push_str("}\n");
+            return code;
+        }
+        let (element, length): (Type, Option<String>) = match &iterated {
+            Type::Array(element, size, _) => ((**element).clone(), Some(self.codegen_expression(size))),
+            Type::DynamicArray(element, _) => ((**element).clone(), None),
+            _ => return code,
+        };
+        let items: String = mangle("for", &[name, "items"]);
+        let index: String = mangle("for", &[name, "index"]);
+        let length: String = length.unwrap_or_else(|| format!("sl_array_length({})", items));
+        let t: String = self.codegen_type(&element);
+        code.push_str("{\n");
+        code.push_str(&format!("{}* {} = {};\n", t, items, self.codegen_expression(iterable)));
+        code.push_str(&format!("for (size_t {} = 0; {} < {}; {}++) {{\n", index, index, length, index));
+        code.push_str(&format!("{} {} = {}[{}];\n", t, name, items, index));
+        self.variable_types.insert(*name, element);
+        code.push_str(&self.codegen_block(body, true));
+        code.push_str("}\n}\n");
+        code
+    }
+    pub fn codegen_expression(&mut self, expression: &Expression) -> String {
+        match expression {
+            Expression::Number(value, _) => value.to_string(),
+            Expression::String(value, location) => {
+                if self.strings.get(location) == Some(&StringConversion::Literal) {
+                    self.uses_strings = true;
+                    let literal: String = Codegen::escape(value, '"');
+                    if self.cpp && value.contains('\0') {
+                        // Otherwise the string would end at the first NUL
+                        format!("std::string(\"{}\", {})", literal, value.len())
+                    } else if self.cpp {
+                        format!("std::string(\"{}\")", literal)
+                    } else {
+                        format!("SL_STRING(\"{}\")", literal)
+                    }
+                } else {
+                    format!("\"{}\"", Codegen::escape(value, '"'))
+                }
+            }
+            Expression::Char(value, _) => format!("'{}'", Codegen::escape(value, '\'')),
+            Expression::Boolean(value, _) => value.to_string(),
+            Expression::Identifier(name, _) if !self.is_variable(name) => self.symbol(name),
+            Expression::Identifier(name, _) => name.to_string(),
+            Expression::Null => "NULL".to_string(),
+            Expression::Call(name, args, location) if self.prints.contains_key(location) => self.codegen_print(name, args, location),
+            Expression::Call(_, args, location) if self.asserts.contains(location) => {
+                self.uses_asserts = true;
+                let position: String = match &self.sources {
+                    Some(sources) => {
+                        let (name, line, column): (String, usize, usize) = sources.position(location);
+                        format!("{}:{}:{}", name, line, column)
+                    }
+                    None => String::new(),
+                };
+                let message: String = match args.get(1) {
+                    Some(Expression::String(message, _)) => format!("\"{}\"", Codegen::escape(message, '"')),
+                    _ => "NULL".to_string(),
+                };
+                format!("SL_ASSERT({}, \"{}\", {})", self.codegen_expression(&args[0]), Codegen::escape(&position, '"'), message)
+            }
+            Expression::Call(name, args, _) => {
+                let mut code: String = String::new();
+                if self.structs.contains(name) {
+                    code.push_str(format!("&(struct {}){{", name).as_str());
+                    for (_, arg) in args.iter().enumerate() {
+                        code.push_str(&format!("{}, ", self.codegen_expression(arg)));
+                    }
+                    if args.len() > 0 {
+                        code.pop();
+                        code.pop();
+                    }
+                    code.push_str("}");
+                    return code;
+                }
+                let callee: String = if self.is_variable(name) { name.to_string() } else { self.symbol(name) };
+                code.push_str(&format!("{}(", callee));
+                for arg in args.iter() {
+                    code.push_str(&format!("{}, ", self.codegen_expression(arg)));
+                }
+                if args.len() > 0 {
+                    code.pop();
+                    code.pop();
+                }
+                code.push_str(")");
+                code
+            }
+            Expression::GenericCall(name, types, args, _) => {
+                let mut code: String = format!("{}(", generic_symbol(name, types));
+                for arg in args.iter() {
+                    code.push_str(&format!("{}, ", self.codegen_expression(arg)));
+                }
+                if args.len() > 0 {
+                    code.pop();
+                    code.pop();
+                }
+                code.push_str(")");
+                code
+            }
+            Expression::Member(expression, member, location) => {
+                if let Some((function, self_type)) = self.methods.get(location) {
+                    return self.codegen_method_call(expression, member, location, function, self_type);
+                }
+                match self.member_types.get(location) {
+                    Some(Type::DynamicArray(_, _)) => return self.codegen_array_method(expression, member),
+                    Some(t @ Type::Map(_, _, _)) => return self.codegen_map_method(t, expression, member),
+                    _ => {}
+                }
+                match &**expression {
+                    Expression::Identifier(name, _) if self.variable_types.contains_key(name) || self.parameter_types.contains_key(name) => {
+                        if let Some(Type::Unknown(type_name, _)) = self.variable_types.get(name) {
+                            if self.struct_enums.contains_key(type_name) && !matches!(**member, Expression::Call(_, _, _)) {
+                                let variant: Symbol = *self.variable_struct_enum_variant.get(name).unwrap();
+                                return format!("{}.{}.{}", name, variant, self.codegen_expression(member));
+                            }
+                        }
+                        self.codegen_member(expression, member, location)
+                    }
+                    Expression::Identifier(name, _) => {
+                        if self.structs.contains(name) {
+                            if let Expression::Identifier(member_id, _) = &**member {
+                                let curr_struct_fields: &Vec<(Symbol, Type)> = self.struct_fields.get(name).unwrap();
+                                for (field_name, field_type) in curr_struct_fields.iter() {
+                                    if field_name != member_id {
+                                        continue;
+                                    }
+                                    if let Type::Function(_, _, _) = field_type.clone() {
+                                        return self.method_symbol(name, member_id);
+                                    } else {
+                                        return format!("{}.{}", name, member_id);
+                                    }
+                                }
+                                self.errors.push(Diagnostic::runtime_error("E0016", format!("Unknown field {} in struct {}", member_id, name), expression.location().clone()));
+                                "".to_string()
+                            } else if let Expression::Call(callee, args, _) = &**member {
+                                if !self.struct_functions.get(name).is_some_and(|functions| functions.contains(callee)) {
+                                    self.errors.push(Diagnostic::runtime_error("E0016", format!("Unknown function {} in struct {}", callee, name), expression.location().clone()));
+                                    return "".to_string();
+                                }
+                                let args: Vec<String> = args.iter().map(|arg| self.codegen_expression(arg)).collect();
+                                format!("{}({})", self.method_symbol(name, callee), args.join(", "))
+                            } else {
+                                format!("{}.{}", name, self.codegen_expression(member))
+                            }
+                        } else if self.enums.contains(&name) {
+                            match &**member {
+                                Expression::Call(callee, args, _) => {
+                                    let mut code: String = String::new();
+                                    code.push_str(&format!("{}[{}](", mangle("values", &[name]), callee));
+                                    for arg in args.iter() {
+                                        code.push_str(&format!("{}, ", self.codegen_expression(arg)));
+                                    }
+                                    if args.len() > 0 {
+                                        code.pop();
+                                        code.pop();
+                                    }
+                                    code.push_str(")");
+                                    code
+                                }
+                                Expression::Identifier(member, _) => {
+                                    format!("{}[{}]", mangle("values", &[name]), member)
+                                }
+                                _ => {
+                                    self.errors.push(Diagnostic::runtime_error("E0023", "Invalid enum member access".to_string(), expression.location().clone()));
+                                    "".to_string()
+                                }
+                            }
+                        } else if self.struct_enums.contains_key(name) {
+                            match &**member {
+                                Expression::Call(callee, args, _) => {
+                                    let mut code: String = String::new();
+                                    if self.current_variable_struct_enum_variant.is_some() {
+                                        self.variable_struct_enum_variant.insert(self.current_variable_struct_enum_variant.unwrap(), *callee);
+                                    }
+                                    code.push_str(&format!("{{{}, .{} = {{ ", mangle("tag", &[name, callee]), callee));
+                                    for arg in args.iter() {
+                                        code.push_str(&format!("{}, ", self.codegen_expression(arg)));
+                                    }
+                                    if args.len() > 0 {
+                                        code.pop();
+                                        code.pop();
+                                    }
+                                    code.push_str(" } }");
+                                    code
+                                }
+                                _ => {
+                                    self.errors.push(Diagnostic::runtime_error("E0023", "Invalid enum member access".to_string(), expression.location().clone()));
+                                    "".to_string()
+                                }
+                            }
+                        } else if self.java_enums.contains(name) {
+                            match &**member {
+                                Expression::Identifier(member, _) => {
+                                    format!("{}[{}]", mangle("values", &[name]), member)
+                                }
+                                _ => {
+                                    self.errors.push(Diagnostic::runtime_error("E0023", "Invalid enum member access".to_string(), expression.location().clone()));
+                                    "".to_string()
+                                }
+                            }
+                        } else {
+                            self.codegen_member(expression, member, location)
+                        }
+                    }
+                    _ => self.codegen_member(expression, member, location),
+                }
+            }
+            Expression::Grouping(expression, _) => {
+                format!("({})", self.codegen_expression(expression))
+            }
+            Expression::NamedArgument(name, expression, _) => {
+                format!(".{} = {}", name, self.codegen_expression(expression))
+            }
+            Expression::Cast(expression, t, location) => {
+                match self.strings.get(location) {
+                    Some(StringConversion::ToCString) => format!("sl_string_to_cstring({})", self.codegen_expression(expression)),
+                    Some(StringConversion::FromCString) => {
+                        self.uses_strings = true;
+                        let value: String = self.codegen_expression(expression);
+                        if self.cpp {
+                            format!("std::string({})", value)
+                        } else {
+                            format!("sl_string_from_cstring({})", value)
+                        }
+                    }
+                    _ if self.strings.get(&expression.location()) == Some(&StringConversion::Literal) => self.codegen_expression(expression),
+                    _ => format!("({}){}", self.codegen_type(t), self.codegen_expression(expression)),
+                }
+            }
+            Expression::UnsafeCast(expression, t, _) => {
+                format!("(({})({}))", self.codegen_type(t), self.codegen_expression(expression))
+            }
+            Expression::SizeOf(t, _) => {
+                format!("sizeof({})", self.codegen_type(t))
+            }
+            Expression::Index(expression, index, location) => {
+                if let Some(Type::Map(key, value, _)) = self.maps.get(location) {
+                    let map: String = self.codegen_expression(expression);
+                    let key: String = self.codegen_map_key(key, index);
+                    return format!("(*({}*)sl_map_at({}, {}))", self.codegen_type(value), map, key);
+                }
+                let base: String = self.codegen_expression(expression);
+                if self.bounds_checks && self.array_indexes.contains(location) {
+                    format!("{}[sl_array_check({}, {})]", base, base, self.codegen_expression(index))
+                } else {
+                    format!("{}[{}]", base, self.codegen_expression(index))
+                }
+            }
+            Expression::Map(entries, location) => {
+                let (t, key, value): (&Type, &Type, &Type) = match self.maps.get(location) {
+                    Some(t @ Type::Map(key, value, _)) if !entries.is_empty() => (t, key, value),
+                    _ => return "NULL".to_string(),
+                };
+                let keys: Vec<String> = entries.iter().map(|(key, _)| self.codegen_expression(key)).collect();
+                let values: Vec<String> = entries.iter().map(|(_, value)| self.codegen_expression(value)).collect();
+                let keys: String = self.codegen_items(key, &keys);
+                let values: String = self.codegen_items(value, &values);
+                format!("sl_map_from({}, {}, {}, {})", entries.len(), keys, values, self.codegen_map_layout(t))
+            }
+            // A literal that makes a `T[]` is copied into one, or is a null
+            // pointer when it's empty
+            Expression::Array(elements, location) if self.arrays.contains_key(location) => {
+                let t: Type = self.arrays[location].clone();
+                let t_code: String = self.codegen_type(&t);
+                let Type::DynamicArray(element, _) = &t else {
+                    return String::new();
+                };
+                if elements.is_empty() {
+                    return format!("({})NULL", t_code);
+                }
+                let items: Vec<String> = elements.iter().map(|element| self.codegen_expression(element)).collect();
+                let items: String = self.codegen_items(element, &items);
+                format!("({})sl_array_from({}, {}, sizeof({}))", t_code, elements.len(), items, self.codegen_type(element))
+            }
+            Expression::Array(elements, _) => {
+                let mut code: String = String::new();
+                code.push_str("{");
+                for element in elements.iter() {
+                    code.push_str(&format!("{}, ", self.codegen_expression(element)));
+                }
+                if elements.len() > 0 {
+                    code.pop();
+                    code.pop();
+                }
+                code.push_str("}");
+                code
+            }
+            Expression::New(identifier, args, location) => {
+                self.uses_new = true;
+                if self.constructors.contains_key(identifier) {
+                    self.require_hosted("`new`", location);
+                    let args: Vec<String> = args.iter().map(|arg| self.codegen_expression(arg)).collect();
+                    return Codegen::codegen_new(identifier, &args);
+                }
+                let mut code: String = String::new();
+                code.push_str(&format!("{}(", self.method_symbol(identifier, "constructor")));
+                for arg in args.iter() {
+                    code.push_str(&format!("{}, ", self.codegen_expression(arg)));
+                }
+                if args.len() > 0 {
+                    code.pop();
+                    code.pop();
+                }
+                code.push_str(")");
+                code
+            }
+            Expression::Unary(op, expression, _) => {
+                let op: String = match op {
+                    TokenKind::Minus => "-".to_string(),
+                    TokenKind::Bang => "!".to_string(),
+                    _ => {
+                        self.errors.push(Diagnostic::runtime_error("E0017", "Invalid unary operator".to_string(), expression.location().clone()));
+                        return String::new();
+                    }
+                };
+                format!("{}{}", op, self.codegen_expression(expression))
+            }
+            Expression::Binary(op, left, right, location) => {
+                match self.strings.get(location) {
+                    Some(StringConversion::Equals | StringConversion::NotEquals) if self.cpp => {}
+                    Some(StringConversion::Equals) => return format!("sl_string_equals({}, {})", self.codegen_expression(left), self.codegen_expression(right)),
+                    Some(StringConversion::NotEquals) => return format!("!sl_string_equals({}, {})", self.codegen_expression(left), self.codegen_expression(right)),
+                    _ => {}
+                }
+                let op: String = match op {
+                    TokenKind::Plus => "+".to_string(),
+                    TokenKind::Minus => "-".to_string(),
+                    TokenKind::Star => "*".to_string(),
+                    TokenKind::Slash => "/".to_string(),
+                    TokenKind::Percent => "%".to_string(),
+                    TokenKind::EqualEqual => "==".to_string(),
+                    TokenKind::BangEqual => "!=".to_string(),
+                    TokenKind::Less => "<".to_string(),
+                    TokenKind::LessEqual => "<=".to_string(),
+                    TokenKind::Greater => ">".to_string(),
+                    TokenKind::GreaterEqual => ">=".to_string(),
+                    _ => {
+                        self.errors.push(Diagnostic::runtime_error("E0017", "Invalid binary operator".to_string(), left.location().clone()));
+                        return String::new();
+                    }
+                };
+                format!("{} {} {}", self.codegen_expression(left), op, self.codegen_expression(right))
+            }
+            Expression::Ternary(condition, left, right, _) => {
+                format!("{} ? {} : {}", self.codegen_expression(condition), self.codegen_expression(left), self.codegen_expression(right))
+            }
+            Expression::Assignment(left, right, _) => {
+                // Storing into a map inserts the key if it isn't there yet
+                if let Expression::Index(map, index, location) = &**left {
+                    if let Some(t) = self.maps.get(location) {
+                        let map: String = self.codegen_expression(map);
+                        return format!("*({}*)sl_map_put(&{}, {}) = {}", self.codegen_map_value_type(t), map, self.codegen_map_entry(t, index), self.codegen_expression(right));
+                    }
+                }
+                if let Some(t) = self.counted_target(left) {
+                    let value: String = self.codegen_owned(&t, right);
+                    return format!("sl_rc_replace((void**)&{}, {})", self.codegen_expression(left), value);
+                }
+                format!("{} = {}", self.codegen_expression(left), self.codegen_expression(right))
+            }
+            Expression::AddressOf(expression, _) => {
+                format!("&{}", self.codegen_expression(expression))
+            }
+            Expression::Dereference(expression, location) => {
+                let pointer: String = self.codegen_expression(expression);
+                format!("*{}", self.codegen_null_check(&pointer, location))
+            }
+            Expression::Range(from, to, _) => {
+                format!("{}..{}", self.codegen_expression(from), self.codegen_expression(to))
+            }
+            Expression::Type(t, _) => {
+                self.codegen_type(t)
+            }
+            Expression::Empty => String::new(),
+            Expression::Error(err) => {
+                self.errors.push(err.clone());
+                String::new()
+            }
+        }
+    }
+}
+// The path of `to` from the directory `from`, for an #include between generated files
+pub fn relative_path(from: &std::path::Path, to: &std::path::Path) -> String {
+    let from: Vec<std::path::Component> = from.components().collect();
+    let to: Vec<std::path::Component> = to.components().collect();
+    let common: usize = from.iter().zip(to.iter()).take_while(|(left, right)| left == right).count();
+    let mut parts: Vec<String> = vec!["..".to_string(); from.len() - common];
+    parts.extend(to[common..].iter().map(|component| component.as_os_str().to_string_lossy().to_string()));
+    parts.join("/")
+}
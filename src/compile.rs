@@ -0,0 +1,228 @@
+use std::collections::HashSet;
+use std::fmt;
+use crate::ast::Statement;
+use crate::bytecode::BytecodeCompiler;
+use crate::checker::Checker;
+use crate::codegen::Codegen;
+use crate::diagnostics::{Diagnostic, SourceMap};
+use crate::importer::{is_module, Importer, Module};
+use crate::lexer::Lexer;
+use crate::lint::{Level, Lint, LintConfig, Linter};
+use crate::optimize::{Optimization, Optimizer};
+use crate::parser::Parser;
+use crate::target::TargetMachine;
+// How `compile` builds a program, which are the flags `sl emit` takes:
+//
+//     let options: CompileOptions = CompileOptions { arc: true, ..CompileOptions::default() };
+#[derive(Debug, Clone, Default)] pub struct CompileOptions {
+    // What diagnostics and #line directives call the source, and where its
+    // imports are found from. `main.sl` if it's empty
+    pub filename: String,
+    // Lints turned on or off over their defaults, as with -W<lint> and -Wno-<lint>
+    pub lints: Vec<(Lint, Level)>,
+    // Make every warning an error (-Werror)
+    pub deny_warnings: bool,
+    pub optimizations: HashSet<Optimization>,
+    // The host's sizes if there's none (--target=<machine>)
+    pub machine: Option<TargetMachine>,
+    pub arc: bool,
+    pub gc: bool,
+    pub debug: bool,
+    pub release: bool,
+    // Generate C++ in place of C (--target=c++)
+    pub cpp: bool,
+    pub hidden: bool,
+    pub freestanding: bool,
+    // Hand back the program's statements as they were parsed
+    pub ast: bool,
+}
+// What `compile` made of a program
+#[derive(Debug, Clone)] pub struct CompileOutput {
+    pub code: String,
+    pub header: String,
+    pub warnings: Vec<Diagnostic>,
+    // Every file read, to render the warnings with
+    pub sources: SourceMap,
+    // The parsed statements, when `CompileOptions::ast` asks for them
+    pub ast: Option<Vec<Statement>>,
+    // What to link the compiled code with, such as -lm for math.h
+    pub link_flags: Vec<String>,
+}
+// Why `compile` failed: the errors, with any warnings found before them. It's
+// a std::error::Error, so `compile(source, &options)?` works anywhere a boxed
+// error or anyhow's is returned, and displays as a line per diagnostic.
+#[derive(Debug, Clone)] pub struct CompileError {
+    pub diagnostics: Vec<Diagnostic>,
+    pub sources: SourceMap,
+}
+impl CompileError {
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|diagnostic| !diagnostic.is_warning())
+    }
+}
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lines: Vec<String> = self.diagnostics.iter().map(|diagnostic| {
+            let (filename, line, column): (String, usize, usize) = self.sources.position(&diagnostic.location);
+            format!("[{}:{}:{}] {}", filename, line, column, diagnostic)
+        }).collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+impl std::error::Error for CompileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.errors().next().map(|diagnostic| diagnostic as &(dyn std::error::Error + 'static))
+    }
+}
+impl CompileOptions {
+    // A code generator for a program, set up the way these options ask. The
+    // code and its header are each generated by one of these.
+    pub fn codegen<'a>(&self, program: &'a Program) -> Codegen<'a> {
+        let mut codegen: Codegen = Codegen::new(&program.statements, &program.checker);
+        codegen.arc = self.arc;
+        codegen.gc = self.gc;
+        codegen.debug = self.debug || !self.release;
+        codegen.bounds_checks = !self.release;
+        codegen.lines = !self.release;
+        codegen.cpp = self.cpp;
+        codegen.hidden = self.hidden;
+        codegen.freestanding = self.freestanding;
+        codegen.machine = self.machine.clone();
+        codegen.sources = Some(std::sync::Arc::new(program.sources.clone()));
+        codegen
+    }
+}
+// A program that's been read, checked and linted, with the warnings that were
+// found, ready to be optimized and handed to a back end
+#[derive(Debug, Clone)] pub struct Program {
+    pub statements: Vec<Statement>,
+    pub checker: Checker,
+    pub sources: SourceMap,
+    pub modules: Vec<Module>,
+    pub warnings: Vec<Diagnostic>,
+}
+// Reads, checks, lints, optimizes and generates a program from its source,
+// without writing anything. Files it imports are still read from disk.
+pub fn compile(source: &str, options: &CompileOptions) -> Result<CompileOutput, CompileError> {
+    let filename: &str = if options.filename.is_empty() { "main.sl" } else { &options.filename };
+    let mut lints: LintConfig = LintConfig::new();
+    lints.levels.extend(options.lints.iter().cloned());
+    lints.deny_warnings = options.deny_warnings;
+
+    let mut importer: Importer = Importer::new();
+    importer.load_contents(filename, source.to_string());
+    let ast: Option<Vec<Statement>> = options.ast.then(|| importer.program());
+    let program: Program = optimize(front_end(importer, lints, options, |_, _| {})?, options);
+    let mut codegen: Codegen = options.codegen(&program);
+    let code: String = codegen.codegen();
+    if !codegen.errors.is_empty() {
+        return Err(CompileError { diagnostics: [program.warnings.clone(), codegen.errors].concat(), sources: program.sources.clone() });
+    }
+    let header: String = options.codegen(&program).header();
+    let link_flags: Vec<String> = program.checker.link_flags();
+    Ok(CompileOutput { code, header, warnings: program.warnings, sources: program.sources, ast, link_flags })
+}
+// Checks and lints what an importer read, stopping at the first stage with
+// errors. `time` is told how long each stage took.
+pub fn front_end(importer: Importer, lints: LintConfig, options: &CompileOptions, time: impl Fn(&'static str, std::time::Duration)) -> Result<Program, CompileError> {
+    let statements: Vec<Statement> = importer.program();
+    let sources: SourceMap = importer.sources;
+    let failed = |errors: &[Diagnostic]| -> CompileError { CompileError { diagnostics: errors.to_vec(), sources: sources.clone() } };
+    if !importer.errors.is_empty() {
+        return Err(failed(&importer.errors));
+    }
+
+    let mut checker: Checker = Checker::new();
+    checker.lints = lints.clone();
+    checker.machine = options.machine.clone().unwrap_or_else(TargetMachine::host);
+    checker.freestanding = options.freestanding;
+    let started: std::time::Instant = std::time::Instant::now();
+    checker.check(&statements);
+    time("checking", started.elapsed());
+    let mut warnings: Vec<Diagnostic> = checker.warnings.clone();
+    if !checker.errors.is_empty() {
+        return Err(failed(&[warnings, checker.errors].concat()));
+    }
+
+    let mut linter: Linter = Linter::new(lints);
+    let started: std::time::Instant = std::time::Instant::now();
+    linter.run(&statements);
+    time("linting", started.elapsed());
+    warnings.extend(linter.context.warnings.iter().cloned());
+    if !linter.context.errors.is_empty() {
+        return Err(failed(&[warnings, linter.context.errors].concat()));
+    }
+    Ok(Program { statements, checker, sources, modules: importer.modules, warnings })
+}
+// Runs the optimizations the options turn on over a checked program
+pub fn optimize(program: Program, options: &CompileOptions) -> Program {
+    let statements: Vec<Statement> = Optimizer::new(&options.optimizations, &program.checker.machine).run(program.statements);
+    Program { statements, ..program }
+}
+// A compiler set up once with its options and then used for any number of
+// programs, one after another or from several threads at once. Each program
+// is compiled from nothing, so none of them sees anything of another:
+//
+//     let session: Session = Session::new(CompileOptions { arc: true, ..CompileOptions::default() });
+//     std::thread::scope(|scope| {
+//         for (filename, source) in files.iter() {
+//             scope.spawn(|| session.compile(filename, source));
+//         }
+//     });
+//
+// The only state compilations share is the interner behind names (see
+// symbol.rs), which is locked and only ever gains names.
+#[derive(Debug, Clone, Default)] pub struct Session {
+    pub options: CompileOptions,
+}
+impl Session {
+    pub fn new(options: CompileOptions) -> Self {
+        Self { options }
+    }
+    // A program by the name of its root file, which its imports are found from
+    pub fn compile(&self, filename: &str, source: &str) -> Result<CompileOutput, CompileError> {
+        compile(source, &CompileOptions { filename: filename.to_string(), ..self.options.clone() })
+    }
+}
+// Every stage of a compilation can be moved to and shared between threads.
+// This stops building if one ever can't.
+const _: fn() = || {
+    fn thread_safe<T: Send + Sync>() {}
+    thread_safe::<Session>();
+    thread_safe::<Lexer>();
+    thread_safe::<Parser>();
+    thread_safe::<Importer>();
+    thread_safe::<Checker>();
+    thread_safe::<Linter>();
+    thread_safe::<Optimizer>();
+    thread_safe::<Codegen>();
+    thread_safe::<BytecodeCompiler>();
+    thread_safe::<CompileOutput>();
+    thread_safe::<CompileError>();
+};
+// The front end on arbitrary input, for a fuzzer to drive. The bytes are read
+// as a file, lexed, parsed, and checked and linted when they parse, and every
+// diagnostic is rendered. Nothing is read from disk, so imported .sl files are
+// left out. Whatever the input, this returning is all that's expected of it:
+//
+//     // fuzz/fuzz_targets/front_end.rs
+//     libfuzzer_sys::fuzz_target!(|data: &[u8]| scripting_language::compile::fuzz(data));
+pub fn fuzz(data: &[u8]) {
+    let source: String = String::from_utf8_lossy(data).into_owned();
+    let mut sources: SourceMap = SourceMap::new();
+    sources.add("fuzz.sl".to_string(), source.clone());
+    let mut parser: Parser = Parser::new(Lexer::new(source), 0);
+    let statements: Vec<Statement> = parser.parse().into_iter().filter(|statement| !matches!(statement, Statement::Import(path, _) if is_module(path))).collect();
+    let mut diagnostics: Vec<Diagnostic> = [parser.lexer.errors, parser.errors].concat();
+    if diagnostics.is_empty() {
+        let mut checker: Checker = Checker::new();
+        checker.check(&statements);
+        let mut linter: Linter = Linter::new(LintConfig::new());
+        linter.run(&statements);
+        diagnostics.extend([checker.errors, checker.warnings, linter.context.errors, linter.context.warnings].concat());
+    }
+    for diagnostic in diagnostics.iter() {
+        sources.render(diagnostic);
+        sources.render_json(diagnostic);
+    }
+}
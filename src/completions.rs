@@ -0,0 +1,272 @@
+use colored::Colorize;
+use crate::cli::{Exit, print_dump};
+use crate::lint::Lint;
+use crate::optimize::Optimization;
+use crate::target::TargetMachine;
+pub(crate) const COMPLETIONS_USAGE: &str = "Usage: sl completions <shell>
+
+Print a script that completes sl's commands, their options and the
+values those take, such as lint names and targets, in bash, zsh, fish or
+powershell. Load it from the shell's startup file:
+
+  bash        source <(sl completions bash) in ~/.bashrc
+  zsh         sl completions zsh > _sl in a directory on $fpath
+  fish        sl completions fish > ~/.config/fish/completions/sl.fish
+  powershell  sl completions powershell | Out-String | Invoke-Expression
+              in $PROFILE
+
+Options:
+  -h, --help             Show this help";
+// Every command, as the completion scripts describe it
+pub(crate) const COMMANDS: [(&str, &str); 17] = [
+    ("check", "Check a program without writing anything"),
+    ("emit", "Write the generated C, or bytecode with --bytecode"),
+    ("build", "Compile a program into an executable with the C compiler"),
+    ("run", "Build a program and run it, or run a .slbc file"),
+    ("test", "Build a program's @test functions and run them"),
+    ("bench", "Build a program's @bench functions optimized and time them"),
+    ("new", "Create a project in a new directory"),
+    ("init", "Create a project in the current directory"),
+    ("fmt", "Format source files, or check that they're formatted"),
+    ("doc", "Write Markdown or HTML documentation for a program's modules"),
+    ("bindgen", "Print external declarations for what a C header declares"),
+    ("lsp", "Run a language server for editors over stdin and stdout"),
+    ("dap", "Run a debug adapter for editors over stdin and stdout"),
+    ("explain", "Describe an error or warning code"),
+    ("completions", "Print a completion script for a shell"),
+    ("help", "Show the help for sl or a command"),
+    ("--help", "Show this help"),
+];
+// The shells `sl completions` writes scripts for
+pub(crate) const SHELLS: [&str; 4] = ["bash", "zsh", "fish", "powershell"];
+// An option as the completion scripts offer it
+pub(crate) struct CompletionFlag {
+    pub(crate) flag: String,
+    pub(crate) description: String,
+    // What the next word is, for a flag such as `-o <path>` that takes one
+    pub(crate) argument: Option<&'static str>,
+    // What can follow the `=` of a flag that ends with one
+    pub(crate) values: Vec<String>,
+    pub(crate) commands: &'static [&'static str],
+}
+impl CompletionFlag {
+    pub(crate) fn new(flag: &str, description: &str, commands: &'static [&'static str]) -> Self {
+        Self { flag: flag.to_string(), description: description.to_string(), argument: None, values: Vec::new(), commands }
+    }
+    pub(crate) fn argument(mut self, argument: &'static str) -> Self {
+        self.argument = Some(argument);
+        self
+    }
+    pub(crate) fn values(mut self, values: &[&str]) -> Self {
+        self.values = values.iter().map(|value| value.to_string()).collect();
+        self
+    }
+    // Each of the options of every command, with the lints, optimizations and
+    // machines spelled out
+    pub(crate) fn all() -> Vec<CompletionFlag> {
+        const COMPILE: &[&str] = &["check", "emit", "build", "run", "test", "bench"];
+        const CC: &[&str] = &["build", "run", "test", "bench", "bindgen"];
+        const OUTPUT: &[&str] = &["emit", "build", "run", "test", "bench", "doc", "bindgen"];
+        const HELP: &[&str] = &["check", "emit", "build", "run", "test", "bench", "new", "init", "fmt", "doc", "bindgen", "lsp", "dap", "completions"];
+        const ALL: &[&str] = &["check", "emit", "build", "run", "test", "bench", "new", "init", "fmt", "doc", "bindgen", "lsp", "dap", "explain", "completions"];
+        let mut flags: Vec<CompletionFlag> = vec![
+            CompletionFlag::new("-h", "Show this help", HELP),
+            CompletionFlag::new("--help", "Show this help", HELP),
+            CompletionFlag::new("--color=", "When to color the output", ALL).values(&["always", "auto", "never"]),
+            CompletionFlag::new("-o", "Where to write the output", OUTPUT).argument("path"),
+            CompletionFlag::new("--output", "Where to write the output", OUTPUT).argument("path"),
+            CompletionFlag::new("--emit=", "Print the tokens, syntax tree, C or semantic tokens", &["emit"])
+                .values(&["tokens", "ast", "c", "semantic-tokens", "source"]),
+            CompletionFlag::new("--cc=", "The C or C++ compiler to use", CC),
+            CompletionFlag::new("--cflag", "Pass a flag to the compiler", CC).argument("flag"),
+            CompletionFlag::new("--lflag", "Pass a flag to the compiler after the generated files", CC).argument("flag"),
+            CompletionFlag::new("--watch", "Build again whenever a file changes", &["build"]),
+            CompletionFlag::new("--name", "What the project and its executable are called", &["new", "init"]).argument("name"),
+            CompletionFlag::new("--check", "List the files that aren't formatted and change nothing", &["fmt"]),
+            CompletionFlag::new("--html", "Write HTML in place of Markdown", &["doc"]),
+        ];
+        for lint in Lint::all() {
+            flags.push(CompletionFlag::new(&format!("-W{}", lint.name()), &format!("Warn about {}", lint.name()), COMPILE));
+            flags.push(CompletionFlag::new(&format!("-Wno-{}", lint.name()), &format!("Allow {}", lint.name()), COMPILE));
+            flags.push(CompletionFlag::new(&format!("-Werror={}", lint.name()), &format!("Make {} an error", lint.name()), COMPILE));
+        }
+        flags.push(CompletionFlag::new("-Werror", "Make every warning an error", COMPILE));
+        flags.push(CompletionFlag::new("-O", "Turn every optimization on", COMPILE));
+        flags.push(CompletionFlag::new("-O0", "Turn every optimization off", COMPILE));
+        for optimization in Optimization::all() {
+            flags.push(CompletionFlag::new(&format!("-O{}", optimization.name()), &format!("Turn on {}", optimization.name()), COMPILE));
+            flags.push(CompletionFlag::new(&format!("-Ono-{}", optimization.name()), &format!("Turn off {}", optimization.name()), COMPILE));
+        }
+        let targets: Vec<&str> = ["c", "c++"].into_iter().chain(TargetMachine::NAMES).collect();
+        flags.extend([
+            CompletionFlag::new("--target=", "c or c++, a machine, or key=value settings", COMPILE).values(&targets),
+            CompletionFlag::new("--arc", "Count references to objects and free them", COMPILE),
+            CompletionFlag::new("--gc", "Collect objects with the Boehm GC", COMPILE),
+            CompletionFlag::new("--release", "Leave out the checks and #line directives and optimize", COMPILE),
+            CompletionFlag::new("--debug", "Check pointers for null even with --release", COMPILE),
+            CompletionFlag::new("--hidden", "Hide functions from outside a shared library", COMPILE),
+            CompletionFlag::new("--freestanding", "Leave out the C library", COMPILE),
+            CompletionFlag::new("--bytecode", "Compile for the virtual machine in place of C", COMPILE),
+            CompletionFlag::new("--emit-layout=", "One C file, or a .c/.h pair per module", COMPILE).values(&["single", "per-module"]),
+            CompletionFlag::new("--message-format=", "How diagnostics are printed", COMPILE).values(&["human", "json"]),
+            CompletionFlag::new("--timings", "Print how long each phase took", COMPILE),
+        ]);
+        flags
+    }
+}
+// Whether a command's other words are files
+pub(crate) fn takes_files(command: &str) -> bool {
+    !matches!(command, "init" | "lsp" | "dap" | "explain" | "completions")
+}
+// The script that completes sl's commands and options in bash
+pub(crate) fn bash_completions(flags: &[CompletionFlag]) -> String {
+    let mut script: String = String::from("# bash completion for sl, from `sl completions bash`\n_sl_values() {\n    case \"$1\" in\n");
+    for flag in flags.iter().filter(|flag| !flag.values.is_empty()) {
+        script += &format!("        {}) echo \"{}\" ;;\n", flag.flag, flag.values.join(" "));
+    }
+    script += "    esac\n}\n_sl() {\n";
+    script += "    local cur=\"${COMP_WORDS[COMP_CWORD]}\" prev=\"${COMP_WORDS[COMP_CWORD-1]}\" flags=\"\" files=\"\"\n";
+    let commands: Vec<&str> = COMMANDS.iter().map(|(name, _)| *name).collect();
+    script += &format!("    if [ \"$COMP_CWORD\" -eq 1 ]; then\n        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n        return\n    fi\n", commands.join(" "));
+    // Bash splits `--emit=ast` into `--emit`, `=` and `ast`
+    script += "    if [ \"$cur\" = \"=\" ]; then\n        COMPREPLY=($(compgen -W \"$(_sl_values \"$prev=\")\"))\n        return\n    fi\n";
+    script += "    if [ \"$prev\" = \"=\" ] && [ \"$COMP_CWORD\" -gt 2 ]; then\n        COMPREPLY=($(compgen -W \"$(_sl_values \"${COMP_WORDS[COMP_CWORD-2]}=\")\" -- \"$cur\"))\n        return\n    fi\n";
+    let paths: Vec<&str> = flags.iter().filter(|flag| flag.argument == Some("path")).map(|flag| flag.flag.as_str()).collect();
+    let others: Vec<&str> = flags.iter().filter(|flag| flag.argument.is_some_and(|argument| argument != "path")).map(|flag| flag.flag.as_str()).collect();
+    script += &format!("    case \"$prev\" in\n        {}) COMPREPLY=($(compgen -f -- \"$cur\")); return ;;\n        {}) return ;;\n    esac\n", paths.join("|"), others.join("|"));
+    script += "    case \"${COMP_WORDS[1]}\" in\n";
+    for (command, _) in COMMANDS.iter().take_while(|(name, _)| *name != "help") {
+        let names: Vec<&str> = flags.iter().filter(|flag| flag.commands.contains(command)).map(|flag| flag.flag.as_str()).collect();
+        let files: &str = if takes_files(command) { " files=1" } else { "" };
+        script += &format!("        {}) flags=\"{}\"{} ;;\n", command, names.join(" "), files);
+    }
+    script += "        help|-h|--help) COMPREPLY=($(compgen -W \"check emit build run test bench new init fmt doc lsp dap completions\" -- \"$cur\")); return ;;\n    esac\n";
+    script += &format!("    if [ \"${{COMP_WORDS[1]}}\" = completions ] && [[ \"$cur\" != -* ]]; then\n        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n", SHELLS.join(" "));
+    script += "    elif [[ \"$cur\" == -* ]]; then\n        COMPREPLY=($(compgen -W \"$flags\" -- \"$cur\"))\n";
+    script += "        [[ \"${COMPREPLY[0]}\" == *= ]] && compopt -o nospace\n";
+    script += "    elif [ -n \"$files\" ]; then\n        COMPREPLY=($(compgen -f -- \"$cur\"))\n    fi\n}\ncomplete -o filenames -F _sl sl\n";
+    script
+}
+// The script that completes sl's commands and options in zsh
+pub(crate) fn zsh_completions(flags: &[CompletionFlag]) -> String {
+    let quote = |text: &str| text.replace('\'', "'\\''").replace('[', "\\[").replace(']', "\\]").replace(':', "\\:");
+    let mut script: String = String::from("#compdef sl\n# zsh completion for sl, from `sl completions zsh`\n_sl() {\n    local -a commands\n    commands=(\n");
+    for (command, description) in COMMANDS.iter().filter(|(name, _)| !name.starts_with('-')) {
+        script += &format!("        '{}:{}'\n", command, quote(description));
+    }
+    script += "    )\n    if (( CURRENT == 2 )); then\n        _describe -t commands 'sl command' commands\n        return\n    fi\n";
+    script += "    local command=$words[2]\n    shift words\n    (( CURRENT-- ))\n    case $command in\n";
+    for (command, _) in COMMANDS.iter().take_while(|(name, _)| *name != "help") {
+        script += &format!("        {})\n            _arguments", command);
+        for flag in flags.iter().filter(|flag| flag.commands.contains(command)) {
+            let value: String = match flag.argument {
+                Some("path") => ":path:_files".to_string(),
+                Some(argument) => format!(":{}: ", argument),
+                None if !flag.values.is_empty() => format!(":value:({})", flag.values.join(" ")),
+                None if flag.flag.ends_with('=') => ":value: ".to_string(),
+                None => String::new(),
+            };
+            script += &format!(" \\\n                '*{}[{}]{}'", flag.flag, quote(&flag.description), value);
+        }
+        if *command == "completions" {
+            script += &format!(" \\\n                '1:shell:({})'", SHELLS.join(" "));
+        } else if takes_files(command) {
+            script += " \\\n                '*:file:_files'";
+        }
+        script += "\n            ;;\n";
+    }
+    script += "        help)\n            _describe -t commands 'sl command' commands\n            ;;\n    esac\n}\n_sl \"$@\"\n";
+    script
+}
+// The script that completes sl's commands and options in fish
+pub(crate) fn fish_completions(flags: &[CompletionFlag]) -> String {
+    let quote = |text: &str| format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"));
+    let mut script: String = String::from("# fish completion for sl, from `sl completions fish`\ncomplete -c sl -f\n");
+    for (command, description) in COMMANDS.iter().filter(|(name, _)| !name.starts_with('-')) {
+        script += &format!("complete -c sl -n __fish_use_subcommand -a {} -d {}\n", command, quote(description));
+    }
+    for flag in flags {
+        let condition: String = format!("'__fish_seen_subcommand_from {}'", flag.commands.join(" "));
+        let name: &str = flag.flag.trim_end_matches('=');
+        let option: String = if let Some(long) = name.strip_prefix("--") {
+            format!("-l {}", long)
+        } else if name.len() == 2 {
+            format!("-s {}", &name[1..])
+        } else {
+            format!("-o {}", &name[1..])
+        };
+        let value: String = match flag.argument {
+            Some("path") => " -r -F".to_string(),
+            Some(_) => " -x".to_string(),
+            None if !flag.values.is_empty() => format!(" -x -a {}", quote(&flag.values.join(" "))),
+            None if flag.flag.ends_with('=') => " -x".to_string(),
+            None => String::new(),
+        };
+        script += &format!("complete -c sl -n {} {}{} -d {}\n", condition, option, value, quote(&flag.description));
+    }
+    let files: Vec<&str> = COMMANDS.iter().map(|(name, _)| *name).take_while(|name| *name != "help").filter(|name| takes_files(name)).collect();
+    script += &format!("complete -c sl -n '__fish_seen_subcommand_from {}' -F\n", files.join(" "));
+    script += &format!("complete -c sl -n '__fish_seen_subcommand_from completions' -a {}\n", quote(&SHELLS.join(" ")));
+    script += "complete -c sl -n '__fish_seen_subcommand_from help' -a 'check emit build run test bench new init fmt doc lsp dap completions'\n";
+    script
+}
+// The script that completes sl's commands and options in PowerShell
+pub(crate) fn powershell_completions(flags: &[CompletionFlag]) -> String {
+    let quote = |text: &str| format!("'{}'", text.replace('\'', "''"));
+    let mut script: String = String::from("# PowerShell completion for sl, from `sl completions powershell`\n");
+    script += "Register-ArgumentCompleter -Native -CommandName sl -ScriptBlock {\n    param($wordToComplete, $commandAst, $cursorPosition)\n";
+    script += "    $words = @($commandAst.CommandElements | ForEach-Object { $_.ToString() })\n";
+    script += "    $commands = @(\n";
+    let commands: Vec<String> = COMMANDS.iter().map(|(name, description)| format!("        @({}, {})", quote(name), quote(description))).collect();
+    script += &commands.join(",\n");
+    script += "\n    )\n";
+    // A word being typed after the command is already one of the words
+    script += "    if ($words.Count -lt 2 -or ($words.Count -eq 2 -and $wordToComplete -ne '')) {\n        $candidates = $commands\n    } else {\n        $candidates = switch ($words[1]) {\n";
+    for (command, _) in COMMANDS.iter().take_while(|(name, _)| *name != "help") {
+        let mut candidates: Vec<String> = Vec::new();
+        for flag in flags.iter().filter(|flag| flag.commands.contains(command)) {
+            candidates.push(format!("@({}, {})", quote(&flag.flag), quote(&flag.description)));
+            for value in &flag.values {
+                candidates.push(format!("@({}, {})", quote(&format!("{}{}", flag.flag, value)), quote(&flag.description)));
+            }
+        }
+        if *command == "completions" {
+            candidates.extend(SHELLS.iter().map(|shell| format!("@({}, 'Print the script for {}')", quote(shell), shell)));
+        }
+        script += &format!("            {} {{ @(\n                {}\n            ) }}\n", quote(command), candidates.join(",\n                "));
+    }
+    script += "            default { @() }\n        }\n";
+    // Anything else is a file, which PowerShell completes when nothing is offered
+    script += "        if (-not $wordToComplete.StartsWith('-') -and $words[1] -ne 'completions') { return }\n    }\n";
+    script += "    $candidates | Where-Object { $_[0] -like \"$wordToComplete*\" } | ForEach-Object {\n";
+    script += "        [System.Management.Automation.CompletionResult]::new($_[0], $_[0], 'ParameterValue', $_[1])\n    }\n}\n";
+    script
+}
+// Prints the completion script for the shell that's asked for. Returns the exit code.
+pub(crate) fn completions(args: &[String]) -> i32 {
+    let Some(shell) = args.first() else {
+        println!("{}", COMPLETIONS_USAGE);
+        return Exit::Usage as i32;
+    };
+    if shell == "-h" || shell == "--help" {
+        println!("{}", COMPLETIONS_USAGE);
+        return 0;
+    }
+    if let Some(arg) = args.get(1) {
+        println!("{}", format!("unexpected `{}`, see `sl completions --help`", arg).red());
+        return Exit::Usage as i32;
+    }
+    let flags: Vec<CompletionFlag> = CompletionFlag::all();
+    let script: String = match shell.as_str() {
+        "bash" => bash_completions(&flags),
+        "zsh" => zsh_completions(&flags),
+        "fish" => fish_completions(&flags),
+        "powershell" | "pwsh" => powershell_completions(&flags),
+        _ => {
+            println!("{}", format!("unknown shell `{}`, expected bash, zsh, fish or powershell", shell).red());
+            return Exit::Usage as i32;
+        }
+    };
+    print_dump(&script);
+    0
+}
@@ -0,0 +1,395 @@
+use std::collections::HashMap;
+use colored::Colorize;
+use crate::cli::Exit;
+use crate::json::{Json, json_string};
+use crate::lsp::{lsp_read, lsp_write};
+pub(crate) const DAP_USAGE: &str = "Usage: sl dap
+
+Run a debug adapter, which an editor starts and talks to over stdin and
+stdout with the Debug Adapter Protocol. A launch builds the program with
+debug information and runs it under gdb, or another debugger that speaks
+gdb's machine interface, such as lldb-mi. Breakpoints, steps and stack
+frames are at .sl lines, which the generated C's #line directives point
+the debugger to.
+
+Launch arguments:
+  program                The .sl file to debug
+  args                   What the program is run with
+  cwd                    The directory to build and run it in
+  debugger               The debugger to run, gdb by default
+  buildFlags             Options for `sl build`, such as --arc. With
+                         --release there are no .sl lines to stop at
+
+Options:
+  -h, --help             Show this help";
+// What the debug adapter waits on: a message from the editor or a line from
+// the debugger, or None once that side has closed
+pub(crate) enum DapInput {
+    Client(Option<String>),
+    Debugger(Option<String>),
+}
+// A program running under a debugger, driven through gdb's machine interface
+pub(crate) struct DebugSession {
+    pub(crate) debugger: std::process::Child,
+    pub(crate) input: std::process::ChildStdin,
+    // Where the executable was built, removed when the session ends
+    pub(crate) directory: std::path::PathBuf,
+    pub(crate) token: usize,
+    // The debugger's numbers for the breakpoints in each file, which an
+    // editor always sets a whole file's worth of at once
+    pub(crate) breakpoints: HashMap<String, Vec<String>>,
+    // A step into something that isn't in a .sl file is stepped out of again
+    pub(crate) stepping_in: bool,
+}
+pub(crate) struct DebugAdapter {
+    pub(crate) inputs: std::sync::mpsc::Receiver<DapInput>,
+    pub(crate) sender: std::sync::mpsc::Sender<DapInput>,
+    // Messages from the editor that came in while waiting on the debugger
+    pub(crate) pending: std::collections::VecDeque<String>,
+    pub(crate) closed: bool,
+    pub(crate) seq: usize,
+    pub(crate) session: Option<DebugSession>,
+}
+impl DebugAdapter {
+    pub(crate) fn new() -> Self {
+        let (sender, inputs) = std::sync::mpsc::channel();
+        let client: std::sync::mpsc::Sender<DapInput> = sender.clone();
+        std::thread::spawn(move || {
+            let mut input = std::io::stdin().lock();
+            loop {
+                let message: Option<String> = lsp_read(&mut input);
+                let done: bool = message.is_none();
+                if client.send(DapInput::Client(message)).is_err() || done {
+                    break;
+                }
+            }
+        });
+        Self { inputs, sender, pending: std::collections::VecDeque::new(), closed: false, seq: 0, session: None }
+    }
+    // The editor's next message, handling what the debugger says until it comes
+    pub(crate) fn next_message(&mut self) -> Option<String> {
+        loop {
+            if let Some(message) = self.pending.pop_front() {
+                return Some(message);
+            }
+            if self.closed {
+                return None;
+            }
+            match self.inputs.recv().ok()? {
+                DapInput::Client(Some(message)) => return Some(message),
+                DapInput::Client(None) => self.closed = true,
+                DapInput::Debugger(Some(line)) => self.debugger_line(&line),
+                DapInput::Debugger(None) => self.end(),
+            }
+        }
+    }
+    // DAP messages are framed the same way as LSP's
+    pub(crate) fn send(&mut self, kind: &str, fields: &str) {
+        self.seq += 1;
+        lsp_write(&format!("{{\"seq\":{},\"type\":\"{}\",{}}}", self.seq, kind, fields));
+    }
+    pub(crate) fn event(&mut self, event: &str, body: &str) {
+        self.send("event", &format!("\"event\":\"{}\",\"body\":{}", event, body));
+    }
+    pub(crate) fn respond(&mut self, request: &Json, result: Result<String, String>) {
+        let fields: String = format!("\"request_seq\":{},\"command\":{}", request.get("seq").render(), request.get("command").render());
+        match result {
+            Ok(body) => self.send("response", &format!("{},\"success\":true,\"body\":{}", fields, body)),
+            Err(message) => self.send("response", &format!("{},\"success\":false,\"message\":{}", fields, json_string(&message))),
+        }
+    }
+    pub(crate) fn output(&mut self, category: &str, text: &str) {
+        self.event("output", &format!("{{\"category\":\"{}\",\"output\":{}}}", category, json_string(text)));
+    }
+    // Runs a gdb/MI command and waits for its result
+    pub(crate) fn mi(&mut self, command: &str) -> Result<Json, String> {
+        use std::io::Write;
+        let session: &mut DebugSession = self.session.as_mut().ok_or("nothing is being debugged")?;
+        session.token += 1;
+        let token: String = session.token.to_string();
+        writeln!(session.input, "{}{}", token, command).map_err(|error| format!("can't talk to the debugger: {}", error))?;
+        loop {
+            match self.inputs.recv().map_err(|_| "the debugger stopped answering")? {
+                DapInput::Debugger(Some(line)) => match line.strip_prefix(&token).and_then(|result| result.strip_prefix('^')) {
+                    Some(result) => {
+                        let (class, rest): (&str, &str) = result.split_once(',').unwrap_or((result, ""));
+                        let results: Json = Json::parse_mi(rest).unwrap_or(Json::Null);
+                        return match class {
+                            "error" => Err(results.get("msg").as_str().unwrap_or("the debugger failed").to_string()),
+                            _ => Ok(results),
+                        };
+                    }
+                    None => self.debugger_line(&line),
+                },
+                DapInput::Debugger(None) => {
+                    self.end();
+                    return Err("the debugger exited".to_string());
+                }
+                DapInput::Client(Some(message)) => self.pending.push_back(message),
+                DapInput::Client(None) => self.closed = true,
+            }
+        }
+    }
+    // Something the debugger said on its own: the program stopping or
+    // exiting, or the program's own output, which is whatever isn't MI
+    pub(crate) fn debugger_line(&mut self, line: &str) {
+        let record: &str = line.trim_start_matches(|character: char| character.is_ascii_digit());
+        if let Some(stopped) = record.strip_prefix("*stopped") {
+            let stop: Json = Json::parse_mi(stopped.trim_start_matches(',')).unwrap_or(Json::Null);
+            self.stopped(&stop);
+        } else if let Some(Json::String(text)) = record.strip_prefix('@').and_then(|text| Json::parse_mi(&format!("text={}", text))).map(|text| text.get("text").clone()) {
+            self.output("stdout", &text);
+        } else if !(line == "(gdb)" || record.starts_with(['^', '*', '+', '=', '~', '&'])) {
+            self.output("stdout", &format!("{}\n", line));
+        }
+    }
+    pub(crate) fn stopped(&mut self, stop: &Json) {
+        let reason: &str = stop.get("reason").as_str().unwrap_or_default();
+        if reason.starts_with("exited") {
+            // gdb gives the exit code in octal
+            let code: i64 = stop.get("exit-code").as_str().and_then(|code| i64::from_str_radix(code, 8).ok()).unwrap_or(if reason == "exited-normally" { 0 } else { 1 });
+            self.event("exited", &format!("{{\"exitCode\":{}}}", code));
+            self.end();
+            return;
+        }
+        let in_program: bool = stop.get("frame").get("file").as_str().is_some_and(|file| file.ends_with(".sl"));
+        if let Some(session) = self.session.as_mut().filter(|session| session.stepping_in && !in_program) {
+            use std::io::Write;
+            let _ = writeln!(session.input, "-exec-finish");
+            return;
+        }
+        if let Some(session) = self.session.as_mut() {
+            session.stepping_in = false;
+        }
+        let (reason, description): (&str, Option<&str>) = match reason {
+            "breakpoint-hit" => ("breakpoint", None),
+            "end-stepping-range" | "function-finished" => ("step", None),
+            "signal-received" => ("exception", stop.get("signal-meaning").as_str()),
+            _ => ("pause", None),
+        };
+        let description: String = description.map(|description| format!(",\"description\":{},\"text\":{}", json_string(description), json_string(description))).unwrap_or_default();
+        self.event("stopped", &format!("{{\"reason\":\"{}\",\"threadId\":1,\"allThreadsStopped\":true{}}}", reason, description));
+    }
+    // Stops the debugger and the program, and tells the editor it's over
+    pub(crate) fn end(&mut self) {
+        let Some(mut session) = self.session.take() else {
+            return;
+        };
+        let _ = session.debugger.kill();
+        let _ = session.debugger.wait();
+        let _ = std::fs::remove_dir_all(&session.directory);
+        self.event("terminated", "{}");
+    }
+    // Builds the program with `sl build` and loads it into the debugger,
+    // without running it until the editor's done setting breakpoints
+    pub(crate) fn launch(&mut self, arguments: &Json) -> Result<String, String> {
+        let program: &str = arguments.get("program").as_str().ok_or("`program` has to name the .sl file to debug")?;
+        let strings = |value: &Json| -> Vec<String> {
+            match value {
+                Json::Array(values) => values.iter().filter_map(|value| value.as_str().map(str::to_string)).collect(),
+                _ => vec![],
+            }
+        };
+        let cwd: std::path::PathBuf = arguments.get("cwd").as_str().map_or_else(|| std::env::current_dir().unwrap_or_default(), std::path::PathBuf::from);
+        let directory: std::path::PathBuf = std::env::temp_dir().join(format!("sl-dap-{}", std::process::id()));
+        let executable: std::path::PathBuf = directory.join(std::path::Path::new(program).with_extension("").file_name().unwrap_or_default());
+        let sl: std::path::PathBuf = std::env::current_exe().map_err(|error| format!("can't find sl itself: {}", error))?;
+        // The build's diagnostics would break up the messages on stdout, so
+        // they're passed on as output
+        let build: std::process::Output = std::process::Command::new(sl)
+            .arg("build")
+            .args(strings(arguments.get("buildFlags")))
+            .arg("--output")
+            .arg(&executable)
+            .arg(program)
+            .current_dir(&cwd)
+            .env("NO_COLOR", "1")
+            .output()
+            .map_err(|error| format!("can't build `{}`: {}", program, error))?;
+        let diagnostics: String = String::from_utf8_lossy(&build.stdout).to_string();
+        if !diagnostics.is_empty() {
+            self.output("console", &diagnostics);
+        }
+        if !build.status.success() {
+            let _ = std::fs::remove_dir_all(&directory);
+            return Err(format!("`{}` couldn't be built", program));
+        }
+        let debugger: String = arguments.get("debugger").as_str().unwrap_or("gdb").to_string();
+        let mut child: std::process::Child = std::process::Command::new(&debugger)
+            .arg("--interpreter=mi2")
+            .current_dir(&cwd)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .map_err(|error| {
+                let _ = std::fs::remove_dir_all(&directory);
+                format!("can't run `{}`: {}", debugger, error)
+            })?;
+        let (Some(input), Some(output)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(format!("can't talk to `{}`", debugger));
+        };
+        let sender: std::sync::mpsc::Sender<DapInput> = self.sender.clone();
+        std::thread::spawn(move || {
+            use std::io::BufRead;
+            for line in std::io::BufReader::new(output).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if sender.send(DapInput::Debugger(Some(line))).is_err() {
+                    return;
+                }
+            }
+            let _ = sender.send(DapInput::Debugger(None));
+        });
+        self.session = Some(DebugSession { debugger: child, input, directory, token: 0, breakpoints: HashMap::new(), stepping_in: false });
+        self.mi(&format!("-file-exec-and-symbols {}", mi_quote(&executable.to_string_lossy())))?;
+        let args: Vec<String> = strings(arguments.get("args")).iter().map(|arg| mi_quote(arg)).collect();
+        if !args.is_empty() {
+            self.mi(&format!("-exec-arguments {}", args.join(" ")))?;
+        }
+        Ok("{}".to_string())
+    }
+    // Replaces the breakpoints in a file, answering with the lines the
+    // debugger could put them on
+    pub(crate) fn set_breakpoints(&mut self, arguments: &Json) -> Result<String, String> {
+        let path: String = arguments.get("source").get("path").as_str().ok_or("the breakpoints' source has no path")?.to_string();
+        let lines: Vec<usize> = match arguments.get("breakpoints") {
+            Json::Array(breakpoints) => breakpoints.iter().filter_map(|breakpoint| breakpoint.get("line").as_usize()).collect(),
+            _ => vec![],
+        };
+        let old: Vec<String> = self.session.as_mut().and_then(|session| session.breakpoints.remove(&path)).unwrap_or_default();
+        if !old.is_empty() {
+            self.mi(&format!("-break-delete {}", old.join(" ")))?;
+        }
+        let mut numbers: Vec<String> = vec![];
+        let mut breakpoints: Vec<String> = vec![];
+        for line in lines {
+            let inserted: Option<Json> = match self.session {
+                Some(_) => self.mi(&format!("-break-insert -f {}", mi_quote(&format!("{}:{}", path, line)))).ok(),
+                None => None,
+            };
+            match inserted.as_ref().map(|inserted| inserted.get("bkpt")) {
+                Some(breakpoint) => {
+                    numbers.extend(breakpoint.get("number").as_str().map(str::to_string));
+                    let line: usize = breakpoint.get("line").as_str().and_then(|line| line.parse().ok()).unwrap_or(line);
+                    breakpoints.push(format!("{{\"verified\":true,\"line\":{}}}", line));
+                }
+                None => breakpoints.push(format!("{{\"verified\":false,\"line\":{}}}", line)),
+            }
+        }
+        if let Some(session) = self.session.as_mut() {
+            session.breakpoints.insert(path, numbers);
+        }
+        Ok(format!("{{\"breakpoints\":[{}]}}", breakpoints.join(",")))
+    }
+    // The call stack, with frames outside the program's .sl files shown as
+    // the C functions they are
+    pub(crate) fn stack_trace(&mut self) -> Result<String, String> {
+        let stack: Json = self.mi("-stack-list-frames --thread 1")?;
+        let Json::Array(frames) = stack.get("stack") else {
+            return Ok("{\"stackFrames\":[],\"totalFrames\":0}".to_string());
+        };
+        let frames: Vec<String> = frames.iter().map(|frame| {
+            let level: &str = frame.get("level").as_str().unwrap_or("0");
+            let name: String = json_string(frame.get("func").as_str().unwrap_or("??"));
+            match (frame.get("file").as_str().filter(|file| file.ends_with(".sl")), frame.get("line").as_str()) {
+                (Some(file), Some(line)) => {
+                    let path: &str = frame.get("fullname").as_str().unwrap_or(file);
+                    let source: String = format!("{{\"name\":{},\"path\":{}}}", json_string(&std::path::Path::new(file).file_name().unwrap_or_default().to_string_lossy()), json_string(path));
+                    format!("{{\"id\":{},\"name\":{},\"source\":{},\"line\":{},\"column\":1}}", level, name, source, line)
+                }
+                _ => format!("{{\"id\":{},\"name\":{},\"line\":0,\"column\":0,\"presentationHint\":\"subtle\"}}", level, name),
+            }
+        }).collect();
+        Ok(format!("{{\"stackFrames\":[{}],\"totalFrames\":{}}}", frames.join(","), frames.len()))
+    }
+    // A frame's locals and arguments, whose reference is the frame's level plus one
+    pub(crate) fn variables(&mut self, reference: usize) -> Result<String, String> {
+        let variables: Json = self.mi(&format!("-stack-list-variables --thread 1 --frame {} --all-values", reference.saturating_sub(1)))?;
+        let Json::Array(variables) = variables.get("variables") else {
+            return Ok("{\"variables\":[]}".to_string());
+        };
+        let variables: Vec<String> = variables.iter().map(|variable| {
+            format!("{{\"name\":{},\"value\":{},\"variablesReference\":0}}", json_string(variable.get("name").as_str().unwrap_or_default()), json_string(variable.get("value").as_str().unwrap_or_default()))
+        }).collect();
+        Ok(format!("{{\"variables\":[{}]}}", variables.join(",")))
+    }
+    pub(crate) fn evaluate(&mut self, arguments: &Json) -> Result<String, String> {
+        let expression: &str = arguments.get("expression").as_str().unwrap_or_default();
+        let frame: usize = arguments.get("frameId").as_usize().unwrap_or(0);
+        let value: Json = self.mi(&format!("-data-evaluate-expression --thread 1 --frame {} {}", frame, mi_quote(expression)))?;
+        Ok(format!("{{\"result\":{},\"variablesReference\":0}}", json_string(value.get("value").as_str().unwrap_or_default())))
+    }
+}
+// A string as an MI command's argument
+pub(crate) fn mi_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+// Answers an editor's Debug Adapter Protocol requests on stdin until it
+// disconnects. Returns the exit code.
+pub(crate) fn debug_adapter(args: &[String]) -> i32 {
+    if let Some(arg) = args.first() {
+        if arg == "-h" || arg == "--help" {
+            println!("{}", DAP_USAGE);
+            return 0;
+        }
+        println!("{}", format!("unknown option `{}`, see `sl dap --help`", arg).red());
+        return Exit::Usage as i32;
+    }
+    let mut adapter: DebugAdapter = DebugAdapter::new();
+    loop {
+        let Some(message) = adapter.next_message() else {
+            adapter.end();
+            return 1;
+        };
+        let Some(request) = Json::parse(&message) else {
+            adapter.output("console", "a message from the editor wasn't JSON\n");
+            continue;
+        };
+        let arguments: &Json = request.get("arguments");
+        let result: Result<String, String> = match request.get("command").as_str().unwrap_or_default() {
+            "initialize" => Ok("{\"supportsConfigurationDoneRequest\":true,\"supportsEvaluateForHovers\":true}".to_string()),
+            "launch" => {
+                let result: Result<String, String> = adapter.launch(arguments);
+                let launched: bool = result.is_ok();
+                adapter.respond(&request, result);
+                // Breakpoints come once the editor knows it can send them
+                if launched {
+                    adapter.event("initialized", "{}");
+                }
+                continue;
+            }
+            "setBreakpoints" => adapter.set_breakpoints(arguments),
+            "configurationDone" => adapter.mi("-exec-run").map(|_| "{}".to_string()),
+            "threads" => Ok("{\"threads\":[{\"id\":1,\"name\":\"main\"}]}".to_string()),
+            "stackTrace" => adapter.stack_trace(),
+            "scopes" => {
+                let frame: usize = arguments.get("frameId").as_usize().unwrap_or(0);
+                Ok(format!("{{\"scopes\":[{{\"name\":\"Locals\",\"variablesReference\":{},\"expensive\":false}}]}}", frame + 1))
+            }
+            "variables" => adapter.variables(arguments.get("variablesReference").as_usize().unwrap_or(0)),
+            "evaluate" => adapter.evaluate(arguments),
+            "continue" => adapter.mi("-exec-continue").map(|_| "{\"allThreadsContinued\":true}".to_string()),
+            "next" => adapter.mi("-exec-next").map(|_| "{}".to_string()),
+            "stepIn" => {
+                if let Some(session) = adapter.session.as_mut() {
+                    session.stepping_in = true;
+                }
+                adapter.mi("-exec-step").map(|_| "{}".to_string())
+            }
+            "stepOut" => adapter.mi("-exec-finish").map(|_| "{}".to_string()),
+            "pause" => adapter.mi("-exec-interrupt").map(|_| "{}".to_string()),
+            command @ ("disconnect" | "terminate") => {
+                adapter.end();
+                adapter.respond(&request, Ok("{}".to_string()));
+                if command == "disconnect" {
+                    return 0;
+                }
+                continue;
+            }
+            command => Err(format!("`{}` isn't supported", command)),
+        };
+        adapter.respond(&request, result);
+    }
+}
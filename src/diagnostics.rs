@@ -0,0 +1,400 @@
+use std::fmt;
+use colored::{ColoredString, Colorize};
+use crate::json::json_string;
+use crate::lexer::TokenLocation;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)] pub enum Severity {
+    Error,
+    // Reported without stopping compilation
+    Warning,
+}
+// The stage that found an error, which is in its name: SyntaxError, TypeError
+// or RuntimeError. A warning made an error by -Werror is only an `error`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)] pub enum Kind {
+    Syntax,
+    Type,
+    Runtime,
+    Lint,
+}
+// Another place a diagnostic points at, such as where a name was first defined
+#[derive(Debug, Clone, PartialEq)] pub struct Label {
+    pub location: TokenLocation,
+    pub message: String,
+}
+// A fix that can be applied without asking: the span is replaced with
+// `replacement`, and `message` says what that does
+#[derive(Debug, Clone, PartialEq)] pub struct Suggestion {
+    pub message: String,
+    pub location: TokenLocation,
+    pub replacement: String,
+}
+// An error or warning. Every diagnostic carries a stable code (E0001, W0002,
+// ...) that `sl explain` can describe, and a span it's about; labels point at
+// other places that explain it, notes add to the message, and suggestions are
+// fixes an editor can apply.
+#[derive(Debug, Clone, PartialEq)] pub struct Diagnostic {
+    pub severity: Severity,
+    pub kind: Kind,
+    pub code: &'static str,
+    pub message: String,
+    pub location: TokenLocation,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub suggestions: Vec<Suggestion>,
+}
+impl Diagnostic {
+    pub fn new(severity: Severity, kind: Kind, code: &'static str, message: String, location: TokenLocation) -> Self {
+        Self { severity, kind, code, message, location, labels: vec![], notes: vec![], suggestions: vec![] }
+    }
+    pub fn syntax_error(code: &'static str, message: String, location: TokenLocation) -> Self {
+        Diagnostic::new(Severity::Error, Kind::Syntax, code, message, location)
+    }
+    pub fn type_error(code: &'static str, message: String, location: TokenLocation) -> Self {
+        Diagnostic::new(Severity::Error, Kind::Type, code, message, location)
+    }
+    pub fn runtime_error(code: &'static str, message: String, location: TokenLocation) -> Self {
+        Diagnostic::new(Severity::Error, Kind::Runtime, code, message, location)
+    }
+    pub fn denied(code: &'static str, message: String, location: TokenLocation) -> Self {
+        Diagnostic::new(Severity::Error, Kind::Lint, code, message, location)
+    }
+    pub fn warning(code: &'static str, message: String, location: TokenLocation) -> Self {
+        Diagnostic::new(Severity::Warning, Kind::Type, code, message, location)
+    }
+    pub fn with_label(mut self, location: TokenLocation, message: String) -> Self {
+        self.labels.push(Label { location, message });
+        self
+    }
+    pub fn with_note(mut self, note: String) -> Self {
+        self.notes.push(note);
+        self
+    }
+    pub fn with_suggestion(mut self, message: String, location: TokenLocation, replacement: String) -> Self {
+        self.suggestions.push(Suggestion { message, location, replacement });
+        self
+    }
+    pub fn is_warning(&self) -> bool {
+        self.severity == Severity::Warning
+    }
+    pub fn location(&self) -> TokenLocation {
+        self.location.clone()
+    }
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+    pub fn code(&self) -> &'static str {
+        self.code
+    }
+    pub fn name(&self) -> String {
+        match (self.severity, self.kind) {
+            (Severity::Warning, _) => "Warning".to_string(),
+            (Severity::Error, Kind::Syntax) => "SyntaxError".to_string(),
+            (Severity::Error, Kind::Type) => "TypeError".to_string(),
+            (Severity::Error, Kind::Runtime) => "RuntimeError".to_string(),
+            (Severity::Error, Kind::Lint) => "error".to_string(),
+        }
+    }
+    // The same diagnostic with every span moved along by `offset`, for one found
+    // in a file before it was placed in the SourceMap
+    pub fn shifted(&self, offset: usize) -> Diagnostic {
+        let shift = |location: &TokenLocation| -> TokenLocation { TokenLocation { start: location.start + offset, end: location.end + offset } };
+        let mut diagnostic: Diagnostic = self.clone();
+        diagnostic.location = shift(&self.location);
+        for label in diagnostic.labels.iter_mut() {
+            label.location = shift(&label.location);
+        }
+        for suggestion in diagnostic.suggestions.iter_mut() {
+            suggestion.location = shift(&suggestion.location);
+        }
+        diagnostic
+    }
+    // The long form of a diagnostic code, printed by `--explain`
+    pub fn explain(code: &str) -> Option<&'static str> {
+        match code {
+            "E0001" => Some("A character that can't start any token.\n\nThe lexer found a character that isn't part of the language, outside of a\nstring or comment.\n\n    var x: int = 4 $ 2\n\nRemove the character or put it inside a string literal."),
+            "E0002" => Some("An unknown escape sequence in a string or char literal.\n\nOnly the escapes C understands can be used in literals.\n\n    var s: string = \"tab\\q\"\n\nUse one of \\n, \\t, \\r, \\0, \\\\, \\' or \\\"."),
+            "E0003" => Some("A name was expected.\n\nThe parser needed an identifier, for example after `.` or in a parameter\nlist, but found some other expression.\n\n    var x: int = point.(1)"),
+            "E0004" => Some("An expression or statement was expected.\n\nThe parser reached a token that can't start one, such as an `end` with no\nblock to close.\n\n    var x: int = )\n\nGive the variable a value or drop the `=`."),
+            "E0005" => Some("A type was expected.\n\nThe parser reached a token that can't start a type.\n\n    var x: = 1\n\nWrite a type such as `int`, `Point*` or `func(int): int`."),
+            "E0006" => Some("An imported .sl file doesn't exist, or a `std.` module isn't one of the\nstandard library's.\n\nImports of .sl files are resolved relative to the directory of the file\nthat imports them.\n\n    import \"lib/missing.sl\"\n\nCheck the path against the importing file's location. The standard library\nhas `std.io`, `std.list`, `std.math`, `std.mem`, `std.os` and\n`std.string`, built into the compiler."),
+            "E0007" => Some("An imported .sl file couldn't be read.\n\nThe file exists but reading it failed, usually because of permissions or\nbecause it isn't valid UTF-8."),
+            "E0008" => Some("Files import each other in a cycle.\n\nA module can't be compiled before the modules it imports, so import chains\nmust not loop back on themselves.\n\n    # a.sl\n    import \"b.sl\"\n\n    # b.sl\n    import \"a.sl\"\n\nMove the shared declarations into a third file that both import."),
+            "E0009" => Some("A top-level name is defined more than once.\n\nStructs, enums, functions, type aliases and annotations share one namespace\nacross the whole program, including imported files.\n\n    func area(): int => 1\n    func area(): int => 2\n\nRename one of the definitions. `external` declarations may repeat."),
+            "E0010" => Some("A name is declared twice in the same scope.\n\nA variable may shadow one from an enclosing block, but not one from the\nsame block.\n\n    var x: int = 1\n    var x: int = 2\n\nAssign to the existing variable instead, or pick another name."),
+            "E0011" => Some("An annotation is used without being declared.\n\nAnnotations must be declared with `annotation` before they are applied.\n\n    annotation Table\n        name: const char*\n    end\n\n    @Table(\"users\")\n    struct User\n        id: int\n    end"),
+            "E0012" => Some("Annotation arguments don't match its fields.\n\nArguments are matched to the annotation's fields by position, then by name.\nEvery field must be given exactly once with a value of a fitting type, and\npositional arguments must come before named ones.\n\n    @Table(\"users\", version: 2)"),
+            "E0013" => Some("An enum variant is defined more than once.\n\nEach variant name may appear only once in its enum.\n\n    enum Color : int\n        Red = 1\n        Red = 2\n    end"),
+            "E0014" => Some("An enum variant needs a value.\n\nVariants without `= value` count up from the previous one, which only works\nfor integer enums.\n\n    enum Color : const char*\n        Red = \"ff0000\"\n        Green\n    end\n\nGive every variant of a non-integer enum a value."),
+            "E0015" => Some("A value that isn't a pointer is dereferenced.\n\nOnly pointers, cstrings and arrays can be dereferenced with `*`.\n\n    var x: int = 1\n    var y: int = *x"),
+            "E0016" => Some("A member access names a field that doesn't exist.\n\nThe field must be declared in the struct, or `Struct.name` must be a struct\nfunction when it is called. Member access on values that aren't structs,\nlike `int`, is also rejected.\n\n    struct Point\n        x: int\n    end\n\n    func main()\n        var p: Point\n        p.y = 1\n    end"),
+            "E0017" => Some("An operator is applied to values it doesn't support.\n\nArithmetic needs numbers (pointers may add or subtract integers), `!` needs a\n`bool`, and comparisons need two numbers, two pointers or two bools.\n\n    var b: bool = true\n    var x: int = b + 1"),
+            "E0018" => Some("A constant or read-only value is assigned to.\n\nConstants and values whose type is `const` can't be assigned after their\ndeclaration.\n\n    const limit: int = 10\n    limit = 20"),
+            "E0019" => Some("A cast between incompatible types.\n\n`as` converts between numbers, between pointers, and between pointers and\n`usize`. Struct values can't be cast.\n\n    var p: Point\n    var n: int = p as int\n\nUse `unsafe_cast[T](value)` when a raw C cast is really intended. Neither\ncasts a struct value, but a pointer to one can be reinterpreted:\n\n    var q: Other = *unsafe_cast[Other*](&p)"),
+            "E0020" => Some("An annotation is applied to something that can't carry it.\n\nDeclared annotations can only be applied to structs. The built-in `@deprecated`\nalso applies to functions, `@extern_name` to any function, and `@static`,\n`@export`, `@entry`, `@test` and `@bench` only to functions outside of structs."),
+            "E0021" => Some("A function type is used where C can't declare one.\n\nFunction types may be used directly for variables, fields and parameters,\nbut not nested inside other types, such as a pointer to or an array of\nfunctions."),
+            "E0022" => Some("A type name that isn't known.\n\nThe name doesn't refer to a struct, enum, type alias or generic parameter.\n\n    var p: Pointt* = null"),
+            "E0023" => Some("An invalid use of an enum.\n\nEnum variants are accessed as `Enum.Variant`, and variants of enums with\nfunction values may be called as `Enum.Variant(args)`."),
+            "E0024" => Some("A type contains itself by value.\n\nA struct can't hold a value of its own type, directly or through other\ntypes, since it would need infinite space.\n\n    struct Node\n        next: Node\n    end\n\nStore it behind a pointer instead: `next: Node*`."),
+            "E0025" => Some("A range is used as a value, or a `for` loop goes over something it can't.\n\n`a..b` counts from `a` up to, but not including, `b`, and only describes what\na `for` loop iterates over; it can't be stored, passed or returned. A `for`\nloop goes over a range of integers or the elements of an array.\n\n    var r: int = 1..10\n\n    for i in 0..10\n        printf(\"%d\\n\", i)\n    end\n\nA map is gone over through its `keys()` or `values()`, which list the\nentries in the same order.\n\n    for name in ages.keys()\n        printf(\"%s is %d\\n\", name, ages[name])\n    end"),
+            "E0026" => Some("A map's keys or values don't match its type.\n\nEvery entry of a map literal needs a key, all keys and all values have to\nhave the same type, and a map is indexed by its key type.\n\n    var ages: map[cstring, int] = [\"ada\": 36, \"alan\": true]"),
+            "E0027" => Some("A `string` is stored in an array or map when generating C++.\n\nThe runtimes behind `T[]` and `map[K, V]` copy their elements bytewise,\nwhich is fine for C but not for a `std::string`.\n\n    var names: string[]\n\nStore `cstring`s instead, or generate C."),
+            "E0028" => Some("Something the bytecode compiler doesn't support.\n\nWith --bytecode, pointers are references to objects and there is no raw\nmemory, so taking addresses, dereferencing, `sizeof` outside of a\nconstructor's `malloc`, maps, generics and struct or Java enums can't be\ncompiled. Of the C library only printf, puts, putchar, exit, strlen, free and\nabs can be called.\n\n    var p: int* = &x\n\nGenerate C for programs that need these."),
+            "E0029" => Some("A function's linkage annotations conflict.\n\n`@static` keeps a function private to the generated C file, `@export` makes\nit visible outside a shared library even with --hidden, and\n`@extern_name(\"name\")` gives it another C symbol name. A function can't be\nboth static and exported, each annotation may be given once, `main` keeps\nits name and linkage, and no two functions may end up with the same C name.\n\n    @static\n    @export\n    func area(): int => 1\n\n`@entry` makes a function the entry point in place of `main`, which is then\ngenerated to call it unless the program is --freestanding. There can only be\none, it can't be static, and it takes and returns what `main` would."),
+            "E0030" => Some("An integer literal doesn't fit in the type it's stored as.\n\nThe sizes of `int` and `usize` are those of the machine given with\n--target=, or of the one compiling otherwise. An `int` is 16 bits on avr and\nmsp430, and a `usize` is as wide as a pointer. A `char` holds -128 to 255.\nNo literal can be bigger than a 64-bit integer, whatever it's stored as.\n\n    // --target=avr\n    var n: int = 40000\n\nUse a wider type, or a target whose types are big enough."),
+            "E0031" => Some("Something needs the C library, which --freestanding leaves out.\n\nFreestanding code has no malloc, stdio or other hosted headers, so the\n`string` type, dynamic arrays, maps, `new` with --target=c++ and imports of\nheaders other than float.h, iso646.h, limits.h, stdalign.h, stdarg.h,\nstdbool.h, stddef.h, stdint.h and stdnoreturn.h can't be used.\n\n    import \"std/stdio.h\"\n\nDeclare what the platform provides with `external` instead."),
+            "E0032" => Some("A program can't be started in this function.\n\n`main`, or the function marked `@entry`, takes no arguments, `argc` and\n`argv`, or all of the arguments as one `string[]` or `cstring[]`, the\nprogram's name first. It returns an `int` exit code or nothing.\n\n    func main(args: int[]): int\n        return 0\n    end"),
+            "E0033" => Some("A name starts with `__sl`, which is kept for generated names.\n\nThe C names the compiler makes up, such as `__sl_fn_5Point4area` for the\nstruct function `Point.area`, all start with `__sl`. Nothing the program\ndefines, and no `@extern_name`, may start with it as well. `external`\ndeclarations may, to use the mangled names of another program.\n\n    var __sl_count: int = 0"),
+            "E0034" => Some("A test or benchmark function has the wrong signature.\n\nFunctions marked `@test` or `@bench` are called by the harness `sl test` or\n`sl bench` generates, so they take no arguments and return nothing.\n\n    @test\n    func adds_up()\n        assert(1 + 1 == 2, \"one and one\")\n    end"),
+            "E0035" => Some("`assert` is called with the wrong arguments.\n\n`assert` takes the condition that has to hold and, optionally, a string\nliteral to print along with where it failed.\n\n    assert(count > 0)\n    assert(count > 0, \"nothing was read\")\n\nUnder `sl test` a failed assertion fails the test it's in. Otherwise it stops\nthe program, unless it's built with --release. A function named `assert`\ntakes its place."),
+            "E0036" => Some("A block isn't closed, or an `end` or `else` has no block to close.\n\nEvery struct, enum, annotation, func, if and while with a body is closed by\nan `end`, which `sl fmt` lines up with it.\n\n    func main()\n        if argc > 1\n            printf(\"hi\\n\")\n    end"),
+            "E0037" => Some("Statements, expressions or types are nested too deeply.\n\nThe parser reads nested code by nesting calls of its own, so it stops at 128\nlevels, such as that many parentheses around a value or `if`s inside each\nother, rather than run out of stack. A chain of operators, as in `a + b + c`\nor `a.b.c`, nests each one in the next, so it counts a level for each. Nothing\nafter that point is parsed.\n\n    var x: int = ((((((((((((1))))))))))))\n\nMove some of the inner parts out into variables or functions."),
+            "E0038" => Some("`print` or `println` is called with a format that doesn't fit its values.\n\nThe first argument is a string literal with a `{}` for each value after it,\nwhich is printed the way its type is: numbers and chars as they are, a `bool`\nas `true` or `false`, strings as their text and other pointers as addresses.\n`{{` and `}}` print a brace. `println` ends the line.\n\n    println(\"{} of {} done\", count, total)\n\nA value whose type isn't known, like what a C function returns, needs one\ngiven with `as`. A function named `print` or `println` takes its place."),
+            "E0039" => Some("A string or char literal runs to the end of the file without its closing quote.\n\n    var s: cstring = \"unfinished\n\nClose the literal with the quote it opened with. A quote inside it is written\n\\\" or \\'."),
+            "W0001" => Some("A declaration shadows one from an outer scope (-Wshadow).\n\nOff by default.\n\n    var x: int = 1\n    if x > 0\n        var x: int = 2\n    end"),
+            "W0002" => Some("A local variable is never used (-Wunused).\n\nOff by default. Assigning to a variable doesn't count as using it. Prefix\nthe name with `_` to keep it without a warning.\n\n    var total: int = 0\n    total = 5"),
+            "W0003" => Some("A local is read before it is initialized (-Wuninitialized).\n\nOn by default. A variable declared without a value must be assigned on every\npath before it is read.\n\n    var x: int\n    if argc > 1\n        x = 1\n    end\n    printf(\"%d\\n\", x)\n\nGive the variable an initial value or assign it in every branch."),
+            "W0004" => Some("A name doesn't follow the naming convention (-Wnaming).\n\nOff by default. Structs, enums, type aliases and annotations are\nUpperCamelCase; functions and variables are snake_case. Externals keep their\nC names.\n\n    struct point\n        x: int\n    end"),
+            "W0005" => Some("A function has too many statements (-Wfunction-length).\n\nOff by default. Statements in nested blocks count too. The limit is 50 and\ncan be changed with `max-function-length = N` in sl.lints."),
+            "W0006" => Some("A deprecated function or struct is used (-Wdeprecated).\n\nOn by default. Functions and structs marked `@deprecated` warn wherever they\nare referenced, outside of their own definition.\n\n    @deprecated(\"use area instead\")\n    func size(): int => 1\n\n    func main()\n        var n: int = size()\n    end"),
+            "W0007" => Some("A C library function is called without importing its header (-Wmissing-import).\n\nOn by default. Functions like printf, malloc and strlen are only declared\nfor C by their header, so calling one that isn't imported leaves C to guess\nat it. Import the header it's in, or declare it with `external`.\n\n    func main()\n        printf(\"hi\\n\")\n    end"),
+            _ => None,
+        }
+    }
+}
+// The kind, code and message, without the source to point into, which
+// `SourceMap::render` shows:
+//
+//     TypeError[E0010]: `x` is already declared in this scope
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.name(), self.code, self.message)
+    }
+}
+impl std::error::Error for Diagnostic {}
+// The closest of some names to one that isn't any of them, for a "did you
+// mean" suggestion. Only names a few edits away count, fewer for short ones.
+pub fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let distance = |a: &str, b: &str| -> usize {
+        let b: Vec<char> = b.chars().collect();
+        let mut row: Vec<usize> = (0..=b.len()).collect();
+        for (i, a) in a.chars().enumerate() {
+            let mut previous: usize = row[0];
+            row[0] = i + 1;
+            for (j, b) in b.iter().enumerate() {
+                let substituted: usize = previous + (a != *b) as usize;
+                previous = row[j + 1];
+                row[j + 1] = substituted.min(row[j] + 1).min(previous + 1);
+            }
+        }
+        row[b.len()]
+    };
+    let limit: usize = (name.chars().count() / 3).max(1);
+    candidates.into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+// Every file taking part in a compilation, laid out one after another so that a
+// TokenLocation on its own is enough to tell which file it points into
+#[derive(Debug, Clone)] pub struct SourceFile {
+    pub name: String,
+    pub contents: String,
+    pub start: usize,
+    // Where each line starts, as a character and a byte offset into the file,
+    // so that finding the line of an offset is a binary search
+    pub lines: Vec<(usize, usize)>,
+    // The number of characters in it
+    pub length: usize,
+}
+impl SourceFile {
+    pub fn new(name: String, contents: String, start: usize) -> Self {
+        let mut lines: Vec<(usize, usize)> = vec![(0, 0)];
+        let mut length: usize = 0;
+        for (byte, c) in contents.char_indices() {
+            length += 1;
+            if c == '\n' {
+                lines.push((length, byte + 1));
+            }
+        }
+        Self { name, contents, start, lines, length }
+    }
+    // The 0-based line a character offset into the file is on
+    pub fn line_index(&self, offset: usize) -> usize {
+        self.lines.partition_point(|(start, _)| *start <= offset) - 1
+    }
+    // The text of a 0-based line, without its newline
+    pub fn line_text(&self, index: usize) -> Option<&str> {
+        let (_, start): (usize, usize) = *self.lines.get(index)?;
+        let end: usize = self.lines.get(index + 1).map_or(self.contents.len(), |(_, next)| next - 1);
+        Some(&self.contents[start..end])
+    }
+    // A character offset into the file as a byte offset, or the file's length
+    // past its end
+    pub fn byte(&self, offset: usize) -> usize {
+        let (character, byte): (usize, usize) = self.lines[self.line_index(offset)];
+        self.contents[byte..].char_indices().nth(offset - character).map_or(self.contents.len(), |(index, _)| byte + index)
+    }
+}
+#[derive(Debug, Clone)] pub struct SourceMap {
+    pub files: Vec<SourceFile>,
+}
+impl Default for SourceMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl SourceMap {
+    pub fn new() -> Self {
+        Self { files: vec![] }
+    }
+    // Returns the offset that locations in this file are shifted by
+    pub fn add(&mut self, name: String, contents: String) -> usize {
+        let start: usize = match self.files.last() {
+            Some(file) => file.start + file.length + 1,
+            None => 0,
+        };
+        self.files.push(SourceFile::new(name, contents, start));
+        start
+    }
+    pub fn file(&self, location: &TokenLocation) -> &SourceFile {
+        match self.files.partition_point(|file| file.start <= location.start) {
+            0 => &self.files[0],
+            index => &self.files[index - 1],
+        }
+    }
+    // The file and 1-based line a location falls on
+    pub fn line(&self, location: &TokenLocation) -> (String, usize) {
+        let (name, line, _): (String, usize, usize) = self.position(location);
+        (name, line)
+    }
+    // The file and 1-based line and column a location falls on
+    pub fn position(&self, location: &TokenLocation) -> (String, usize, usize) {
+        let file: &SourceFile = self.file(location);
+        let offset: usize = location.start.saturating_sub(file.start).min(file.length);
+        let line: usize = file.line_index(offset);
+        (file.name.clone(), line + 1, offset - file.lines[line].0 + 1)
+    }
+    // The 0-based line and character an offset falls on, with characters
+    // counted in UTF-16 code units as the Language Server Protocol counts them
+    pub fn utf16_position(&self, offset: usize) -> (usize, usize) {
+        let file: &SourceFile = self.file(&TokenLocation { start: offset, end: offset });
+        let offset: usize = offset.saturating_sub(file.start).min(file.length);
+        let line: usize = file.line_index(offset);
+        let (start, byte): (usize, usize) = file.lines[line];
+        (line, file.contents[byte..].chars().take(offset - start).map(char::len_utf16).sum())
+    }
+    // The offset of a 0-based line and UTF-16 character in a file, kept to
+    // the end of the line, or of the file past its last line
+    pub fn utf16_offset(&self, file: &SourceFile, line: usize, character: usize) -> usize {
+        let Some(text) = file.line_text(line) else {
+            return file.start + file.length;
+        };
+        let mut offset: usize = file.start + file.lines[line].0;
+        let mut units: usize = 0;
+        for c in text.chars() {
+            units += c.len_utf16();
+            if units > character {
+                break;
+            }
+            offset += 1;
+        }
+        offset
+    }
+    // The message, then the line it points at with the span underlined (up to
+    // the end of that line), the same for each label, and the notes and
+    // suggestions below them:
+    //
+    //     [main.sl:3:6] TypeError[E0010]: `x` is already declared in this scope
+    //       |
+    //     3 |     var x: int = 2
+    //       |         ^
+    //       |
+    //     2 |     var x: int = 1
+    //       |         - declared here first
+    //       = note: `sl explain E0010` has more about this
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        let (filename, line, column): (String, usize, usize) = self.position(&diagnostic.location);
+        let color = |text: &str| -> ColoredString {
+            match diagnostic.severity {
+                Severity::Warning => text.yellow(),
+                Severity::Error => text.red(),
+            }
+        };
+        let message: String = format!("[{}:{}:{}] {}", filename, line, column, diagnostic);
+        if self.files.is_empty() {
+            return color(&message).to_string();
+        }
+        // Every line shown gets a gutter as wide as the longest line number
+        let lines: Vec<usize> = [&diagnostic.location].into_iter().chain(diagnostic.labels.iter().map(|label| &label.location)).map(|location| self.line(location).1).collect();
+        let gutter: String = " ".repeat(lines.iter().max().unwrap_or(&1).to_string().len());
+        let mut rendered: String = color(&message).to_string();
+        rendered.push_str(&self.snippet(&diagnostic.location, &gutter, &color("^"), None));
+        for label in diagnostic.labels.iter() {
+            let (label_file, line, column): (String, usize, usize) = self.position(&label.location);
+            if label_file != filename {
+                rendered.push_str(&format!("\n{} {} {}:{}:{}", gutter, "-->".blue(), label_file, line, column));
+            }
+            rendered.push_str(&self.snippet(&label.location, &gutter, &"-".blue(), Some(&label.message)));
+        }
+        for note in diagnostic.notes.iter() {
+            rendered.push_str(&format!("\n{} {} note: {}", gutter, "=".blue(), note));
+        }
+        for suggestion in diagnostic.suggestions.iter() {
+            rendered.push_str(&format!("\n{} {} help: {}", gutter, "=".blue(), suggestion.message));
+        }
+        if Diagnostic::explain(diagnostic.code).is_some() {
+            rendered.push_str(&format!("\n{} {} note: `sl explain {}` has more about this", gutter, "=".blue(), diagnostic.code));
+        }
+        rendered
+    }
+    // The line a span is on with the span marked, as it appears under a
+    // diagnostic's message
+    pub fn snippet(&self, location: &TokenLocation, gutter: &str, marker: &ColoredString, label: Option<&str>) -> String {
+        let file: &SourceFile = self.file(location);
+        let (_, line_number, column): (String, usize, usize) = self.position(location);
+        let Some(line) = file.line_text(line_number - 1) else {
+            return String::new();
+        };
+        // Tabs are widened so the underline lines up whatever the terminal does with them
+        let widen = |characters: &[char]| -> String { characters.iter().map(|character| if *character == '\t' { "    ".to_string() } else { character.to_string() }).collect() };
+        let characters: Vec<char> = line.trim_end_matches('\r').chars().collect();
+        let start: usize = (column - 1).min(characters.len());
+        let length: usize = location.end.saturating_sub(location.start).min(characters.len() - start).max(1);
+        let indent: String = widen(&characters[..start]).chars().map(|_| ' ').collect();
+        let width: usize = widen(&characters[start..(start + length).min(characters.len())]).chars().count().max(1);
+        let underline: String = marker.to_string().repeat(width);
+        let label: String = label.map(|label| format!(" {}", label.blue())).unwrap_or_default();
+        format!("\n{} {}\n{} {} {}\n{} {} {}{}{}", gutter, "|".blue(), format!("{:>width$}", line_number, width = gutter.len()).blue(), "|".blue(), widen(&characters), gutter, "|".blue(), indent, underline, label)
+    }
+    // A diagnostic as one line of JSON for --message-format=json. Spans are in
+    // bytes from the start of their file, lines and columns count from 1.
+    pub fn render_json(&self, diagnostic: &Diagnostic) -> String {
+        let span = |location: &TokenLocation| -> String {
+            let file: &SourceFile = self.file(location);
+            let byte = |offset: usize| -> usize { file.byte(offset.saturating_sub(file.start)) };
+            let (_, line, column): (String, usize, usize) = self.position(location);
+            format!("\"file\":{},\"span\":{{\"start\":{},\"end\":{}}},\"line\":{},\"column\":{}", json_string(&file.name), byte(location.start), byte(location.end.max(location.start)), line, column)
+        };
+        let severity: &str = match diagnostic.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        let labels: Vec<String> = diagnostic.labels.iter().map(|label| format!("{{\"message\":{},{}}}", json_string(&label.message), span(&label.location))).collect();
+        let notes: Vec<String> = diagnostic.notes.iter().map(|note| json_string(note)).collect();
+        let suggestions: Vec<String> = diagnostic.suggestions.iter()
+            .map(|suggestion| format!("{{\"message\":{},\"replacement\":{},{}}}", json_string(&suggestion.message), json_string(&suggestion.replacement), span(&suggestion.location)))
+            .collect();
+        format!(
+            "{{\"code\":{},\"severity\":\"{}\",\"kind\":\"{}\",\"message\":{},{},\"labels\":[{}],\"notes\":[{}],\"suggestions\":[{}]}}",
+            json_string(diagnostic.code),
+            severity,
+            diagnostic.name(),
+            json_string(&diagnostic.message),
+            span(&diagnostic.location),
+            labels.join(","),
+            notes.join(","),
+            suggestions.join(",")
+        )
+    }
+}
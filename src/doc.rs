@@ -0,0 +1,177 @@
+use crate::ast::{Annotation, Expression, Statement, Type};
+use crate::diagnostics::SourceMap;
+use crate::importer::Module;
+use crate::lexer::TokenLocation;
+use crate::symbol::Symbol;
+// A declaration as `sl doc` lists it: what it's called, how it's declared, its
+// fields or variants, and the `///` comment lines right above it
+pub struct DocItem {
+    // The heading it's listed under, such as "Functions"
+    pub section: &'static str,
+    pub name: String,
+    pub signature: String,
+    pub members: Vec<String>,
+    pub docs: String,
+    // The message of a @deprecated
+    pub deprecated: Option<String>,
+}
+impl DocItem {
+    pub const SECTIONS: [&'static str; 6] = ["Functions", "Structs", "Enums", "Type aliases", "Constants", "Annotations"];
+    // A module's declarations in the order they're written, leaving out @static
+    // functions, which can't be used from outside it
+    pub fn collect(module: &Module, sources: &SourceMap) -> Vec<DocItem> {
+        let mut items: Vec<DocItem> = vec![];
+        for statement in module.statements.iter() {
+            let (inner, annotations): (&Statement, &[Annotation]) = match statement {
+                Statement::Annotated(inner, annotations, _) => (inner, annotations),
+                _ => (statement, &[]),
+            };
+            if annotations.iter().any(|annotation| annotation.name == "static") {
+                continue;
+            }
+            let Some(mut item) = DocItem::declaration(inner, "") else {
+                continue;
+            };
+            item.docs = DocItem::comment(sources, &statement.location());
+            item.deprecated = annotations.iter().find(|annotation| annotation.name == "deprecated").map(|annotation| match annotation.arguments.first() {
+                Some(Expression::String(message, _)) => message.clone(),
+                _ => String::new(),
+            });
+            items.push(item);
+        }
+        items
+    }
+    pub fn declaration(statement: &Statement, prefix: &str) -> Option<DocItem> {
+        let fields = |fields: &[(Symbol, Type)]| -> Vec<String> {
+            fields.iter().map(|(name, t)| format!("{}: {}", name, t.name())).collect()
+        };
+        let item = |section: &'static str, name: &str, signature: String, members: Vec<String>| -> Option<DocItem> {
+            Some(DocItem { section, name: name.to_string(), signature: format!("{}{}", prefix, signature), members, docs: String::new(), deprecated: None })
+        };
+        match statement {
+            Statement::External(inner, _) => DocItem::declaration(inner, &format!("{}external ", prefix)),
+            Statement::Inline(inner, _) => DocItem::declaration(inner, &format!("{}inline ", prefix)),
+            Statement::Generic(inner, parameters, _) => {
+                let parameters: Vec<String> = parameters.iter().map(|(name, t)| match t {
+                    Some(t) => format!("{}: {}", name, t.name()),
+                    None => name.to_string(),
+                }).collect();
+                let mut item: DocItem = DocItem::declaration(inner, prefix)?;
+                item.signature = item.signature.replacen('(', &format!("[{}](", parameters.join(", ")), 1);
+                Some(item)
+            }
+            Statement::Function(name, args, return_type, _, _) => item("Functions", name, DocItem::function(name, args, return_type), vec![]),
+            Statement::StructFunction(struct_name, name, args, return_type, _, _) => {
+                let name: String = format!("{}.{}", struct_name, name);
+                item("Functions", &name, DocItem::function(&name, args, return_type), vec![])
+            }
+            Statement::Struct(name, members, _) => item("Structs", name, format!("struct {}", name), fields(members)),
+            Statement::Enum(name, t, variants, _) => {
+                let variants: Vec<String> = variants.iter().map(|(variant, value, _)| match value {
+                    Expression::Number(value, _) => format!("{} = {}", variant, value),
+                    _ => variant.to_string(),
+                }).collect();
+                item("Enums", name, format!("enum {} : {}", name, t.name()), variants)
+            }
+            Statement::StructEnum(name, variants, _) => {
+                let variants: Vec<String> = variants.iter().map(|(variant, members)| format!("{}({})", variant, fields(members).join(", "))).collect();
+                item("Enums", name, format!("enum {}", name), variants)
+            }
+            Statement::JavaEnum(name, constructor, variants, _) => {
+                let variants: Vec<String> = variants.iter().map(|(variant, _)| variant.to_string()).collect();
+                item("Enums", name, format!("enum {}({})", name, fields(constructor).join(", ")), variants)
+            }
+            Statement::TypeAlias(name, types, _) if types.is_empty() => item("Type aliases", name, format!("type {}", name), vec![]),
+            Statement::TypeAlias(name, types, _) => {
+                let types: Vec<String> = types.iter().map(Type::name).collect();
+                item("Type aliases", name, format!("type {} = {}", name, types.join(" | ")), vec![])
+            }
+            Statement::Constant(name, t, _, _) => item("Constants", name, format!("const {}: {}", name, t.name()), vec![]),
+            Statement::Annotation(name, members, _) => item("Annotations", name, format!("annotation {}", name), fields(members)),
+            _ => None,
+        }
+    }
+    pub fn function(name: &str, args: &[(Symbol, Type)], return_type: &Type) -> String {
+        let args: Vec<String> = args.iter().map(|(name, t)| format!("{}: {}", name, t.name())).collect();
+        match return_type {
+            Type::Void(_) => format!("func {}({})", name, args.join(", ")),
+            _ => format!("func {}({}): {}", name, args.join(", "), return_type.name()),
+        }
+    }
+    // The run of `///` lines above a declaration, past any annotations on it
+    pub fn comment(sources: &SourceMap, location: &TokenLocation) -> String {
+        let (_, line): (String, usize) = sources.line(location);
+        let mut docs: Vec<&str> = vec![];
+        for text in sources.file(location).contents.lines().take(line - 1).collect::<Vec<&str>>().into_iter().rev() {
+            let text: &str = text.trim();
+            match text.strip_prefix("///") {
+                Some(doc) => docs.push(doc.strip_prefix(' ').unwrap_or(doc).trim_end()),
+                None if text.starts_with('@') && docs.is_empty() => {}
+                None => break,
+            }
+        }
+        docs.reverse();
+        docs.join("\n")
+    }
+    // A module's page in Markdown
+    pub fn markdown(name: &str, items: &[DocItem]) -> String {
+        let mut page: String = format!("# {}\n", name);
+        for section in DocItem::SECTIONS.iter() {
+            let items: Vec<&DocItem> = items.iter().filter(|item| item.section == *section).collect();
+            if items.is_empty() {
+                continue;
+            }
+            page.push_str(&format!("\n## {}\n", section));
+            for item in items.iter() {
+                page.push_str(&format!("\n### {}\n\n```sl\n{}\n```\n", item.name, item.signature));
+                if let Some(message) = &item.deprecated {
+                    page.push_str(&format!("\n**Deprecated**{}\n", if message.is_empty() { String::new() } else { format!(": {}", message) }));
+                }
+                if !item.docs.is_empty() {
+                    page.push_str(&format!("\n{}\n", item.docs));
+                }
+                if !item.members.is_empty() {
+                    page.push('\n');
+                    for member in item.members.iter() {
+                        page.push_str(&format!("- `{}`\n", member));
+                    }
+                }
+            }
+        }
+        page
+    }
+    pub fn escape_html(text: &str) -> String {
+        text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+    }
+    // A module's page in HTML, with a paragraph per blank-line-separated part of
+    // each comment
+    pub fn html(name: &str, items: &[DocItem]) -> String {
+        let escape = DocItem::escape_html;
+        let mut page: String = format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n<h1>{}</h1>\n", escape(name), escape(name));
+        for section in DocItem::SECTIONS.iter() {
+            let items: Vec<&DocItem> = items.iter().filter(|item| item.section == *section).collect();
+            if items.is_empty() {
+                continue;
+            }
+            page.push_str(&format!("<h2>{}</h2>\n", section));
+            for item in items.iter() {
+                page.push_str(&format!("<h3 id=\"{}\">{}</h3>\n<pre><code>{}</code></pre>\n", escape(&item.name), escape(&item.name), escape(&item.signature)));
+                if let Some(message) = &item.deprecated {
+                    page.push_str(&format!("<p><strong>Deprecated</strong>{}</p>\n", if message.is_empty() { String::new() } else { format!(": {}", escape(message)) }));
+                }
+                for paragraph in item.docs.split("\n\n").filter(|paragraph| !paragraph.trim().is_empty()) {
+                    page.push_str(&format!("<p>{}</p>\n", escape(paragraph)));
+                }
+                if !item.members.is_empty() {
+                    page.push_str("<ul>\n");
+                    for member in item.members.iter() {
+                        page.push_str(&format!("<li><code>{}</code></li>\n", escape(member)));
+                    }
+                    page.push_str("</ul>\n");
+                }
+            }
+        }
+        page.push_str("</body>\n</html>\n");
+        page
+    }
+}
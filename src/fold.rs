@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use crate::ast::{Annotation, Expression, Statement, Type};
+use crate::symbol::Symbol;
+// A pass that rebuilds the syntax tree, taking each node by value and
+// returning what replaces it. Each method rebuilds the node from its folded
+// children by default, so a rewrite overrides the nodes it changes and calls
+// the matching `walk_` function for the ones it doesn't, or to fold the
+// children first. Blocks are folded as a whole, so a rewrite can add or remove
+// the statements in them.
+pub trait Folder {
+    fn fold_block(&mut self, body: Vec<Statement>) -> Vec<Statement> {
+        walk_block(self, body)
+    }
+    fn fold_statement(&mut self, statement: Statement) -> Statement {
+        walk_statement(self, statement)
+    }
+    fn fold_expression(&mut self, expression: Expression) -> Expression {
+        walk_expression(self, expression)
+    }
+    fn fold_type(&mut self, t: Type) -> Type {
+        walk_type(self, t)
+    }
+}
+pub fn walk_block<F: Folder + ?Sized>(folder: &mut F, body: Vec<Statement>) -> Vec<Statement> {
+    body.into_iter().map(|statement| folder.fold_statement(statement)).collect()
+}
+pub fn walk_fields<F: Folder + ?Sized>(folder: &mut F, fields: Vec<(Symbol, Type)>) -> Vec<(Symbol, Type)> {
+    fields.into_iter().map(|(name, t)| (name, folder.fold_type(t))).collect()
+}
+pub fn walk_expressions<F: Folder + ?Sized>(folder: &mut F, expressions: Vec<Expression>) -> Vec<Expression> {
+    expressions.into_iter().map(|expression| folder.fold_expression(expression)).collect()
+}
+pub fn walk_annotation<F: Folder + ?Sized>(folder: &mut F, annotation: Annotation) -> Annotation {
+    Annotation { arguments: walk_expressions(folder, annotation.arguments), ..annotation }
+}
+pub fn walk_statement<F: Folder + ?Sized>(folder: &mut F, statement: Statement) -> Statement {
+    match statement {
+        Statement::Generic(statement, type_parameters, location) => {
+            let type_parameters = type_parameters.into_iter().map(|(name, constraint)| (name, constraint.map(|t| folder.fold_type(t)))).collect();
+            Statement::Generic(Box::new(folder.fold_statement(*statement)), type_parameters, location)
+        }
+        Statement::Annotated(statement, annotations, location) => {
+            let annotations: Vec<Annotation> = annotations.into_iter().map(|annotation| walk_annotation(folder, annotation)).collect();
+            Statement::Annotated(Box::new(folder.fold_statement(*statement)), annotations, location)
+        }
+        Statement::External(statement, location) => Statement::External(Box::new(folder.fold_statement(*statement)), location),
+        Statement::Inline(statement, location) => Statement::Inline(Box::new(folder.fold_statement(*statement)), location),
+        Statement::Annotation(name, fields, location) => Statement::Annotation(name, walk_fields(folder, fields), location),
+        Statement::Struct(name, fields, location) => Statement::Struct(name, walk_fields(folder, fields), location),
+        Statement::Enum(name, t, variants, location) => {
+            let t: Type = folder.fold_type(t);
+            let variants = variants.into_iter().map(|(variant, value, location)| (variant, folder.fold_expression(value), location)).collect();
+            Statement::Enum(name, t, variants, location)
+        }
+        Statement::StructEnum(name, variants, location) => {
+            let variants = variants.into_iter().map(|(variant, fields)| (variant, walk_fields(folder, fields))).collect();
+            Statement::StructEnum(name, variants, location)
+        }
+        Statement::JavaEnum(name, fields, values, location) => {
+            let fields: Vec<(Symbol, Type)> = walk_fields(folder, fields);
+            let values = values.into_iter().map(|(value, args)| (value, walk_expressions(folder, args))).collect();
+            Statement::JavaEnum(name, fields, values, location)
+        }
+        Statement::TypeAlias(name, types, location) => Statement::TypeAlias(name, types.into_iter().map(|t| folder.fold_type(t)).collect(), location),
+        Statement::Function(name, args, return_type, body, location) => {
+            let args: Vec<(Symbol, Type)> = walk_fields(folder, args);
+            let return_type: Type = folder.fold_type(return_type);
+            Statement::Function(name, args, return_type, folder.fold_block(body), location)
+        }
+        Statement::StructFunction(struct_name, name, args, return_type, body, location) => {
+            let args: Vec<(Symbol, Type)> = walk_fields(folder, args);
+            let return_type: Type = folder.fold_type(return_type);
+            Statement::StructFunction(struct_name, name, args, return_type, folder.fold_block(body), location)
+        }
+        Statement::Variable(name, t, value, location) => {
+            let t: Type = folder.fold_type(t);
+            Statement::Variable(name, t, folder.fold_expression(value), location)
+        }
+        Statement::Constant(name, t, value, location) => {
+            let t: Type = folder.fold_type(t);
+            Statement::Constant(name, t, folder.fold_expression(value), location)
+        }
+        Statement::Return(value, location) => Statement::Return(folder.fold_expression(value), location),
+        Statement::Expression(value, location) => Statement::Expression(folder.fold_expression(value), location),
+        Statement::While(condition, body, location) => {
+            let condition: Expression = folder.fold_expression(condition);
+            Statement::While(condition, folder.fold_block(body), location)
+        }
+        Statement::For(name, iterable, body, location) => {
+            let iterable: Expression = folder.fold_expression(iterable);
+            Statement::For(name, iterable, folder.fold_block(body), location)
+        }
+        Statement::If(condition, body, else_body, location) => {
+            let condition: Expression = folder.fold_expression(condition);
+            let body: Vec<Statement> = folder.fold_block(body);
+            Statement::If(condition, body, folder.fold_block(else_body), location)
+        }
+        statement @ (Statement::Break(_) | Statement::Continue(_) | Statement::Import(_, _)) => statement,
+    }
+}
+pub fn walk_expression<F: Folder + ?Sized>(folder: &mut F, expression: Expression) -> Expression {
+    match expression {
+        Expression::Call(name, args, location) => Expression::Call(name, walk_expressions(folder, args), location),
+        Expression::GenericCall(name, types, args, location) => {
+            let types: Vec<Type> = types.into_iter().map(|t| folder.fold_type(t)).collect();
+            Expression::GenericCall(name, types, walk_expressions(folder, args), location)
+        }
+        Expression::New(name, args, location) => Expression::New(name, walk_expressions(folder, args), location),
+        Expression::Array(elements, location) => Expression::Array(walk_expressions(folder, elements), location),
+        Expression::Member(left, right, location) => Expression::Member(Box::new(folder.fold_expression(*left)), Box::new(folder.fold_expression(*right)), location),
+        Expression::Index(left, right, location) => Expression::Index(Box::new(folder.fold_expression(*left)), Box::new(folder.fold_expression(*right)), location),
+        Expression::Assignment(left, right, location) => Expression::Assignment(Box::new(folder.fold_expression(*left)), Box::new(folder.fold_expression(*right)), location),
+        Expression::Binary(op, left, right, location) => Expression::Binary(op, Box::new(folder.fold_expression(*left)), Box::new(folder.fold_expression(*right)), location),
+        Expression::Range(left, right, location) => Expression::Range(Box::new(folder.fold_expression(*left)), Box::new(folder.fold_expression(*right)), location),
+        Expression::Ternary(condition, then, otherwise, location) => {
+            let condition: Expression = folder.fold_expression(*condition);
+            let then: Expression = folder.fold_expression(*then);
+            Expression::Ternary(Box::new(condition), Box::new(then), Box::new(folder.fold_expression(*otherwise)), location)
+        }
+        Expression::NamedArgument(name, value, location) => Expression::NamedArgument(name, Box::new(folder.fold_expression(*value)), location),
+        Expression::Cast(value, t, location) => {
+            let value: Expression = folder.fold_expression(*value);
+            Expression::Cast(Box::new(value), folder.fold_type(t), location)
+        }
+        Expression::UnsafeCast(value, t, location) => {
+            let value: Expression = folder.fold_expression(*value);
+            Expression::UnsafeCast(Box::new(value), folder.fold_type(t), location)
+        }
+        Expression::Unary(op, value, location) => Expression::Unary(op, Box::new(folder.fold_expression(*value)), location),
+        Expression::Grouping(value, location) => Expression::Grouping(Box::new(folder.fold_expression(*value)), location),
+        Expression::AddressOf(value, location) => Expression::AddressOf(Box::new(folder.fold_expression(*value)), location),
+        Expression::Dereference(value, location) => Expression::Dereference(Box::new(folder.fold_expression(*value)), location),
+        Expression::Map(entries, location) => {
+            let entries = entries.into_iter().map(|(key, value)| (folder.fold_expression(key), folder.fold_expression(value))).collect();
+            Expression::Map(entries, location)
+        }
+        Expression::SizeOf(t, location) => Expression::SizeOf(folder.fold_type(t), location),
+        Expression::Type(t, location) => Expression::Type(folder.fold_type(t), location),
+        expression @ (Expression::Number(..) | Expression::String(..) | Expression::Char(..) | Expression::Boolean(..) | Expression::Identifier(..)
+        | Expression::Null | Expression::Error(_) | Expression::Empty) => expression,
+    }
+}
+pub fn walk_type<F: Folder + ?Sized>(folder: &mut F, t: Type) -> Type {
+    match t {
+        Type::Function(args, return_type, location) => {
+            let args: Vec<Type> = args.into_iter().map(|t| folder.fold_type(t)).collect();
+            Type::Function(args, Box::new(folder.fold_type(*return_type)), location)
+        }
+        Type::Array(t, size, location) => {
+            let t: Type = folder.fold_type(*t);
+            Type::Array(Box::new(t), Box::new(folder.fold_expression(*size)), location)
+        }
+        Type::Map(key, value, location) => {
+            let key: Type = folder.fold_type(*key);
+            Type::Map(Box::new(key), Box::new(folder.fold_type(*value)), location)
+        }
+        Type::Pointer(t, location) => Type::Pointer(Box::new(folder.fold_type(*t)), location),
+        Type::DynamicArray(t, location) => Type::DynamicArray(Box::new(folder.fold_type(*t)), location),
+        Type::Volatile(t, location) => Type::Volatile(Box::new(folder.fold_type(*t)), location),
+        Type::Const(t, location) => Type::Const(Box::new(folder.fold_type(*t)), location),
+        Type::Restrict(t, location) => Type::Restrict(Box::new(folder.fold_type(*t)), location),
+        t => t,
+    }
+}
+// Puts types in place of the type parameters they're given for
+pub struct Substitution(pub HashMap<Symbol, Type>);
+impl Folder for Substitution {
+    fn fold_type(&mut self, t: Type) -> Type {
+        match t {
+            Type::Unknown(name, _) | Type::GenericType(name, _) if self.0.contains_key(&name) => self.0[&name].clone(),
+            t => walk_type(self, t),
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use crate::diagnostics::Diagnostic;
+use crate::lexer::{Lexer, Token, TokenKind, TokenLocation};
+use crate::parser::Parser;
+// Lays a file out again from its tokens, comments included. Blocks are indented
+// a tab per level with `else` and `end` lined up with what opened them, binary
+// operators get a space on either side and commas and colons one after. Line
+// breaks are kept, but runs of blank lines are squeezed into one.
+pub struct Formatter;
+impl Formatter {
+    pub fn format(contents: &str) -> Result<String, Vec<Diagnostic>> {
+        let mut lexer: Lexer = Lexer::with_comments(contents.to_string());
+        let tokens: Vec<Token> = lexer.lex();
+        if !lexer.errors.is_empty() {
+            return Err(lexer.errors);
+        }
+        // Laying out a file that doesn't parse could only make it worse
+        let mut parser: Parser = Parser::new(Lexer::new(contents.to_string()), 0);
+        parser.parse();
+        if !parser.errors.is_empty() {
+            return Err(parser.errors);
+        }
+        let source: Vec<char> = contents.chars().collect();
+        let mut lines: Vec<Vec<Token>> = vec![vec![]];
+        for token in tokens.into_iter() {
+            match token.kind {
+                TokenKind::Newline => lines.push(vec![]),
+                _ => lines.last_mut().unwrap().push(token),
+            }
+        }
+        let mut code: String = String::new();
+        // Where each open block starts, and how many brackets are open, which
+        // carries a line on to the next
+        let mut blocks: Vec<TokenLocation> = vec![];
+        let mut nesting: usize = 0;
+        let mut blank: bool = false;
+        for line in lines.iter() {
+            let Some(first) = line.first() else {
+                blank = !code.is_empty();
+                continue;
+            };
+            if blank {
+                code.push('\n');
+                blank = false;
+            }
+            let mut indent: usize = blocks.len();
+            match first.kind {
+                TokenKind::End | TokenKind::Else if blocks.is_empty() => {
+                    return Err(vec![Diagnostic::syntax_error("E0036", format!("`{}` without a block to close", first.value), first.location.clone())]);
+                }
+                TokenKind::End => {
+                    blocks.pop();
+                    indent -= 1;
+                }
+                TokenKind::Else => indent -= 1,
+                TokenKind::CloseParen | TokenKind::CloseBracket => {}
+                _ if nesting > 0 => indent += 1,
+                _ => {}
+            }
+            code.push_str(&"\t".repeat(indent));
+            code.push_str(&Formatter::line(&source, line));
+            code.push('\n');
+            for token in line.iter() {
+                match token.kind {
+                    TokenKind::OpenParen | TokenKind::OpenBracket => nesting += 1,
+                    TokenKind::CloseParen | TokenKind::CloseBracket => nesting = nesting.saturating_sub(1),
+                    _ => {}
+                }
+            }
+            if Formatter::opens_block(line) {
+                blocks.push(first.location.clone());
+            }
+        }
+        if let Some(location) = blocks.pop() {
+            return Err(vec![Diagnostic::syntax_error("E0036", "this block is never closed with `end`".to_string(), location)]);
+        }
+        Ok(code)
+    }
+    // The tokens of a file with what they were written as, which formatting
+    // mustn't change
+    pub fn tokens(contents: &str) -> Vec<(TokenKind, String)> {
+        let source: Vec<char> = contents.chars().collect();
+        Lexer::with_comments(contents.to_string()).lex().iter()
+            .filter(|token| token.kind != TokenKind::Newline)
+            .map(|token| (token.kind.clone(), Formatter::text(&source, token)))
+            .collect()
+    }
+    // Whether a line starts a block that an `end` closes. `else` carries on the
+    // block of its `if`, and a func with `=>`, an external func, which has no
+    // body, or a declaration ending in `end` is done in one line.
+    pub fn opens_block(line: &[Token]) -> bool {
+        let kinds: Vec<&TokenKind> = line.iter().map(|token| &token.kind).filter(|kind| **kind != TokenKind::Comment).collect();
+        let Some(start) = kinds.iter().position(|kind| !matches!(kind, TokenKind::External | TokenKind::Inline)) else {
+            return false;
+        };
+        if kinds.last() == Some(&&TokenKind::End) && start + 1 < kinds.len() {
+            return false;
+        }
+        match kinds[start] {
+            TokenKind::Annotation | TokenKind::Struct | TokenKind::Enum | TokenKind::While | TokenKind::For | TokenKind::If => true,
+            TokenKind::Func => !kinds.contains(&&TokenKind::FatArrow) && !kinds[..start].contains(&&TokenKind::External),
+            _ => false,
+        }
+    }
+    pub fn text(source: &[char], token: &Token) -> String {
+        match token.kind {
+            TokenKind::Comment => token.value.clone(),
+            _ => source[token.location.start.min(source.len())..token.location.end.min(source.len())].iter().collect(),
+        }
+    }
+    // What a value or a type can end with, after which an operator is binary
+    pub fn ends_operand(kind: &TokenKind) -> bool {
+        matches!(kind, TokenKind::Identifier | TokenKind::StringLit | TokenKind::CharLit | TokenKind::NumberLit
+            | TokenKind::True | TokenKind::False | TokenKind::Null | TokenKind::CloseParen | TokenKind::CloseBracket
+            | TokenKind::Int | TokenKind::Usize | TokenKind::String | TokenKind::CString | TokenKind::Char | TokenKind::Bool | TokenKind::Void)
+    }
+    // A line's tokens with one space between them where it's wanted
+    pub fn line(source: &[char], line: &[Token]) -> String {
+        // Prefix operators, and the `*`s of a pointer type, which hug what they're
+        // next to. A type's stars are found from the last, which is followed by
+        // what can come after a type.
+        let mut pointer: Vec<bool> = vec![false; line.len()];
+        for i in (1..line.len()).rev() {
+            let previous: &TokenKind = &line[i - 1].kind;
+            pointer[i] = line[i].kind == TokenKind::Star && (Formatter::ends_operand(previous) || *previous == TokenKind::Star) && match line.get(i + 1) {
+                None => true,
+                Some(next) if next.kind == TokenKind::Star => pointer[i + 1],
+                Some(next) => matches!(next.kind, TokenKind::Comment | TokenKind::Comma | TokenKind::CloseParen | TokenKind::CloseBracket
+                    | TokenKind::Equal | TokenKind::Pipe | TokenKind::FatArrow | TokenKind::End),
+            };
+        }
+        let unary: Vec<bool> = (0..line.len()).map(|i| {
+            matches!(line[i].kind, TokenKind::Minus | TokenKind::Bang | TokenKind::Ampersand | TokenKind::Star)
+                && (i == 0 || !Formatter::ends_operand(&line[i - 1].kind)) && !pointer[i]
+        }).collect();
+        let mut code: String = String::new();
+        for (i, token) in line.iter().enumerate() {
+            if i > 0 {
+                let previous: &TokenKind = &line[i - 1].kind;
+                let space: bool = match token.kind {
+                    TokenKind::Comment => true,
+                    _ if unary[i - 1] => false,
+                    _ if matches!(previous, TokenKind::OpenParen | TokenKind::OpenBracket | TokenKind::Dot | TokenKind::At | TokenKind::Range) => false,
+                    TokenKind::CloseParen | TokenKind::CloseBracket | TokenKind::Comma | TokenKind::Dot | TokenKind::Range => false,
+                    // An enum's type is set apart from its name, as in `enum Color : int`
+                    TokenKind::Colon => i >= 2 && line[i - 2].kind == TokenKind::Enum,
+                    TokenKind::Star => !pointer[i],
+                    TokenKind::OpenParen => !(matches!(previous, TokenKind::Identifier | TokenKind::CloseParen | TokenKind::CloseBracket | TokenKind::Func)),
+                    TokenKind::OpenBracket => !(Formatter::ends_operand(previous) || *previous == TokenKind::UnsafeCast),
+                    _ => true,
+                };
+                if space {
+                    code.push(' ');
+                }
+            }
+            code.push_str(&Formatter::text(source, token));
+        }
+        code
+    }
+}
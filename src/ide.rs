@@ -0,0 +1,1025 @@
+use std::collections::HashSet;
+use crate::ast::{Annotation, Expression, Statement, Type};
+use crate::checker::{Checker, Signature};
+use crate::diagnostics::{Diagnostic, SourceFile, SourceMap, Suggestion};
+use crate::doc::DocItem;
+use crate::format::Formatter;
+use crate::importer::{Importer, Module};
+use crate::json::Json;
+use crate::lexer::{Lexer, Token, TokenKind, TokenLocation};
+use crate::lint::{LintConfig, Linter};
+use crate::symbol::Symbol;
+use crate::visit::{walk_statements, Visitor};
+// What an editor asks about a file as it's edited: its diagnostics, the
+// declaration, type and completions at a place in it, and its syntax tree as
+// JSON. It's built from the file's current contents, with what it imports read
+// from disk, and doesn't print anything.
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct Ide {
+    pub filename: String,
+    pub contents: String,
+    pub json: String,
+    pub statements: Vec<Statement>,
+    pub sources: SourceMap,
+    pub modules: Vec<Module>,
+    // The file's own tokens, whose locations are offsets into it
+    pub tokens: Vec<Token>,
+    pub checker: Checker,
+    pub diagnostics: Vec<Diagnostic>,
+}
+// What's named at a place in a file. `location` is the name itself, and
+// `declaration` where it was declared. `t` is its type, or the type a struct
+// or enum's name stands for, and is left out for a type alias or annotation.
+#[derive(Debug, Clone)] pub struct TypeInfo {
+    pub name: String,
+    pub kind: &'static str,
+    pub t: Option<Type>,
+    pub signature: String,
+    pub docs: String,
+    pub location: TokenLocation,
+    pub declaration: Option<TokenLocation>,
+}
+// The call an offset is inside the parentheses of: how what's called was
+// declared, the parameters its arguments are for, and which of them the
+// argument at the offset is. `active` is None past the last parameter.
+#[derive(Debug, Clone)] pub struct SignatureHelp {
+    pub label: String,
+    pub docs: String,
+    pub parameters: Vec<(Symbol, Type)>,
+    pub active: Option<usize>,
+}
+// A declaration in a file's outline, with what's declared inside it. `range`
+// is the whole declaration and `selection` its name.
+#[derive(Debug, Clone)] pub struct Outline {
+    pub name: String,
+    pub detail: String,
+    pub kind: &'static str,
+    pub range: TokenLocation,
+    pub selection: TokenLocation,
+    pub children: Vec<Outline>,
+}
+#[allow(dead_code)]
+impl Ide {
+    pub const KEYWORDS: [&'static str; 30] = [
+        "annotation", "struct", "end", "enum", "external", "inline", "func", "type", "var", "const", "return", "import", "as",
+        "unsafe_cast", "sizeof", "new", "true", "false", "null", "if", "else", "while", "break", "continue",
+        "int", "usize", "string", "cstring", "char", "bool",
+    ];
+    // What `semantic_tokens` calls tokens, named as LSP's semantic token types
+    pub const TOKEN_TYPES: [&'static str; 15] = [
+        "keyword", "type", "struct", "enum", "function", "method", "parameter", "variable", "property", "enumMember", "decorator",
+        "number", "string", "comment", "operator",
+    ];
+    pub fn new(filename: String, contents: String) -> Self {
+        let mut lints: LintConfig = LintConfig::new();
+        if let Ok(config) = std::fs::read_to_string(std::path::Path::new(&filename).with_file_name("sl.lints")) {
+            let _ = lints.parse(&config);
+        }
+        let mut importer: Importer = Importer::new();
+        importer.load_contents(&filename, contents.clone());
+        let statements: Vec<Statement> = importer.program();
+        let mut checker: Checker = Checker::new();
+        let mut diagnostics: Vec<Diagnostic> = importer.errors.clone();
+        // Like a compile, only the first stage with errors reports them. What
+        // parsed of a file that didn't is still checked, for the types that
+        // completions and hovers need while it's being written.
+        checker.lints = lints.clone();
+        checker.check(&statements);
+        if diagnostics.is_empty() {
+            diagnostics.extend(checker.errors.iter().cloned());
+            diagnostics.extend(checker.warnings.iter().cloned());
+            if checker.errors.is_empty() {
+                let mut linter: Linter = Linter::new(lints);
+                linter.run(&statements);
+                diagnostics.extend(linter.context.errors.iter().cloned());
+                diagnostics.extend(linter.context.warnings.iter().cloned());
+            }
+        }
+        Self {
+            filename,
+            contents: contents.clone(),
+            json: String::new(),
+            statements,
+            sources: importer.sources,
+            modules: importer.modules,
+            tokens: Lexer::new(contents).lex(),
+            checker,
+            diagnostics,
+        }
+    }
+    // The diagnostics that point into the file itself rather than what it imports
+    pub fn diagnostics(&self) -> Vec<&Diagnostic> {
+        self.diagnostics.iter().filter(|error| self.sources.file(&error.location()).start == 0).collect()
+    }
+    // The fixes of the file's diagnostics that overlap a range, each with the
+    // diagnostic it's for. Only those that edit the file itself are given.
+    pub fn fixes(&self, range: &TokenLocation) -> Vec<(&Diagnostic, &Suggestion)> {
+        self.diagnostics().into_iter()
+            .filter(|diagnostic| diagnostic.location.start <= range.end && range.start <= diagnostic.location.end)
+            .flat_map(|diagnostic| diagnostic.suggestions.iter().map(move |suggestion| (diagnostic, suggestion)))
+            .filter(|(_, suggestion)| self.sources.file(&suggestion.location).start == 0)
+            .collect()
+    }
+    // A 0-based line and character as an offset into the file, with the
+    // character in UTF-16 code units as an editor gives it
+    pub fn offset(&self, line: usize, character: usize) -> usize {
+        self.sources.utf16_offset(&self.sources.files[0], line, character)
+    }
+    // The 0-based line and UTF-16 character a location starts and ends at
+    pub fn range(&self, location: &TokenLocation) -> ((usize, usize), (usize, usize)) {
+        (self.sources.utf16_position(location.start), self.sources.utf16_position(location.end.max(location.start)))
+    }
+    // The path of the file a location is in
+    pub fn path(&self, location: &TokenLocation) -> std::path::PathBuf {
+        let name: &String = &self.sources.file(location).name;
+        match self.modules.iter().find(|module| module.name == *name) {
+            Some(module) => module.path.clone(),
+            None => name.into(),
+        }
+    }
+    // Where what's named at an offset was declared
+    pub fn definition(&self, offset: usize) -> Option<TokenLocation> {
+        let index: usize = self.identifier(offset)?;
+        self.resolve(&self.tokens, index).or_else(|| self.declared_at(&self.tokens, index))
+    }
+    // Where the identifier at `index` of a file's tokens was declared, when
+    // it's a use of something
+    pub fn resolve(&self, tokens: &[Token], index: usize) -> Option<TokenLocation> {
+        let token: &Token = &tokens[index];
+        if let Some((declared, _)) = self.checker.references.get(&token.location) {
+            return Some(declared.clone());
+        }
+        if let Some(struct_name) = self.receiver(tokens, index) {
+            if let Some(method) = self.declaration(&format!("{}.{}", struct_name, token.value)) {
+                return Some(self.method_name(method));
+            }
+            return self.field(&struct_name, &token.value).map(|t| t.location());
+        }
+        // A variant, after its enum's name and a `.`
+        if index >= 2 && tokens[index - 1].kind == TokenKind::Dot {
+            if let Some(Statement::Enum(_, _, variants, _)) = self.declaration(&tokens[index - 2].value).map(Ide::inner) {
+                return variants.iter().find(|(variant, _, _)| *variant == token.value).map(|(_, _, location)| location.clone());
+            }
+        }
+        self.declaration(&token.value).map(Statement::location)
+            .filter(|_| !(index >= 1 && tokens[index - 1].kind == TokenKind::Dot))
+    }
+    // Where the identifier at `index` of a file's tokens is declared, when it's
+    // the name in a declaration, as the location uses of it resolve to. That's
+    // a parameter or field's type, and a method's struct name.
+    pub fn declared_at(&self, tokens: &[Token], index: usize) -> Option<TokenLocation> {
+        let token: &Token = &tokens[index];
+        let previous = |back: usize| index.checked_sub(back).map(|index| &tokens[index].kind);
+        // `Struct` in `func Struct.method` names the struct, and `method` the method
+        if previous(1) == Some(&TokenKind::Func) && tokens.get(index + 1).is_some_and(|next| next.kind == TokenKind::Dot) {
+            return None;
+        }
+        if previous(1) == Some(&TokenKind::Dot) && previous(3) == Some(&TokenKind::Func) {
+            return self.declaration(&format!("{}.{}", tokens[index - 2].value, token.value)).map(|method| self.method_name(method));
+        }
+        for statement in self.statements.iter() {
+            if statement.location() == token.location && !matches!(Ide::inner(statement), Statement::StructFunction(..)) {
+                return Some(token.location.clone());
+            }
+            if let Statement::Enum(_, _, variants, _) = Ide::inner(statement) {
+                if variants.iter().any(|(_, _, location)| *location == token.location) {
+                    return Some(token.location.clone());
+                }
+            }
+        }
+        // A local variable is declared where the checker says
+        if tokens.get(index + 1).is_none_or(|next| next.kind != TokenKind::Colon) || matches!(previous(1), Some(TokenKind::Var | TokenKind::Const)) {
+            return None;
+        }
+        // A parameter or field, whose type is the first of that name's after it
+        // with no other declaration of the name in between
+        let end: usize = tokens.last().map_or(0, |last| last.location.end);
+        let t: TokenLocation = self.statements.iter().flat_map(|statement| match Ide::inner(statement) {
+            Statement::Function(_, fields, _, _, _) | Statement::StructFunction(_, _, fields, _, _, _) | Statement::Struct(_, fields, _)
+            | Statement::JavaEnum(_, fields, _, _) | Statement::Annotation(_, fields, _) => fields.as_slice(),
+            _ => &[],
+        }).filter(|(name, t)| *name == token.value && token.location.end <= t.location().start && t.location().end <= end)
+            .map(|(_, t)| t.location())
+            .min_by_key(|location| location.start)?;
+        let redeclared: bool = tokens[index + 1..].windows(2).take_while(|pair| pair[0].location.start < t.start)
+            .any(|pair| pair[0].value == token.value && pair[1].kind == TokenKind::Colon);
+        (!redeclared).then_some(t)
+    }
+    // Every use across the program of what's named at an offset, in the file
+    // itself and what it imports, and where it's declared if asked for
+    pub fn references(&self, offset: usize, declaration: bool) -> Vec<TokenLocation> {
+        let Some(index) = self.identifier(offset) else {
+            return vec![];
+        };
+        let name: &String = &self.tokens[index].value;
+        let Some(target) = self.resolve(&self.tokens, index).or_else(|| self.declared_at(&self.tokens, index)) else {
+            return vec![];
+        };
+        let mut references: Vec<TokenLocation> = vec![];
+        for file in self.sources.files.iter() {
+            let mut tokens: Vec<Token> = Lexer::new(file.contents.clone()).lex();
+            for token in tokens.iter_mut() {
+                token.location.start += file.start;
+                token.location.end += file.start;
+            }
+            for (index, token) in tokens.iter().enumerate() {
+                if token.kind != TokenKind::Identifier || token.value != *name {
+                    continue;
+                }
+                // A local variable's declaration resolves to itself
+                let (declared, declares): (TokenLocation, bool) = match self.declared_at(&tokens, index) {
+                    Some(declared) => (declared, true),
+                    None => match self.resolve(&tokens, index) {
+                        Some(resolved) => (resolved.clone(), resolved == token.location),
+                        None => continue,
+                    },
+                };
+                if declared == target && (declaration || !declares) {
+                    references.push(token.location.clone());
+                }
+            }
+        }
+        references
+    }
+    // What's named at an offset, from what the checker found: what kind of
+    // thing it is, its type when it has one, and how it was declared
+    pub fn type_info(&self, offset: usize) -> Option<TypeInfo> {
+        let index: usize = self.identifier(offset)?;
+        let token: &Token = &self.tokens[index];
+        let info = |kind: &'static str, t: Option<Type>, (signature, docs): (String, String), declaration: Option<TokenLocation>| TypeInfo {
+            name: token.value.clone(), kind, t, signature, docs, location: token.location.clone(), declaration,
+        };
+        // A global's uses are looked up like the rest of the top-level declarations
+        let local: Option<&(TokenLocation, Type)> = self.checker.references.get(&token.location)
+            .filter(|(declared, _)| self.declaration(&token.value).is_none_or(|statement| statement.location() != *declared));
+        if let Some((declared, t)) = local {
+            // A parameter's uses point at its type, where the checker declared it
+            let parameter: bool = self.statements.iter().any(|statement| match Ide::inner(statement) {
+                Statement::Function(_, args, _, _, _) | Statement::StructFunction(_, _, args, _, _, _) => args.iter().any(|(_, t)| t.location() == *declared),
+                _ => false,
+            });
+            let kind: &'static str = if parameter { "parameter" } else { "variable" };
+            return Some(info(kind, Some(t.clone()), (format!("{}: {}", token.value, t.name()), String::new()), Some(declared.clone())));
+        }
+        if let Some(struct_name) = self.receiver(&self.tokens, index) {
+            let name: String = format!("{}.{}", struct_name, token.value);
+            return match self.declaration(&name) {
+                Some(method) => Some(info("method", self.function_type(&name, &token.location), self.describe(method)?, Some(self.method_name(method)))),
+                None => {
+                    let t: Type = self.field(&struct_name, &token.value)?;
+                    let declaration: Option<TokenLocation> = self.definition(offset);
+                    Some(info("field", Some(t.clone()), (format!("{}: {}", token.value, t.name()), String::new()), declaration))
+                }
+            };
+        }
+        // A variant, after its enum's name and a `.`
+        if index >= 2 && self.tokens[index - 1].kind == TokenKind::Dot {
+            if let Some(Statement::Enum(name, _, variants, _)) = self.declaration(&self.tokens[index - 2].value).map(Ide::inner) {
+                let (_, _, location): &(Symbol, Expression, TokenLocation) = variants.iter().find(|(variant, _, _)| *variant == token.value)?;
+                let t: Type = Type::Enum(*name, location.clone());
+                return Some(info("variant", Some(t), (format!("{}.{}", name, token.value), String::new()), Some(location.clone())));
+            }
+        }
+        let statement: &Statement = self.declaration(&token.value)?;
+        let (kind, t): (&'static str, Option<Type>) = match Ide::inner(statement) {
+            Statement::Function(_, _, _, _, _) => ("function", self.function_type(&token.value, &token.location)),
+            Statement::Struct(name, _, location) => ("struct", Some(Type::Struct(*name, location.clone()))),
+            Statement::Enum(name, _, _, location) | Statement::StructEnum(name, _, location) | Statement::JavaEnum(name, _, _, location) => {
+                ("enum", Some(Type::Enum(*name, location.clone())))
+            }
+            Statement::TypeAlias(_, _, _) => ("type", None),
+            Statement::Annotation(_, _, _) => ("annotation", None),
+            Statement::Constant(_, t, _, location) | Statement::Variable(_, t, _, location) => {
+                let kind: &'static str = if matches!(Ide::inner(statement), Statement::Constant(..)) { "constant" } else { "variable" };
+                (kind, Some(self.checker.references.get(location).map_or(t.clone(), |(_, t)| t.clone())))
+            }
+            _ => return None,
+        };
+        Some(info(kind, t, self.describe(statement)?, Some(statement.location())))
+    }
+    // A function's type, as the checker has its signature
+    pub fn function_type(&self, name: &str, location: &TokenLocation) -> Option<Type> {
+        let (args, return_type): &Signature = self.checker.functions.get(&Symbol::intern(name))?;
+        Some(Type::Function(args.iter().map(|(_, t)| t.clone()).collect(), Box::new(return_type.clone()), location.clone()))
+    }
+    // How what's named at an offset was declared, and its `///` comment, as Markdown
+    pub fn hover(&self, offset: usize) -> Option<String> {
+        let info: TypeInfo = self.type_info(offset)?;
+        Some(if info.docs.is_empty() { format!("```sl\n{}\n```", info.signature) } else { format!("```sl\n{}\n```\n\n{}", info.signature, info.docs) })
+    }
+    // What could be written at an offset: the members of what's before a `.`,
+    // or else the locals in scope, what the program declares and the keywords.
+    // Each comes with its type or declaration and what kind of thing it is.
+    pub fn completions(&self, offset: usize) -> Vec<(String, String, &'static str)> {
+        let mut completions: Vec<(String, String, &'static str)> = vec![];
+        // Past a `.` and anything of the member already typed, on the same line
+        let before: Vec<&Token> = self.tokens.iter().filter(|token| token.location.end <= offset).collect();
+        let dot: Option<usize> = match before.as_slice() {
+            [.., dot, name] if dot.kind == TokenKind::Dot && name.kind == TokenKind::Identifier && name.location.end == offset => Some(before.len() - 2),
+            [.., dot] if dot.kind == TokenKind::Dot => Some(before.len() - 1),
+            _ => None,
+        };
+        if let Some(dot) = dot {
+            let dot: usize = self.tokens.iter().position(|token| token.location == before[dot].location).unwrap();
+            if let Some(struct_name) = self.receiver(&self.tokens, dot + 1) {
+                for (name, t) in self.checker.structs.get(&struct_name).cloned().unwrap_or_default() {
+                    completions.push((name.to_string(), t.name(), "field"));
+                }
+                for statement in self.statements.iter() {
+                    if let Some(name) = Ide::declared_name(statement).and_then(|name| name.strip_prefix(&format!("{}.", struct_name)).map(str::to_string)) {
+                        let signature: String = self.describe(statement).map(|(signature, _)| signature).unwrap_or_default();
+                        completions.push((name, signature, "method"));
+                    }
+                }
+            } else {
+                let variants: Vec<(Symbol, Symbol)> = match dot.checked_sub(1).and_then(|index| self.declaration(&self.tokens[index].value)).map(Ide::inner) {
+                    Some(Statement::Enum(name, _, variants, _)) => variants.iter().map(|(variant, _, _)| (*name, *variant)).collect(),
+                    Some(Statement::StructEnum(name, variants, _)) => variants.iter().map(|(variant, _)| (*name, *variant)).collect(),
+                    Some(Statement::JavaEnum(name, _, variants, _)) => variants.iter().map(|(variant, _)| (*name, *variant)).collect(),
+                    _ => vec![],
+                };
+                for (name, variant) in variants {
+                    completions.push((variant.to_string(), name.to_string(), "variant"));
+                }
+            }
+            return completions;
+        }
+        for (name, t) in self.locals(offset) {
+            completions.push((name.to_string(), t.name(), "variable"));
+        }
+        for statement in self.statements.iter() {
+            let Some(name) = Ide::declared_name(statement).filter(|name| !name.contains('.')) else {
+                continue;
+            };
+            let kind: &'static str = match Ide::inner(statement) {
+                Statement::Function(_, _, _, _, _) => "function",
+                Statement::Struct(_, _, _) => "struct",
+                Statement::Enum(_, _, _, _) | Statement::StructEnum(_, _, _) | Statement::JavaEnum(_, _, _, _) => "enum",
+                Statement::TypeAlias(_, _, _) => "type",
+                Statement::Constant(_, _, _, _) => "constant",
+                Statement::Annotation(_, _, _) => "annotation",
+                _ => "variable",
+            };
+            let signature: String = self.describe(statement).map(|(signature, _)| signature).unwrap_or_default();
+            completions.push((name, signature, kind));
+        }
+        for keyword in Ide::KEYWORDS.iter() {
+            completions.push((keyword.to_string(), String::new(), "keyword"));
+        }
+        completions
+    }
+    // The call whose parentheses an offset is in, on the same line, and its
+    // parameters. A method called on a value takes its `self` from it, making
+    // a struct takes its constructor's parameters or else its fields, and a
+    // value of a function type has parameters without names.
+    pub fn signature_help(&self, offset: usize) -> Option<SignatureHelp> {
+        let before: usize = self.tokens.iter().take_while(|token| token.location.end <= offset).count();
+        // The `(` still open at the offset, and where the argument at it starts
+        let mut depth: usize = 0;
+        let mut commas: usize = 0;
+        let mut argument: usize = before;
+        let mut open: Option<usize> = None;
+        for index in (0..before).rev() {
+            match self.tokens[index].kind {
+                TokenKind::Newline => return None,
+                TokenKind::CloseParen | TokenKind::CloseBracket => depth += 1,
+                TokenKind::OpenParen | TokenKind::OpenBracket if depth > 0 => depth -= 1,
+                TokenKind::OpenParen => {
+                    open = Some(index);
+                    break;
+                }
+                TokenKind::OpenBracket => return None,
+                TokenKind::Comma if depth == 0 => {
+                    if commas == 0 {
+                        argument = index + 1;
+                    }
+                    commas += 1;
+                }
+                _ => {}
+            }
+        }
+        let open: usize = open?;
+        if commas == 0 {
+            argument = open + 1;
+        }
+        let callee: &Token = self.tokens.get(open.checked_sub(1)?).filter(|token| token.kind == TokenKind::Identifier)?;
+        let previous = |back: usize| open.checked_sub(back).and_then(|index| self.tokens.get(index));
+        let owner: Option<&Statement> = previous(3).filter(|_| previous(2).is_some_and(|dot| dot.kind == TokenKind::Dot))
+            .and_then(|owner| self.declaration(&owner.value)).map(Ide::inner);
+        let describe = |statement: &Statement| self.describe(statement).unwrap_or_default();
+        let new: bool = previous(2).is_some_and(|token| token.kind == TokenKind::New);
+        let local: Option<Type> = self.checker.references.get(&callee.location).map(|(_, t)| t.clone())
+            .or_else(|| self.locals(offset).into_iter().rev().find(|(name, _)| *name == callee.value).map(|(_, t)| t));
+        let (label, docs, parameters): (String, String, Vec<(Symbol, Type)>) = if new || (owner.is_none() && local.is_none() && self.checker.structs.contains_key(&Symbol::intern(&callee.value))) {
+            let fields: &Vec<(Symbol, Type)> = self.checker.structs.get(&Symbol::intern(&callee.value))?;
+            // A struct with a constructor is made with its parameters. One that's
+            // written out names them, and the one made for it sets the fields
+            // in order.
+            let written: Option<&Signature> = self.checker.functions.get(&Symbol::intern(&format!("{}.constructor", callee.value)));
+            let parameters: Vec<(Symbol, Type)> = match (fields.iter().find(|(field, _)| field == "constructor"), written) {
+                (Some(_), Some((parameters, _))) => parameters.clone(),
+                (Some((_, Type::Function(parameters, _, _))), None) => parameters.iter().zip(fields.iter()).map(|(t, (field, _))| (*field, t.clone())).collect(),
+                _ => fields.iter().filter(|(field, _)| field != "constructor").cloned().collect(),
+            };
+            let (_, docs): (String, String) = self.declaration(&callee.value).map(describe).unwrap_or_default();
+            (format!("{}{}({})", if new { "new " } else { "" }, callee.value, Ide::parameter_list(&parameters)), docs, parameters)
+        } else if let Some(Statement::StructEnum(name, variants, _)) = owner {
+            let (_, fields): &(Symbol, Vec<(Symbol, Type)>) = variants.iter().find(|(variant, _)| *variant == callee.value)?;
+            (format!("{}.{}({})", name, callee.value, Ide::parameter_list(fields)), String::new(), fields.clone())
+        } else if let Some(Statement::Struct(name, _, _)) = owner {
+            let function: &Statement = self.declaration(&format!("{}.{}", name, callee.value))?;
+            let (label, docs): (String, String) = describe(function);
+            (label, docs, self.checker.functions.get(&Symbol::intern(&format!("{}.{}", name, callee.value)))?.0.clone())
+        } else if let Some(struct_name) = self.receiver(&self.tokens, open - 1) {
+            let name: String = format!("{}.{}", struct_name, callee.value);
+            match self.declaration(&name) {
+                Some(method) => {
+                    let (label, docs): (String, String) = describe(method);
+                    (label, docs, self.checker.functions.get(&Symbol::intern(&name))?.0.iter().skip(1).cloned().collect())
+                }
+                None => {
+                    let (label, parameters): (String, Vec<(Symbol, Type)>) = Ide::unnamed(self.field(&struct_name, &callee.value)?)?;
+                    (label, String::new(), parameters)
+                }
+            }
+        } else if let Some(t) = local {
+            let (label, parameters): (String, Vec<(Symbol, Type)>) = Ide::unnamed(t)?;
+            (label, String::new(), parameters)
+        } else {
+            let (label, docs): (String, String) = self.declaration(&callee.value).map(describe).unwrap_or_default();
+            let (parameters, return_type): &Signature = self.checker.functions.get(&Symbol::intern(&callee.value))?;
+            let label: String = if label.is_empty() { format!("func {}({}): {}", callee.value, Ide::parameter_list(parameters), return_type.name()) } else { label };
+            (label, docs, parameters.clone())
+        };
+        // A named argument is for the parameter it names
+        let active: Option<usize> = match (self.tokens.get(argument), self.tokens.get(argument + 1)) {
+            (Some(name), Some(colon)) if name.kind == TokenKind::Identifier && colon.kind == TokenKind::Colon && colon.location.end <= offset => {
+                parameters.iter().position(|(parameter, _)| *parameter == name.value)
+            }
+            _ => Some(commas).filter(|commas| *commas < parameters.len()),
+        };
+        Some(SignatureHelp { label, docs, parameters, active })
+    }
+    // A value of a function type, whose parameters have no names
+    pub fn unnamed(t: Type) -> Option<(String, Vec<(Symbol, Type)>)> {
+        let mut t: Type = t;
+        while let Type::Pointer(inner, _) | Type::Const(inner, _) = t {
+            t = *inner;
+        }
+        let Type::Function(parameters, _, _) = &t else {
+            return None;
+        };
+        Some((t.name(), parameters.iter().map(|t| (Symbol::default(), t.clone())).collect()))
+    }
+    // How parameters or fields are written in a signature, as in `a: int, b: int`
+    pub fn parameter_list(parameters: &[(Symbol, Type)]) -> String {
+        parameters.iter().map(|(name, t)| Ide::parameter(name, t)).collect::<Vec<String>>().join(", ")
+    }
+    pub fn parameter(name: &Symbol, t: &Type) -> String {
+        if name.is_empty() { t.name() } else { format!("{}: {}", name, t.name()) }
+    }
+    // The file's own declarations as an outline: structs with their fields,
+    // enums with their variants, and functions, methods as `Struct.method`,
+    // constants and the rest
+    pub fn outline(&self) -> Vec<Outline> {
+        let mut outline: Vec<Outline> = vec![];
+        for statement in self.statements.iter().filter(|statement| self.sources.file(&statement.location()).start == 0) {
+            let Some(name) = Ide::declared_name(statement) else {
+                continue;
+            };
+            let location: TokenLocation = statement.location();
+            let detail: String = self.describe(statement).map(|(signature, _)| signature).unwrap_or_default();
+            let range: TokenLocation = self.extent(&location);
+            let item = |kind: &'static str, children: Vec<Outline>| Outline { name: name.clone(), detail: detail.clone(), kind, range: range.clone(), selection: location.clone(), children };
+            match Ide::inner(statement) {
+                Statement::Struct(_, fields, _) => {
+                    let children: Vec<Outline> = fields.iter().map(|(field, t)| self.member(field, t.name(), "field", &range, Some(&t.location()))).collect();
+                    outline.push(item("struct", children));
+                }
+                Statement::Enum(_, _, variants, _) => {
+                    let children: Vec<Outline> = variants.iter().map(|(variant, _, location)| {
+                        let range: TokenLocation = self.extent(location);
+                        Outline { name: variant.to_string(), detail: String::new(), kind: "variant", range, selection: location.clone(), children: vec![] }
+                    }).collect();
+                    outline.push(item("enum", children));
+                }
+                Statement::StructEnum(_, variants, _) => {
+                    let children: Vec<Outline> = variants.iter().map(|(variant, fields)| {
+                        let mut member: Outline = self.member(variant, String::new(), "variant", &range, None);
+                        member.children = fields.iter().map(|(field, t)| self.member(field, t.name(), "field", &range, Some(&t.location()))).collect();
+                        member
+                    }).collect();
+                    outline.push(item("enum", children));
+                }
+                Statement::JavaEnum(_, fields, variants, _) => {
+                    let mut children: Vec<Outline> = fields.iter().map(|(field, t)| self.member(field, t.name(), "field", &range, Some(&t.location()))).collect();
+                    children.extend(variants.iter().map(|(variant, _)| self.member(variant, String::new(), "variant", &range, None)));
+                    outline.push(item("enum", children));
+                }
+                Statement::StructFunction(_, _, _, _, _, _) => outline.push(item("method", vec![])),
+                Statement::Function(_, _, _, _, _) => outline.push(item("function", vec![])),
+                Statement::TypeAlias(_, _, _) => outline.push(item("type", vec![])),
+                Statement::Constant(_, _, _, _) => outline.push(item("constant", vec![])),
+                Statement::Annotation(_, _, _) => outline.push(item("annotation", vec![])),
+                _ => outline.push(item("variable", vec![])),
+            }
+        }
+        outline
+    }
+    // A field or variant of a declaration, found by its name within the
+    // declaration's range, or just before its type for a field
+    pub fn member(&self, name: &Symbol, detail: String, kind: &'static str, range: &TokenLocation, t: Option<&TokenLocation>) -> Outline {
+        let tokens: Vec<&Token> = self.tokens.iter().filter(|token| range.start <= token.location.start && token.location.end <= range.end).collect();
+        let selection: TokenLocation = match t.and_then(|t| tokens.iter().rposition(|token| token.location.end <= t.start && token.value == name.as_str())) {
+            Some(index) => tokens[index].location.clone(),
+            None => tokens.iter().skip(1).find(|token| token.kind == TokenKind::Identifier && token.value == name.as_str())
+                .map_or(range.clone(), |token| token.location.clone()),
+        };
+        Outline { name: name.to_string(), detail, kind, range: self.extent(&selection), selection, children: vec![] }
+    }
+    // All of the declaration whose name is at a location: from its line, or
+    // the annotations before it, to the `end` that closes it or the end of the line
+    pub fn extent(&self, location: &TokenLocation) -> TokenLocation {
+        let lines: Vec<&[Token]> = self.tokens.split(|token| token.kind == TokenKind::Newline).filter(|line| !line.is_empty()).collect();
+        let Some(mut first) = lines.iter().position(|line| line.last().is_some_and(|token| location.start < token.location.end.max(token.location.start + 1))) else {
+            return location.clone();
+        };
+        let mut last: usize = first;
+        if Formatter::opens_block(lines[first]) {
+            let mut depth: usize = 0;
+            for (index, line) in lines.iter().enumerate().skip(first) {
+                if Formatter::opens_block(line) {
+                    depth += 1;
+                } else if line[0].kind == TokenKind::End {
+                    depth -= 1;
+                }
+                last = index;
+                if depth == 0 {
+                    break;
+                }
+            }
+        }
+        while first > 0 && lines[first - 1][0].kind == TokenKind::At {
+            first -= 1;
+        }
+        TokenLocation { start: lines[first][0].location.start, end: lines[last].last().map_or(location.end, |token| token.location.end) }
+    }
+    // What each token of the file is, for highlighting, as one of
+    // TOKEN_TYPES. Punctuation, and names nothing is known about, are left out.
+    pub fn semantic_tokens(&self) -> Vec<(TokenLocation, &'static str)> {
+        let tokens: Vec<Token> = Lexer::with_comments(self.contents.clone()).lex();
+        // A parameter's uses point at its type, where the checker declared it
+        let mut parameters: HashSet<TokenLocation> = HashSet::new();
+        for statement in self.statements.iter() {
+            if let Statement::Function(_, args, _, _, _) | Statement::StructFunction(_, _, args, _, _, _) = Ide::inner(statement) {
+                parameters.extend(args.iter().map(|(_, t)| t.location()));
+            }
+        }
+        let mut classified: Vec<(TokenLocation, &'static str)> = vec![];
+        // How deep in the parentheses of a function's parameters
+        let mut header_depth: usize = 0;
+        let mut in_header: bool = false;
+        for (index, token) in tokens.iter().enumerate() {
+            let previous: Option<&TokenKind> = index.checked_sub(1).map(|index| &tokens[index].kind);
+            let next: Option<&TokenKind> = tokens.get(index + 1).map(|token| &token.kind);
+            match token.kind {
+                TokenKind::Func => in_header = true,
+                TokenKind::OpenParen if in_header => header_depth += 1,
+                TokenKind::CloseParen if in_header => {
+                    header_depth = header_depth.saturating_sub(1);
+                    in_header = header_depth > 0;
+                }
+                _ => {}
+            }
+            let kind: Option<&'static str> = match token.kind {
+                TokenKind::Int | TokenKind::Usize | TokenKind::String | TokenKind::CString | TokenKind::Char | TokenKind::Bool | TokenKind::Void => Some("type"),
+                TokenKind::Annotation | TokenKind::Struct | TokenKind::End | TokenKind::Enum | TokenKind::External | TokenKind::Inline | TokenKind::Func
+                | TokenKind::Type | TokenKind::Var | TokenKind::Return | TokenKind::Import | TokenKind::As | TokenKind::UnsafeCast | TokenKind::SizeOf
+                | TokenKind::New | TokenKind::True | TokenKind::False | TokenKind::Null | TokenKind::If | TokenKind::Else | TokenKind::While | TokenKind::For
+                | TokenKind::In | TokenKind::Switch | TokenKind::Case | TokenKind::Break | TokenKind::Continue | TokenKind::Default | TokenKind::Volatile
+                | TokenKind::Const | TokenKind::Restrict => Some("keyword"),
+                TokenKind::NumberLit => Some("number"),
+                TokenKind::StringLit | TokenKind::CharLit => Some("string"),
+                TokenKind::Comment => Some("comment"),
+                TokenKind::Equal | TokenKind::EqualEqual | TokenKind::Bang | TokenKind::BangEqual | TokenKind::Greater | TokenKind::GreaterEqual
+                | TokenKind::Less | TokenKind::LessEqual | TokenKind::Plus | TokenKind::PlusEqual | TokenKind::Minus | TokenKind::MinusEqual
+                | TokenKind::Star | TokenKind::StarEqual | TokenKind::Slash | TokenKind::SlashEqual | TokenKind::Percent | TokenKind::PercentEqual
+                | TokenKind::Ampersand | TokenKind::Pipe | TokenKind::FatArrow | TokenKind::Range => Some("operator"),
+                TokenKind::Identifier => self.classify(&tokens, index, &parameters).or(match (previous, next) {
+                    (Some(TokenKind::At), _) => Some("decorator"),
+                    (_, Some(TokenKind::Colon)) if header_depth > 0 => Some("parameter"),
+                    // A struct's field
+                    (Some(TokenKind::Newline) | None, Some(TokenKind::Colon)) => Some("property"),
+                    (_, Some(TokenKind::OpenParen)) => Some("function"),
+                    _ => None,
+                }),
+                _ => None,
+            };
+            if let Some(kind) = kind {
+                classified.push((token.location.clone(), kind));
+            }
+        }
+        classified
+    }
+    // What the checker or the program's declarations say a name is
+    pub fn classify(&self, tokens: &[Token], index: usize, parameters: &HashSet<TokenLocation>) -> Option<&'static str> {
+        let token: &Token = &tokens[index];
+        if let Some((declared, _)) = self.checker.references.get(&token.location) {
+            return Some(if parameters.contains(declared) { "parameter" } else { "variable" });
+        }
+        let previous: Option<&Token> = index.checked_sub(1).map(|index| &tokens[index]);
+        if previous.is_some_and(|previous| previous.kind == TokenKind::Dot) {
+            // The Ide's own tokens have no comments, so the member is found by where it is
+            let member: usize = self.tokens.iter().position(|other| other.location == token.location)?;
+            if let Some(struct_name) = self.receiver(&self.tokens, member) {
+                return Some(if self.declaration(&format!("{}.{}", struct_name, token.value)).is_some() { "method" } else { "property" });
+            }
+            let owner: &Token = &tokens[index.checked_sub(2)?];
+            return match self.declaration(&owner.value).map(Ide::inner) {
+                Some(Statement::Enum(_, _, _, _) | Statement::StructEnum(_, _, _) | Statement::JavaEnum(_, _, _, _)) => Some("enumMember"),
+                // The function's name in `func Struct.function`
+                Some(Statement::Struct(_, _, _)) => Some("method"),
+                _ => None,
+            };
+        }
+        match self.declaration(&token.value).map(Ide::inner)? {
+            Statement::Function(_, _, _, _, _) => Some("function"),
+            Statement::Struct(_, _, _) => Some("struct"),
+            Statement::Enum(_, _, _, _) | Statement::StructEnum(_, _, _) | Statement::JavaEnum(_, _, _, _) => Some("enum"),
+            Statement::TypeAlias(_, _, _) => Some("type"),
+            Statement::Annotation(_, _, _) => Some("decorator"),
+            Statement::Constant(_, _, _, _) | Statement::Variable(_, _, _, _) => Some("variable"),
+            _ => None,
+        }
+    }
+    // The identifier an offset is in or just after
+    pub fn identifier(&self, offset: usize) -> Option<usize> {
+        self.tokens.iter().position(|token| token.kind == TokenKind::Identifier && token.location.start <= offset && offset <= token.location.end)
+    }
+    // The struct whose member the identifier at `index` is, when it follows a `.`
+    pub fn receiver(&self, tokens: &[Token], index: usize) -> Option<Symbol> {
+        let dot: &Token = tokens.get(index.checked_sub(1)?)?;
+        if dot.kind != TokenKind::Dot {
+            return None;
+        }
+        // A member that didn't parse has no type recorded, but what's before it may
+        let t: Type = match self.checker.member_types.get(&dot.location) {
+            Some(t) => t.clone(),
+            None => self.checker.references.get(&tokens.get(index.checked_sub(2)?)?.location)?.1.clone(),
+        };
+        let mut t: &Type = &t;
+        while let Type::Pointer(inner, _) | Type::Const(inner, _) | Type::Volatile(inner, _) | Type::Restrict(inner, _) = t {
+            t = inner;
+        }
+        match t {
+            Type::Unknown(name, _) | Type::Struct(name, _) if self.checker.structs.contains_key(name) => Some(*name),
+            _ => None,
+        }
+    }
+    // Where `method` is in `func Struct.method`, which the method's statement
+    // is at the start of
+    pub fn method_name(&self, method: &Statement) -> TokenLocation {
+        let location: TokenLocation = method.location();
+        let file: &SourceFile = self.sources.file(&location);
+        let lexed: Vec<Token>;
+        let tokens: &[Token] = if file.start == 0 {
+            &self.tokens
+        } else {
+            lexed = Lexer::new(file.contents.clone()).lex();
+            &lexed
+        };
+        tokens.iter().position(|token| token.location.start + file.start == location.start)
+            .and_then(|index| tokens.get(index + 2))
+            .map_or(location, |name| TokenLocation { start: name.location.start + file.start, end: name.location.end + file.start })
+    }
+    pub fn field(&self, struct_name: &Symbol, name: &str) -> Option<Type> {
+        self.checker.structs.get(struct_name)?.iter().find(|(field, _)| field == name).map(|(_, t)| t.clone())
+    }
+    // The parameters and variables of the function an offset is in that are
+    // declared before it
+    pub fn locals(&self, offset: usize) -> Vec<(Symbol, Type)> {
+        let function: Option<&Statement> = self.statements.iter()
+            .filter(|statement| statement.location().start <= offset && self.sources.file(&statement.location()).start == 0)
+            .max_by_key(|statement| statement.location().start);
+        let (args, body): (&[(Symbol, Type)], &[Statement]) = match function.map(Ide::inner) {
+            Some(Statement::Function(_, args, _, body, _)) | Some(Statement::StructFunction(_, _, args, _, body, _)) => (args, body),
+            _ => return vec![],
+        };
+        let mut locals: Locals = Locals { offset, ide: self, locals: args.to_vec() };
+        walk_statements(&mut locals, body);
+        locals.locals
+    }
+    // The top-level declaration of a name, which is `Struct.function` for a struct's
+    pub fn declaration(&self, name: &str) -> Option<&Statement> {
+        self.statements.iter().find(|statement| Ide::declared_name(statement).as_deref() == Some(name))
+    }
+    pub fn declared_name(statement: &Statement) -> Option<String> {
+        match Ide::inner(statement) {
+            Statement::Function(name, _, _, _, _) | Statement::Struct(name, _, _) | Statement::Enum(name, _, _, _)
+            | Statement::StructEnum(name, _, _) | Statement::JavaEnum(name, _, _, _) | Statement::TypeAlias(name, _, _)
+            | Statement::Constant(name, _, _, _) | Statement::Variable(name, _, _, _) | Statement::Annotation(name, _, _) => Some(name.to_string()),
+            Statement::StructFunction(struct_name, name, _, _, _, _) => Some(format!("{}.{}", struct_name, name)),
+            _ => None,
+        }
+    }
+    // A declaration without its annotations, `external` or type parameters
+    pub fn inner(statement: &Statement) -> &Statement {
+        match statement {
+            Statement::Annotated(inner, _, _) | Statement::External(inner, _) | Statement::Inline(inner, _) | Statement::Generic(inner, _, _) => Ide::inner(inner),
+            statement => statement,
+        }
+    }
+    // A top-level declaration as `sl doc` shows it, and its comment
+    pub fn describe(&self, statement: &Statement) -> Option<(String, String)> {
+        let declaration: &Statement = match statement {
+            Statement::Annotated(inner, _, _) => inner,
+            statement => statement,
+        };
+        let signature: String = match (DocItem::declaration(declaration, ""), Ide::inner(statement)) {
+            (Some(item), _) => item.signature,
+            (None, Statement::Variable(name, t, _, location)) => {
+                let t: Type = self.checker.references.get(location).map_or(t.clone(), |(_, t)| t.clone());
+                format!("var {}: {}", name, t.name())
+            }
+            _ => return None,
+        };
+        Some((signature, DocItem::comment(&self.sources, &statement.location())))
+    }
+    // The syntax tree as JSON, each node an object with its variant as `type`
+    pub fn jsonify(&mut self) -> String {
+        self.json = self.jsonify_body(&self.statements).render();
+        self.json.clone()
+    }
+    pub fn jsonify_statement(&self, statement: &Statement) -> Json {
+        match statement {
+            Statement::Generic(statement, parameters, location) => {
+                let parameters: Vec<Json> = parameters.iter().map(|(name, bound)| Json::Object(vec![
+                    ("name".to_string(), self.jsonify_name(name)),
+                    ("bound".to_string(), bound.as_ref().map_or(Json::Null, |t| self.jsonify_type(t))),
+                ])).collect();
+                self.node("Generic", vec![("parameters", Json::Array(parameters)), ("statement", self.jsonify_statement(statement))], location)
+            }
+            Statement::Annotated(statement, annotations, location) => {
+                let annotations: Vec<Json> = annotations.iter().map(|annotation| self.jsonify_annotation(annotation)).collect();
+                self.node("Annotated", vec![("annotations", Json::Array(annotations)), ("statement", self.jsonify_statement(statement))], location)
+            }
+            Statement::Annotation(name, parameters, location) => {
+                self.node("Annotation", vec![("name", self.jsonify_name(name)), ("parameters", self.jsonify_fields(parameters))], location)
+            }
+            Statement::Struct(name, fields, location) => {
+                self.node("Struct", vec![("name", self.jsonify_name(name)), ("fields", self.jsonify_fields(fields))], location)
+            }
+            Statement::Enum(name, value_type, values, location) => {
+                let values: Vec<Json> = values.iter().map(|(value, expression, location)| Json::Object(vec![
+                    ("name".to_string(), self.jsonify_name(value)),
+                    ("value".to_string(), self.jsonify_expression(expression)),
+                    ("location".to_string(), self.jsonify_location(location)),
+                ])).collect();
+                self.node("Enum", vec![("name", self.jsonify_name(name)), ("value_type", self.jsonify_type(value_type)), ("values", Json::Array(values))], location)
+            }
+            Statement::StructEnum(name, variants, location) => {
+                let variants: Vec<Json> = variants.iter().map(|(variant, fields)| Json::Object(vec![
+                    ("name".to_string(), self.jsonify_name(variant)),
+                    ("fields".to_string(), self.jsonify_fields(fields)),
+                ])).collect();
+                self.node("StructEnum", vec![("name", self.jsonify_name(name)), ("variants", Json::Array(variants))], location)
+            }
+            Statement::JavaEnum(name, fields, variants, location) => {
+                let variants: Vec<Json> = variants.iter().map(|(variant, arguments)| Json::Object(vec![
+                    ("name".to_string(), self.jsonify_name(variant)),
+                    ("arguments".to_string(), self.jsonify_expressions(arguments)),
+                ])).collect();
+                self.node("JavaEnum", vec![("name", self.jsonify_name(name)), ("fields", self.jsonify_fields(fields)), ("variants", Json::Array(variants))], location)
+            }
+            Statement::TypeAlias(name, types, location) => {
+                self.node("TypeAlias", vec![("name", self.jsonify_name(name)), ("types", self.jsonify_types(types))], location)
+            }
+            Statement::Function(name, parameters, return_type, body, location) => self.node("Function", vec![
+                ("name", self.jsonify_name(name)),
+                ("parameters", self.jsonify_fields(parameters)),
+                ("return_type", self.jsonify_type(return_type)),
+                ("body", self.jsonify_body(body)),
+            ], location),
+            Statement::StructFunction(struct_name, name, parameters, return_type, body, location) => self.node("StructFunction", vec![
+                ("struct", self.jsonify_name(struct_name)),
+                ("name", self.jsonify_name(name)),
+                ("parameters", self.jsonify_fields(parameters)),
+                ("return_type", self.jsonify_type(return_type)),
+                ("body", self.jsonify_body(body)),
+            ], location),
+            Statement::Variable(name, value_type, value, location) => self.node("Variable", vec![
+                ("name", self.jsonify_name(name)),
+                ("value_type", self.jsonify_type(value_type)),
+                ("value", self.jsonify_expression(value)),
+            ], location),
+            Statement::Constant(name, value_type, value, location) => self.node("Constant", vec![
+                ("name", self.jsonify_name(name)),
+                ("value_type", self.jsonify_type(value_type)),
+                ("value", self.jsonify_expression(value)),
+            ], location),
+            Statement::Return(value, location) => self.node("Return", vec![("value", self.jsonify_expression(value))], location),
+            Statement::While(condition, body, location) => {
+                self.node("While", vec![("condition", self.jsonify_expression(condition)), ("body", self.jsonify_body(body))], location)
+            }
+            Statement::For(name, iterable, body, location) => self.node("For", vec![
+                ("name", self.jsonify_name(name)),
+                ("iterable", self.jsonify_expression(iterable)),
+                ("body", self.jsonify_body(body)),
+            ], location),
+            Statement::Break(location) => self.node("Break", vec![], location),
+            Statement::Continue(location) => self.node("Continue", vec![], location),
+            Statement::If(condition, body, else_body, location) => self.node("If", vec![
+                ("condition", self.jsonify_expression(condition)),
+                ("body", self.jsonify_body(body)),
+                ("else_body", self.jsonify_body(else_body)),
+            ], location),
+            Statement::External(statement, location) => self.node("External", vec![("statement", self.jsonify_statement(statement))], location),
+            Statement::Inline(statement, location) => self.node("Inline", vec![("statement", self.jsonify_statement(statement))], location),
+            Statement::Import(path, location) => self.node("Import", vec![("path", Json::String(path.clone()))], location),
+            Statement::Expression(expression, location) => self.node("Expression", vec![("expression", self.jsonify_expression(expression))], location),
+        }
+    }
+    pub fn jsonify_expression(&self, expression: &Expression) -> Json {
+        match expression {
+            Expression::Number(value, location) => self.node("Number", vec![("value", Json::Number(*value as f64))], location),
+            Expression::String(value, location) => self.node("String", vec![("value", Json::String(value.clone()))], location),
+            Expression::Char(value, location) => self.node("Char", vec![("value", Json::String(value.clone()))], location),
+            Expression::Boolean(value, location) => self.node("Boolean", vec![("value", Json::Bool(*value))], location),
+            Expression::Identifier(name, location) => self.node("Identifier", vec![("name", self.jsonify_name(name))], location),
+            Expression::Null => Json::Object(vec![("type".to_string(), Json::String("Null".to_string()))]),
+            Expression::Call(name, arguments, location) => {
+                self.node("Call", vec![("name", self.jsonify_name(name)), ("arguments", self.jsonify_expressions(arguments))], location)
+            }
+            Expression::GenericCall(name, types, arguments, location) => self.node("GenericCall", vec![
+                ("name", self.jsonify_name(name)),
+                ("types", self.jsonify_types(types)),
+                ("arguments", self.jsonify_expressions(arguments)),
+            ], location),
+            Expression::Member(value, member, location) => {
+                self.node("Member", vec![("value", self.jsonify_expression(value)), ("member", self.jsonify_expression(member))], location)
+            }
+            Expression::NamedArgument(name, value, location) => {
+                self.node("NamedArgument", vec![("name", self.jsonify_name(name)), ("value", self.jsonify_expression(value))], location)
+            }
+            Expression::Cast(value, t, location) => self.node("Cast", vec![("value", self.jsonify_expression(value)), ("to", self.jsonify_type(t))], location),
+            Expression::UnsafeCast(value, t, location) => {
+                self.node("UnsafeCast", vec![("value", self.jsonify_expression(value)), ("to", self.jsonify_type(t))], location)
+            }
+            Expression::SizeOf(t, location) => self.node("SizeOf", vec![("of", self.jsonify_type(t))], location),
+            Expression::Index(value, index, location) => {
+                self.node("Index", vec![("value", self.jsonify_expression(value)), ("index", self.jsonify_expression(index))], location)
+            }
+            Expression::Array(values, location) => self.node("Array", vec![("values", self.jsonify_expressions(values))], location),
+            Expression::Map(entries, location) => {
+                let entries: Vec<Json> = entries.iter().map(|(key, value)| Json::Object(vec![
+                    ("key".to_string(), self.jsonify_expression(key)),
+                    ("value".to_string(), self.jsonify_expression(value)),
+                ])).collect();
+                self.node("Map", vec![("entries", Json::Array(entries))], location)
+            }
+            Expression::New(name, arguments, location) => {
+                self.node("New", vec![("name", self.jsonify_name(name)), ("arguments", self.jsonify_expressions(arguments))], location)
+            }
+            Expression::Ternary(condition, then, otherwise, location) => self.node("Ternary", vec![
+                ("condition", self.jsonify_expression(condition)),
+                ("then", self.jsonify_expression(then)),
+                ("else", self.jsonify_expression(otherwise)),
+            ], location),
+            Expression::Assignment(target, value, location) => {
+                self.node("Assignment", vec![("target", self.jsonify_expression(target)), ("value", self.jsonify_expression(value))], location)
+            }
+            Expression::Binary(op, left, right, location) => self.node("Binary", vec![
+                ("operator", Json::String(Checker::operator(op).to_string())),
+                ("left", self.jsonify_expression(left)),
+                ("right", self.jsonify_expression(right)),
+            ], location),
+            Expression::Unary(op, value, location) => {
+                self.node("Unary", vec![("operator", Json::String(Checker::operator(op).to_string())), ("value", self.jsonify_expression(value))], location)
+            }
+            Expression::Grouping(value, location) => self.node("Grouping", vec![("value", self.jsonify_expression(value))], location),
+            Expression::AddressOf(value, location) => self.node("AddressOf", vec![("value", self.jsonify_expression(value))], location),
+            Expression::Dereference(value, location) => self.node("Dereference", vec![("value", self.jsonify_expression(value))], location),
+            Expression::Range(start, end, location) => {
+                self.node("Range", vec![("start", self.jsonify_expression(start)), ("end", self.jsonify_expression(end))], location)
+            }
+            Expression::Type(t, location) => self.node("Type", vec![("value", self.jsonify_type(t))], location),
+            // What didn't parse, with the error the parser gave it
+            Expression::Error(error) => self.node("Error", vec![("message", Json::String(error.to_string()))], &error.location()),
+            Expression::Empty => Json::Object(vec![("type".to_string(), Json::String("Empty".to_string()))]),
+        }
+    }
+    pub fn jsonify_type(&self, t: &Type) -> Json {
+        match t {
+            Type::Int(location) => self.node("Int", vec![], location),
+            Type::Usize(location) => self.node("Usize", vec![], location),
+            Type::String(location) => self.node("String", vec![], location),
+            Type::CString(location) => self.node("CString", vec![], location),
+            Type::Char(location) => self.node("Char", vec![], location),
+            Type::Bool(location) => self.node("Bool", vec![], location),
+            Type::Void(location) => self.node("Void", vec![], location),
+            Type::Struct(name, location) => self.node("Struct", vec![("name", self.jsonify_name(name))], location),
+            Type::Enum(name, location) => self.node("Enum", vec![("name", self.jsonify_name(name))], location),
+            Type::Function(parameters, return_type, location) => {
+                self.node("Function", vec![("parameters", self.jsonify_types(parameters)), ("return_type", self.jsonify_type(return_type))], location)
+            }
+            Type::Pointer(inner, location) => self.node("Pointer", vec![("inner", self.jsonify_type(inner))], location),
+            Type::Array(inner, size, location) => self.node("Array", vec![("inner", self.jsonify_type(inner)), ("size", self.jsonify_expression(size))], location),
+            Type::DynamicArray(inner, location) => self.node("DynamicArray", vec![("inner", self.jsonify_type(inner))], location),
+            Type::Map(key, value, location) => self.node("Map", vec![("key", self.jsonify_type(key)), ("value", self.jsonify_type(value))], location),
+            Type::Volatile(inner, location) => self.node("Volatile", vec![("inner", self.jsonify_type(inner))], location),
+            Type::Const(inner, location) => self.node("Const", vec![("inner", self.jsonify_type(inner))], location),
+            Type::Restrict(inner, location) => self.node("Restrict", vec![("inner", self.jsonify_type(inner))], location),
+            Type::GenericType(name, location) => self.node("GenericType", vec![("name", self.jsonify_name(name))], location),
+            // A name that's resolved later, or `unknown` when it's empty
+            Type::Unknown(name, location) => self.node("Unknown", vec![("name", self.jsonify_name(name))], location),
+            Type::Error(error, location) => self.node("Error", vec![("message", Json::String(error.to_string()))], location),
+        }
+    }
+    pub fn jsonify_annotation(&self, annotation: &Annotation) -> Json {
+        Json::Object(vec![
+            ("name".to_string(), self.jsonify_name(&annotation.name)),
+            ("args".to_string(), self.jsonify_expressions(&annotation.arguments)),
+            ("location".to_string(), self.jsonify_location(&annotation.location)),
+        ])
+    }
+    // A node of the tree: its variant, what it holds and where it is
+    pub fn node(&self, kind: &str, members: Vec<(&str, Json)>, location: &TokenLocation) -> Json {
+        let mut object: Vec<(String, Json)> = vec![("type".to_string(), Json::String(kind.to_string()))];
+        object.extend(members.into_iter().map(|(name, value)| (name.to_string(), value)));
+        object.push(("location".to_string(), self.jsonify_location(location)));
+        Json::Object(object)
+    }
+    pub fn jsonify_name(&self, name: &Symbol) -> Json {
+        Json::String(name.to_string())
+    }
+    pub fn jsonify_body(&self, body: &[Statement]) -> Json {
+        Json::Array(body.iter().map(|statement| self.jsonify_statement(statement)).collect())
+    }
+    pub fn jsonify_expressions(&self, expressions: &[Expression]) -> Json {
+        Json::Array(expressions.iter().map(|expression| self.jsonify_expression(expression)).collect())
+    }
+    pub fn jsonify_types(&self, types: &[Type]) -> Json {
+        Json::Array(types.iter().map(|t| self.jsonify_type(t)).collect())
+    }
+    // Parameters or fields, each as its name and type
+    pub fn jsonify_fields(&self, fields: &[(Symbol, Type)]) -> Json {
+        Json::Array(fields.iter().map(|(name, t)| Json::Object(vec![
+            ("name".to_string(), self.jsonify_name(name)),
+            ("value_type".to_string(), self.jsonify_type(t)),
+        ])).collect())
+    }
+    // Offsets, and the same as 0-based lines and UTF-16 characters for an editor
+    pub fn jsonify_location(&self, location: &TokenLocation) -> Json {
+        let position = |(line, character): (usize, usize)| Json::Object(vec![
+            ("line".to_string(), Json::Number(line as f64)),
+            ("character".to_string(), Json::Number(character as f64)),
+        ]);
+        let (start, end): ((usize, usize), (usize, usize)) = self.range(location);
+        Json::Object(vec![
+            ("start".to_string(), Json::Number(location.start as f64)),
+            ("end".to_string(), Json::Number(location.end as f64)),
+            ("range".to_string(), Json::Object(vec![("start".to_string(), position(start)), ("end".to_string(), position(end))])),
+        ])
+    }
+}
+// The variables and constants a function body declares before an offset,
+// with the type the checker gave each. Those of a block are only in scope
+// within it.
+pub struct Locals<'a> {
+    pub offset: usize,
+    pub ide: &'a Ide,
+    pub locals: Vec<(Symbol, Type)>,
+}
+impl Visitor for Locals<'_> {
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Variable(name, t, _, location) | Statement::Constant(name, t, _, location) if location.start < self.offset => {
+                let t: Type = self.ide.checker.references.get(location).map_or(t.clone(), |(_, t)| t.clone());
+                self.locals.push((*name, t));
+            }
+            Statement::While(_, body, location) if self.within(location) => walk_statements(self, body),
+            Statement::For(name, _, body, location) if self.within(location) => {
+                if let Some((_, t)) = self.ide.checker.references.get(location) {
+                    self.locals.push((*name, t.clone()));
+                }
+                walk_statements(self, body);
+            }
+            Statement::If(_, body, else_body, location) if self.within(location) => {
+                // The `else` starts a block of its own
+                match else_body.first() {
+                    Some(first) if self.ide.extent(&first.location()).start <= self.offset => walk_statements(self, else_body),
+                    _ => walk_statements(self, body),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+impl Locals<'_> {
+    // Whether the offset is inside the block a statement opens
+    pub fn within(&self, location: &TokenLocation) -> bool {
+        let extent: TokenLocation = self.ide.extent(location);
+        extent.start <= self.offset && self.offset <= extent.end
+    }
+}
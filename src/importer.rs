@@ -0,0 +1,191 @@
+use crate::ast::Statement;
+use crate::diagnostics::{closest, Diagnostic, SourceMap};
+use crate::lexer::{Lexer, TokenLocation};
+use crate::parser::Parser;
+// One .sl file of the program, with its `import "x.sl"` statements taken out and
+// recorded as edges to the modules they name
+#[derive(Debug, Clone)] pub struct Module {
+    // As given to the source map, which is where its generated files go
+    pub name: String,
+    pub path: std::path::PathBuf,
+    pub imports: Vec<usize>,
+    pub statements: Vec<Statement>,
+}
+// The standard library, built into the compiler so it's there wherever the
+// compiler is. `import "std.io"` loads `std/io.sl`.
+pub const STD_MODULES: [(&str, &str); 6] = [
+    ("io", include_str!("../std/io.sl")),
+    ("list", include_str!("../std/list.sl")),
+    ("math", include_str!("../std/math.sl")),
+    ("mem", include_str!("../std/mem.sl")),
+    ("os", include_str!("../std/os.sl")),
+    ("string", include_str!("../std/string.sl")),
+];
+// Whether an import names a module of the program rather than a C header
+pub fn is_module(path: &str) -> bool {
+    path.ends_with(".sl") || path.starts_with("std.")
+}
+// Loads a program and everything it imports into a module graph. Imports are
+// found relative to the importing file, or in the standard library for ones
+// like `std.io`, and each file is loaded once; other imports are C headers
+// that codegen #includes.
+#[derive(Debug, Clone)] pub struct Importer {
+    pub sources: SourceMap,
+    // Modules are only added once everything they import has been, so this is
+    // always in dependency order
+    pub modules: Vec<Module>,
+    // Files whose imports are currently being resolved, outermost first
+    pub stack: Vec<(std::path::PathBuf, String)>,
+    pub errors: Vec<Diagnostic>,
+    // How long lexing and parsing every file took, and the tokens they had
+    pub parsing: std::time::Duration,
+    pub tokens: usize,
+}
+impl Default for Importer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Importer {
+    pub fn new() -> Self {
+        Self {
+            sources: SourceMap::new(),
+            modules: vec![],
+            stack: vec![],
+            errors: vec![],
+            parsing: std::time::Duration::ZERO,
+            tokens: 0,
+        }
+    }
+    // Loads a root file of the program, returning its module index
+    pub fn load(&mut self, filename: &str) -> Result<usize, String> {
+        let contents: String = read_source(filename)?;
+        let path: std::path::PathBuf = std::fs::canonicalize(filename).unwrap_or_else(|_| filename.into());
+        if let Some(index) = self.modules.iter().position(|module| module.path == path) {
+            return Ok(index);
+        }
+        Ok(self.load_source(filename, path, contents))
+    }
+    // Loads a root file that isn't on disk, such as one read from stdin
+    pub fn load_contents(&mut self, filename: &str, contents: String) -> usize {
+        self.load_source(filename, filename.into(), contents)
+    }
+    // The statements of every module, dependencies first, as one program
+    pub fn program(&self) -> Vec<Statement> {
+        let mut statements: Vec<Statement> = vec![];
+        for module in self.modules.iter() {
+            statements.extend(module.statements.iter().cloned());
+        }
+        statements
+    }
+    pub fn load_source(&mut self, filename: &str, path: std::path::PathBuf, contents: String) -> usize {
+        self.stack.push((path.clone(), filename.to_string()));
+        let offset: usize = self.sources.add(filename.to_string(), contents.clone());
+        let mut module: Module = Module { name: filename.to_string(), path, imports: vec![], statements: vec![] };
+        // The file's lexed as it's parsed, but what's parsed only counts when
+        // every token lexed
+        let started: std::time::Instant = std::time::Instant::now();
+        let mut parser: Parser = Parser::new(Lexer::new(contents), offset);
+        let statements: Vec<Statement> = parser.parse();
+        self.parsing += started.elapsed();
+        self.tokens += parser.lexed();
+        for error in parser.lexer.errors.iter() {
+            self.errors.push(error.shifted(offset));
+        }
+        if parser.lexer.errors.is_empty() {
+            if !parser.errors.is_empty() {
+                self.errors.extend(parser.errors);
+                // What did parse is kept for the editor, without following its imports
+                module.statements = statements.into_iter().filter(|statement| !matches!(statement, Statement::Import(path, _) if is_module(path))).collect();
+            } else {
+                let directory: std::path::PathBuf = std::path::Path::new(filename).parent().map(|parent| parent.to_path_buf()).unwrap_or_default();
+                for statement in statements.into_iter() {
+                    match statement {
+                        Statement::Import(path, location) if is_module(&path) => {
+                            let loaded: Option<usize> = match path.strip_prefix("std.") {
+                                Some(name) => self.load_std(name, &location),
+                                None => self.load_import(&directory.join(&path), &path, &location),
+                            };
+                            if let Some(index) = loaded.filter(|index| !module.imports.contains(index)) {
+                                module.imports.push(index);
+                            }
+                        }
+                        statement => module.statements.push(statement),
+                    }
+                }
+            }
+        }
+        self.stack.pop();
+        self.modules.push(module);
+        self.modules.len() - 1
+    }
+    // A module of the standard library goes where it would be as a file next to
+    // the program's root, so its generated files go there too
+    pub fn load_std(&mut self, name: &str, location: &TokenLocation) -> Option<usize> {
+        let Some((_, contents)) = STD_MODULES.iter().find(|(module, _)| *module == name) else {
+            let mut error: Diagnostic = Diagnostic::type_error("E0006", format!("there's no `std.{}` in the standard library", name), location.clone());
+            if let Some(similar) = closest(name, STD_MODULES.iter().map(|(module, _)| *module)) {
+                error = error.with_note(format!("there's a module named `std.{}`", similar));
+            }
+            self.errors.push(error);
+            return None;
+        };
+        let (root, filename): (std::path::PathBuf, String) = self.stack.first().cloned().unwrap_or_default();
+        let file: std::path::PathBuf = std::path::Path::new("std").join(format!("{}.sl", name));
+        let path: std::path::PathBuf = root.parent().unwrap_or(std::path::Path::new("")).join(&file);
+        if self.import_cycle(&path, location) {
+            return None;
+        }
+        if let Some(index) = self.modules.iter().position(|module| module.path == path) {
+            return Some(index);
+        }
+        let filename: std::path::PathBuf = std::path::Path::new(&filename).parent().unwrap_or(std::path::Path::new("")).join(&file);
+        Some(self.load_source(&filename.to_string_lossy(), path, contents.to_string()))
+    }
+    pub fn load_import(&mut self, path: &std::path::Path, name: &str, location: &TokenLocation) -> Option<usize> {
+        let canonical: std::path::PathBuf = match std::fs::canonicalize(path) {
+            Ok(canonical) => canonical,
+            Err(_) => {
+                self.errors.push(Diagnostic::type_error("E0006", format!("cannot find imported file `{}`", name), location.clone()));
+                return None;
+            }
+        };
+        if self.import_cycle(&canonical, location) {
+            return None;
+        }
+        if let Some(index) = self.modules.iter().position(|module| module.path == canonical) {
+            return Some(index);
+        }
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Some(self.load_source(&path.to_string_lossy(), canonical, contents)),
+            Err(error) => {
+                self.errors.push(Diagnostic::type_error("E0007", format!("cannot read imported file `{}`: {}", name, error), location.clone()));
+                None
+            }
+        }
+    }
+    // Reports importing a file that's still being loaded, which means it
+    // imports itself
+    pub fn import_cycle(&mut self, path: &std::path::Path, location: &TokenLocation) -> bool {
+        let Some(index) = self.stack.iter().position(|(file, _)| file == path) else {
+            return false;
+        };
+        let mut chain: Vec<String> = self.stack[index..].iter().map(|(_, name)| name.clone()).collect();
+        chain.push(self.stack[index].1.clone());
+        self.errors.push(Diagnostic::type_error("E0008", format!("import cycle: {}", chain.join(" -> ")), location.clone()));
+        true
+    }
+}
+// Why a source file couldn't be read, said the way its name was given
+pub fn source_error(filename: &str, error: &std::io::Error) -> String {
+    match error.kind() {
+        std::io::ErrorKind::NotFound => format!("`{}` doesn't exist", filename),
+        std::io::ErrorKind::PermissionDenied => format!("no permission to read `{}`", filename),
+        std::io::ErrorKind::IsADirectory => format!("`{}` is a directory, not a file", filename),
+        std::io::ErrorKind::InvalidData => format!("`{}` isn't UTF-8 text", filename),
+        _ => format!("can't read `{}`: {}", filename, error),
+    }
+}
+pub fn read_source(filename: &str) -> Result<String, String> {
+    std::fs::read_to_string(filename).map_err(|error| source_error(filename, &error))
+}
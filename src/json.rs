@@ -0,0 +1,267 @@
+// A string literal in JSON
+pub fn json_string(value: &str) -> String {
+    let mut json: String = String::from("\"");
+    for character in value.chars() {
+        match character {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            character if (character as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", character as u32)),
+            character => json.push(character),
+        }
+    }
+    json.push('"');
+    json
+}
+// A JSON value, as the language server is sent them and `Ide::jsonify`
+// writes the syntax tree
+#[derive(Debug, Clone, PartialEq)] pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+impl Json {
+    pub fn parse(text: &str) -> Option<Json> {
+        let characters: Vec<char> = text.chars().collect();
+        let mut current: usize = 0;
+        let value: Json = Json::value(&characters, &mut current)?;
+        Json::whitespace(&characters, &mut current);
+        (current == characters.len()).then_some(value)
+    }
+    // A member of an object, which is null when it's missing or this isn't an object
+    pub fn get(&self, key: &str) -> &Json {
+        match self {
+            Json::Object(members) => members.iter().find(|(name, _)| name == key).map_or(&Json::Null, |(_, value)| value),
+            _ => &Json::Null,
+        }
+    }
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => Some(value),
+            _ => None,
+        }
+    }
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Number(value) if *value >= 0.0 => Some(*value as usize),
+            _ => None,
+        }
+    }
+    // Back to text, for echoing a request's id
+    pub fn render(&self) -> String {
+        match self {
+            Json::Null => "null".to_string(),
+            Json::Bool(value) => value.to_string(),
+            Json::Number(value) => value.to_string(),
+            Json::String(value) => json_string(value),
+            Json::Array(values) => format!("[{}]", values.iter().map(Json::render).collect::<Vec<String>>().join(",")),
+            Json::Object(members) => format!("{{{}}}", members.iter().map(|(name, value)| format!("{}:{}", json_string(name), value.render())).collect::<Vec<String>>().join(",")),
+        }
+    }
+    pub fn whitespace(characters: &[char], current: &mut usize) {
+        while characters.get(*current).is_some_and(|character| character.is_whitespace()) {
+            *current += 1;
+        }
+    }
+    pub fn value(characters: &[char], current: &mut usize) -> Option<Json> {
+        Json::whitespace(characters, current);
+        let rest: String = characters[*current..characters.len().min(*current + 5)].iter().collect();
+        for (word, value) in [("null", Json::Null), ("true", Json::Bool(true)), ("false", Json::Bool(false))] {
+            if rest.starts_with(word) {
+                *current += word.len();
+                return Some(value);
+            }
+        }
+        match characters.get(*current)? {
+            '"' => Json::string(characters, current).map(Json::String),
+            '[' => {
+                *current += 1;
+                let mut values: Vec<Json> = vec![];
+                Json::whitespace(characters, current);
+                if characters.get(*current) == Some(&']') {
+                    *current += 1;
+                    return Some(Json::Array(values));
+                }
+                loop {
+                    values.push(Json::value(characters, current)?);
+                    Json::whitespace(characters, current);
+                    match characters.get(*current)? {
+                        ',' => *current += 1,
+                        ']' => break,
+                        _ => return None,
+                    }
+                }
+                *current += 1;
+                Some(Json::Array(values))
+            }
+            '{' => {
+                *current += 1;
+                let mut members: Vec<(String, Json)> = vec![];
+                Json::whitespace(characters, current);
+                if characters.get(*current) == Some(&'}') {
+                    *current += 1;
+                    return Some(Json::Object(members));
+                }
+                loop {
+                    Json::whitespace(characters, current);
+                    let name: String = Json::string(characters, current)?;
+                    Json::whitespace(characters, current);
+                    if characters.get(*current)? != &':' {
+                        return None;
+                    }
+                    *current += 1;
+                    members.push((name, Json::value(characters, current)?));
+                    Json::whitespace(characters, current);
+                    match characters.get(*current)? {
+                        ',' => *current += 1,
+                        '}' => break,
+                        _ => return None,
+                    }
+                }
+                *current += 1;
+                Some(Json::Object(members))
+            }
+            _ => {
+                let start: usize = *current;
+                while characters.get(*current).is_some_and(|character| matches!(character, '0'..='9' | '-' | '+' | '.' | 'e' | 'E')) {
+                    *current += 1;
+                }
+                characters[start..*current].iter().collect::<String>().parse::<f64>().ok().map(Json::Number)
+            }
+        }
+    }
+    pub fn string(characters: &[char], current: &mut usize) -> Option<String> {
+        if characters.get(*current)? != &'"' {
+            return None;
+        }
+        *current += 1;
+        let mut value: String = String::new();
+        loop {
+            let character: char = *characters.get(*current)?;
+            *current += 1;
+            match character {
+                '"' => return Some(value),
+                '\\' => {
+                    let escaped: char = *characters.get(*current)?;
+                    *current += 1;
+                    match escaped {
+                        'n' => value.push('\n'),
+                        't' => value.push('\t'),
+                        'r' => value.push('\r'),
+                        'b' => value.push('\u{8}'),
+                        'f' => value.push('\u{c}'),
+                        'u' => {
+                            let mut unit: u32 = Json::hex(characters, current)?;
+                            // A character outside the basic plane comes as a surrogate pair.
+                            // Half of one on its own is read as U+FFFD, as is anything
+                            // else that isn't a character
+                            if (0xd800..0xdc00).contains(&unit) && characters.get(*current) == Some(&'\\') && characters.get(*current + 1) == Some(&'u') {
+                                let mut after: usize = *current + 2;
+                                let low: u32 = Json::hex(characters, &mut after)?;
+                                if (0xdc00..0xe000).contains(&low) {
+                                    *current = after;
+                                    unit = 0x10000 + ((unit - 0xd800) << 10) + (low - 0xdc00);
+                                }
+                            }
+                            value.push(char::from_u32(unit).unwrap_or('\u{fffd}'));
+                        }
+                        escaped => value.push(escaped),
+                    }
+                }
+                character => value.push(character),
+            }
+        }
+    }
+    pub fn hex(characters: &[char], current: &mut usize) -> Option<u32> {
+        let digits: String = characters.get(*current..*current + 4)?.iter().collect();
+        *current += 4;
+        u32::from_str_radix(&digits, 16).ok()
+    }
+    // What follows a gdb/MI record's class, `name=value,...`, as an object.
+    // Lists of `name=value` keep only the values, since the names repeat.
+    pub fn parse_mi(text: &str) -> Option<Json> {
+        let characters: Vec<char> = text.chars().collect();
+        let mut current: usize = 0;
+        let mut members: Vec<(String, Json)> = vec![];
+        while current < characters.len() {
+            members.push(Json::mi_result(&characters, &mut current)?);
+            match characters.get(current) {
+                Some(',') => current += 1,
+                None => break,
+                _ => return None,
+            }
+        }
+        Some(Json::Object(members))
+    }
+    pub fn mi_result(characters: &[char], current: &mut usize) -> Option<(String, Json)> {
+        let start: usize = *current;
+        while characters.get(*current)? != &'=' {
+            *current += 1;
+        }
+        let name: String = characters[start..*current].iter().collect();
+        *current += 1;
+        Some((name, Json::mi_value(characters, current)?))
+    }
+    pub fn mi_value(characters: &[char], current: &mut usize) -> Option<Json> {
+        let (close, tuple): (char, bool) = match characters.get(*current)? {
+            '"' => return Json::mi_string(characters, current).map(Json::String),
+            '{' => ('}', true),
+            '[' => (']', false),
+            _ => return None,
+        };
+        *current += 1;
+        let mut members: Vec<(String, Json)> = vec![];
+        while characters.get(*current)? != &close {
+            // A list holds either values or `name=value` results
+            let member: (String, Json) = match characters.get(*current)? {
+                '"' | '{' | '[' => (String::new(), Json::mi_value(characters, current)?),
+                _ => Json::mi_result(characters, current)?,
+            };
+            members.push(member);
+            if characters.get(*current)? == &',' {
+                *current += 1;
+            }
+        }
+        *current += 1;
+        Some(if tuple { Json::Object(members) } else { Json::Array(members.into_iter().map(|(_, value)| value).collect()) })
+    }
+    // A C string, with its octal escapes
+    pub fn mi_string(characters: &[char], current: &mut usize) -> Option<String> {
+        *current += 1;
+        let mut bytes: Vec<u8> = vec![];
+        loop {
+            let character: char = *characters.get(*current)?;
+            *current += 1;
+            match character {
+                '"' => return Some(String::from_utf8_lossy(&bytes).to_string()),
+                '\\' => {
+                    let escaped: char = *characters.get(*current)?;
+                    *current += 1;
+                    match escaped {
+                        'n' => bytes.push(b'\n'),
+                        't' => bytes.push(b'\t'),
+                        'r' => bytes.push(b'\r'),
+                        '0'..='7' => {
+                            let mut byte: u32 = escaped.to_digit(8)?;
+                            for _ in 0..2 {
+                                match characters.get(*current).and_then(|digit| digit.to_digit(8)) {
+                                    Some(digit) => byte = byte * 8 + digit,
+                                    None => break,
+                                }
+                                *current += 1;
+                            }
+                            bytes.push(byte as u8);
+                        }
+                        escaped => bytes.extend(escaped.to_string().bytes()),
+                    }
+                }
+                character => bytes.extend(character.to_string().bytes()),
+            }
+        }
+    }
+}
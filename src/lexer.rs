@@ -0,0 +1,445 @@
+use crate::diagnostics::Diagnostic;
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)] pub struct TokenLocation {
+    pub start: usize,
+    pub end: usize
+}
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)] pub enum TokenKind {
+    // Literals
+    Identifier,
+    StringLit,
+    CharLit,
+    NumberLit,
+
+    // Keywords
+    Annotation,
+    Struct,
+    End,
+    Enum,
+    External,
+    Inline,
+    Func,
+    Type,
+    Var,
+    Return,
+    Import,
+    As,
+    UnsafeCast,
+    SizeOf,
+    New,
+    True,
+    False,
+    Null,
+    If,
+    Else,
+    While,
+    For,
+    In,
+    Switch,
+    Case,
+    Break,
+    Continue,
+    Default,
+
+    // Types
+    Int,
+    Usize,
+    String,
+    CString,
+    Char,
+    Bool,
+    Void,
+
+    // Type Qualifiers
+    Volatile,
+    Const,
+    Restrict,
+
+    // Operators and Punctuation
+    Colon,
+    Comma,
+    Dot,
+    At,
+    Pipe,
+    Ampersand,
+    OpenParen,
+    CloseParen,
+    OpenBracket,
+    CloseBracket,
+    Equal,
+    EqualEqual,
+    Bang,
+    BangEqual,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+    Plus,
+    PlusEqual,
+    Minus,
+    MinusEqual,
+    Star,
+    StarEqual,
+    Slash,
+    SlashEqual,
+    Percent,
+    PercentEqual,
+    FatArrow,
+    Range, // ..
+
+    // Special
+    Newline,
+    // Only kept by a lexer made with `with_comments`
+    Comment,
+    Error,
+    EndOfFile,
+}
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone)] pub struct Token {
+    pub kind: TokenKind,
+    pub value: String,
+    pub location: TokenLocation,
+}
+impl Token {
+    pub fn location(&self) -> TokenLocation {
+        self.location.clone()
+    }
+}
+#[derive(Debug, Clone)] pub struct Lexer {
+    pub contents: String,
+    // The contents as characters, which `current` and locations count in
+    pub characters: Vec<char>,
+    pub current: usize,
+    pub errors: Vec<Diagnostic>,
+    // Keep comments as tokens, each followed by the end of its line, for `sl fmt`
+    pub comments: bool,
+}
+impl Lexer {
+    pub fn new(contents: String) -> Self {
+        Self {
+            characters: contents.chars().collect(),
+            contents,
+            current: 0,
+            errors: vec![],
+            comments: false,
+        }
+    }
+    pub fn with_comments(contents: String) -> Self {
+        Self { comments: true, ..Lexer::new(contents) }
+    }
+    // Every token at once, for what looks at all of them. The parser takes
+    // them one at a time instead.
+    pub fn lex(&mut self) -> Vec<Token> {
+        self.by_ref().collect()
+    }
+    pub fn current(&mut self) -> char {
+        self.characters.get(self.current).copied().unwrap_or('\0')
+    }
+    pub fn advance(&mut self) {
+        self.current += 1
+    }
+    // The character an escape sequence stands for, with the lexer on the
+    // character after the backslash. Literals hold decoded text, which codegen
+    // escapes again for C.
+    pub fn escape(&mut self) -> Option<char> {
+        match self.current() {
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            'r' => Some('\r'),
+            '0' => Some('\0'),
+            '\'' => Some('\''),
+            '"' => Some('"'),
+            '\\' => Some('\\'),
+            _ => None,
+        }
+    }
+}
+impl Iterator for Lexer {
+    type Item = Token;
+    fn next(&mut self) -> Option<Token> {
+        while self.current < self.characters.len() {
+            match self.current() {
+                '\t' | ' ' | '\r' => self.advance(),
+                '\n' => {
+                    self.advance();
+                    return Some(Token { kind: TokenKind::Newline, value: "\n".to_string(), location: TokenLocation { start: self.current, end: self.current } });
+                }
+                'a'..='z' | 'A'..='Z' | '_' => {
+                    let mut value: String = String::new();
+                    let start: usize = self.current;
+                    while self.current < self.characters.len() && self.current().is_alphanumeric() || self.current() == '_' {
+                        value.push_str(self.current().to_string().as_str());
+                        self.advance();
+                    }
+                    let kind: TokenKind = match value.as_str() {
+                        "annotation" => TokenKind::Annotation,
+                        "struct" => TokenKind::Struct,
+                        "enum" => TokenKind::Enum,
+                        "end" => TokenKind::End,
+                        "external" => TokenKind::External,
+                        "inline" => TokenKind::Inline,
+                        "func" => TokenKind::Func,
+                        "type" => TokenKind::Type,
+                        "var" => TokenKind::Var,
+                        "return" => TokenKind::Return,
+                        "import" => TokenKind::Import,
+                        "as" => TokenKind::As,
+                        "unsafe_cast" => TokenKind::UnsafeCast,
+                        "sizeof" => TokenKind::SizeOf,
+                        "new" => TokenKind::New,
+                        "true" => TokenKind::True,
+                        "false" => TokenKind::False,
+                        "null" => TokenKind::Null,
+                        "if" => TokenKind::If,
+                        "else" => TokenKind::Else,
+                        "while" => TokenKind::While,
+                        "for" => TokenKind::For,
+                        "in" => TokenKind::In,
+                        "switch" => TokenKind::Switch,
+                        "case" => TokenKind::Case,
+                        "break" => TokenKind::Break,
+                        "continue" => TokenKind::Continue,
+                        "default" => TokenKind::Default,
+                        "int" => TokenKind::Int,
+                        "usize" => TokenKind::Usize,
+                        "string" => TokenKind::String,
+                        "cstring" => TokenKind::CString,
+                        "char" => TokenKind::Char,
+                        "bool" => TokenKind::Bool,
+                        "void" => TokenKind::Void,
+                        "volatile" => TokenKind::Volatile,
+                        "const" => TokenKind::Const,
+                        "restrict" => TokenKind::Restrict,
+                        _ => TokenKind::Identifier
+                    };
+                    return Some(Token { kind, value, location: TokenLocation { start, end: self.current } });
+                }
+                '"' => {
+                    let mut value: String = String::new();
+                    let start: usize = self.current;
+                    self.advance();
+                    while self.current < self.characters.len() && self.current() != '"' {
+                        let val: char = self.current();
+                        match val {
+                            '\\' => {
+                                self.advance();
+                                match self.escape() {
+                                    Some(escaped) => value.push(escaped),
+                                    None => self.errors.push(Diagnostic::syntax_error("E0002", "Invalid escape sequence".to_string(), TokenLocation { start: self.current, end: self.current })),
+                                }
+                            }
+                            _ => value.push_str(val.to_string().as_str())
+                        }
+                        self.advance();
+                    }
+                    if self.current < self.characters.len() {
+                        self.advance();
+                    } else {
+                        self.errors.push(Diagnostic::syntax_error("E0039", "this string literal is never closed".to_string(), TokenLocation { start, end: self.current }));
+                    }
+                    return Some(Token { kind: TokenKind::StringLit, value, location: TokenLocation { start, end: self.current } })
+                }
+                '\'' => {
+                    let mut value: String = String::new();
+                    let start: usize = self.current;
+                    self.advance();
+                    while self.current < self.characters.len() && self.current() != '\'' {
+                        let val: char = self.current();
+                        match val {
+                            '\\' => {
+                                self.advance();
+                                match self.escape() {
+                                    Some(escaped) => value.push(escaped),
+                                    None => self.errors.push(Diagnostic::syntax_error("E0002", "Invalid escape sequence".to_string(), TokenLocation { start: self.current, end: self.current })),
+                                }
+                            }
+                            _ => value.push_str(val.to_string().as_str())
+                        }
+                        self.advance();
+                    }
+                    if self.current < self.characters.len() {
+                        self.advance();
+                    } else {
+                        self.errors.push(Diagnostic::syntax_error("E0039", "this char literal is never closed".to_string(), TokenLocation { start, end: self.current }));
+                    }
+                    return Some(Token { kind: TokenKind::CharLit, value, location: TokenLocation { start, end: self.current } })
+                }
+                '0'..='9' => {
+                    let mut value: String = String::new();
+                    let start: usize = self.current;
+                    while self.current < self.characters.len() && self.current().is_ascii_digit() {
+                        value.push_str(self.current().to_string().as_str());
+                        self.advance();
+                    }
+                    return Some(Token { kind: TokenKind::NumberLit, value, location: TokenLocation { start, end: self.current } });
+                }
+                ':' => {
+                    let start: usize = self.current;
+                    self.advance();
+                    return Some(Token { kind: TokenKind::Colon, value: ":".to_string(), location: TokenLocation { start, end: self.current } });
+                }
+                ',' => {
+                    let start: usize = self.current;
+                    self.advance();
+                    return Some(Token { kind: TokenKind::Comma, value: ",".to_string(), location: TokenLocation { start, end: self.current } });
+                }
+                '.' => {
+                    let start: usize = self.current;
+                    self.advance();
+                    if self.current() == '.' {
+                        self.advance();
+                        return Some(Token { kind: TokenKind::Range, value: "..".to_string(), location: TokenLocation { start, end: self.current } });
+                    } else {
+                        return Some(Token { kind: TokenKind::Dot, value: ".".to_string(), location: TokenLocation { start, end: self.current } });
+                    }
+                }
+                '@' => {
+                    let start: usize = self.current;
+                    self.advance();
+                    return Some(Token { kind: TokenKind::At, value: "@".to_string(), location: TokenLocation { start, end: self.current } });
+                }
+                '|' => {
+                    let start: usize = self.current;
+                    self.advance();
+                    return Some(Token { kind: TokenKind::Pipe, value: "|".to_string(), location: TokenLocation { start, end: self.current } });
+                }
+                '&' => {
+                    let start: usize = self.current;
+                    self.advance();
+                    return Some(Token { kind: TokenKind::Ampersand, value: "&".to_string(), location: TokenLocation { start, end: self.current } });
+                }
+                '(' => {
+                    let start: usize = self.current;
+                    self.advance();
+                    return Some(Token { kind: TokenKind::OpenParen, value: "(".to_string(), location: TokenLocation { start, end: self.current } });
+                }
+                ')' => {
+                    let start: usize = self.current;
+                    self.advance();
+                    return Some(Token { kind: TokenKind::CloseParen, value: ")".to_string(), location: TokenLocation { start, end: self.current } });
+                }
+                '[' => {
+                    let start: usize = self.current;
+                    self.advance();
+                    return Some(Token { kind: TokenKind::OpenBracket, value: "[".to_string(), location: TokenLocation { start, end: self.current } });
+                }
+                ']' => {
+                    let start: usize = self.current;
+                    self.advance();
+                    return Some(Token { kind: TokenKind::CloseBracket, value: "]".to_string(), location: TokenLocation { start, end: self.current } });
+                }
+                '=' => {
+                    let start: usize = self.current;
+                    self.advance();
+                    if self.current < self.characters.len() && self.current() == '>' {
+                        self.advance();
+                        return Some(Token { kind: TokenKind::FatArrow, value: "=>".to_string(), location: TokenLocation { start, end: self.current } });
+                    } else if self.current < self.characters.len() && self.current() == '=' {
+                        self.advance();
+                        return Some(Token { kind: TokenKind::EqualEqual, value: "==".to_string(), location: TokenLocation { start, end: self.current } });
+                    } else {
+                        return Some(Token { kind: TokenKind::Equal, value: "=".to_string(), location: TokenLocation { start, end: self.current } });
+                    }
+                }
+                '!' => {
+                    let start: usize = self.current;
+                    self.advance();
+                    if self.current < self.characters.len() && self.current() == '=' {
+                        self.advance();
+                        return Some(Token { kind: TokenKind::BangEqual, value: "!=".to_string(), location: TokenLocation { start, end: self.current } });
+                    } else {
+                        return Some(Token { kind: TokenKind::Bang, value: "!".to_string(), location: TokenLocation { start, end: self.current } });
+                    }
+                }
+                '<' => {
+                    let start: usize = self.current;
+                    self.advance();
+                    if self.current < self.characters.len() && self.current() == '=' {
+                        self.advance();
+                        return Some(Token { kind: TokenKind::LessEqual, value: "<=".to_string(), location: TokenLocation { start, end: self.current } });
+                    } else {
+                        return Some(Token { kind: TokenKind::Less, value: "<".to_string(), location: TokenLocation { start, end: self.current } });
+                    }
+                }
+                '>' => {
+                    let start: usize = self.current;
+                    self.advance();
+                    if self.current < self.characters.len() && self.current() == '=' {
+                        self.advance();
+                        return Some(Token { kind: TokenKind::GreaterEqual, value: ">=".to_string(), location: TokenLocation { start, end: self.current } });
+                    } else {
+                        return Some(Token { kind: TokenKind::Greater, value: ">".to_string(), location: TokenLocation { start, end: self.current } });
+                    }
+                }
+                '+' => {
+                    let start: usize = self.current;
+                    self.advance();
+                    if self.current < self.characters.len() && self.current() == '=' {
+                        self.advance();
+                        return Some(Token { kind: TokenKind::PlusEqual, value: "+=".to_string(), location: TokenLocation { start, end: self.current } });
+                    } else {
+                        return Some(Token { kind: TokenKind::Plus, value: "+".to_string(), location: TokenLocation { start, end: self.current } });
+                    }
+                }
+                '-' => {
+                    let start: usize = self.current;
+                    self.advance();
+                    if self.current < self.characters.len() && self.current() == '=' {
+                        self.advance();
+                        return Some(Token { kind: TokenKind::MinusEqual, value: "-=".to_string(), location: TokenLocation { start, end: self.current } });
+                    } else {
+                        return Some(Token { kind: TokenKind::Minus, value: "-".to_string(), location: TokenLocation { start, end: self.current } });
+                    }
+                }
+                '*' => {
+                    let start: usize = self.current;
+                    self.advance();
+                    if self.current < self.characters.len() && self.current() == '=' {
+                        self.advance();
+                        return Some(Token { kind: TokenKind::StarEqual, value: "*=".to_string(), location: TokenLocation { start, end: self.current } });
+                    } else {
+                        return Some(Token { kind: TokenKind::Star, value: "*".to_string(), location: TokenLocation { start, end: self.current } });
+                    }
+                }
+                '/' => {
+                    let start: usize = self.current;
+                    self.advance();
+                    if self.current() == '/' {
+                        self.advance();
+                        let mut value: String = "//".to_string();
+                        while self.current < self.characters.len() && self.current() != '\n' {
+                            value.push(self.current());
+                            self.advance();
+                        }
+                        // The newline after it is still the end of the line it's on
+                        if self.comments {
+                            return Some(Token { kind: TokenKind::Comment, value: value.trim_end().to_string(), location: TokenLocation { start, end: self.current } });
+                        }
+                    } else if self.current < self.characters.len() && self.current() == '=' {
+                        self.advance();
+                        return Some(Token { kind: TokenKind::SlashEqual, value: "/=".to_string(), location: TokenLocation { start, end: self.current } });
+                    } else {
+                        return Some(Token { kind: TokenKind::Slash, value: "/".to_string(), location: TokenLocation { start, end: self.current } });
+                    }
+                }
+                '%' => {
+                    let start: usize = self.current;
+                    self.advance();
+                    if self.current < self.characters.len() && self.current() == '=' {
+                        self.advance();
+                        return Some(Token { kind: TokenKind::PercentEqual, value: "%=".to_string(), location: TokenLocation { start, end: self.current } });
+                    } else {
+                        return Some(Token { kind: TokenKind::Percent, value: "%".to_string(), location: TokenLocation { start, end: self.current } });
+                    }
+                }
+                _ => {
+                    let unexpected: char = self.current();
+                    self.errors.push(Diagnostic::syntax_error("E0001", format!("Unexpected character: {}", unexpected), TokenLocation { start: self.current, end: self.current + 1 }));
+                    self.advance();
+                }
+            }
+        }
+        None
+    }
+}
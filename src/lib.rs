@@ -0,0 +1,48 @@
+// The compiler for .sl programs, as a library for editors, build systems and
+// tests to embed. A program goes through the stages in the order below: its
+// source is lexed into tokens, parsed into statements, checked, linted and
+// optimized, and then generated as C or compiled to bytecode for the VM.
+//
+// With the `serde` feature, tokens and the syntax tree can be serialized, to
+// keep them or hand them to another tool as JSON or any other format.
+
+// Where things are in a program's source, and what's wrong with it
+pub mod diagnostics;
+// Names, interned so they're cheap to copy and compare
+pub mod symbol;
+pub mod lexer;
+pub mod ast;
+// Passes that look over the syntax tree without changing it, and ones that
+// rebuild it
+pub mod visit;
+pub mod fold;
+// Syntax trees built in code rather than parsed
+pub mod builder;
+pub mod parser;
+// Reads a program's root file and everything it imports
+pub mod importer;
+pub mod checker;
+pub mod lint;
+pub mod optimize;
+// The sizes of C's types on the machine a program is built for
+pub mod target;
+pub mod codegen;
+pub mod bytecode;
+pub mod vm;
+pub mod format;
+// A syntax tree written back out as source
+pub mod unparse;
+pub mod doc;
+// `external` declarations written from a C header
+pub mod bindgen;
+// Queries an editor asks about a file, such as what's under the cursor
+pub mod ide;
+pub mod json;
+// Everything from a program's source to its C in one call
+pub mod compile;
+pub use compile::{compile, CompileError, CompileOptions, CompileOutput, Session};
+// The `sl` command itself
+pub mod cli;
+mod completions;
+mod lsp;
+mod dap;
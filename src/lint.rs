@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use crate::ast::{Expression, Statement};
+use crate::diagnostics::Diagnostic;
+use crate::lexer::TokenLocation;
+use crate::symbol::Symbol;
+use crate::visit::{walk_expression, walk_statement, walk_statements, Visitor};
+// Checks that only ever warn. Each one can be turned on with -W<name>, off with
+// -Wno-<name>, or made fatal with -Werror=<name>, or with the rest by -Werror
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)] pub enum Lint {
+    Shadow,
+    Unused,
+    Uninitialized,
+    Naming,
+    FunctionLength,
+    Deprecated,
+    MissingImport,
+}
+#[derive(Debug, Clone, Copy, PartialEq)] pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+impl Lint {
+    pub fn all() -> Vec<Lint> {
+        vec![Lint::Shadow, Lint::Unused, Lint::Uninitialized, Lint::Naming, Lint::FunctionLength, Lint::Deprecated, Lint::MissingImport]
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Lint::Shadow => "shadow",
+            Lint::Unused => "unused",
+            Lint::Uninitialized => "uninitialized",
+            Lint::Naming => "naming",
+            Lint::FunctionLength => "function-length",
+            Lint::Deprecated => "deprecated",
+            Lint::MissingImport => "missing-import",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Lint> {
+        Lint::all().into_iter().find(|lint| lint.name() == name)
+    }
+    pub fn code(&self) -> &'static str {
+        match self {
+            Lint::Shadow => "W0001",
+            Lint::Unused => "W0002",
+            Lint::Uninitialized => "W0003",
+            Lint::Naming => "W0004",
+            Lint::FunctionLength => "W0005",
+            Lint::Deprecated => "W0006",
+            Lint::MissingImport => "W0007",
+        }
+    }
+    pub fn default_level(&self) -> Level {
+        match self {
+            Lint::Shadow => Level::Allow,
+            Lint::Unused => Level::Allow,
+            Lint::Uninitialized => Level::Warn,
+            Lint::Naming => Level::Allow,
+            Lint::FunctionLength => Level::Allow,
+            Lint::Deprecated => Level::Warn,
+            Lint::MissingImport => Level::Warn,
+        }
+    }
+    // Parses a single -W command line flag
+    pub fn from_flag(flag: &str) -> Option<(Lint, Level)> {
+        if let Some(name) = flag.strip_prefix("-Werror=") {
+            return Lint::from_name(name).map(|lint| (lint, Level::Deny));
+        }
+        if let Some(name) = flag.strip_prefix("-Wno-") {
+            return Lint::from_name(name).map(|lint| (lint, Level::Allow));
+        }
+        flag.strip_prefix("-W").and_then(Lint::from_name).map(|lint| (lint, Level::Warn))
+    }
+}
+// Lint levels and options, from the defaults, then an sl.lints file, then -W flags.
+// The file holds one `name = value` per line, where the value is a level
+// (allow, warn, deny) for a lint or a number for an option. A level for
+// `warnings` is -Werror's: deny makes every lint that warns an error.
+//
+//     naming = warn
+//     function-length = deny
+//     max-function-length = 30
+//     warnings = deny
+#[derive(Debug, Clone)] pub struct LintConfig {
+    pub levels: HashMap<Lint, Level>,
+    pub max_function_length: usize,
+    pub deny_warnings: bool,
+}
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl LintConfig {
+    pub fn new() -> Self {
+        Self {
+            levels: Lint::all().into_iter().map(|lint| (lint, lint.default_level())).collect(),
+            max_function_length: 50,
+            deny_warnings: false,
+        }
+    }
+    pub fn parse(&mut self, contents: &str) -> Result<(), String> {
+        for (number, line) in contents.lines().enumerate() {
+            let line: &str = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value): (&str, &str) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => return Err(format!("line {}: expected `name = value`", number + 1)),
+            };
+            if key == "max-function-length" {
+                self.max_function_length = value.parse().map_err(|_| format!("line {}: `{}` is not a number", number + 1, value))?;
+                continue;
+            }
+            let level: Level = match value {
+                "allow" => Level::Allow,
+                "warn" => Level::Warn,
+                "deny" => Level::Deny,
+                _ => return Err(format!("line {}: unknown level `{}`", number + 1, value)),
+            };
+            if key == "warnings" {
+                self.deny_warnings = level == Level::Deny;
+                continue;
+            }
+            let lint: Lint = Lint::from_name(key).ok_or(format!("line {}: unknown lint `{}`", number + 1, key))?;
+            self.levels.insert(lint, level);
+        }
+        Ok(())
+    }
+    // Returns the diagnostic it reported, for a fix to be attached to, or None
+    // when the lint is off
+    pub fn report<'a>(&self, lint: Lint, message: String, location: TokenLocation, warnings: &'a mut Vec<Diagnostic>, errors: &'a mut Vec<Diagnostic>) -> Option<&'a mut Diagnostic> {
+        let (diagnostics, diagnostic): (&mut Vec<Diagnostic>, Diagnostic) = match self.levels[&lint] {
+            Level::Allow => return None,
+            Level::Warn if self.deny_warnings => (errors, Diagnostic::denied(lint.code(), format!("{} [-W{} -Werror]", message, lint.name()), location)),
+            Level::Warn => (warnings, Diagnostic::warning(lint.code(), format!("{} [-W{}]", message, lint.name()), location)),
+            Level::Deny => (errors, Diagnostic::denied(lint.code(), format!("{} [-Werror={}]", message, lint.name()), location)),
+        };
+        diagnostics.push(diagnostic);
+        diagnostics.last_mut()
+    }
+}
+// A lint that isn't part of the checker: it's shown every statement and
+// expression in the program and reports through the context. Passes are kept
+// by linters, which run on any thread
+pub trait LintPass: Send + Sync {
+    fn lint(&self) -> Lint;
+    fn check_statement(&mut self, _statement: &Statement, _context: &mut LintContext) {}
+    fn check_expression(&mut self, _expression: &Expression, _context: &mut LintContext) {}
+}
+pub struct LintContext {
+    pub config: LintConfig,
+    pub warnings: Vec<Diagnostic>,
+    pub errors: Vec<Diagnostic>,
+}
+impl LintContext {
+    pub fn report(&mut self, lint: Lint, message: String, location: TokenLocation) {
+        self.config.report(lint, message, location, &mut self.warnings, &mut self.errors);
+    }
+}
+// Every registered lint pass; add new style rules here
+pub fn lint_passes() -> Vec<Box<dyn LintPass>> {
+    vec![Box::new(NamingLint), Box::new(FunctionLengthLint)]
+}
+pub struct Linter {
+    pub passes: Vec<Box<dyn LintPass>>,
+    pub context: LintContext,
+}
+impl Linter {
+    pub fn new(config: LintConfig) -> Self {
+        // Passes that are turned off never run
+        let passes: Vec<Box<dyn LintPass>> = lint_passes().into_iter().filter(|pass| config.levels[&pass.lint()] != Level::Allow).collect();
+        Self {
+            passes,
+            context: LintContext { config, warnings: vec![], errors: vec![] },
+        }
+    }
+    pub fn run(&mut self, statements: &[Statement]) {
+        walk_statements(self, statements);
+    }
+}
+impl Visitor for Linter {
+    fn visit_statement(&mut self, statement: &Statement) {
+        for pass in self.passes.iter_mut() {
+            pass.check_statement(statement, &mut self.context);
+        }
+        // Externals are declarations of C's, with nothing of ours to lint
+        if !matches!(statement, Statement::External(_, _)) {
+            walk_statement(self, statement);
+        }
+    }
+    fn visit_expression(&mut self, expression: &Expression) {
+        for pass in self.passes.iter_mut() {
+            pass.check_expression(expression, &mut self.context);
+        }
+        walk_expression(self, expression);
+    }
+}
+// Types are UpperCamelCase, functions and variables snake_case. Externals are
+// left alone since their names come from C
+pub struct NamingLint;
+impl NamingLint {
+    pub fn is_camel_case(name: &str) -> bool {
+        name.starts_with(|c: char| c.is_ascii_uppercase()) && !name.contains('_')
+    }
+    pub fn is_snake_case(name: &str) -> bool {
+        name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    }
+}
+impl LintPass for NamingLint {
+    fn lint(&self) -> Lint {
+        Lint::Naming
+    }
+    fn check_statement(&mut self, statement: &Statement, context: &mut LintContext) {
+        let (kind, name, location, camel): (&str, &Symbol, &TokenLocation, bool) = match statement {
+            Statement::Struct(name, _, location) => ("struct", name, location, true),
+            Statement::Enum(name, _, _, location) | Statement::JavaEnum(name, _, _, location) => ("enum", name, location, true),
+            Statement::StructEnum(name, _, location) => ("enum", name, location, true),
+            Statement::TypeAlias(name, _, location) => ("type", name, location, true),
+            Statement::Annotation(name, _, location) => ("annotation", name, location, true),
+            Statement::Function(name, _, _, _, location) | Statement::StructFunction(_, name, _, _, _, location) => ("function", name, location, false),
+            Statement::Variable(name, _, _, location) => ("variable", name, location, false),
+            _ => return,
+        };
+        if camel && !NamingLint::is_camel_case(name) {
+            context.report(Lint::Naming, format!("{} `{}` should be UpperCamelCase", kind, name), location.clone());
+        } else if !camel && !NamingLint::is_snake_case(name) {
+            context.report(Lint::Naming, format!("{} `{}` should be snake_case", kind, name), location.clone());
+        }
+    }
+}
+// Flags functions with more statements than `max-function-length`, nested
+// blocks included
+pub struct FunctionLengthLint;
+impl FunctionLengthLint {
+    pub fn count(body: &[Statement]) -> usize {
+        body.iter().map(|statement| match statement {
+            Statement::While(_, body, _) | Statement::For(_, _, body, _) => 1 + FunctionLengthLint::count(body),
+            Statement::If(_, body, else_body, _) => 1 + FunctionLengthLint::count(body) + FunctionLengthLint::count(else_body),
+            _ => 1,
+        }).sum()
+    }
+}
+impl LintPass for FunctionLengthLint {
+    fn lint(&self) -> Lint {
+        Lint::FunctionLength
+    }
+    fn check_statement(&mut self, statement: &Statement, context: &mut LintContext) {
+        let (name, body, location): (String, &Vec<Statement>, &TokenLocation) = match statement {
+            Statement::Function(name, _, _, body, location) => (name.to_string(), body, location),
+            Statement::StructFunction(struct_name, name, _, _, body, location) => (format!("{}.{}", struct_name, name), body, location),
+            _ => return,
+        };
+        let length: usize = FunctionLengthLint::count(body);
+        if length > context.config.max_function_length {
+            context.report(Lint::FunctionLength, format!("`{}` has {} statements, more than the limit of {}", name, length, context.config.max_function_length), location.clone());
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+use colored::Colorize;
+use crate::cli::Exit;
+use crate::diagnostics::Diagnostic;
+use crate::ide::{Ide, Outline};
+use crate::json::{Json, json_string};
+use crate::lexer::TokenLocation;
+pub(crate) const LSP_USAGE: &str = "Usage: sl lsp
+
+Run a language server, which an editor starts and talks to over stdin and
+stdout with the Language Server Protocol. Each open file is checked as it
+changes, and the server answers hover, go-to-definition, find-references,
+completion, signature help, code action, document symbol and semantic token
+requests from what was found. Code actions are the fixes that come with
+diagnostics, such as closing a block with `end`.
+
+Options:
+  -h, --help             Show this help";
+// Answers an editor's Language Server Protocol messages on stdin until it says
+// to exit. Every open file is checked again whenever it changes, and its
+// diagnostics are sent back. Returns the exit code.
+pub(crate) fn language_server(args: &[String]) -> i32 {
+    if let Some(arg) = args.first() {
+        if arg == "-h" || arg == "--help" {
+            println!("{}", LSP_USAGE);
+            return 0;
+        }
+        println!("{}", format!("unknown option `{}`, see `sl lsp --help`", arg).red());
+        return Exit::Usage as i32;
+    }
+    // Half-written code can still make the front end panic, which mustn't take
+    // the server down with it or write to stdout
+    std::panic::set_hook(Box::new(|_| {}));
+    let mut input = std::io::stdin().lock();
+    let mut documents: HashMap<String, Ide> = HashMap::new();
+    let mut shut_down: bool = false;
+    loop {
+        let Some(message) = lsp_read(&mut input) else {
+            return 1;
+        };
+        let Some(message) = Json::parse(&message) else {
+            lsp_write("{\"jsonrpc\":\"2.0\",\"id\":null,\"error\":{\"code\":-32700,\"message\":\"parse error\"}}");
+            continue;
+        };
+        let id: &Json = message.get("id");
+        let params: &Json = message.get("params");
+        let uri: String = params.get("textDocument").get("uri").as_str().unwrap_or_default().to_string();
+        // Where a request points, as an offset into the file it's about
+        let position = |ide: &Ide| -> usize {
+            let position: &Json = params.get("position");
+            ide.offset(position.get("line").as_usize().unwrap_or(0), position.get("character").as_usize().unwrap_or(0))
+        };
+        let result: Option<String> = match message.get("method").as_str().unwrap_or_default() {
+            "initialize" => {
+                let legend: Vec<String> = Ide::TOKEN_TYPES.iter().map(|name| json_string(name)).collect();
+                Some(format!("{{\"capabilities\":{{\"textDocumentSync\":1,\"hoverProvider\":true,\"definitionProvider\":true,\"documentSymbolProvider\":true,\"referencesProvider\":true,\"codeActionProvider\":{{\"codeActionKinds\":[\"quickfix\"]}},\"signatureHelpProvider\":{{\"triggerCharacters\":[\"(\",\",\"]}},\"completionProvider\":{{\"triggerCharacters\":[\".\"]}},\"semanticTokensProvider\":{{\"legend\":{{\"tokenTypes\":[{}],\"tokenModifiers\":[]}},\"full\":true}}}},\"serverInfo\":{{\"name\":\"sl\"}}}}", legend.join(",")))
+            }
+            "shutdown" => {
+                shut_down = true;
+                Some("null".to_string())
+            }
+            "exit" => return if shut_down { 0 } else { 1 },
+            // The whole file comes with each change
+            method @ ("textDocument/didOpen" | "textDocument/didChange") => {
+                let text: Option<&str> = match method {
+                    "textDocument/didOpen" => params.get("textDocument").get("text").as_str(),
+                    _ => match params.get("contentChanges") {
+                        Json::Array(changes) => changes.last().and_then(|change| change.get("text").as_str()),
+                        _ => None,
+                    },
+                };
+                if let Some(text) = text {
+                    let path: String = lsp_path(&uri);
+                    match std::panic::catch_unwind(|| Ide::new(path, text.to_string())) {
+                        Ok(ide) => {
+                            lsp_publish(&uri, Some(&ide));
+                            documents.insert(uri.clone(), ide);
+                        }
+                        Err(_) => {
+                            documents.remove(&uri);
+                        }
+                    }
+                }
+                None
+            }
+            "textDocument/didClose" => {
+                documents.remove(&uri);
+                lsp_publish(&uri, None);
+                None
+            }
+            "textDocument/hover" => Some(match documents.get(&uri).and_then(|ide| Some((ide, ide.type_info(position(ide))?, ide.hover(position(ide))?))) {
+                Some((ide, info, hover)) => format!("{{\"contents\":{{\"kind\":\"markdown\",\"value\":{}}},\"range\":{}}}", json_string(&hover), lsp_range(ide, &info.location)),
+                None => "null".to_string(),
+            }),
+            "textDocument/definition" => Some(match documents.get(&uri).and_then(|ide| ide.definition(position(ide)).map(|location| (ide, location))) {
+                Some((ide, location)) => lsp_location(ide, &uri, &location),
+                None => "null".to_string(),
+            }),
+            "textDocument/references" => Some(match documents.get(&uri) {
+                Some(ide) => {
+                    let declaration: bool = *params.get("context").get("includeDeclaration") == Json::Bool(true);
+                    let locations: Vec<String> = ide.references(position(ide), declaration).iter().map(|location| lsp_location(ide, &uri, location)).collect();
+                    format!("[{}]", locations.join(","))
+                }
+                None => "null".to_string(),
+            }),
+            "textDocument/completion" => Some(match documents.get(&uri) {
+                Some(ide) => {
+                    let items: Vec<String> = ide.completions(position(ide)).iter().map(|(label, detail, kind)| {
+                        // CompletionItemKind
+                        let kind: usize = match *kind {
+                            "method" => 2,
+                            "function" => 3,
+                            "field" => 5,
+                            "variable" => 6,
+                            "enum" => 13,
+                            "keyword" => 14,
+                            "variant" => 20,
+                            "constant" => 21,
+                            "struct" => 22,
+                            _ => 25,
+                        };
+                        format!("{{\"label\":{},\"kind\":{},\"detail\":{}}}", json_string(label), kind, json_string(detail))
+                    }).collect();
+                    format!("[{}]", items.join(","))
+                }
+                None => "[]".to_string(),
+            }),
+            "textDocument/signatureHelp" => Some(match documents.get(&uri).and_then(|ide| ide.signature_help(position(ide))) {
+                Some(help) => {
+                    // Each parameter as where it is in the label, in UTF-16 code units
+                    let mut from: usize = help.label.find('(').map_or(0, |open| open + 1);
+                    let parameters: Vec<String> = help.parameters.iter().map(|(name, t)| {
+                        let text: String = Ide::parameter(name, t);
+                        match help.label[from..].find(&text) {
+                            Some(start) => {
+                                let start: usize = from + start;
+                                from = start + text.len();
+                                format!("{{\"label\":[{},{}]}}", help.label[..start].encode_utf16().count(), help.label[..from].encode_utf16().count())
+                            }
+                            None => format!("{{\"label\":{}}}", json_string(&text)),
+                        }
+                    }).collect();
+                    let active: String = help.active.map_or("null".to_string(), |active| active.to_string());
+                    format!(
+                        "{{\"signatures\":[{{\"label\":{},\"documentation\":{{\"kind\":\"markdown\",\"value\":{}}},\"parameters\":[{}]}}],\"activeSignature\":0,\"activeParameter\":{}}}",
+                        json_string(&help.label),
+                        json_string(&help.docs),
+                        parameters.join(","),
+                        active
+                    )
+                }
+                None => "null".to_string(),
+            }),
+            // A quick fix for each suggestion of the diagnostics in the range,
+            // as an edit of the file
+            "textDocument/codeAction" => Some(match documents.get(&uri) {
+                Some(ide) => {
+                    let range: &Json = params.get("range");
+                    let offset = |position: &Json| -> usize { ide.offset(position.get("line").as_usize().unwrap_or(0), position.get("character").as_usize().unwrap_or(0)) };
+                    let range: TokenLocation = TokenLocation { start: offset(range.get("start")), end: offset(range.get("end")) };
+                    let actions: Vec<String> = ide.fixes(&range).iter().map(|(diagnostic, suggestion)| format!(
+                        "{{\"title\":{},\"kind\":\"quickfix\",\"diagnostics\":[{}],\"isPreferred\":true,\"edit\":{{\"changes\":{{{}:[{{\"range\":{},\"newText\":{}}}]}}}}}}",
+                        json_string(&suggestion.message),
+                        lsp_diagnostic(ide, &uri, diagnostic),
+                        json_string(&uri),
+                        lsp_range(ide, &suggestion.location),
+                        json_string(&suggestion.replacement)
+                    )).collect();
+                    format!("[{}]", actions.join(","))
+                }
+                None => "[]".to_string(),
+            }),
+            "textDocument/documentSymbol" => Some(match documents.get(&uri) {
+                Some(ide) => format!("[{}]", ide.outline().iter().map(|item| lsp_symbol(ide, item)).collect::<Vec<String>>().join(",")),
+                None => "[]".to_string(),
+            }),
+            // Each token as its line and start relative to the one before, its
+            // length and the index of its type
+            "textDocument/semanticTokens/full" => Some(match documents.get(&uri) {
+                Some(ide) => {
+                    let mut data: Vec<String> = vec![];
+                    let (mut last_line, mut last_character): (usize, usize) = (0, 0);
+                    for (location, kind) in ide.semantic_tokens() {
+                        let ((line, character), (end_line, end_character)): ((usize, usize), (usize, usize)) = ide.range(&location);
+                        // A token over several lines can't be given
+                        if end_line != line {
+                            continue;
+                        }
+                        let delta: usize = if line == last_line { character - last_character } else { character };
+                        let index: usize = Ide::TOKEN_TYPES.iter().position(|name| *name == kind).unwrap_or(0);
+                        data.push(format!("{},{},{},{},0", line - last_line, delta, end_character - character, index));
+                        (last_line, last_character) = (line, character);
+                    }
+                    format!("{{\"data\":[{}]}}", data.join(","))
+                }
+                None => "null".to_string(),
+            }),
+            _ if *id != Json::Null => {
+                lsp_write(&format!("{{\"jsonrpc\":\"2.0\",\"id\":{},\"error\":{{\"code\":-32601,\"message\":\"method not found\"}}}}", id.render()));
+                None
+            }
+            // Notifications that don't need anything done
+            _ => None,
+        };
+        if let Some(result) = result {
+            lsp_write(&format!("{{\"jsonrpc\":\"2.0\",\"id\":{},\"result\":{}}}", id.render(), result));
+        }
+    }
+}
+// The body of the next message, after its Content-Length header
+pub(crate) fn lsp_read(input: &mut impl std::io::BufRead) -> Option<String> {
+    let mut length: Option<usize> = None;
+    loop {
+        let mut header: String = String::new();
+        if input.read_line(&mut header).ok()? == 0 {
+            return None;
+        }
+        let header: &str = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().ok();
+            }
+        }
+    }
+    let mut body: Vec<u8> = vec![0; length?];
+    input.read_exact(&mut body).ok()?;
+    String::from_utf8(body).ok()
+}
+pub(crate) fn lsp_write(body: &str) {
+    use std::io::Write;
+    let mut output = std::io::stdout().lock();
+    let _ = write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body);
+    let _ = output.flush();
+}
+// Sends a file's diagnostics, or clears them once it's closed
+pub(crate) fn lsp_publish(uri: &str, ide: Option<&Ide>) {
+    let diagnostics: Vec<String> = match ide {
+        Some(ide) => ide.diagnostics().iter().map(|diagnostic| lsp_diagnostic(ide, uri, diagnostic)).collect(),
+        None => vec![],
+    };
+    lsp_write(&format!("{{\"jsonrpc\":\"2.0\",\"method\":\"textDocument/publishDiagnostics\",\"params\":{{\"uri\":{},\"diagnostics\":[{}]}}}}", json_string(uri), diagnostics.join(",")));
+}
+// A Diagnostic. Labels become related information and notes are added to the
+// message. Suggestions go in `data` as the edits that make them.
+pub(crate) fn lsp_diagnostic(ide: &Ide, uri: &str, diagnostic: &Diagnostic) -> String {
+    let severity: usize = if diagnostic.is_warning() { 2 } else { 1 };
+    let message: String = [diagnostic.message.clone()].into_iter().chain(diagnostic.notes.iter().map(|note| format!("note: {}", note))).collect::<Vec<String>>().join("\n");
+    let related: Vec<String> = diagnostic.labels.iter().map(|label| format!("{{\"location\":{},\"message\":{}}}", lsp_location(ide, uri, &label.location), json_string(&label.message))).collect();
+    let suggestions: Vec<String> = diagnostic.suggestions.iter()
+        .map(|suggestion| format!("{{\"title\":{},\"range\":{},\"newText\":{}}}", json_string(&suggestion.message), lsp_range(ide, &suggestion.location), json_string(&suggestion.replacement)))
+        .collect();
+    format!(
+        "{{\"range\":{},\"severity\":{},\"code\":{},\"source\":\"sl\",\"message\":{},\"relatedInformation\":[{}],\"data\":{{\"suggestions\":[{}]}}}}",
+        lsp_range(ide, &diagnostic.location),
+        severity,
+        json_string(diagnostic.code),
+        json_string(&message),
+        related.join(","),
+        suggestions.join(",")
+    )
+}
+// A DocumentSymbol, with what's declared inside it
+pub(crate) fn lsp_symbol(ide: &Ide, item: &Outline) -> String {
+    // SymbolKind
+    let kind: usize = match item.kind {
+        "method" => 6,
+        "field" => 8,
+        "enum" => 10,
+        "function" => 12,
+        "variable" => 13,
+        "constant" => 14,
+        "variant" => 22,
+        "struct" => 23,
+        "type" => 26,
+        _ => 19,
+    };
+    let children: Vec<String> = item.children.iter().map(|child| lsp_symbol(ide, child)).collect();
+    format!(
+        "{{\"name\":{},\"detail\":{},\"kind\":{},\"range\":{},\"selectionRange\":{},\"children\":[{}]}}",
+        json_string(&item.name),
+        json_string(&item.detail),
+        kind,
+        lsp_range(ide, &item.range),
+        lsp_range(ide, &item.selection),
+        children.join(",")
+    )
+}
+// A Location in the file a document's uri names, or in one it imports
+pub(crate) fn lsp_location(ide: &Ide, uri: &str, location: &TokenLocation) -> String {
+    let path: std::path::PathBuf = ide.path(location);
+    let uri: String = if ide.sources.file(location).start == 0 { uri.to_string() } else { lsp_uri(&std::fs::canonicalize(&path).unwrap_or(path)) };
+    format!("{{\"uri\":{},\"range\":{}}}", json_string(&uri), lsp_range(ide, location))
+}
+pub(crate) fn lsp_range(ide: &Ide, location: &TokenLocation) -> String {
+    let ((line, character), (end_line, end_character)): ((usize, usize), (usize, usize)) = ide.range(location);
+    format!("{{\"start\":{{\"line\":{},\"character\":{}}},\"end\":{{\"line\":{},\"character\":{}}}}}", line, character, end_line, end_character)
+}
+// A file:// URI as a path, undoing its percent-encoding
+pub(crate) fn lsp_path(uri: &str) -> String {
+    let path: &[u8] = uri.strip_prefix("file://").unwrap_or(uri).as_bytes();
+    let mut bytes: Vec<u8> = vec![];
+    let mut index: usize = 0;
+    while index < path.len() {
+        let escaped: Option<u8> = path.get(index + 1..index + 3).and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match (path[index], escaped) {
+            (b'%', Some(byte)) => {
+                bytes.push(byte);
+                index += 3;
+            }
+            (byte, _) => {
+                bytes.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).to_string()
+}
+pub(crate) fn lsp_uri(path: &std::path::Path) -> String {
+    let mut uri: String = "file://".to_string();
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => uri.push(byte as char),
+            byte => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
@@ -0,0 +1,557 @@
+use std::collections::{HashMap, HashSet};
+use crate::ast::{Expression, Statement, Type};
+use crate::codegen::mangle;
+use crate::fold::{self, walk_expression, Folder};
+use crate::lexer::{TokenKind, TokenLocation};
+use crate::symbol::Symbol;
+use crate::target::TargetMachine;
+use crate::visit::{self, walk_statements, Visitor};
+// Rewrites of the checked program before codegen. None of them run unless
+// asked for: -O turns them all on, -O<name> a single one, -Ono-<name> turns one
+// off again and -O0 all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)] pub enum Optimization {
+    Inline,
+    ConstantFolding,
+    DeadBranches,
+    UnusedVariables,
+}
+impl Optimization {
+    pub fn all() -> Vec<Optimization> {
+        vec![Optimization::Inline, Optimization::ConstantFolding, Optimization::DeadBranches, Optimization::UnusedVariables]
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Optimization::Inline => "inline",
+            Optimization::ConstantFolding => "constant-folding",
+            Optimization::DeadBranches => "dead-branches",
+            Optimization::UnusedVariables => "unused-variables",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Optimization> {
+        Optimization::all().into_iter().find(|optimization| optimization.name() == name)
+    }
+    // Applies a single -O command line flag to the enabled set
+    pub fn apply_flag(flag: &str, enabled: &mut HashSet<Optimization>) -> bool {
+        match flag {
+            "-O" | "-O1" => enabled.extend(Optimization::all()),
+            "-O0" => enabled.clear(),
+            flag => match flag.strip_prefix("-Ono-") {
+                Some(name) => match Optimization::from_name(name) {
+                    Some(optimization) => {
+                        enabled.remove(&optimization);
+                    }
+                    None => return false,
+                },
+                None => match flag.strip_prefix("-O").and_then(Optimization::from_name) {
+                    Some(optimization) => {
+                        enabled.insert(optimization);
+                    }
+                    None => return false,
+                },
+            },
+        }
+        true
+    }
+}
+// An optimization is handed every expression after its operands, every block
+// after its statements, and every function body last, each to be rewritten.
+// Passes are kept by optimizers, which compile on any thread
+pub trait OptimizationPass: Send + Sync {
+    fn optimization(&self) -> Optimization;
+    fn optimize_expression(&mut self, expression: Expression) -> Expression {
+        expression
+    }
+    fn optimize_block(&mut self, body: Vec<Statement>) -> Vec<Statement> {
+        body
+    }
+    fn optimize_function(&mut self, _args: &[(Symbol, Type)], body: Vec<Statement>) -> Vec<Statement> {
+        body
+    }
+    // Sees the whole program before anything is rewritten
+    fn prepare(&mut self, _statements: &[Statement]) {}
+}
+// Every registered optimization, in the order they run; add new passes here
+pub fn optimization_passes(machine: &TargetMachine) -> Vec<Box<dyn OptimizationPass>> {
+    vec![Box::new(Inliner::new()), Box::new(ConstantFolding { machine: machine.clone() }), Box::new(DeadBranches), Box::new(UnusedVariables)]
+}
+pub struct Optimizer {
+    pub passes: Vec<Box<dyn OptimizationPass>>,
+}
+impl Optimizer {
+    pub fn new(enabled: &HashSet<Optimization>, machine: &TargetMachine) -> Self {
+        Self { passes: optimization_passes(machine).into_iter().filter(|pass| enabled.contains(&pass.optimization())).collect() }
+    }
+    pub fn run(&mut self, statements: Vec<Statement>) -> Vec<Statement> {
+        let mut statements: Vec<Statement> = statements;
+        for pass in self.passes.iter_mut() {
+            pass.prepare(&statements);
+            statements = PassFolder { pass: pass.as_mut() }.fold_block(statements);
+        }
+        statements
+    }
+    // Rebuilds an expression with f applied to each of its operands
+    pub fn map_children(expression: Expression, f: &mut dyn FnMut(Expression) -> Expression) -> Expression {
+        walk_expression(&mut Operands { f }, expression)
+    }
+    // The value of a condition that's known at compile time
+    pub fn constant_condition(condition: &Expression) -> Option<bool> {
+        match condition {
+            Expression::Number(value, _) => Some(*value != 0),
+            Expression::Boolean(value, _) => Some(*value),
+            Expression::Grouping(value, _) => Optimizer::constant_condition(value),
+            _ => None,
+        }
+    }
+}
+// Runs a pass over the program. Generics are only templates until they're
+// instantiated and externals have nothing to optimize, so neither is
+// touched, and types are left as written.
+pub struct PassFolder<'a> {
+    pub pass: &'a mut dyn OptimizationPass,
+}
+impl Folder for PassFolder<'_> {
+    fn fold_block(&mut self, body: Vec<Statement>) -> Vec<Statement> {
+        let body: Vec<Statement> = fold::walk_block(self, body);
+        self.pass.optimize_block(body)
+    }
+    fn fold_statement(&mut self, statement: Statement) -> Statement {
+        match statement {
+            Statement::Function(name, args, return_type, body, location) => {
+                let body: Vec<Statement> = self.fold_block(body);
+                let body: Vec<Statement> = self.pass.optimize_function(&args, body);
+                Statement::Function(name, args, return_type, body, location)
+            }
+            Statement::StructFunction(struct_name, name, args, return_type, body, location) => {
+                let body: Vec<Statement> = self.fold_block(body);
+                let body: Vec<Statement> = self.pass.optimize_function(&args, body);
+                Statement::StructFunction(struct_name, name, args, return_type, body, location)
+            }
+            statement @ (Statement::Generic(..) | Statement::External(..)) => statement,
+            statement => fold::walk_statement(self, statement),
+        }
+    }
+    fn fold_expression(&mut self, expression: Expression) -> Expression {
+        let expression: Expression = walk_expression(self, expression);
+        self.pass.optimize_expression(expression)
+    }
+    fn fold_type(&mut self, t: Type) -> Type {
+        t
+    }
+}
+// Applies a function to an expression's operands and nothing deeper
+pub struct Operands<'a> {
+    pub f: &'a mut dyn FnMut(Expression) -> Expression,
+}
+impl Folder for Operands<'_> {
+    fn fold_expression(&mut self, expression: Expression) -> Expression {
+        (self.f)(expression)
+    }
+    fn fold_type(&mut self, t: Type) -> Type {
+        t
+    }
+}
+// Calls to `inline` functions whose body is a single expression are replaced
+// by that expression. Arguments that are more than a literal or a variable go
+// into temporaries declared before the statement, so they're still evaluated
+// once; where that can't be done, in a loop condition or a ternary branch, the
+// call is only inlined if none of its arguments need one.
+pub struct Inliner {
+    pub functions: HashMap<Symbol, (Vec<(Symbol, Type)>, Expression)>,
+    pub temporaries: usize,
+}
+impl Default for Inliner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Inliner {
+    pub fn new() -> Self {
+        Self { functions: HashMap::new(), temporaries: 0 }
+    }
+    pub fn block(&mut self, body: Vec<Statement>, locals: &HashSet<Symbol>) -> Vec<Statement> {
+        let mut result: Vec<Statement> = vec![];
+        for statement in body {
+            let mut hoisted: Vec<Statement> = vec![];
+            let statement: Statement = match statement {
+                Statement::Variable(name, t, value, location) => Statement::Variable(name, t, self.expression(value, true, &mut hoisted, locals, &mut vec![]), location),
+                Statement::Constant(name, t, value, location) => Statement::Constant(name, t, self.expression(value, true, &mut hoisted, locals, &mut vec![]), location),
+                Statement::Return(value, location) => Statement::Return(self.expression(value, true, &mut hoisted, locals, &mut vec![]), location),
+                Statement::Expression(value, location) => Statement::Expression(self.expression(value, true, &mut hoisted, locals, &mut vec![]), location),
+                Statement::If(condition, body, else_body, location) => {
+                    let condition: Expression = self.expression(condition, true, &mut hoisted, locals, &mut vec![]);
+                    Statement::If(condition, self.block(body, locals), self.block(else_body, locals), location)
+                }
+                Statement::While(condition, body, location) => {
+                    let condition: Expression = self.expression(condition, false, &mut hoisted, locals, &mut vec![]);
+                    Statement::While(condition, self.block(body, locals), location)
+                }
+                // What a loop goes over is worked out once, before it starts
+                Statement::For(name, iterable, body, location) => {
+                    let iterable: Expression = self.expression(iterable, true, &mut hoisted, locals, &mut vec![]);
+                    Statement::For(name, iterable, self.block(body, locals), location)
+                }
+                statement => statement,
+            };
+            result.extend(hoisted);
+            result.push(statement);
+        }
+        result
+    }
+    // `inlining` holds the functions being expanded, so recursion stops
+    pub fn expression(&mut self, expression: Expression, hoist: bool, hoisted: &mut Vec<Statement>, locals: &HashSet<Symbol>, inlining: &mut Vec<Symbol>) -> Expression {
+        let expression: Expression = match expression {
+            // Only one branch runs, so nothing in them can be hoisted
+            Expression::Ternary(condition, then, otherwise, location) => {
+                let condition: Expression = self.expression(*condition, hoist, hoisted, locals, inlining);
+                let then: Expression = self.expression(*then, false, hoisted, locals, inlining);
+                let otherwise: Expression = self.expression(*otherwise, false, hoisted, locals, inlining);
+                return Expression::Ternary(Box::new(condition), Box::new(then), Box::new(otherwise), location);
+            }
+            expression => Optimizer::map_children(expression, &mut |expression| self.expression(expression, hoist, hoisted, locals, inlining)),
+        };
+        match expression {
+            Expression::Call(name, args, location) if self.functions.contains_key(&name) && !locals.contains(&name) && !inlining.contains(&name) => {
+                match self.inline_call(&name, &args, &location, hoist, hoisted, locals) {
+                    Some(body) => {
+                        inlining.push(name);
+                        let body: Expression = self.expression(body, hoist, hoisted, locals, inlining);
+                        inlining.pop();
+                        DeadBranches::taken(body, location)
+                    }
+                    None => Expression::Call(name, args, location),
+                }
+            }
+            expression => expression,
+        }
+    }
+    pub fn inline_call(&mut self, name: &Symbol, args: &[Expression], location: &TokenLocation, hoist: bool, hoisted: &mut Vec<Statement>, locals: &HashSet<Symbol>) -> Option<Expression> {
+        let (params, body): (Vec<(Symbol, Type)>, Expression) = self.functions[name].clone();
+        if args.len() != params.len() || args.iter().any(|arg| matches!(arg, Expression::NamedArgument(..))) {
+            return None;
+        }
+        // The body can't refer to anything the call site's locals would hide
+        let mut names: HashSet<Symbol> = HashSet::new();
+        Inliner::free_names(&body, &mut names);
+        if names.iter().any(|name| locals.contains(name) && !params.iter().any(|(param, _)| param == name)) {
+            return None;
+        }
+        let mut written: HashSet<Symbol> = HashSet::new();
+        Inliner::written_names(&body, &mut written);
+        let needs_temporary: Vec<bool> = params.iter().zip(args.iter()).map(|((param, _), arg)| {
+            written.contains(param) || !matches!(arg, Expression::Number(..) | Expression::Boolean(..) | Expression::Char(..) | Expression::Identifier(..) | Expression::Null)
+        }).collect();
+        if !hoist && needs_temporary.contains(&true) {
+            return None;
+        }
+        let mut substitutions: HashMap<Symbol, Expression> = HashMap::new();
+        for (((param, t), arg), needs_temporary) in params.iter().zip(args.iter()).zip(needs_temporary) {
+            if needs_temporary {
+                let temporary: Symbol = Symbol::intern(&mangle("inline", &[&self.temporaries.to_string(), param]));
+                hoisted.push(Statement::Variable(temporary, t.clone(), arg.clone(), location.clone()));
+                substitutions.insert(*param, Expression::Identifier(temporary, location.clone()));
+            } else {
+                substitutions.insert(*param, arg.clone());
+            }
+        }
+        self.temporaries += 1;
+        Some(Inliner::substitute(body, &substitutions))
+    }
+    pub fn substitute(expression: Expression, substitutions: &HashMap<Symbol, Expression>) -> Expression {
+        match expression {
+            Expression::Identifier(name, location) => substitutions.get(&name).cloned().unwrap_or(Expression::Identifier(name, location)),
+            // A parameter holding a function that's called
+            Expression::Call(name, args, location) => {
+                let args: Vec<Expression> = args.into_iter().map(|arg| Inliner::substitute(arg, substitutions)).collect();
+                match substitutions.get(&name) {
+                    Some(Expression::Identifier(function, _)) => Expression::Call(*function, args, location),
+                    _ => Expression::Call(name, args, location),
+                }
+            }
+            // The right of a `.` is a field or method name, not a variable
+            Expression::Member(base, member, location) => {
+                let member: Expression = match *member {
+                    Expression::Call(name, args, location) => Expression::Call(name, args.into_iter().map(|arg| Inliner::substitute(arg, substitutions)).collect(), location),
+                    member => member,
+                };
+                Expression::Member(Box::new(Inliner::substitute(*base, substitutions)), Box::new(member), location)
+            }
+            expression => Optimizer::map_children(expression, &mut |expression| Inliner::substitute(expression, substitutions)),
+        }
+    }
+    // Every name an expression looks up, leaving out field and method names
+    pub fn free_names(expression: &Expression, names: &mut HashSet<Symbol>) {
+        match expression {
+            Expression::Member(base, member, _) => {
+                Inliner::free_names(base, names);
+                if let Expression::Call(_, args, _) = &**member {
+                    for arg in args.iter() {
+                        Inliner::free_names(arg, names);
+                    }
+                }
+            }
+            Expression::Identifier(name, _) => {
+                names.insert(*name);
+            }
+            Expression::Call(name, args, _) => {
+                names.insert(*name);
+                for arg in args.iter() {
+                    Inliner::free_names(arg, names);
+                }
+            }
+            expression => {
+                Optimizer::map_children(expression.clone(), &mut |expression| {
+                    Inliner::free_names(&expression, names);
+                    expression
+                });
+            }
+        }
+    }
+    // Names that are assigned to or have their address taken
+    pub fn written_names(expression: &Expression, names: &mut HashSet<Symbol>) {
+        if let Expression::Assignment(target, _, _) | Expression::AddressOf(target, _) = expression {
+            if let Expression::Identifier(name, _) = &**target {
+                names.insert(*name);
+            }
+        }
+        Optimizer::map_children(expression.clone(), &mut |expression| {
+            Inliner::written_names(&expression, names);
+            expression
+        });
+    }
+    // Every variable a function declares, at any depth
+    pub fn declared_names(body: &[Statement], names: &mut HashSet<Symbol>) {
+        for statement in body.iter() {
+            match statement {
+                Statement::Variable(name, _, _, _) | Statement::Constant(name, _, _, _) => {
+                    names.insert(*name);
+                }
+                Statement::While(_, body, _) => Inliner::declared_names(body, names),
+                Statement::For(name, _, body, _) => {
+                    names.insert(*name);
+                    Inliner::declared_names(body, names);
+                }
+                Statement::If(_, body, else_body, _) => {
+                    Inliner::declared_names(body, names);
+                    Inliner::declared_names(else_body, names);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+impl OptimizationPass for Inliner {
+    fn optimization(&self) -> Optimization {
+        Optimization::Inline
+    }
+    fn prepare(&mut self, statements: &[Statement]) {
+        for statement in statements.iter() {
+            let statement: &Statement = match statement {
+                Statement::Annotated(statement, _, _) => statement,
+                statement => statement,
+            };
+            let Statement::Inline(function, _) = statement else {
+                continue;
+            };
+            if let Statement::Function(name, args, _, body, _) = &**function {
+                if let [Statement::Return(value, _) | Statement::Expression(value, _)] = body.as_slice() {
+                    if !matches!(value, Expression::Empty) {
+                        self.functions.insert(*name, (args.clone(), value.clone()));
+                    }
+                }
+            }
+        }
+    }
+    fn optimize_function(&mut self, args: &[(Symbol, Type)], body: Vec<Statement>) -> Vec<Statement> {
+        let mut locals: HashSet<Symbol> = args.iter().map(|(name, _)| *name).collect();
+        Inliner::declared_names(&body, &mut locals);
+        self.block(body, &locals)
+    }
+}
+// Arithmetic and comparisons on integer literals are done at compile time.
+// Only results that fit in the target's int are folded, so nothing that would
+// overflow at runtime changes meaning.
+pub struct ConstantFolding {
+    pub machine: TargetMachine,
+}
+impl ConstantFolding {
+    pub fn number(expression: &Expression) -> Option<i64> {
+        match expression {
+            Expression::Number(value, _) => Some(*value),
+            Expression::Boolean(value, _) => Some(*value as i64),
+            _ => None,
+        }
+    }
+    // A `sizeof` is unsigned, so it's only folded into arithmetic that stays
+    // positive, where that can't change the result
+    pub fn operand(&self, op: &TokenKind, expression: &Expression) -> Option<i64> {
+        match expression {
+            Expression::SizeOf(t, _) if matches!(op, TokenKind::Plus | TokenKind::Star | TokenKind::Slash | TokenKind::Percent) => self.machine.size_of(t),
+            expression => ConstantFolding::number(expression),
+        }
+    }
+    pub fn fold_binary(op: &TokenKind, left: i64, right: i64) -> Option<i64> {
+        match op {
+            TokenKind::Plus => left.checked_add(right),
+            TokenKind::Minus => left.checked_sub(right),
+            TokenKind::Star => left.checked_mul(right),
+            // Dividing by zero is left for the C compiler to warn about
+            TokenKind::Slash => left.checked_div(right),
+            TokenKind::Percent => left.checked_rem(right),
+            TokenKind::EqualEqual => Some((left == right) as i64),
+            TokenKind::BangEqual => Some((left != right) as i64),
+            TokenKind::Less => Some((left < right) as i64),
+            TokenKind::LessEqual => Some((left <= right) as i64),
+            TokenKind::Greater => Some((left > right) as i64),
+            TokenKind::GreaterEqual => Some((left >= right) as i64),
+            _ => None,
+        }
+    }
+}
+impl OptimizationPass for ConstantFolding {
+    fn optimization(&self) -> Optimization {
+        Optimization::ConstantFolding
+    }
+    fn optimize_expression(&mut self, expression: Expression) -> Expression {
+        let folded: Option<(i64, TokenLocation)> = match &expression {
+            Expression::Binary(op, left, right, location) => match (self.operand(op, left), self.operand(op, right)) {
+                // Next to a `sizeof`, a negative operand would become unsigned
+                (Some(left_value), Some(right_value)) if (left_value >= 0 && right_value >= 0) || !matches!((&**left, &**right), (Expression::SizeOf(..), _) | (_, Expression::SizeOf(..))) => {
+                    ConstantFolding::fold_binary(op, left_value, right_value).map(|value| (value, location.clone()))
+                }
+                _ => None,
+            },
+            Expression::Unary(TokenKind::Minus, value, location) => match **value {
+                Expression::Number(value, _) => value.checked_neg().map(|value| (value, location.clone())),
+                _ => None,
+            },
+            Expression::Unary(TokenKind::Bang, value, location) => ConstantFolding::number(value).map(|value| ((value == 0) as i64, location.clone())),
+            Expression::Grouping(value, location) => match **value {
+                Expression::Number(value, _) => Some((value, location.clone())),
+                _ => None,
+            },
+            _ => None,
+        };
+        let (min, max): (i128, i128) = self.machine.range(&Type::Int(TokenLocation { start: 0, end: 0 })).unwrap();
+        match folded {
+            // The most negative int can't be written as a literal in C
+            Some((value, location)) if value as i128 > min && value as i128 <= max => Expression::Number(value, location),
+            _ => expression,
+        }
+    }
+}
+// Branches that can't be taken are removed: `if` and `while` on a constant
+// condition, ternaries on one, and statements after a `return`, `break` or
+// `continue` in the same block
+pub struct DeadBranches;
+impl DeadBranches {
+    pub fn declares(body: &[Statement]) -> bool {
+        body.iter().any(|statement| matches!(statement, Statement::Variable(..) | Statement::Constant(..)))
+    }
+    // The branch of a ternary that's taken, which keeps the ternary's
+    // precedence unless it's a single term
+    pub fn taken(branch: Expression, location: TokenLocation) -> Expression {
+        match branch {
+            Expression::Number(..) | Expression::String(..) | Expression::Char(..) | Expression::Boolean(..) | Expression::Identifier(..) | Expression::Grouping(..) => branch,
+            branch => Expression::Grouping(Box::new(branch), location),
+        }
+    }
+}
+impl OptimizationPass for DeadBranches {
+    fn optimization(&self) -> Optimization {
+        Optimization::DeadBranches
+    }
+    fn optimize_expression(&mut self, expression: Expression) -> Expression {
+        match expression {
+            Expression::Ternary(condition, then, otherwise, location) => match Optimizer::constant_condition(&condition) {
+                Some(true) => DeadBranches::taken(*then, location),
+                Some(false) => DeadBranches::taken(*otherwise, location),
+                None => Expression::Ternary(condition, then, otherwise, location),
+            },
+            expression => expression,
+        }
+    }
+    fn optimize_block(&mut self, body: Vec<Statement>) -> Vec<Statement> {
+        let mut result: Vec<Statement> = vec![];
+        for statement in body {
+            let ends_block: bool = matches!(statement, Statement::Return(..) | Statement::Break(_) | Statement::Continue(_));
+            match statement {
+                Statement::If(condition, body, else_body, location) => match Optimizer::constant_condition(&condition) {
+                    Some(taken) => {
+                        let branch: Vec<Statement> = if taken { body } else { else_body };
+                        // A branch with its own variables keeps its block so
+                        // their names stay scoped to it
+                        if DeadBranches::declares(&branch) {
+                            result.push(Statement::If(Expression::Number(1, location.clone()), branch, vec![], location));
+                        } else {
+                            result.extend(branch);
+                        }
+                    }
+                    None => result.push(Statement::If(condition, body, else_body, location)),
+                },
+                Statement::While(condition, _, _) if Optimizer::constant_condition(&condition) == Some(false) => {}
+                statement => result.push(statement),
+            }
+            if ends_block {
+                break;
+            }
+        }
+        result
+    }
+}
+// Locals that are never mentioned again are dropped, as long as computing
+// their value can't have side effects
+pub struct UnusedVariables;
+impl UnusedVariables {
+    pub fn is_pure(expression: &Expression) -> bool {
+        match expression {
+            Expression::Number(..) | Expression::String(..) | Expression::Char(..) | Expression::Boolean(..) | Expression::Identifier(..) | Expression::Null | Expression::SizeOf(..) | Expression::Empty => true,
+            Expression::Grouping(value, _) | Expression::Unary(_, value, _) | Expression::Cast(value, _, _) | Expression::UnsafeCast(value, _, _) => UnusedVariables::is_pure(value),
+            Expression::Binary(_, left, right, _) => UnusedVariables::is_pure(left) && UnusedVariables::is_pure(right),
+            _ => false,
+        }
+    }
+    pub fn prune(body: Vec<Statement>, used: &HashSet<Symbol>, removed: &mut bool) -> Vec<Statement> {
+        body.into_iter().filter_map(|statement| match statement {
+            Statement::Variable(name, _, value, _) | Statement::Constant(name, _, value, _) if !used.contains(&name) && UnusedVariables::is_pure(&value) => {
+                *removed = true;
+                None
+            }
+            Statement::While(condition, body, location) => Some(Statement::While(condition, UnusedVariables::prune(body, used, removed), location)),
+            Statement::For(name, iterable, body, location) => Some(Statement::For(name, iterable, UnusedVariables::prune(body, used, removed), location)),
+            Statement::If(condition, body, else_body, location) => {
+                let body: Vec<Statement> = UnusedVariables::prune(body, used, removed);
+                Some(Statement::If(condition, body, UnusedVariables::prune(else_body, used, removed), location))
+            }
+            statement => Some(statement),
+        }).collect()
+    }
+}
+// Every name a block mentions, counting any call by name, since a local can
+// hold a function
+pub struct MentionedNames(pub HashSet<Symbol>);
+impl Visitor for MentionedNames {
+    fn visit_expression(&mut self, expression: &Expression) {
+        if let Expression::Identifier(name, _) | Expression::Call(name, _, _) | Expression::GenericCall(name, _, _, _) = expression {
+            self.0.insert(*name);
+        }
+        visit::walk_expression(self, expression);
+    }
+}
+impl OptimizationPass for UnusedVariables {
+    fn optimization(&self) -> Optimization {
+        Optimization::UnusedVariables
+    }
+    fn optimize_function(&mut self, _args: &[(Symbol, Type)], body: Vec<Statement>) -> Vec<Statement> {
+        // Dropping one variable can leave the ones it was computed from unused
+        let mut body: Vec<Statement> = body;
+        loop {
+            let mut used: MentionedNames = MentionedNames(HashSet::new());
+            walk_statements(&mut used, &body);
+            let mut removed: bool = false;
+            body = UnusedVariables::prune(body, &used.0, &mut removed);
+            if !removed {
+                return body;
+            }
+        }
+    }
+}
@@ -0,0 +1,1180 @@
+use crate::ast::{Annotation, Expression, Statement, Type};
+use crate::diagnostics::{closest, Diagnostic};
+use crate::lexer::{Lexer, Token, TokenKind, TokenLocation};
+use crate::symbol::Symbol;
+// How deeply statements, expressions and types may nest, short of where
+// parsing or checking them would run out of the 2MiB of stack a spawned
+// thread gets, even in a debug build
+pub const MAX_DEPTH: usize = 128;
+// How many tokens past the current one the parser looks at
+pub const LOOKAHEAD: usize = 2;
+#[derive(Debug, Clone)] pub struct Parser {
+    // Where the tokens come from, lexed as they're needed
+    pub lexer: Lexer,
+    // What's added to the lexer's locations, for a file that isn't at the
+    // start of the source map
+    pub offset: usize,
+    // The tokens from the one before the current one to LOOKAHEAD past it,
+    // and the index of the first of them
+    pub window: std::collections::VecDeque<Token>,
+    pub first: usize,
+    pub statements: Vec<Statement>,
+    pub current: usize,
+    pub errors: Vec<Diagnostic>,
+    pub depth: usize,
+    // Whether what's being parsed is external, so a function can be declared
+    // without a body for C to define
+    pub external: bool,
+    // What's past the last token: the end of the file, which is where the last
+    // token ends, or at the offset when there are none
+    pub end: Token,
+}
+impl Parser {
+    pub fn new(lexer: Lexer, offset: usize) -> Self {
+        let mut parser: Parser = Self {
+            lexer,
+            offset,
+            window: std::collections::VecDeque::new(),
+            first: 0,
+            statements: vec![],
+            current: 0,
+            errors: vec![],
+            depth: 0,
+            external: false,
+            end: Token { kind: TokenKind::EndOfFile, value: String::new(), location: TokenLocation { start: offset, end: offset } },
+        };
+        parser.fill();
+        parser
+    }
+    pub fn parse(&mut self) -> Vec<Statement> {
+        while self.current().kind != TokenKind::EndOfFile {
+            if self.current().kind == TokenKind::Newline {
+                self.advance();
+                continue;
+            }
+            let statement: Statement = self.parse_statement();
+            self.statements.push(statement);
+        }
+        std::mem::take(&mut self.statements)
+    }
+    pub fn parse_statement(&mut self) -> Statement {
+        let location: TokenLocation = self.current().location();
+        self.nested(Parser::parse_any_statement).unwrap_or(Statement::Expression(Expression::Empty, location))
+    }
+    pub fn parse_any_statement(&mut self) -> Statement {
+        let start: usize = self.current;
+        self.check_misspelled_keyword();
+        let statement: Statement = match self.current().kind.clone() {
+            TokenKind::Annotation => self.parse_annotation(),
+            TokenKind::At => self.parse_annotated(),
+            TokenKind::External => self.parse_external(),
+            TokenKind::Inline => self.parse_inline(),
+            TokenKind::Struct => self.parse_struct(),
+            TokenKind::Enum => self.parse_enum(),
+            TokenKind::Type => self.parse_type_alias(),
+            TokenKind::Func => self.parse_function(),
+            TokenKind::Var => self.parse_variable(),
+            TokenKind::Const => self.parse_constant(),
+            TokenKind::Return => self.parse_return(),
+            TokenKind::Import => self.parse_import(),
+            TokenKind::While => self.parse_while(),
+            TokenKind::For => self.parse_for(),
+            TokenKind::Break => self.parse_break(),
+            TokenKind::Continue => self.parse_continue(),
+            TokenKind::If => self.parse_if(),
+            _ => {
+                let location: TokenLocation = self.current().location();
+                Statement::Expression(self.parse_expression(), location)
+            }
+        };
+        // What can't start a statement, such as a stray `end`, is skipped so that
+        // parsing goes on
+        if self.current == start {
+            let token: Token = self.current().clone();
+            // This says more than that it isn't an expression either
+            self.errors.retain(|error| error.location().start != token.location.start);
+            self.errors.push(Diagnostic::syntax_error("E0004", format!("expected a statement, but got {:?}", token.kind), token.location));
+            self.advance();
+        }
+        statement
+    }
+    // A name followed by another name, like `strcut Point`, is most likely a
+    // keyword spelled wrong when it's close to one. It's read as that keyword
+    // so the rest of the statement parses.
+    pub fn check_misspelled_keyword(&mut self) {
+        const KEYWORDS: [&str; 14] = ["annotation", "external", "inline", "struct", "enum", "type", "func", "var", "const", "return", "import", "while", "for", "if"];
+        let token: Token = self.current().clone();
+        if token.kind != TokenKind::Identifier || self.peek().kind != TokenKind::Identifier {
+            return;
+        }
+        if let Some(keyword) = closest(&token.value, KEYWORDS) {
+            let message: String = format!("expected a statement, but got `{} {}`", token.value, self.peek().value);
+            self.errors.push(Diagnostic::syntax_error("E0004", message, token.location.clone())
+                .with_suggestion(format!("did you mean `{}`?", keyword), token.location, keyword.to_string()));
+            if let (Some(keyword), Some(token)) = (Lexer::new(keyword.to_string()).next(), self.window.get_mut(self.current - self.first)) {
+                token.kind = keyword.kind;
+            }
+        }
+    }
+    // Whether a block goes on, which it doesn't at its `end` or at the end of
+    // the file, where it's reported as never closed
+    pub fn in_block(&mut self, start: &TokenLocation) -> bool {
+        match self.current().kind {
+            TokenKind::End => false,
+            TokenKind::EndOfFile => {
+                // Unless the parser gave up on the rest of the file
+                // The fix closes it at the end of the file, on a line of its own
+                if !self.errors.iter().any(|error| error.code() == "E0037") {
+                    let end: TokenLocation = self.current().location();
+                    let replacement: &str = if self.current > 0 && self.token(self.current - 1).kind != TokenKind::Newline { "\nend\n" } else { "end\n" };
+                    self.errors.push(Diagnostic::syntax_error("E0036", "this block is never closed with `end`".to_string(), start.clone())
+                        .with_suggestion("close it with `end`".to_string(), end, replacement.to_string()));
+                }
+                false
+            }
+            _ => true,
+        }
+    }
+    pub fn parse_break(&mut self) -> Statement {
+        let location: TokenLocation = self.current().location();
+        self.expect(TokenKind::Break);
+        self.expect(TokenKind::Newline);
+        Statement::Break(location)
+    }
+    pub fn parse_continue(&mut self) -> Statement {
+        let location: TokenLocation = self.current().location();
+        self.expect(TokenKind::Continue);
+        self.expect(TokenKind::Newline);
+        Statement::Continue(location)
+    }
+    pub fn parse_annotation(&mut self) -> Statement {
+        self.expect(TokenKind::Annotation);
+        let name_location: TokenLocation = self.current().location();
+        let name: Symbol = self.expect_name();
+        if self.current().kind == TokenKind::End {
+            self.advance();
+            return Statement::Annotation(name, vec![], name_location);
+        } else {
+            self.expect(TokenKind::Newline);
+        }
+        let mut fields: Vec<(Symbol, Type)> = vec![];
+        while self.in_block(&name_location) {
+            let start: usize = self.current;
+            let name: Symbol = self.expect_name();
+            self.expect(TokenKind::Colon);
+            let type_: Type = self.parse_type();
+            fields.push((name, type_));
+            self.expect(TokenKind::Newline);
+            self.moved_on(start);
+        }
+        self.expect(TokenKind::End);
+        Statement::Annotation(name, fields, name_location)
+    }
+    pub fn parse_annotated(&mut self) -> Statement {
+        let mut annotations: Vec<Annotation> = vec![];
+        while self.current().kind == TokenKind::At {
+            self.expect(TokenKind::At);
+            let name_location: TokenLocation = self.current().location();
+            let name: Symbol = self.expect_name();
+            let mut arguments: Vec<Expression> = vec![];
+            if self.current().kind == TokenKind::OpenParen {
+                self.expect(TokenKind::OpenParen);
+                while self.in_list(TokenKind::CloseParen) {
+                    let start: usize = self.current;
+                    if self.current().kind == TokenKind::Identifier && self.peek().kind == TokenKind::Colon {
+                        let argument_location: TokenLocation = self.current().location();
+                        let argument_name: Symbol = self.expect_name();
+                        self.expect(TokenKind::Colon);
+                        let value: Expression = self.parse_expression();
+                        arguments.push(Expression::NamedArgument(argument_name, Box::new(value), argument_location));
+                    } else {
+                        arguments.push(self.parse_expression());
+                    }
+                    if self.current().kind == TokenKind::Comma {
+                        self.expect(TokenKind::Comma);
+                    }
+                    self.moved_on(start);
+                }
+                self.expect(TokenKind::CloseParen);
+            }
+            self.expect(TokenKind::Newline);
+            annotations.push(Annotation { name, arguments, location: name_location });
+        }
+        let statement: Statement = self.parse_statement();
+        let location: TokenLocation = statement.location();
+        Statement::Annotated(Box::new(statement), annotations, location)
+    }
+    pub fn parse_external(&mut self) -> Statement {
+        let location: TokenLocation = self.current().location();
+        self.expect(TokenKind::External);
+        // A type C defines can be named without saying what it is, as in
+        // `external type FILE`, and is left unchecked
+        if self.current().kind == TokenKind::Type && self.token(self.current + 2).kind != TokenKind::Equal {
+            self.expect(TokenKind::Type);
+            let name_location: TokenLocation = self.current().location();
+            let name: Symbol = self.expect_name();
+            return Statement::External(Box::new(Statement::TypeAlias(name, vec![], name_location)), location);
+        }
+        let external: bool = std::mem::replace(&mut self.external, true);
+        let statement: Statement = self.parse_statement();
+        self.external = external;
+        Statement::External(Box::new(statement), location)
+    }
+    pub fn parse_inline(&mut self) -> Statement {
+        let location: TokenLocation = self.current().location();
+        self.expect(TokenKind::Inline);
+        let statement: Statement = self.parse_statement();
+        Statement::Inline(Box::new(statement), location)
+    }
+    pub fn parse_struct(&mut self) -> Statement {
+        self.expect(TokenKind::Struct);
+        let location: TokenLocation = self.current().location();
+        let name: Symbol = self.expect_name();
+        if self.current().kind == TokenKind::End {
+            self.advance();
+            return Statement::Struct(name, vec![], location);
+        } else {
+            self.expect(TokenKind::Newline);
+        }
+        let mut fields: Vec<(Symbol, Type)> = vec![];
+        while self.in_block(&location) {
+            let start: usize = self.current;
+            if self.current().kind == TokenKind::Newline {
+                self.advance();
+                continue;
+            }
+            let field_name: Symbol = self.expect_name();
+            self.expect(TokenKind::Colon);
+            let field_type: Type = self.parse_type();
+            self.expect(TokenKind::Newline);
+            fields.push((field_name, field_type));
+            self.moved_on(start);
+        }
+        self.expect(TokenKind::End);
+        Statement::Struct(name, fields, location)
+    }
+    pub fn parse_enum(&mut self) -> Statement {
+        self.expect(TokenKind::Enum);
+        let location: TokenLocation = self.current().location();
+        let name: Symbol = self.expect_name();
+        if self.current().kind == TokenKind::OpenParen {
+            self.expect(TokenKind::OpenParen);
+            let mut constructor: Vec<(Symbol, Type)> = vec![];
+            while self.in_list(TokenKind::CloseParen) {
+                let start: usize = self.current;
+                let field_name: Symbol = self.expect_name();
+                self.expect(TokenKind::Colon);
+                let field_type: Type = self.parse_type();
+                constructor.push((field_name, field_type));
+                if self.current().kind == TokenKind::Comma {
+                    self.expect(TokenKind::Comma);
+                }
+                self.moved_on(start);
+            }
+            self.expect(TokenKind::CloseParen);
+            self.expect(TokenKind::Newline);
+            let mut values: Vec<(Symbol, Vec<Expression>)> = vec![];
+            while self.in_block(&location) {
+                let start: usize = self.current;
+                if self.current().kind == TokenKind::Newline {
+                    self.advance();
+                    continue;
+                }
+                let value_name: Symbol = self.expect_name();
+                self.expect(TokenKind::OpenParen);
+                let mut value_fields: Vec<Expression> = vec![];
+                while self.in_list(TokenKind::CloseParen) {
+                    let start: usize = self.current;
+                    value_fields.push(self.parse_expression());
+                    if self.current().kind == TokenKind::Comma {
+                        self.expect(TokenKind::Comma);
+                    }
+                    self.moved_on(start);
+                }
+                self.expect(TokenKind::CloseParen);
+                self.expect(TokenKind::Newline);
+                values.push((value_name, value_fields));
+                self.moved_on(start);
+            }
+            self.expect(TokenKind::End);
+            return Statement::JavaEnum(name, constructor, values, location);
+        }
+        if self.current().kind == TokenKind::Newline {
+            self.expect(TokenKind::Newline);
+            let mut values: Vec<(Symbol, Vec<(Symbol, Type)>)> = vec![];
+            while self.in_block(&location) {
+                let start: usize = self.current;
+                if self.current().kind == TokenKind::Newline {
+                    self.advance();
+                    continue;
+                }
+                let value_name: Symbol = self.expect_name();
+                self.expect(TokenKind::OpenParen);
+                let mut value_fields: Vec<(Symbol, Type)> = vec![];
+                while self.in_list(TokenKind::CloseParen) {
+                    let start: usize = self.current;
+                    let field_name: Symbol = self.expect_name();
+                    self.expect(TokenKind::Colon);
+                    let field_type: Type = self.parse_type();
+                    value_fields.push((field_name, field_type));
+                    if self.current().kind == TokenKind::Comma {
+                        self.expect(TokenKind::Comma);
+                    }
+                    self.moved_on(start);
+                }
+                self.expect(TokenKind::CloseParen);
+                self.expect(TokenKind::Newline);
+                values.push((value_name, value_fields));
+                self.moved_on(start);
+            }
+            self.expect(TokenKind::End);
+            return Statement::StructEnum(name, values, location);
+        }
+        self.expect(TokenKind::Colon);
+        let enum_type: Type = self.parse_type();
+        self.expect(TokenKind::Newline);
+        let mut variants: Vec<(Symbol, Expression, TokenLocation)> = vec![];
+        while self.in_block(&location) {
+            let start: usize = self.current;
+            if self.current().kind == TokenKind::Newline {
+                self.advance();
+                continue;
+            }
+            let value_location: TokenLocation = self.current().location();
+            let variant_name: Symbol = self.expect_name();
+            // Without a value the variant counts up from the previous one
+            let mut variant_value: Expression = Expression::Empty;
+            if self.current().kind == TokenKind::Equal {
+                self.expect(TokenKind::Equal);
+                variant_value = self.parse_expression();
+            }
+            self.expect(TokenKind::Newline);
+            variants.push((variant_name, variant_value, value_location));
+            self.moved_on(start);
+        }
+        self.expect(TokenKind::End);
+        Statement::Enum(name, enum_type, variants, location)
+    }
+    pub fn parse_type_alias(&mut self) -> Statement {
+        self.expect(TokenKind::Type);
+        let location: TokenLocation = self.current().location();
+        let name: Symbol = self.expect_name();
+        self.expect(TokenKind::Equal);
+        let mut types: Vec<Type> = vec![];
+        while self.in_list(TokenKind::Newline) {
+            let start: usize = self.current;
+            types.push(self.parse_type());
+            if self.current().kind == TokenKind::Pipe {
+                self.expect(TokenKind::Pipe);
+            }
+            self.moved_on(start);
+        }
+        Statement::TypeAlias(name, types, location)
+    }
+    pub fn parse_function(&mut self) -> Statement {
+        self.expect(TokenKind::Func);
+        let location: TokenLocation = self.current().location();
+        let mut name: Symbol = self.expect_name();
+        let mut struct_name: Symbol = Symbol::default();
+        if self.current().kind == TokenKind::Dot {
+            struct_name = name;
+            self.expect(TokenKind::Dot);
+            name = self.expect_name();
+        }
+        let mut type_parameters: Vec<(Symbol, Option<Type>)> = vec![];
+        if self.current().kind == TokenKind::OpenBracket {
+            self.expect(TokenKind::OpenBracket);
+            while self.in_list(TokenKind::CloseBracket) {
+                let start: usize = self.current;
+                let type_parameter_name: Symbol = self.expect_name();
+                let mut type_parameter_type: Option<Type> = None;
+                if self.current().kind == TokenKind::Colon {
+                    self.expect(TokenKind::Colon);
+                    type_parameter_type = Some(self.parse_type());
+                }
+                type_parameters.push((type_parameter_name, type_parameter_type));
+                if self.current().kind == TokenKind::Comma {
+                    self.expect(TokenKind::Comma);
+                }
+                self.moved_on(start);
+            }
+            self.expect(TokenKind::CloseBracket);
+        }
+        self.expect(TokenKind::OpenParen);
+        let mut args: Vec<(Symbol, Type)> = vec![];
+        while self.in_list(TokenKind::CloseParen) {
+            let start: usize = self.current;
+            let arg_name: Symbol = self.expect_name();
+            self.expect(TokenKind::Colon);
+            let arg_type: Type = self.parse_type();
+            args.push((arg_name, arg_type));
+            if self.current().kind == TokenKind::Comma {
+                self.expect(TokenKind::Comma);
+            }
+            self.moved_on(start);
+        }
+        self.expect(TokenKind::CloseParen);
+        let mut return_type: Type = Type::Void(self.current().location());
+        if self.current().kind == TokenKind::Colon {
+            self.expect(TokenKind::Colon);
+            return_type = self.parse_type();
+        }
+        let mut body: Vec<Statement> = vec![];
+        if self.external && matches!(self.current().kind, TokenKind::Newline | TokenKind::EndOfFile) {
+            // A prototype, for a function C defines
+        } else if self.current().kind == TokenKind::FatArrow {
+            self.expect(TokenKind::FatArrow);
+            let expression: Expression = self.parse_expression();
+            let return_location: TokenLocation = expression.location();
+            body.push(Statement::Return(expression, return_location));
+            self.expect(TokenKind::Newline);
+        } else {
+            while self.in_block(&location) {
+                if self.current().kind == TokenKind::Newline {
+                    self.expect(TokenKind::Newline);
+                    continue;
+                }
+                let statement: Statement = self.parse_statement();
+                body.push(statement);
+            }
+            self.expect(TokenKind::End);
+        }
+        let mut statement: Statement = if !struct_name.is_empty() {
+            Statement::StructFunction(struct_name, name, args, return_type, body, location.clone())
+        } else {
+            Statement::Function(name, args, return_type, body, location.clone())
+        };
+        if type_parameters.len() > 0 {
+            statement = Statement::Generic(Box::new(statement), type_parameters, location);
+        }
+        statement
+    }
+    pub fn parse_variable(&mut self) -> Statement {
+        self.expect(TokenKind::Var);
+        let location: TokenLocation = self.current().location();
+        let name: Symbol = self.expect_name();
+        let mut t: Type = Type::Unknown(Symbol::default(), self.current().location());
+        if self.current().kind == TokenKind::Colon {
+            self.expect(TokenKind::Colon);
+            t = self.parse_type();
+        }
+        let mut value: Expression = Expression::Empty;
+        if self.current().kind == TokenKind::Equal {
+            self.expect(TokenKind::Equal);
+            value = self.parse_expression();
+        }
+        self.expect(TokenKind::Newline);
+        Statement::Variable(name, t, value, location)
+    }
+    pub fn parse_constant(&mut self) -> Statement {
+        self.expect(TokenKind::Const);
+        let location: TokenLocation = self.current().location();
+        let name: Symbol = self.expect_name();
+        self.expect(TokenKind::Colon);
+        let t: Type = self.parse_type();
+        self.expect(TokenKind::Equal);
+        let value: Expression = self.parse_expression();
+        self.expect(TokenKind::Newline);
+        Statement::Constant(name, t, value, location)
+    }
+    pub fn parse_return(&mut self) -> Statement {
+        self.expect(TokenKind::Return);
+        let value: Expression = self.parse_expression();
+        self.expect(TokenKind::Newline);
+        let location: TokenLocation = value.location();
+        Statement::Return(value, location)
+    }
+    pub fn parse_import(&mut self) -> Statement {
+        let location: TokenLocation = self.current().location();
+        self.expect(TokenKind::Import);
+        let path: String = self.expect(TokenKind::StringLit).value;
+        self.expect(TokenKind::Newline);
+        Statement::Import(path, location)
+    }
+    pub fn parse_while(&mut self) -> Statement {
+        let location: TokenLocation = self.current().location();
+        self.expect(TokenKind::While);
+        let condition: Expression = self.parse_expression();
+        self.expect(TokenKind::Newline);
+        let mut body: Vec<Statement> = vec![];
+        while self.in_block(&location) {
+            if self.current().kind == TokenKind::Newline {
+                self.expect(TokenKind::Newline);
+                continue;
+            }
+            let statement: Statement = self.parse_statement();
+            body.push(statement);
+        }
+        self.expect(TokenKind::End);
+        Statement::While(condition, body, location)
+    }
+    // `for name in a..b` counts from a up to but not including b, and
+    // `for name in array` goes over an array's items
+    pub fn parse_for(&mut self) -> Statement {
+        self.expect(TokenKind::For);
+        let location: TokenLocation = self.current().location();
+        let name: Symbol = self.expect_name();
+        self.expect(TokenKind::In);
+        let iterable: Expression = self.parse_expression();
+        self.expect(TokenKind::Newline);
+        let mut body: Vec<Statement> = vec![];
+        while self.in_block(&location) {
+            if self.current().kind == TokenKind::Newline {
+                self.expect(TokenKind::Newline);
+                continue;
+            }
+            let statement: Statement = self.parse_statement();
+            body.push(statement);
+        }
+        self.expect(TokenKind::End);
+        Statement::For(name, iterable, body, location)
+    }
+    pub fn parse_if(&mut self) -> Statement {
+        let location: TokenLocation = self.current().location();
+        self.expect(TokenKind::If);
+        let condition: Expression = self.parse_expression();
+        self.expect(TokenKind::Newline);
+        let mut body: Vec<Statement> = vec![];
+        while self.in_block(&location) && self.current().kind != TokenKind::Else {
+            if self.current().kind == TokenKind::Newline {
+                self.expect(TokenKind::Newline);
+                continue;
+            }
+            let statement: Statement = self.parse_statement();
+            body.push(statement);
+        }
+        let mut else_body: Vec<Statement> = vec![];
+        if self.current().kind == TokenKind::Else {
+            self.expect(TokenKind::Else);
+            if self.current().kind == TokenKind::If {
+                let statement: Statement = self.parse_if();
+                let else_location: TokenLocation = statement.location();
+                else_body.push(statement);
+                return Statement::If(condition, body, else_body, else_location);
+            }
+            self.expect(TokenKind::Newline);
+            while self.in_block(&location) {
+                if self.current().kind == TokenKind::Newline {
+                    self.expect(TokenKind::Newline);
+                    continue;
+                }
+                let statement: Statement = self.parse_statement();
+                else_body.push(statement);
+            }
+        }
+        self.expect(TokenKind::End);
+        Statement::If(condition, body, else_body, location)
+    }
+
+    pub fn parse_expression(&mut self) -> Expression {
+        self.nested(Parser::parse_ternary).unwrap_or(Expression::Empty)
+    }
+    pub fn parse_ternary(&mut self) -> Expression {
+        let mut expression: Expression = self.parse_assignment();
+        if self.current().kind == TokenKind::If {
+            let location: TokenLocation = self.current().location();
+            self.expect(TokenKind::If);
+            let condition: Expression = self.parse_expression();
+            self.expect(TokenKind::Else);
+            let else_expression: Expression = self.parse_expression();
+            expression = Expression::Ternary(Box::new(condition), Box::new(expression), Box::new(else_expression), location);
+        }
+        expression
+    }
+    pub fn parse_assignment(&mut self) -> Expression {
+        let mut expression: Expression = self.parse_comparison();
+        if self.current().kind == TokenKind::Equal {
+            let location: TokenLocation = self.current().location();
+            self.expect(TokenKind::Equal);
+            let right: Expression = self.parse_expression();
+            expression = Expression::Assignment(Box::new(expression), Box::new(right), location);
+        }
+        expression
+    }
+    pub fn parse_comparison(&mut self) -> Expression {
+        let depth: usize = self.depth;
+        let mut expression: Expression = self.parse_additive();
+        while (self.current().kind == TokenKind::EqualEqual
+            || self.current().kind == TokenKind::BangEqual
+            || self.current().kind == TokenKind::Less
+            || self.current().kind == TokenKind::LessEqual
+            || self.current().kind == TokenKind::Greater
+            || self.current().kind == TokenKind::GreaterEqual)
+            && self.deeper()
+        {
+            let location: TokenLocation = self.current().location();
+            let op: TokenKind = self.current().kind.clone();
+            self.expect(op.clone());
+            let right: Expression = self.parse_additive();
+            expression = Expression::Binary(op, Box::new(expression), Box::new(right), location);
+        }
+        self.depth = depth;
+        expression
+    }
+    pub fn parse_additive(&mut self) -> Expression {
+        let depth: usize = self.depth;
+        let mut expression: Expression = self.parse_multiplicative();
+        while (self.current().kind == TokenKind::Plus || self.current().kind == TokenKind::Minus) && self.deeper() {
+            let location: TokenLocation = self.current().location();
+            let op: TokenKind = self.current().kind.clone();
+            self.expect(op.clone());
+            let right: Expression = self.parse_multiplicative();
+            expression = Expression::Binary(op, Box::new(expression), Box::new(right), location);
+        }
+        self.depth = depth;
+        expression
+    }
+    pub fn parse_multiplicative(&mut self) -> Expression {
+        let depth: usize = self.depth;
+        let mut expression: Expression = self.parse_grouping();
+        while (self.current().kind == TokenKind::Star || self.current().kind == TokenKind::Slash || self.current().kind == TokenKind::Percent) && self.deeper() {
+            let location: TokenLocation = self.current().location();
+            let op: TokenKind = self.current().kind.clone();
+            self.expect(op.clone());
+            let right: Expression = self.parse_unary();
+            expression = Expression::Binary(op, Box::new(expression), Box::new(right), location);
+        }
+        self.depth = depth;
+        expression
+    }
+    pub fn parse_grouping(&mut self) -> Expression {
+        let location: TokenLocation = self.current().location();
+        if self.current().kind == TokenKind::OpenParen {
+            self.expect(TokenKind::OpenParen);
+            let expression: Expression = self.parse_expression();
+            self.expect(TokenKind::CloseParen);
+            Expression::Grouping(Box::new(expression), location)
+        } else {
+            self.parse_unary()
+        }
+    }
+    pub fn parse_unary(&mut self) -> Expression {
+        self.nested(Parser::parse_prefixed).unwrap_or(Expression::Empty)
+    }
+    pub fn parse_prefixed(&mut self) -> Expression {
+        let location: TokenLocation = self.current().location();
+        if self.current().kind == TokenKind::Minus {
+            self.expect(TokenKind::Minus);
+            let expression: Expression = self.parse_unary();
+            Expression::Unary(TokenKind::Minus, Box::new(expression), location)
+        } else if self.current().kind == TokenKind::Bang {
+            self.expect(TokenKind::Bang);
+            let expression: Expression = self.parse_unary();
+            Expression::Unary(TokenKind::Bang, Box::new(expression), location)
+        } else if self.current().kind == TokenKind::Ampersand {
+            self.expect(TokenKind::Ampersand);
+            let expression: Expression = self.parse_unary();
+            Expression::AddressOf(Box::new(expression), location)
+        } else if self.current().kind == TokenKind::Star {
+            self.expect(TokenKind::Star);
+            let expression: Expression = self.parse_unary();
+            Expression::Dereference(Box::new(expression), location)
+        } else {
+            self.parse_index()
+        }
+    }
+    pub fn parse_index(&mut self) -> Expression {
+        let location: TokenLocation = self.current().location();
+        let depth: usize = self.depth;
+        let mut expression: Expression = self.parse_member();
+        let mut indices: Vec<Expression> = Vec::new();
+        let mut is_generic: bool = false;
+        while (self.current().kind == TokenKind::OpenBracket || self.current().kind == TokenKind::Dot) && self.deeper() {
+            if self.current().kind == TokenKind::Dot {
+                let member_location: TokenLocation = self.current().location();
+                self.expect(TokenKind::Dot);
+                expression = Expression::Member(Box::new(expression), Box::new(self.parse_call()), member_location);
+                continue;
+            }
+            self.expect(TokenKind::OpenBracket);
+            indices = Vec::new();
+            while self.in_list(TokenKind::CloseBracket) {
+                let start: usize = self.current;
+                // A pointer to a named type can't be an index, which would need
+                // something after the `*`
+                if self.current().kind == TokenKind::Identifier && self.peek().kind == TokenKind::Star && matches!(self.token(self.current + 2).kind, TokenKind::CloseBracket | TokenKind::Comma) {
+                    let type_location: TokenLocation = self.current().location();
+                    indices.push(Expression::Type(self.parse_type(), type_location));
+                } else {
+                    indices.push(self.parse_expression());
+                }
+                if self.current().kind == TokenKind::Comma {
+                    self.expect(TokenKind::Comma);
+                }
+                self.moved_on(start);
+            }
+            self.expect(TokenKind::CloseBracket);
+            if indices.len() == 1 {
+                expression = Expression::Index(Box::new(expression), Box::new(indices[0].clone()), location.clone());
+            } else {
+                is_generic = true;
+                break;
+            }
+        }
+        self.depth = depth;
+        if self.current().kind == TokenKind::OpenParen || is_generic {
+            expression = match expression {
+                Expression::Index(identifier, _, _) if !is_generic => *identifier,
+                expression => expression,
+            };
+            self.expect(TokenKind::OpenParen);
+            let mut arguments: Vec<Expression> = Vec::new();
+            if self.current().kind != TokenKind::CloseParen {
+                arguments.push(self.parse_expression());
+                while self.current().kind == TokenKind::Comma {
+                    self.expect(TokenKind::Comma);
+                    arguments.push(self.parse_expression());
+                }
+            }
+            self.expect(TokenKind::CloseParen);
+            let mut types: Vec<Type> = Vec::new();
+            for argument in indices.iter() {
+                types.push(match argument {
+                    Expression::Type(t, _) => t.clone(),
+                    // A struct, enum or type parameter, which read as a name
+                    Expression::Identifier(name, location) => Type::Unknown(*name, location.clone()),
+                    argument => Type::Error(self.reported(Diagnostic::syntax_error("E0005", format!("expected Type, but got {:?}", argument), argument.location())), argument.location()),
+                });
+            }
+            let callee: Expression = match expression {
+                Expression::Member(_, member, _) => *member,
+                expression => expression,
+            };
+            let name: Symbol = match callee {
+                Expression::Identifier(name, _) => name,
+                callee => {
+                    self.error(Diagnostic::syntax_error("E0003", format!("Expected identifier, found {:?}", callee), callee.location()));
+                    Symbol::default()
+                }
+            };
+            expression = Expression::GenericCall(name, types, arguments, location);
+        }
+        expression
+    }
+    pub fn parse_member(&mut self) -> Expression {
+        let depth: usize = self.depth;
+        let mut expression: Expression = self.parse_cast();
+        while self.current().kind == TokenKind::Dot && self.deeper() {
+            let location: TokenLocation = self.current().location();
+            self.expect(TokenKind::Dot);
+            expression = Expression::Member(Box::new(expression), Box::new(self.parse_call()), location);
+        }
+        self.depth = depth;
+        expression
+    }
+    pub fn parse_cast(&mut self) -> Expression {
+        let depth: usize = self.depth;
+        let mut expression: Expression = self.parse_range();
+        while self.current().kind == TokenKind::As && self.deeper() {
+            let location: TokenLocation = self.current().location();
+            self.expect(TokenKind::As);
+            let t: Type = self.parse_type();
+            expression = Expression::Cast(Box::new(expression), t, location);
+        }
+        self.depth = depth;
+        expression
+    }
+    pub fn parse_range(&mut self) -> Expression {
+        let depth: usize = self.depth;
+        let mut expression: Expression = self.parse_call();
+        while self.current().kind == TokenKind::Range && self.deeper() {
+            let location: TokenLocation = self.current().location();
+            self.expect(TokenKind::Range);
+            expression = Expression::Range(Box::new(expression), Box::new(self.parse_expression()), location);
+        }
+        self.depth = depth;
+        expression
+    }
+    pub fn parse_call(&mut self) -> Expression {
+        let depth: usize = self.depth;
+        let mut expression: Expression = self.parse_primary();
+        while self.current().kind == TokenKind::OpenParen && self.deeper() {
+            let location: TokenLocation = self.current().location();
+            self.expect(TokenKind::OpenParen);
+            let mut args: Vec<Expression> = vec![];
+            while self.in_list(TokenKind::CloseParen) {
+                let start: usize = self.current;
+                if self.current().kind == TokenKind::Identifier && self.peek().kind == TokenKind::Colon {
+                    let name_location: TokenLocation = self.current().location();
+                    let name: Symbol = self.expect_name();
+                    self.expect(TokenKind::Colon);
+                    let value: Expression = self.parse_expression();
+                    args.push(Expression::NamedArgument(name, Box::new(value), name_location));
+                    if self.current().kind == TokenKind::Comma {
+                        self.expect(TokenKind::Comma);
+                    }
+                    continue;
+                }
+                let arg: Expression = self.parse_expression();
+                args.push(arg);
+                if self.current().kind == TokenKind::Comma {
+                    self.expect(TokenKind::Comma);
+                }
+                self.moved_on(start);
+            }
+            self.expect(TokenKind::CloseParen);
+            let name = match expression {
+                Expression::Identifier(name, _) => name,
+                _ => {
+                    let location: TokenLocation = self.current().location();
+                    self.error(Diagnostic::syntax_error("E0003", format!("Expected identifier, found {:?}", expression), location));
+                    Symbol::default()
+                }
+            };
+            expression = Expression::Call(name, args, location);
+        }
+        self.depth = depth;
+        expression
+    }
+    pub fn parse_primary(&mut self) -> Expression {
+        match self.current().kind.clone() {
+            TokenKind::NumberLit => {
+                let location: TokenLocation = self.current().location();
+                let digits: String = self.expect(TokenKind::NumberLit).value;
+                match digits.parse::<i64>() {
+                    Ok(value) => Expression::Number(value, location),
+                    Err(_) => {
+                        self.error(Diagnostic::syntax_error("E0030", format!("`{}` doesn't fit in 64 bits", digits), location.clone()));
+                        Expression::Number(0, location)
+                    }
+                }
+            }
+            TokenKind::StringLit => {
+                let location: TokenLocation = self.current().location();
+                let value: String = self.expect(TokenKind::StringLit).value;
+                Expression::String(value, location)
+            }
+            TokenKind::CharLit => {
+                let location: TokenLocation = self.current().location();
+                let value: String = self.expect(TokenKind::CharLit).value;
+                Expression::Char(value, location)
+            }
+            TokenKind::True => {
+                let location: TokenLocation = self.current().location();
+                self.expect(TokenKind::True);
+                Expression::Boolean(true, location)
+            }
+            TokenKind::False => {
+                let location: TokenLocation = self.current().location();
+                self.expect(TokenKind::False);
+                Expression::Boolean(false, location)
+            }
+            TokenKind::Identifier => {
+                let location: TokenLocation = self.current().location();
+                let name: Symbol = self.expect_name();
+                Expression::Identifier(name, location)
+            }
+            TokenKind::SizeOf => {
+                let location: TokenLocation = self.current().location();
+                self.expect(TokenKind::SizeOf);
+                let t: Type = self.parse_type();
+                Expression::SizeOf(t, location)
+            }
+            TokenKind::UnsafeCast => {
+                let location: TokenLocation = self.current().location();
+                self.expect(TokenKind::UnsafeCast);
+                self.expect(TokenKind::OpenBracket);
+                let t: Type = self.parse_type();
+                self.expect(TokenKind::CloseBracket);
+                self.expect(TokenKind::OpenParen);
+                let expression: Expression = self.parse_expression();
+                self.expect(TokenKind::CloseParen);
+                Expression::UnsafeCast(Box::new(expression), t, location)
+            }
+            TokenKind::OpenBracket => {
+                let location: TokenLocation = self.current().location();
+                self.expect(TokenKind::OpenBracket);
+                // `[:]` is an empty map and `[key: value, ...]` a map literal
+                if self.current().kind == TokenKind::Colon {
+                    self.expect(TokenKind::Colon);
+                    self.expect(TokenKind::CloseBracket);
+                    return Expression::Map(vec![], location);
+                }
+                let mut values: Vec<Expression> = vec![];
+                let mut entries: Vec<(Expression, Expression)> = vec![];
+                while self.in_list(TokenKind::CloseBracket) {
+                    let start: usize = self.current;
+                    let expression: Expression = self.parse_expression();
+                    if self.current().kind == TokenKind::Colon {
+                        self.expect(TokenKind::Colon);
+                        entries.push((expression, self.parse_expression()));
+                    } else {
+                        values.push(expression);
+                    }
+                    if self.current().kind == TokenKind::Comma {
+                        self.expect(TokenKind::Comma);
+                    }
+                    self.moved_on(start);
+                }
+                self.expect(TokenKind::CloseBracket);
+                if !entries.is_empty() {
+                    if !values.is_empty() {
+                        return Expression::Error(self.reported(Diagnostic::syntax_error("E0026", "every element of a map literal needs a key".to_string(), location)));
+                    }
+                    return Expression::Map(entries, location);
+                }
+                Expression::Array(values, location)
+            }
+            TokenKind::New => {
+                let location: TokenLocation = self.current().location();
+                self.expect(TokenKind::New);
+                let identifier: Symbol = self.expect_name();
+                self.expect(TokenKind::OpenParen);
+                let mut args: Vec<Expression> = vec![];
+                while self.in_list(TokenKind::CloseParen) {
+                    let start: usize = self.current;
+                    let arg: Expression = self.parse_expression();
+                    args.push(arg);
+                    if self.current().kind == TokenKind::Comma {
+                        self.expect(TokenKind::Comma);
+                    }
+                    self.moved_on(start);
+                }
+                self.expect(TokenKind::CloseParen);
+                Expression::New(identifier, args, location)
+            }
+            TokenKind::Null => {
+                self.expect(TokenKind::Null);
+                Expression::Null
+            }
+            TokenKind::Int | TokenKind::String | TokenKind::CString | TokenKind::Char | TokenKind::Usize => {
+                let location: TokenLocation = self.current().location();
+                let t: Type = self.parse_type();
+                Expression::Type(t, location)
+            }
+            kind => {
+                let location: TokenLocation = self.current().location();
+                Expression::Error(self.reported(Diagnostic::syntax_error("E0004", format!("expected Expression, but got {:?}", kind), location)))
+            }
+        }
+    }
+
+    pub fn parse_type(&mut self) -> Type {
+        let location: TokenLocation = self.current().location();
+        self.nested(Parser::parse_any_type).unwrap_or(Type::Void(location))
+    }
+    pub fn parse_any_type(&mut self) -> Type {
+        let location: TokenLocation = self.current().location();
+        let t: Type = match self.current().kind.clone() {
+            TokenKind::Int => {
+                self.expect(TokenKind::Int);
+                Type::Int(location)
+            }
+            TokenKind::Usize => {
+                self.expect(TokenKind::Usize);
+                Type::Usize(location)
+            }
+            TokenKind::String => {
+                self.expect(TokenKind::String);
+                Type::String(location)
+            }
+            TokenKind::CString => {
+                self.expect(TokenKind::CString);
+                Type::CString(location)
+            }
+            TokenKind::Char => {
+                self.expect(TokenKind::Char);
+                Type::Char(location)
+            }
+            TokenKind::Bool => {
+                self.expect(TokenKind::Bool);
+                Type::Bool(location)
+            }
+            TokenKind::Void => {
+                self.expect(TokenKind::Void);
+                Type::Void(location)
+            }
+            TokenKind::Identifier => {
+                let name: Symbol = self.expect_name();
+                if name == "map" && self.current().kind == TokenKind::OpenBracket {
+                    let location: TokenLocation = self.current().location();
+                    self.expect(TokenKind::OpenBracket);
+                    let key: Type = self.parse_type();
+                    self.expect(TokenKind::Comma);
+                    let value: Type = self.parse_type();
+                    self.expect(TokenKind::CloseBracket);
+                    Type::Map(Box::new(key), Box::new(value), location)
+                } else {
+                    Type::Unknown(name, location)
+                }
+            }
+            TokenKind::Func => {
+                self.expect(TokenKind::Func);
+                self.expect(TokenKind::OpenParen);
+                let mut args: Vec<Type> = vec![];
+                while self.in_list(TokenKind::CloseParen) {
+                    let start: usize = self.current;
+                    let arg: Type = self.parse_type();
+                    args.push(arg);
+                    if self.current().kind == TokenKind::Comma {
+                        self.expect(TokenKind::Comma);
+                    }
+                    self.moved_on(start);
+                }
+                self.expect(TokenKind::CloseParen);
+                let location: TokenLocation = self.current().location();
+                let mut return_type: Type = Type::Void(location.clone());
+                if self.current().kind == TokenKind::Colon {
+                    self.expect(TokenKind::Colon);
+                    return_type = self.parse_type();
+                }
+                Type::Function(args, Box::new(return_type), location)
+            }
+            TokenKind::Volatile => {
+                self.expect(TokenKind::Volatile);
+                let t: Type = self.parse_type();
+                Type::Volatile(Box::new(t), location)
+            }
+            TokenKind::Const => {
+                self.expect(TokenKind::Const);
+                let t: Type = self.parse_type();
+                Type::Const(Box::new(t), location)
+            }
+            TokenKind::Restrict => {
+                self.expect(TokenKind::Restrict);
+                let t: Type = self.parse_type();
+                Type::Restrict(Box::new(t), location)
+            }
+            kind => Type::Error(self.reported(Diagnostic::syntax_error("E0005", format!("expected Type, but got {:?}", kind), location.clone())), location),
+        };
+        if self.current().kind == TokenKind::Star {
+            // As many levels as there are stars, as in `void**`
+            let mut t: Type = t;
+            while self.current().kind == TokenKind::Star {
+                let location: TokenLocation = self.current().location();
+                self.expect(TokenKind::Star);
+                t = Type::Pointer(Box::new(t), location);
+            }
+            t
+        } else if self.current().kind == TokenKind::OpenBracket {
+            let location: TokenLocation = self.current().location();
+            self.expect(TokenKind::OpenBracket);
+            if self.current().kind == TokenKind::CloseBracket {
+                self.expect(TokenKind::CloseBracket);
+                return Type::DynamicArray(Box::new(t), location);
+            }
+            let size: Expression = self.parse_expression();
+            self.expect(TokenKind::CloseBracket);
+            Type::Array(Box::new(t), Box::new(size), location)
+        } else {
+            t
+        }
+    }
+
+    pub fn advance(&mut self) {
+        self.current += 1;
+        // Only the token before the current one is looked back at
+        while self.first + 1 < self.current && !self.window.is_empty() {
+            self.window.pop_front();
+            self.first += 1;
+        }
+        self.fill();
+    }
+    // Lexes up to LOOKAHEAD tokens past the current one, unless the file ends
+    // first
+    pub fn fill(&mut self) {
+        while self.first + self.window.len() <= self.current + LOOKAHEAD {
+            let Some(mut token) = self.lexer.next() else {
+                break;
+            };
+            token.location.start += self.offset;
+            token.location.end += self.offset;
+            self.end.location = TokenLocation { start: token.location.end, end: token.location.end };
+            self.window.push_back(token);
+        }
+    }
+    // How many tokens have been lexed, which is all of them once the parser's
+    // at the end of the file
+    pub fn lexed(&self) -> usize {
+        self.first + self.window.len()
+    }
+    pub fn current(&self) -> &Token {
+        self.token(self.current)
+    }
+    // The token after the current one
+    pub fn peek(&self) -> &Token {
+        self.token(self.current + 1)
+    }
+    // Past the last token there's only the end of the file. Only the tokens
+    // from the one before the current one to LOOKAHEAD past it are kept.
+    pub fn token(&self, index: usize) -> &Token {
+        index.checked_sub(self.first).and_then(|index| self.window.get(index)).unwrap_or(&self.end)
+    }
+    // Parses something that can hold more of its kind, unless it's already
+    // nested MAX_DEPTH deep. Then that's reported and the parser gives up on the
+    // rest of the file, which it sees the end of from there on.
+    pub fn nested<T>(&mut self, parse: impl FnOnce(&mut Parser) -> T) -> Option<T> {
+        if !self.deeper() {
+            return None;
+        }
+        let parsed: T = parse(self);
+        self.depth -= 1;
+        Some(parsed)
+    }
+    // Goes a level deeper, unless that's past MAX_DEPTH. A loop that adds an
+    // operator to what it's parsed so far puts that a level deeper each time,
+    // so it calls this for each one and puts `depth` back once it's done: a
+    // long chain of them is as deep a tree as the same number of parentheses.
+    pub fn deeper(&mut self) -> bool {
+        if self.depth >= MAX_DEPTH {
+            if self.current().kind != TokenKind::EndOfFile {
+                let location: TokenLocation = self.current().location();
+                self.errors.push(Diagnostic::syntax_error("E0037", format!("this is nested more than {} deep", MAX_DEPTH), location));
+                while self.current().kind != TokenKind::EndOfFile {
+                    self.advance();
+                }
+            }
+            return false;
+        }
+        self.depth += 1;
+        true
+    }
+    // Reports a syntax error, unless there already is one where it is. A parser
+    // that's lost tends to trip over the same token more than once.
+    pub fn error(&mut self, error: Diagnostic) {
+        if !self.errors.iter().any(|reported| reported.location().start == error.location().start) {
+            self.errors.push(error);
+        }
+    }
+    // The same, for an error that's also kept in the tree
+    pub fn reported(&mut self, error: Diagnostic) -> Diagnostic {
+        self.error(error.clone());
+        error
+    }
+    // The current token when it's of this kind, which is then moved past. When
+    // it isn't, that's reported and an error token comes back in its place. The
+    // end of the file only gets reported if nothing before it has, since
+    // whatever's left open there, such as a block, has been already.
+    pub fn expect(&mut self, kind: TokenKind) -> Token {
+        let token: Token = self.current().clone();
+        if token.kind == kind {
+            self.advance();
+            return token;
+        }
+        let message: String = if token.kind == TokenKind::EndOfFile { "unexpected end of file".to_string() } else { format!("expected {:?}, but got {:?}", kind, token.kind) };
+        if token.kind != TokenKind::EndOfFile || self.errors.is_empty() {
+            let code: &'static str = if kind == TokenKind::Identifier { "E0003" } else { "E0004" };
+            self.error(Diagnostic::syntax_error(code, format!("expected {:?}, but got {:?}", kind, token.kind), token.location.clone()));
+        }
+        Token { kind: TokenKind::Error, value: message, location: token.location }
+    }
+    // Whether a list goes on, which it doesn't at the token that closes it or at
+    // the end of the file, where that token is reported missing
+    pub fn in_list(&mut self, close: TokenKind) -> bool {
+        let kind: TokenKind = self.current().kind.clone();
+        kind != close && kind != TokenKind::EndOfFile
+    }
+    // A turn of a loop that read nothing from `start` on skips the token it's
+    // stuck on, so that every loop comes to an end
+    pub fn moved_on(&mut self, start: usize) {
+        if self.current == start {
+            let token: Token = self.current().clone();
+            self.error(Diagnostic::syntax_error("E0004", format!("unexpected {:?}", token.kind), token.location));
+            self.advance();
+        }
+    }
+    // An identifier's name, interned
+    pub fn expect_name(&mut self) -> Symbol {
+        Symbol::intern(&self.expect(TokenKind::Identifier).value)
+    }
+}
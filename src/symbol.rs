@@ -0,0 +1,149 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::{OnceLock, RwLock};
+// A name, interned so that copying it is free and comparing two of them is one
+// pointer compare:
+//
+//     let name: Symbol = Symbol::intern("main");
+//     assert!(name == "main" && name == Symbol::intern("main"));
+//
+// It orders by its text, so sorting names gives the same order as before they
+// were interned.
+//
+// The interner is the process's rather than a compile's, since a name has to
+// read as its text wherever it ends up, from `Display` to an error message,
+// without anything to look it up in. Only interning takes its lock; reading a
+// name back is following the pointer. Each distinct name is stored once and
+// never freed, so what's kept is bounded by the different names the process
+// has read, not by how often it reads them: the language server checking a
+// file again on every edit only adds the names that are new.
+#[derive(Clone, Copy)] pub struct Symbol(&'static str);
+fn interner() -> &'static RwLock<HashSet<&'static str>> {
+    static INTERNER: OnceLock<RwLock<HashSet<&'static str>>> = OnceLock::new();
+    INTERNER.get_or_init(|| RwLock::new(HashSet::new()))
+}
+impl Symbol {
+    pub fn intern(name: &str) -> Self {
+        if let Some(interned) = interner().read().unwrap_or_else(|poisoned| poisoned.into_inner()).get(name) {
+            return Symbol(interned);
+        }
+        let mut interner = interner().write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(interned) = interner.get(name) {
+            return Symbol(interned);
+        }
+        let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+        interner.insert(name);
+        Symbol(name)
+    }
+    pub fn as_str(&self) -> &'static str {
+        self.0
+    }
+}
+// There's one copy of each name, so where it is tells names apart
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.0, other.0)
+    }
+}
+impl Eq for Symbol {}
+impl std::hash::Hash for Symbol {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::ptr::hash(self.0, state)
+    }
+}
+impl Default for Symbol {
+    fn default() -> Self {
+        Symbol::intern("")
+    }
+}
+impl std::ops::Deref for Symbol {
+    type Target = str;
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Symbol::intern(name)
+    }
+}
+impl From<String> for Symbol {
+    fn from(name: String) -> Self {
+        Symbol::intern(&name)
+    }
+}
+impl From<&String> for Symbol {
+    fn from(name: &String) -> Self {
+        Symbol::intern(name)
+    }
+}
+impl From<Symbol> for String {
+    fn from(symbol: Symbol) -> Self {
+        symbol.as_str().to_string()
+    }
+}
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+impl PartialEq<Symbol> for String {
+    fn eq(&self, other: &Symbol) -> bool {
+        self == other.as_str()
+    }
+}
+impl PartialEq<Symbol> for &str {
+    fn eq(&self, other: &Symbol) -> bool {
+        *self == other.as_str()
+    }
+}
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        if self == other { std::cmp::Ordering::Equal } else { self.as_str().cmp(other.as_str()) }
+    }
+}
+// Both print the text, as the String a name used to be would
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+// Written out as the name itself, and interned again when read back
+#[cfg(feature = "serde")]
+impl serde::Serialize for Symbol {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Symbol {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name: String = serde::Deserialize::deserialize(deserializer)?;
+        Ok(Symbol::intern(&name))
+    }
+}
@@ -0,0 +1,118 @@
+use crate::ast::{Expression, Type};
+// The machine the generated C is compiled for, which decides how big `int`
+// and `usize` are. Given with --target= as a known machine, optionally followed
+// by overrides, or just the overrides on top of the host:
+//
+//     --target=avr
+//     --target=arm,endian=big
+//     --target=pointer=32,int=16,long=32
+#[derive(Debug, Clone, PartialEq)] pub struct TargetMachine {
+    pub name: String,
+    pub pointer_width: u32,
+    pub short_width: u32,
+    pub int_width: u32,
+    pub long_width: u32,
+    pub long_long_width: u32,
+    pub big_endian: bool,
+}
+impl TargetMachine {
+    // The machines a target can start with, which `from_name` knows the widths of
+    pub const NAMES: [&'static str; 10] = ["host", "x86_64", "aarch64", "riscv64", "i686", "arm", "riscv32", "wasm32", "avr", "msp430"];
+    pub fn host() -> Self {
+        Self {
+            name: "host".to_string(),
+            pointer_width: usize::BITS,
+            short_width: 16,
+            int_width: 32,
+            // Windows keeps `long` at 32 bits even on 64-bit machines
+            long_width: if cfg!(windows) { 32 } else { usize::BITS },
+            long_long_width: 64,
+            big_endian: cfg!(target_endian = "big"),
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (pointer_width, int_width, long_width): (u32, u32, u32) = match name {
+            "host" => return Some(TargetMachine::host()),
+            "x86_64" | "aarch64" | "riscv64" => (64, 32, 64),
+            "i686" | "arm" | "riscv32" | "wasm32" => (32, 32, 32),
+            "avr" | "msp430" => (16, 16, 32),
+            _ => return None,
+        };
+        Some(Self { name: name.to_string(), pointer_width, short_width: 16, int_width, long_width, long_long_width: 64, big_endian: false })
+    }
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut parts: std::iter::Peekable<std::str::Split<char>> = spec.split(',').peekable();
+        let mut target: TargetMachine = match parts.peek().and_then(|name| TargetMachine::from_name(name)) {
+            Some(target) => {
+                parts.next();
+                target
+            }
+            None => TargetMachine::host(),
+        };
+        for part in parts {
+            let (key, value): (&str, &str) = match part.split_once('=') {
+                Some(pair) => pair,
+                None => return Err(format!("unknown target `{}`, expected `c`, `c++`, a machine or `key=value`", part)),
+            };
+            if key == "endian" {
+                target.big_endian = match value {
+                    "little" => false,
+                    "big" => true,
+                    _ => return Err(format!("unknown endianness `{}`, expected `little` or `big`", value)),
+                };
+                continue;
+            }
+            let width: u32 = match value.parse() {
+                Ok(width @ (8 | 16 | 32 | 64)) => width,
+                _ => return Err(format!("`{}` is not a width of 8, 16, 32 or 64 bits", value)),
+            };
+            match key {
+                "pointer" => target.pointer_width = width,
+                "short" => target.short_width = width,
+                "int" => target.int_width = width,
+                "long" => target.long_width = width,
+                "long-long" => target.long_long_width = width,
+                _ => return Err(format!("unknown target property `{}`", key)),
+            }
+        }
+        if spec.contains('=') {
+            target.name = spec.to_string();
+        }
+        Ok(target)
+    }
+    // The values a literal may have when it's stored as `t`. A `char` may hold
+    // anything that fits in a byte, signed or not
+    pub fn range(&self, t: &Type) -> Option<(i128, i128)> {
+        match t {
+            Type::Int(_) => Some((-(1i128 << (self.int_width - 1)), (1i128 << (self.int_width - 1)) - 1)),
+            Type::Usize(_) => Some((0, (1i128 << self.pointer_width) - 1)),
+            Type::Char(_) => Some((-128, 255)),
+            _ => None,
+        }
+    }
+    // The size in bytes of a type whose layout doesn't depend on the C compiler
+    pub fn size_of(&self, t: &Type) -> Option<i64> {
+        match t {
+            Type::Int(_) => Some(self.int_width as i64 / 8),
+            Type::Usize(_) | Type::CString(_) | Type::Pointer(_, _) | Type::DynamicArray(_, _) | Type::Map(_, _, _) => Some(self.pointer_width as i64 / 8),
+            Type::Char(_) | Type::Bool(_) => Some(1),
+            Type::Array(t, size, _) => match **size {
+                Expression::Number(size, _) => self.size_of(t)?.checked_mul(size),
+                _ => None,
+            },
+            Type::Const(t, _) | Type::Volatile(t, _) | Type::Restrict(t, _) => self.size_of(t),
+            _ => None,
+        }
+    }
+    // Fails to compile the generated code with a C compiler for some other machine
+    pub fn assertions(&self, cpp: bool) -> String {
+        let assert: &str = if cpp { "static_assert" } else { "_Static_assert" };
+        let mut code: String = String::new();
+        for (t, width) in [("void*", self.pointer_width), ("short", self.short_width), ("int", self.int_width), ("long", self.long_width), ("long long", self.long_long_width)] {
+            code.push_str(&format!("{}(sizeof({}) * 8 == {}, \"generated for a {}-bit `{}` (--target={})\");\n", assert, t, width, width, t, self.name));
+        }
+        let order: &str = if self.big_endian { "__ORDER_BIG_ENDIAN__" } else { "__ORDER_LITTLE_ENDIAN__" };
+        code.push_str(&format!("#if defined(__BYTE_ORDER__) && __BYTE_ORDER__ != {}\n#error \"generated for a {} endian machine (--target={})\"\n#endif\n", order, if self.big_endian { "big" } else { "little" }, self.name));
+        code
+    }
+}
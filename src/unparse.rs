@@ -0,0 +1,268 @@
+use crate::ast::{Annotation, Expression, Statement, Type};
+use crate::checker::Checker;
+use crate::lexer::TokenKind;
+use crate::symbol::Symbol;
+// Writes a syntax tree back out as source, in one canonical layout: a tab per
+// block level, a blank line around top-level declarations that take more than
+// a line, and parentheses only where the tree has a grouping or would read
+// differently without one. Comments and the original layout aren't in the
+// tree, so `sl fmt` works from tokens instead; this is for source generated
+// or rewritten from a tree, which parses back into the same one.
+pub struct Unparser;
+impl Unparser {
+    pub fn unparse(statements: &[Statement]) -> String {
+        let mut code: String = String::new();
+        let mut multiline: bool = false;
+        for statement in statements.iter() {
+            let text: String = Unparser::statement(statement, 0);
+            let lines: bool = text.lines().count() > 1;
+            if !code.is_empty() && (multiline || lines) {
+                code.push('\n');
+            }
+            code.push_str(&text);
+            multiline = lines;
+        }
+        code
+    }
+    // A statement as lines indented `depth` tabs, each ending in a newline
+    pub fn statement(statement: &Statement, depth: usize) -> String {
+        let indent: String = "\t".repeat(depth);
+        match statement {
+            Statement::Generic(statement, type_parameters, _) => Unparser::function(statement, type_parameters, depth),
+            Statement::Annotated(statement, annotations, _) => {
+                let annotations: String = annotations.iter().map(|annotation| format!("{}{}\n", indent, Unparser::annotation(annotation))).collect();
+                annotations + &Unparser::statement(statement, depth)
+            }
+            Statement::Annotation(name, fields, _) => Unparser::fields_block(&format!("{}annotation {}", indent, name), fields, depth),
+            Statement::Struct(name, fields, _) => Unparser::fields_block(&format!("{}struct {}", indent, name), fields, depth),
+            Statement::Enum(name, t, variants, _) => {
+                let mut code: String = format!("{}enum {}: {}\n", indent, name, Unparser::type_(t));
+                for (variant, value, _) in variants.iter() {
+                    match value {
+                        Expression::Empty => code.push_str(&format!("{}\t{}\n", indent, variant)),
+                        value => code.push_str(&format!("{}\t{} = {}\n", indent, variant, Unparser::expression(value))),
+                    }
+                }
+                code + &indent + "end\n"
+            }
+            Statement::StructEnum(name, variants, _) => {
+                let mut code: String = format!("{}enum {}\n", indent, name);
+                for (variant, fields) in variants.iter() {
+                    code.push_str(&format!("{}\t{}({})\n", indent, variant, Unparser::parameters(fields)));
+                }
+                code + &indent + "end\n"
+            }
+            Statement::JavaEnum(name, fields, values, _) => {
+                let mut code: String = format!("{}enum {}({})\n", indent, name, Unparser::parameters(fields));
+                for (value, args) in values.iter() {
+                    code.push_str(&format!("{}\t{}({})\n", indent, value, Unparser::expressions(args)));
+                }
+                code + &indent + "end\n"
+            }
+            Statement::TypeAlias(name, types, _) if types.is_empty() => format!("{}type {}\n", indent, name),
+            Statement::TypeAlias(name, types, _) => {
+                let types: Vec<String> = types.iter().map(Unparser::type_).collect();
+                format!("{}type {} = {}\n", indent, name, types.join(" | "))
+            }
+            Statement::Function(..) | Statement::StructFunction(..) => Unparser::function(statement, &[], depth),
+            Statement::Variable(name, t, value, _) => {
+                let mut code: String = format!("{}var {}", indent, name);
+                if !matches!(t, Type::Unknown(name, _) if name.is_empty()) {
+                    code.push_str(&format!(": {}", Unparser::type_(t)));
+                }
+                if !matches!(value, Expression::Empty) {
+                    code.push_str(&format!(" = {}", Unparser::expression(value)));
+                }
+                code + "\n"
+            }
+            Statement::Constant(name, t, value, _) => format!("{}const {}: {} = {}\n", indent, name, Unparser::type_(t), Unparser::expression(value)),
+            Statement::Return(Expression::Empty, _) => format!("{}return\n", indent),
+            Statement::Return(value, _) => format!("{}return {}\n", indent, Unparser::expression(value)),
+            Statement::While(condition, body, _) => {
+                format!("{}while {}\n{}{}end\n", indent, Unparser::expression(condition), Unparser::block(body, depth + 1), indent)
+            }
+            Statement::For(name, iterable, body, _) => {
+                format!("{}for {} in {}\n{}{}end\n", indent, name, Unparser::expression(iterable), Unparser::block(body, depth + 1), indent)
+            }
+            Statement::Break(_) => format!("{}break\n", indent),
+            Statement::Continue(_) => format!("{}continue\n", indent),
+            Statement::If(..) => format!("{}{}", indent, Unparser::conditional(statement, depth)),
+            // A function C defines is only its signature
+            Statement::External(statement, _) if matches!(&**statement, Statement::Function(_, _, _, body, _) if body.is_empty()) => {
+                format!("{}external {}\n", indent, Unparser::statement(statement, depth).trim().trim_end_matches(" end"))
+            }
+            Statement::External(statement, _) => format!("{}external {}", indent, Unparser::statement(statement, depth).trim_start()),
+            Statement::Inline(statement, _) => format!("{}inline {}", indent, Unparser::statement(statement, depth).trim_start()),
+            Statement::Import(path, _) => format!("{}import \"{}\"\n", indent, Unparser::escape(path, '"')),
+            Statement::Expression(expression, _) => format!("{}{}\n", indent, Unparser::expression(expression)),
+        }
+    }
+    pub fn block(body: &[Statement], depth: usize) -> String {
+        body.iter().map(|statement| Unparser::statement(statement, depth)).collect()
+    }
+    // An `if` from the keyword on, with an `else` holding only another `if`
+    // written as `else if`, which closes both with one `end`
+    pub fn conditional(statement: &Statement, depth: usize) -> String {
+        let indent: String = "\t".repeat(depth);
+        let Statement::If(condition, body, else_body, _) = statement else {
+            return Unparser::statement(statement, depth);
+        };
+        let mut code: String = format!("if {}\n{}", Unparser::expression(condition), Unparser::block(body, depth + 1));
+        match else_body.as_slice() {
+            [] => code.push_str(&format!("{}end\n", indent)),
+            [else_if @ Statement::If(..)] => code.push_str(&format!("{}else {}", indent, Unparser::conditional(else_if, depth))),
+            else_body => code.push_str(&format!("{}else\n{}{}end\n", indent, Unparser::block(else_body, depth + 1), indent)),
+        }
+        code
+    }
+    // A function or struct function, with the type parameters of a generic one
+    // after its name
+    pub fn function(statement: &Statement, type_parameters: &[(Symbol, Option<Type>)], depth: usize) -> String {
+        let indent: String = "\t".repeat(depth);
+        let (name, args, return_type, body): (String, &[(Symbol, Type)], &Type, &[Statement]) = match statement {
+            Statement::Function(name, args, return_type, body, _) => (name.to_string(), args, return_type, body),
+            Statement::StructFunction(struct_name, name, args, return_type, body, _) => (format!("{}.{}", struct_name, name), args, return_type, body),
+            statement => return Unparser::statement(statement, depth),
+        };
+        let mut code: String = format!("{}func {}", indent, name);
+        if !type_parameters.is_empty() {
+            let type_parameters: Vec<String> = type_parameters.iter().map(|(name, constraint)| match constraint {
+                Some(constraint) => format!("{}: {}", name, Unparser::type_(constraint)),
+                None => name.to_string(),
+            }).collect();
+            code.push_str(&format!("[{}]", type_parameters.join(", ")));
+        }
+        code.push_str(&format!("({})", Unparser::parameters(args)));
+        if !matches!(return_type, Type::Void(_)) {
+            code.push_str(&format!(": {}", Unparser::type_(return_type)));
+        }
+        if body.is_empty() {
+            return code + " end\n";
+        }
+        format!("{}\n{}{}end\n", code, Unparser::block(body, depth + 1), indent)
+    }
+    pub fn fields_block(header: &str, fields: &[(Symbol, Type)], depth: usize) -> String {
+        if fields.is_empty() {
+            return format!("{} end\n", header);
+        }
+        let fields: String = fields.iter().map(|(name, t)| format!("{}\t{}: {}\n", "\t".repeat(depth), name, Unparser::type_(t))).collect();
+        format!("{}\n{}{}end\n", header, fields, "\t".repeat(depth))
+    }
+    pub fn parameters(fields: &[(Symbol, Type)]) -> String {
+        fields.iter().map(|(name, t)| format!("{}: {}", name, Unparser::type_(t))).collect::<Vec<String>>().join(", ")
+    }
+    pub fn annotation(annotation: &Annotation) -> String {
+        if annotation.arguments.is_empty() {
+            return format!("@{}", annotation.name);
+        }
+        format!("@{}({})", annotation.name, Unparser::expressions(&annotation.arguments))
+    }
+    pub fn expressions(expressions: &[Expression]) -> String {
+        expressions.iter().map(Unparser::expression).collect::<Vec<String>>().join(", ")
+    }
+    // How tightly an expression binds, following the parser: a ternary the
+    // loosest, then assignment, comparisons, `+`, `*`, prefix operators, casts
+    // and ranges, and everything written as a single term
+    pub fn precedence(expression: &Expression) -> u8 {
+        match expression {
+            Expression::Ternary(..) => 0,
+            Expression::Assignment(..) => 1,
+            Expression::Binary(TokenKind::Plus | TokenKind::Minus, _, _, _) => 3,
+            Expression::Binary(TokenKind::Star | TokenKind::Slash | TokenKind::Percent, _, _, _) => 4,
+            Expression::Binary(..) => 2,
+            Expression::Unary(..) | Expression::AddressOf(..) | Expression::Dereference(..) => 5,
+            Expression::Cast(..) | Expression::Range(..) => 6,
+            _ => 7,
+        }
+    }
+    // An operand, in parentheses if it binds looser than where it is needs
+    pub fn operand(expression: &Expression, precedence: u8) -> String {
+        if Unparser::precedence(expression) < precedence {
+            return format!("({})", Unparser::expression(expression));
+        }
+        Unparser::expression(expression)
+    }
+    pub fn expression(expression: &Expression) -> String {
+        match expression {
+            Expression::Number(value, _) => value.to_string(),
+            Expression::String(value, _) => format!("\"{}\"", Unparser::escape(value, '"')),
+            Expression::Char(value, _) => format!("'{}'", Unparser::escape(value, '\'')),
+            Expression::Boolean(value, _) => value.to_string(),
+            Expression::Identifier(name, _) => name.to_string(),
+            Expression::Null => "null".to_string(),
+            Expression::Call(name, args, _) => format!("{}({})", name, Unparser::expressions(args)),
+            Expression::GenericCall(name, types, args, _) => {
+                let types: Vec<String> = types.iter().map(Unparser::type_).collect();
+                format!("{}[{}]({})", name, types.join(", "), Unparser::expressions(args))
+            }
+            Expression::Member(left, right, _) => format!("{}.{}", Unparser::operand(left, 7), Unparser::expression(right)),
+            Expression::NamedArgument(name, value, _) => format!("{}: {}", name, Unparser::expression(value)),
+            // Casts chain, but a range's end would take in the `as`
+            Expression::Cast(value, t, _) if matches!(**value, Expression::Cast(..)) => format!("{} as {}", Unparser::expression(value), Unparser::type_(t)),
+            Expression::Cast(value, t, _) => format!("{} as {}", Unparser::operand(value, 7), Unparser::type_(t)),
+            Expression::UnsafeCast(value, t, _) => format!("unsafe_cast[{}]({})", Unparser::type_(t), Unparser::expression(value)),
+            Expression::SizeOf(t, _) => format!("sizeof {}", Unparser::type_(t)),
+            Expression::Index(left, index, _) => format!("{}[{}]", Unparser::operand(left, 7), Unparser::expression(index)),
+            Expression::Array(elements, _) => format!("[{}]", Unparser::expressions(elements)),
+            Expression::Map(entries, _) if entries.is_empty() => "[:]".to_string(),
+            Expression::Map(entries, _) => {
+                let entries: Vec<String> = entries.iter().map(|(key, value)| format!("{}: {}", Unparser::expression(key), Unparser::expression(value))).collect();
+                format!("[{}]", entries.join(", "))
+            }
+            Expression::New(name, args, _) => format!("new {}({})", name, Unparser::expressions(args)),
+            Expression::Ternary(condition, then, otherwise, _) => {
+                format!("{} if {} else {}", Unparser::operand(then, 1), Unparser::operand(condition, 1), Unparser::expression(otherwise))
+            }
+            Expression::Assignment(left, right, _) => format!("{} = {}", Unparser::operand(left, 2), Unparser::expression(right)),
+            Expression::Binary(op, left, right, _) => {
+                let precedence: u8 = Unparser::precedence(expression);
+                format!("{} {} {}", Unparser::operand(left, precedence), Checker::operator(op), Unparser::operand(right, precedence + 1))
+            }
+            Expression::Unary(op, value, _) => format!("{}{}", Checker::operator(op), Unparser::operand(value, 5)),
+            Expression::Grouping(value, _) => format!("({})", Unparser::expression(value)),
+            Expression::AddressOf(value, _) => format!("&{}", Unparser::operand(value, 5)),
+            Expression::Dereference(value, _) => format!("*{}", Unparser::operand(value, 5)),
+            Expression::Range(from, to, _) => format!("{}..{}", Unparser::operand(from, 7), Unparser::expression(to)),
+            Expression::Type(t, _) => Unparser::type_(t),
+            Expression::Error(_) | Expression::Empty => String::new(),
+        }
+    }
+    pub fn type_(t: &Type) -> String {
+        match t {
+            Type::Function(args, return_type, _) => {
+                let args: Vec<String> = args.iter().map(Unparser::type_).collect();
+                match **return_type {
+                    Type::Void(_) => format!("func({})", args.join(", ")),
+                    ref return_type => format!("func({}): {}", args.join(", "), Unparser::type_(return_type)),
+                }
+            }
+            Type::Pointer(t, _) => format!("{}*", Unparser::type_(t)),
+            Type::Array(t, size, _) => format!("{}[{}]", Unparser::type_(t), Unparser::expression(size)),
+            Type::DynamicArray(t, _) => format!("{}[]", Unparser::type_(t)),
+            Type::Map(key, value, _) => format!("map[{}, {}]", Unparser::type_(key), Unparser::type_(value)),
+            Type::Volatile(t, _) => format!("volatile {}", Unparser::type_(t)),
+            Type::Const(t, _) => format!("const {}", Unparser::type_(t)),
+            Type::Restrict(t, _) => format!("restrict {}", Unparser::type_(t)),
+            t => t.name(),
+        }
+    }
+    // A string or character's value with the escapes the lexer reads
+    pub fn escape(value: &str, quote: char) -> String {
+        let mut code: String = String::new();
+        for character in value.chars() {
+            match character {
+                '\n' => code.push_str("\\n"),
+                '\t' => code.push_str("\\t"),
+                '\r' => code.push_str("\\r"),
+                '\0' => code.push_str("\\0"),
+                '\\' => code.push_str("\\\\"),
+                character if character == quote => {
+                    code.push('\\');
+                    code.push(character);
+                }
+                character => code.push(character),
+            }
+        }
+        code
+    }
+}
@@ -0,0 +1,166 @@
+use crate::ast::{Annotation, Expression, Statement, Type};
+use crate::symbol::Symbol;
+// A pass over the syntax tree that only looks at it. Each method walks into
+// the node's children by default, so an implementation overrides the ones it
+// cares about and calls the matching `walk_` function to keep going deeper,
+// or doesn't to skip the node's children.
+pub trait Visitor {
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement);
+    }
+    fn visit_expression(&mut self, expression: &Expression) {
+        walk_expression(self, expression);
+    }
+    fn visit_type(&mut self, t: &Type) {
+        walk_type(self, t);
+    }
+}
+pub fn walk_statements<V: Visitor + ?Sized>(visitor: &mut V, statements: &[Statement]) {
+    for statement in statements.iter() {
+        visitor.visit_statement(statement);
+    }
+}
+pub fn walk_fields<V: Visitor + ?Sized>(visitor: &mut V, fields: &[(Symbol, Type)]) {
+    for (_, t) in fields.iter() {
+        visitor.visit_type(t);
+    }
+}
+pub fn walk_annotation<V: Visitor + ?Sized>(visitor: &mut V, annotation: &Annotation) {
+    for arg in annotation.arguments.iter() {
+        visitor.visit_expression(arg);
+    }
+}
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &Statement) {
+    match statement {
+        Statement::Generic(statement, type_parameters, _) => {
+            for constraint in type_parameters.iter().filter_map(|(_, constraint)| constraint.as_ref()) {
+                visitor.visit_type(constraint);
+            }
+            visitor.visit_statement(statement);
+        }
+        Statement::Annotated(statement, annotations, _) => {
+            for annotation in annotations.iter() {
+                walk_annotation(visitor, annotation);
+            }
+            visitor.visit_statement(statement);
+        }
+        Statement::External(statement, _) | Statement::Inline(statement, _) => visitor.visit_statement(statement),
+        Statement::Annotation(_, fields, _) | Statement::Struct(_, fields, _) => walk_fields(visitor, fields),
+        Statement::Enum(_, t, variants, _) => {
+            visitor.visit_type(t);
+            for (_, value, _) in variants.iter() {
+                visitor.visit_expression(value);
+            }
+        }
+        Statement::StructEnum(_, variants, _) => {
+            for (_, fields) in variants.iter() {
+                walk_fields(visitor, fields);
+            }
+        }
+        Statement::JavaEnum(_, fields, values, _) => {
+            walk_fields(visitor, fields);
+            for (_, args) in values.iter() {
+                for arg in args.iter() {
+                    visitor.visit_expression(arg);
+                }
+            }
+        }
+        Statement::TypeAlias(_, types, _) => {
+            for t in types.iter() {
+                visitor.visit_type(t);
+            }
+        }
+        Statement::Function(_, args, return_type, body, _) | Statement::StructFunction(_, _, args, return_type, body, _) => {
+            walk_fields(visitor, args);
+            visitor.visit_type(return_type);
+            walk_statements(visitor, body);
+        }
+        Statement::Variable(_, t, value, _) | Statement::Constant(_, t, value, _) => {
+            visitor.visit_type(t);
+            visitor.visit_expression(value);
+        }
+        Statement::Return(value, _) | Statement::Expression(value, _) => visitor.visit_expression(value),
+        Statement::While(condition, body, _) => {
+            visitor.visit_expression(condition);
+            walk_statements(visitor, body);
+        }
+        Statement::For(_, iterable, body, _) => {
+            visitor.visit_expression(iterable);
+            walk_statements(visitor, body);
+        }
+        Statement::If(condition, body, else_body, _) => {
+            visitor.visit_expression(condition);
+            walk_statements(visitor, body);
+            walk_statements(visitor, else_body);
+        }
+        Statement::Break(_) | Statement::Continue(_) | Statement::Import(_, _) => {}
+    }
+}
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &Expression) {
+    match expression {
+        Expression::Call(_, args, _) | Expression::New(_, args, _) | Expression::Array(args, _) => {
+            for arg in args.iter() {
+                visitor.visit_expression(arg);
+            }
+        }
+        Expression::GenericCall(_, types, args, _) => {
+            for t in types.iter() {
+                visitor.visit_type(t);
+            }
+            for arg in args.iter() {
+                visitor.visit_expression(arg);
+            }
+        }
+        Expression::Member(left, right, _)
+        | Expression::Index(left, right, _)
+        | Expression::Assignment(left, right, _)
+        | Expression::Binary(_, left, right, _)
+        | Expression::Range(left, right, _) => {
+            visitor.visit_expression(left);
+            visitor.visit_expression(right);
+        }
+        Expression::Ternary(condition, then, otherwise, _) => {
+            visitor.visit_expression(condition);
+            visitor.visit_expression(then);
+            visitor.visit_expression(otherwise);
+        }
+        Expression::Cast(value, t, _) | Expression::UnsafeCast(value, t, _) => {
+            visitor.visit_expression(value);
+            visitor.visit_type(t);
+        }
+        Expression::NamedArgument(_, value, _)
+        | Expression::Unary(_, value, _)
+        | Expression::Grouping(value, _)
+        | Expression::AddressOf(value, _)
+        | Expression::Dereference(value, _) => visitor.visit_expression(value),
+        Expression::Map(entries, _) => {
+            for (key, value) in entries.iter() {
+                visitor.visit_expression(key);
+                visitor.visit_expression(value);
+            }
+        }
+        Expression::SizeOf(t, _) | Expression::Type(t, _) => visitor.visit_type(t),
+        Expression::Number(..) | Expression::String(..) | Expression::Char(..) | Expression::Boolean(..) | Expression::Identifier(..)
+        | Expression::Null | Expression::Error(_) | Expression::Empty => {}
+    }
+}
+pub fn walk_type<V: Visitor + ?Sized>(visitor: &mut V, t: &Type) {
+    match t {
+        Type::Function(args, return_type, _) => {
+            for arg in args.iter() {
+                visitor.visit_type(arg);
+            }
+            visitor.visit_type(return_type);
+        }
+        Type::Array(t, size, _) => {
+            visitor.visit_type(t);
+            visitor.visit_expression(size);
+        }
+        Type::Map(key, value, _) => {
+            visitor.visit_type(key);
+            visitor.visit_type(value);
+        }
+        Type::Pointer(t, _) | Type::DynamicArray(t, _) | Type::Volatile(t, _) | Type::Const(t, _) | Type::Restrict(t, _) => visitor.visit_type(t),
+        _ => {}
+    }
+}
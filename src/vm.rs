@@ -0,0 +1,430 @@
+use crate::bytecode::{Bytecode, BytecodeFunction, Constant, NATIVES, Op};
+#[derive(Debug, Clone)] pub enum Value {
+    Int(i64),
+    String(std::rc::Rc<str>),
+    // Structs, and what points to them
+    Object(std::rc::Rc<std::cell::RefCell<Vec<Value>>>),
+    Array(std::rc::Rc<std::cell::RefCell<Vec<Value>>>),
+    Function(u16),
+    Null,
+}
+impl Value {
+    pub fn is_true(&self) -> bool {
+        !matches!(self, Value::Int(0) | Value::Null)
+    }
+    pub fn equals(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Int(left), Value::Int(right)) => left == right,
+            (Value::String(left), Value::String(right)) => left == right,
+            (Value::Object(left), Value::Object(right)) | (Value::Array(left), Value::Array(right)) => std::rc::Rc::ptr_eq(left, right),
+            (Value::Function(left), Value::Function(right)) => left == right,
+            // A null pointer is zero, like in C
+            (Value::Null | Value::Int(0), Value::Null | Value::Int(0)) => true,
+            _ => false,
+        }
+    }
+}
+pub struct Frame {
+    pub function: u16,
+    pub ip: usize,
+    pub base: usize,
+    // Whether the function value is below the arguments, to be dropped as well
+    pub from_value: bool,
+}
+// Runs bytecode. Errors at runtime end the program, as a crash in C would.
+pub struct Vm<'a> {
+    pub bytecode: &'a Bytecode,
+    pub stack: Vec<Value>,
+    pub frames: Vec<Frame>,
+    pub globals: Vec<Value>,
+}
+impl<'a> Vm<'a> {
+    pub const MAX_FRAMES: usize = 10_000;
+    pub fn new(bytecode: &'a Bytecode) -> Self {
+        Self { bytecode, stack: vec![], frames: vec![], globals: vec![Value::Null; bytecode.globals as usize] }
+    }
+    // Returns what main returned, which becomes the exit code
+    pub fn run(&mut self, args: &[String]) -> Result<i64, String> {
+        self.stack.push(Value::Int(args.len() as i64));
+        self.stack.push(Value::Array(std::rc::Rc::new(std::cell::RefCell::new(args.iter().map(|arg| Value::String(arg.as_str().into())).collect()))));
+        self.call(self.bytecode.entry, 2, false)?;
+        loop {
+            let op: Op = match Op::from_byte(self.read_u8()?) {
+                Some(op) => op,
+                None => return Err("invalid instruction".to_string()),
+            };
+            match op {
+                Op::Constant => {
+                    let index: usize = self.read_u16()? as usize;
+                    let value: Value = match self.bytecode.constants.get(index) {
+                        Some(Constant::Int(value)) => Value::Int(*value),
+                        Some(Constant::String(value)) => Value::String(value.as_str().into()),
+                        None => return Err("invalid constant".to_string()),
+                    };
+                    self.stack.push(value);
+                }
+                Op::Null => self.stack.push(Value::Null),
+                Op::Pop => {
+                    self.pop()?;
+                }
+                Op::Dup => {
+                    let value: Value = self.peek()?;
+                    self.stack.push(value);
+                }
+                Op::GetLocal => {
+                    let slot: usize = self.base() + self.read_u16()? as usize;
+                    let value: Value = self.stack.get(slot).cloned().ok_or("invalid local")?;
+                    self.stack.push(value);
+                }
+                Op::SetLocal => {
+                    let slot: usize = self.base() + self.read_u16()? as usize;
+                    let value: Value = self.peek()?;
+                    *self.stack.get_mut(slot).ok_or("invalid local")? = value;
+                }
+                Op::GetGlobal => {
+                    let slot: usize = self.read_u16()? as usize;
+                    let value: Value = self.globals.get(slot).cloned().ok_or("invalid global")?;
+                    self.stack.push(value);
+                }
+                Op::SetGlobal => {
+                    let slot: usize = self.read_u16()? as usize;
+                    let value: Value = self.peek()?;
+                    *self.globals.get_mut(slot).ok_or("invalid global")? = value;
+                }
+                Op::Function => {
+                    let index: u16 = self.read_u16()?;
+                    self.stack.push(Value::Function(index));
+                }
+                Op::Add | Op::Subtract | Op::Multiply | Op::Divide | Op::Remainder | Op::Less | Op::LessEqual | Op::Greater | Op::GreaterEqual => {
+                    let right: i64 = self.pop_int()?;
+                    let left: i64 = self.pop_int()?;
+                    let value: i64 = match op {
+                        Op::Add => left.wrapping_add(right),
+                        Op::Subtract => left.wrapping_sub(right),
+                        Op::Multiply => left.wrapping_mul(right),
+                        Op::Divide | Op::Remainder if right == 0 => return Err("division by zero".to_string()),
+                        Op::Divide => left.wrapping_div(right),
+                        Op::Remainder => left.wrapping_rem(right),
+                        Op::Less => (left < right) as i64,
+                        Op::LessEqual => (left <= right) as i64,
+                        Op::Greater => (left > right) as i64,
+                        _ => (left >= right) as i64,
+                    };
+                    self.stack.push(Value::Int(value));
+                }
+                Op::Negate => {
+                    let value: i64 = self.pop_int()?;
+                    self.stack.push(Value::Int(value.wrapping_neg()));
+                }
+                Op::Not => {
+                    let value: Value = self.pop()?;
+                    self.stack.push(Value::Int(!value.is_true() as i64));
+                }
+                Op::Equal | Op::NotEqual => {
+                    let right: Value = self.pop()?;
+                    let left: Value = self.pop()?;
+                    self.stack.push(Value::Int((left.equals(&right) == (op == Op::Equal)) as i64));
+                }
+                Op::Jump => {
+                    let offset: i32 = self.read_i32()?;
+                    self.jump(offset)?;
+                }
+                Op::JumpIfFalse => {
+                    let offset: i32 = self.read_i32()?;
+                    if !self.pop()?.is_true() {
+                        self.jump(offset)?;
+                    }
+                }
+                Op::Call => {
+                    let function: u16 = self.read_u16()?;
+                    let count: u8 = self.read_u8()?;
+                    self.call(function, count, false)?;
+                }
+                Op::CallValue => {
+                    let count: usize = self.read_u8()? as usize;
+                    let callee: usize = self.stack.len().checked_sub(count + 1).ok_or("stack underflow")?;
+                    match self.stack[callee] {
+                        Value::Function(function) => self.call(function, count as u8, true)?,
+                        Value::Null | Value::Int(0) => return Err("call through a null function pointer".to_string()),
+                        _ => return Err("call of something that isn't a function".to_string()),
+                    }
+                }
+                Op::CallNative => {
+                    let native: u16 = self.read_u16()?;
+                    let count: usize = self.read_u8()? as usize;
+                    let start: usize = self.stack.len().checked_sub(count).ok_or("stack underflow")?;
+                    let args: Vec<Value> = self.stack.split_off(start);
+                    let value: Value = Vm::call_native(native, &args)?;
+                    self.stack.push(value);
+                }
+                Op::Return => {
+                    let value: Value = self.pop()?;
+                    let frame: Frame = self.frames.pop().ok_or("return outside of a function")?;
+                    if self.frames.is_empty() {
+                        return match value {
+                            Value::Int(code) => Ok(code),
+                            _ => Ok(0),
+                        };
+                    }
+                    self.stack.truncate(frame.base - frame.from_value as usize);
+                    self.stack.push(value);
+                }
+                Op::Object => {
+                    let count: usize = self.read_u16()? as usize;
+                    self.stack.push(Value::Object(std::rc::Rc::new(std::cell::RefCell::new(vec![Value::Int(0); count]))));
+                }
+                Op::GetField => {
+                    let field: usize = self.read_u16()? as usize;
+                    let value: Value = match self.pop()? {
+                        Value::Object(object) => object.borrow().get(field).cloned().ok_or("invalid field")?,
+                        _ => return Err("null pointer dereference".to_string()),
+                    };
+                    self.stack.push(value);
+                }
+                Op::SetField => {
+                    let field: usize = self.read_u16()? as usize;
+                    let value: Value = self.pop()?;
+                    match self.pop()? {
+                        Value::Object(object) => *object.borrow_mut().get_mut(field).ok_or("invalid field")? = value.clone(),
+                        _ => return Err("null pointer dereference".to_string()),
+                    }
+                    self.stack.push(value);
+                }
+                Op::Copy => {
+                    let value: Value = match self.pop()? {
+                        Value::Object(object) => Value::Object(std::rc::Rc::new(std::cell::RefCell::new(object.borrow().clone()))),
+                        value => value,
+                    };
+                    self.stack.push(value);
+                }
+                Op::Array => {
+                    let count: usize = self.read_u16()? as usize;
+                    let start: usize = self.stack.len().checked_sub(count).ok_or("stack underflow")?;
+                    let elements: Vec<Value> = self.stack.split_off(start);
+                    self.stack.push(Value::Array(std::rc::Rc::new(std::cell::RefCell::new(elements))));
+                }
+                Op::ArrayOf => {
+                    let length: usize = self.read_u32()? as usize;
+                    self.stack.push(Value::Array(std::rc::Rc::new(std::cell::RefCell::new(vec![Value::Int(0); length]))));
+                }
+                Op::Index => {
+                    let index: i64 = self.pop_int()?;
+                    let array = self.pop_array()?;
+                    let value: Value = array.borrow().get(index as usize).cloned().ok_or_else(|| Vm::out_of_bounds(index, array.borrow().len()))?;
+                    self.stack.push(value);
+                }
+                Op::SetIndex => {
+                    let value: Value = self.pop()?;
+                    let index: i64 = self.pop_int()?;
+                    let array = self.pop_array()?;
+                    let length: usize = array.borrow().len();
+                    *array.borrow_mut().get_mut(index as usize).ok_or_else(|| Vm::out_of_bounds(index, length))? = value.clone();
+                    self.stack.push(value);
+                }
+                Op::ArrayPush => {
+                    let value: Value = self.pop()?;
+                    self.pop_array()?.borrow_mut().push(value.clone());
+                    self.stack.push(value);
+                }
+                Op::ArrayPop => {
+                    let value: Value = self.pop_array()?.borrow_mut().pop().ok_or("pop from an empty array")?;
+                    self.stack.push(value);
+                }
+                Op::ArrayLength => {
+                    let length: usize = match self.pop()? {
+                        Value::Array(array) => array.borrow().len(),
+                        _ => 0,
+                    };
+                    self.stack.push(Value::Int(length as i64));
+                }
+            }
+        }
+    }
+    pub fn call(&mut self, function: u16, count: u8, from_value: bool) -> Result<(), String> {
+        let callee: &BytecodeFunction = self.bytecode.functions.get(function as usize).ok_or("invalid function")?;
+        if callee.arity != count {
+            return Err(format!("`{}` takes {} argument(s) but {} were given", callee.name, callee.arity, count));
+        }
+        if self.frames.len() >= Vm::MAX_FRAMES {
+            return Err("stack overflow".to_string());
+        }
+        let base: usize = self.stack.len().checked_sub(count as usize).ok_or("stack underflow")?;
+        for _ in count as u16..callee.locals {
+            self.stack.push(Value::Null);
+        }
+        self.frames.push(Frame { function, ip: 0, base, from_value });
+        Ok(())
+    }
+    pub fn call_native(native: u16, args: &[Value]) -> Result<Value, String> {
+        use std::io::Write;
+        let int = |index: usize| match args.get(index) {
+            Some(Value::Int(value)) => Ok(*value),
+            Some(Value::Null) => Ok(0),
+            _ => Err(format!("`{}` expects a number", NATIVES[native as usize])),
+        };
+        let string = |index: usize| match args.get(index) {
+            Some(Value::String(value)) => Ok(Vm::c_string(value).to_string()),
+            _ => Err(format!("`{}` expects a string", NATIVES[native as usize])),
+        };
+        let mut stdout = std::io::stdout();
+        let written: usize = match NATIVES.get(native as usize).copied() {
+            Some("printf") => {
+                let output: String = format_printf(&string(0)?, &args[1..])?;
+                let _ = stdout.write_all(output.as_bytes());
+                output.len()
+            }
+            Some("puts") => {
+                let _ = writeln!(stdout, "{}", string(0)?);
+                1
+            }
+            Some("putchar") => {
+                let _ = stdout.write_all(&[int(0)? as u8]);
+                int(0)? as usize
+            }
+            Some("exit") => {
+                let _ = stdout.flush();
+                std::process::exit(int(0)? as i32);
+            }
+            Some("strlen") => string(0)?.len(),
+            Some("abs") => int(0)?.unsigned_abs() as usize,
+            Some(_) => 0,
+            None => return Err("invalid native function".to_string()),
+        };
+        Ok(Value::Int(written as i64))
+    }
+    // What C sees of a string, which ends at its first NUL
+    pub fn c_string(value: &str) -> &str {
+        value.split('\0').next().unwrap_or_default()
+    }
+    pub fn out_of_bounds(index: i64, length: usize) -> String {
+        format!("index {} is out of bounds for an array of length {}", index, length)
+    }
+    pub fn base(&self) -> usize {
+        self.frames.last().map(|frame| frame.base).unwrap_or(0)
+    }
+    pub fn pop(&mut self) -> Result<Value, String> {
+        self.stack.pop().ok_or_else(|| "stack underflow".to_string())
+    }
+    pub fn peek(&self) -> Result<Value, String> {
+        self.stack.last().cloned().ok_or_else(|| "stack underflow".to_string())
+    }
+    pub fn pop_int(&mut self) -> Result<i64, String> {
+        match self.pop()? {
+            Value::Int(value) => Ok(value),
+            Value::Null => Ok(0),
+            _ => Err("arithmetic on something that isn't a number".to_string()),
+        }
+    }
+    pub fn pop_array(&mut self) -> Result<std::rc::Rc<std::cell::RefCell<Vec<Value>>>, String> {
+        match self.pop()? {
+            Value::Array(array) => Ok(array),
+            _ => Err("null pointer dereference".to_string()),
+        }
+    }
+    pub fn jump(&mut self, offset: i32) -> Result<(), String> {
+        let frame: &mut Frame = self.frames.last_mut().ok_or("jump outside of a function")?;
+        frame.ip = frame.ip.checked_add_signed(offset as isize).ok_or("invalid jump")?;
+        Ok(())
+    }
+    pub fn read_u8(&mut self) -> Result<u8, String> {
+        let frame: &mut Frame = self.frames.last_mut().ok_or("no function is running")?;
+        let byte: u8 = *self.bytecode.functions[frame.function as usize].code.get(frame.ip).ok_or("ran past the end of a function")?;
+        frame.ip += 1;
+        Ok(byte)
+    }
+    pub fn read_u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes([self.read_u8()?, self.read_u8()?]))
+    }
+    pub fn read_u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes([self.read_u8()?, self.read_u8()?, self.read_u8()?, self.read_u8()?]))
+    }
+    pub fn read_i32(&mut self) -> Result<i32, String> {
+        Ok(self.read_u32()? as i32)
+    }
+}
+// printf for the VM: flags, width, precision and length modifiers are
+// understood, with every number held as an i64
+pub fn format_printf(format: &str, args: &[Value]) -> Result<String, String> {
+    let mut output: String = String::new();
+    let mut args = args.iter();
+    let mut characters = format.chars().peekable();
+    while let Some(character) = characters.next() {
+        if character != '%' {
+            output.push(character);
+            continue;
+        }
+        let mut flags: String = String::new();
+        while let Some(flag) = characters.next_if(|c| "-+ 0#".contains(*c)) {
+            flags.push(flag);
+        }
+        let number = |characters: &mut std::iter::Peekable<std::str::Chars>, args: &mut std::slice::Iter<Value>| -> Option<usize> {
+            if characters.next_if_eq(&'*').is_some() {
+                return match args.next() {
+                    Some(Value::Int(value)) => Some(*value as usize),
+                    _ => None,
+                };
+            }
+            let mut digits: String = String::new();
+            while let Some(digit) = characters.next_if(|c| c.is_ascii_digit()) {
+                digits.push(digit);
+            }
+            digits.parse().ok()
+        };
+        let width: Option<usize> = number(&mut characters, &mut args);
+        let precision: Option<usize> = characters.next_if_eq(&'.').map(|_| number(&mut characters, &mut args).unwrap_or(0));
+        while characters.next_if(|c| "hlzjt".contains(*c)).is_some() {}
+        let conversion: char = characters.next().ok_or("a format string ends in the middle of a conversion")?;
+        if conversion == '%' {
+            output.push('%');
+            continue;
+        }
+        let arg: Value = args.next().cloned().ok_or("printf has fewer arguments than its format string needs")?;
+        let int: i64 = match &arg {
+            Value::Int(value) => *value,
+            _ => 0,
+        };
+        let mut text: String = match conversion {
+            'd' | 'i' => {
+                let digits: String = int.unsigned_abs().to_string();
+                let digits: String = match precision {
+                    Some(precision) => format!("{:0>precision$}", digits),
+                    None => digits,
+                };
+                let sign: &str = if int < 0 { "-" } else if flags.contains('+') { "+" } else if flags.contains(' ') { " " } else { "" };
+                format!("{}{}", sign, digits)
+            }
+            'u' => (int as u64).to_string(),
+            'x' => format!("{}{:x}", if flags.contains('#') && int != 0 { "0x" } else { "" }, int as u64),
+            'X' => format!("{}{:X}", if flags.contains('#') && int != 0 { "0X" } else { "" }, int as u64),
+            'o' => format!("{:o}", int as u64),
+            'c' => ((int as u8) as char).to_string(),
+            's' => match &arg {
+                Value::String(value) => match precision {
+                    Some(precision) => Vm::c_string(value).chars().take(precision).collect(),
+                    None => Vm::c_string(value).to_string(),
+                },
+                Value::Null => "(null)".to_string(),
+                _ => return Err("`%s` expects a string".to_string()),
+            },
+            'p' => match &arg {
+                Value::Null => "(nil)".to_string(),
+                Value::Object(object) | Value::Array(object) => format!("{:p}", object.as_ptr()),
+                _ => format!("{:#x}", int),
+            },
+            conversion => return Err(format!("`%{}` isn't supported by the VM's printf", conversion)),
+        };
+        if let Some(width) = width {
+            let padding: usize = width.saturating_sub(text.chars().count());
+            if flags.contains('-') {
+                text.push_str(&" ".repeat(padding));
+            } else if flags.contains('0') && precision.is_none() && "diuxXo".contains(conversion) {
+                let sign: usize = if text.starts_with(['-', '+', ' ']) { 1 } else { 0 };
+                text.insert_str(sign, &"0".repeat(padding));
+            } else {
+                text.insert_str(0, &" ".repeat(padding));
+            }
+        }
+        output.push_str(&text);
+    }
+    Ok(output)
+}
@@ -1,3 +1 @@
-cargo run test.sl
-gcc -o test test.c
-./test
\ No newline at end of file
+cargo run -- run test.sl
\ No newline at end of file
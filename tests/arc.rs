@@ -0,0 +1,43 @@
+use scripting_language::compile::{compile, CompileOptions, CompileOutput};
+
+const PROGRAM: &str = "import \"std/stdlib.h\"
+
+struct Node
+\tvalue: int
+end
+
+func Node.constructor(value: int): Node*
+\tvar self: Node* = malloc(sizeof Node) as Node*
+\tself.value = value
+\treturn self
+end
+
+struct Point
+\tx: int
+end
+
+func main(): int
+\tvar n: Node* = new Node(1)
+\tvar m: Node* = n
+\tvar point: Point
+\tpoint.x = 2
+\tvar p: Point* = &point
+\tvar q: Point* = p
+\treturn m.value + q.x
+end
+";
+
+// Whether a pointer is counted is decided by its type: a struct that's only
+// ever made by `new` is, and one the program also points at some other way
+// isn't, so the runtime never looks for a count that isn't there
+#[test]
+fn counts_by_type() {
+    let options: CompileOptions = CompileOptions { arc: true, ..CompileOptions::default() };
+    let output: CompileOutput = compile(PROGRAM, &options).unwrap();
+    assert!(output.code.contains("sl_rc_alloc(sizeof(struct Node)"), "{}", output.code);
+    assert!(output.code.contains("sl_retain(n)"), "{}", output.code);
+    assert!(output.code.contains("sl_release(n)"), "{}", output.code);
+    assert!(!output.code.contains("sl_retain(p)"), "{}", output.code);
+    assert!(!output.code.contains("sl_release(p)") && !output.code.contains("sl_release(q)"), "{}", output.code);
+    assert!(!output.code.contains("magic"), "{}", output.code);
+}
@@ -0,0 +1,40 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+// Builds and runs a program read from stdin, which needs a C compiler
+fn run(source: &str, args: &[&str]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_scripting-language"))
+        .arg("run")
+        .args(args)
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(source.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+const LITERALS: &str = "func main(): int
+\tvar xs: int[] = [1, 2]
+\tvar ys: int[] = []
+\txs.push(3)
+\tys.push(4)
+\tprintln(\"{} {} {} {}\", xs.len(), xs[0], xs[2], ys[0])
+\tys = [7, 8, 9]
+\tprintln(\"{} {}\", ys.len(), ys[2])
+\treturn 0
+end
+";
+
+// Array literals given to a `T[]`, empty or not, used to become a brace
+// initializer for a pointer
+#[test]
+fn literals_make_dynamic_arrays() {
+    for args in [&[][..], &["--release"][..]] {
+        let output: Output = run(LITERALS, args);
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "3 1 3 4\n3 9\n");
+    }
+}
@@ -0,0 +1,62 @@
+use scripting_language::diagnostics::Diagnostic;
+use scripting_language::ide::Ide;
+
+// The codes of a file's errors and warnings, in the order they're found
+fn codes(source: &str) -> Vec<&'static str> {
+    let ide: Ide = Ide::new("test.sl".to_string(), source.to_string());
+    ide.diagnostics().iter().map(|diagnostic: &&Diagnostic| diagnostic.code).collect()
+}
+
+const STRUCTS: &str = "struct A
+\tx: int
+end
+struct B
+\ty: int
+end
+";
+
+// C casts nothing to or from a struct value, so neither does `unsafe_cast`,
+// but a pointer to one can still be reinterpreted
+#[test]
+fn struct_casts() {
+    for cast in ["a as B", "unsafe_cast[B](a)", "unsafe_cast[B](1)"] {
+        let source: String = format!("{}func main(): int\n\tvar a: A\n\tvar b: B = {}\n\treturn b.y\nend\n", STRUCTS, cast);
+        assert_eq!(codes(&source), ["E0019"], "{}", cast);
+    }
+    for cast in ["a as A", "unsafe_cast[A](a)", "*unsafe_cast[A*](&a)"] {
+        let source: String = format!("{}func main(): int\n\tvar a: A\n\tvar b: A = {}\n\treturn b.x\nend\n", STRUCTS, cast);
+        assert_eq!(codes(&source), Vec::<&str>::new(), "{}", cast);
+    }
+}
+
+// A `for` loop counts through a range of integers or goes over an array, and
+// a range is good for nothing else
+#[test]
+fn for_loops() {
+    for (iterable, expected) in [("0..3", vec![]), ("[1, 2, 3]", vec![]), ("numbers", vec![]), ("0..true", vec!["E0025"]), ("n", vec!["E0025"]), ("null", vec!["E0025"])] {
+        let source: String = format!("func main(): int\n\tvar numbers: int[3] = [1, 2, 3]\n\tvar n: int = 3\n\tvar total: int = 0\n\tfor x in {}\n\t\ttotal = total + numbers[0] + n\n\tend\n\treturn total\nend\n", iterable);
+        assert_eq!(codes(&source), expected, "{}", iterable);
+    }
+    assert_eq!(codes("func main(): int\n\tvar r: int = 1..10\n\treturn r\nend\n"), ["E0025"]);
+}
+
+// A map itself isn't gone over, its keys or values are
+#[test]
+fn map_loops() {
+    for (iterable, expected) in [("ages.keys()", vec![]), ("ages.values()", vec![]), ("ages", vec!["E0025"])] {
+        let source: String = format!("func main(): int\n\tvar ages: map[cstring, int] = [\"ada\": 36]\n\tvar total: int = 0\n\tfor x in {}\n\t\ttotal = total + 1\n\tend\n\treturn total\nend\n", iterable);
+        assert_eq!(codes(&source), expected, "{}", iterable);
+    }
+}
+
+// Each use of something deprecated is one warning, and the first also points
+// at where it was marked
+#[test]
+fn deprecated_label() {
+    let source: &str = "@deprecated(\"use area\")\nfunc size(): int => 1\nfunc main(): int\n\tvar a: int = size()\n\tvar b: int = size()\n\treturn a + b\nend\n";
+    let ide: Ide = Ide::new("test.sl".to_string(), source.to_string());
+    let diagnostics: Vec<&Diagnostic> = ide.diagnostics();
+    assert_eq!(diagnostics.iter().map(|diagnostic| diagnostic.code).collect::<Vec<&str>>(), ["W0006", "W0006"]);
+    let labels: Vec<(usize, &str)> = diagnostics.iter().flat_map(|diagnostic| diagnostic.labels.iter()).map(|label| (label.location.start, label.message.as_str())).collect();
+    assert_eq!(labels, [(source.find("size").unwrap(), "`size` was marked deprecated here")]);
+}
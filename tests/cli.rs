@@ -0,0 +1,227 @@
+use std::process::{Command, Output};
+
+fn sl(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_scripting-language")).args(args).output().unwrap()
+}
+
+// A directory of its own for a test's files, gone once it's dropped
+struct Scratch(std::path::PathBuf);
+impl Scratch {
+    fn new(name: &str) -> Self {
+        let directory: std::path::PathBuf = std::env::temp_dir().join(format!("sl-cli-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        Scratch(directory)
+    }
+    fn file(&self, name: &str, contents: &str) -> String {
+        let path: std::path::PathBuf = self.0.join(name);
+        std::fs::write(&path, contents).unwrap();
+        path.display().to_string()
+    }
+    fn path(&self, name: &str) -> String {
+        self.0.join(name).display().to_string()
+    }
+}
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+// A compiled program isn't text, so it's only checked for being there before
+// it's run
+#[test]
+fn runs_bytecode() {
+    let scratch: Scratch = Scratch::new("bytecode");
+    let source: String = scratch.file("answer.sl", "func main(): int\n\tprintln(\"{}\", 42)\n\treturn 0\nend\n");
+    let bytecode: String = scratch.path("answer.slbc");
+    assert!(sl(&["build", "--bytecode", "-o", &bytecode, &source]).status.success());
+    let output: Output = sl(&["run", &bytecode]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "42\n", "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(output.status.success());
+    assert_eq!(sl(&["run", &scratch.path("missing.slbc")]).status.code(), Some(4));
+}
+
+#[test]
+fn exit_codes() {
+    let scratch: Scratch = Scratch::new("exit");
+    let good: String = scratch.file("good.sl", "func main(): int\n\treturn 0\nend\n");
+    let bad: String = scratch.file("bad.sl", "func main(): int\n\tprintln(\"{} {}\", 1)\n\treturn 0\nend\n");
+    assert_eq!(sl(&["check", &good]).status.code(), Some(0));
+    assert_eq!(sl(&["check", &bad]).status.code(), Some(1));
+    assert_eq!(sl(&["check", "--bogus", &good]).status.code(), Some(2));
+    assert_eq!(sl(&["check", &scratch.path("missing.sl")]).status.code(), Some(4));
+}
+
+#[test]
+fn explain_help() {
+    for flag in ["--help", "-h"] {
+        let output: Output = sl(&["explain", flag]);
+        assert!(output.status.success());
+        assert!(String::from_utf8(output.stdout).unwrap().starts_with("Usage: sl explain"));
+    }
+    assert!(sl(&["explain", "E0001"]).status.success());
+    assert_eq!(sl(&["explain", "E9999"]).status.code(), Some(2));
+}
+
+// `run` builds somewhere else, but a header next to the program is still
+// found
+#[test]
+fn runs_with_local_header() {
+    let scratch: Scratch = Scratch::new("header");
+    scratch.file("local.h", "static int answer(void) { return 42; }\n");
+    let source: String = scratch.file("main.sl", "import \"local.h\"\nexternal func answer(): int\n\nfunc main(): int\n\tprintln(\"{}\", answer())\n\treturn 0\nend\n");
+    let output: Output = sl(&["run", &source]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "42\n", "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(output.status.success());
+}
+
+// Both backends go through a range and an array the same way, skipping and
+// stopping where they're told to
+#[test]
+fn runs_for_loops() {
+    let scratch: Scratch = Scratch::new("for");
+    let source: String = scratch.file("loops.sl", "func main(): int
+\tvar total: int = 0
+\tfor i in 0..10
+\t\tif i == 3
+\t\t\tcontinue
+\t\tend
+\t\tif i == 6
+\t\t\tbreak
+\t\tend
+\t\ttotal = total + i
+\tend
+\tvar numbers: int[] = [10, 20]
+\tfor n in numbers
+\t\ttotal = total + n
+\tend
+\tprintln(\"{}\", total)
+\treturn 0
+end
+");
+    for backend in [None, Some("--bytecode")] {
+        let args: Vec<&str> = ["run"].into_iter().chain(backend).chain([source.as_str()]).collect();
+        let output: Output = sl(&args);
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "42\n", "{:?} {}", backend, String::from_utf8_lossy(&output.stderr));
+        assert!(output.status.success());
+    }
+}
+
+// A map's keys and values come out in the same order
+#[test]
+fn runs_map_loops() {
+    let scratch: Scratch = Scratch::new("map");
+    let source: String = scratch.file("ages.sl", "func main(): int
+\tvar ages: map[cstring, int] = [\"ada\": 36, \"alan\": 41, \"grace\": 85]
+\tvar keys: cstring[] = ages.keys()
+\tvar values: int[] = ages.values()
+\tvar total: int = 0
+\tvar i: usize = 0
+\tfor name in keys
+\t\tif ages[name] == values[i]
+\t\t\ttotal = total + values[i]
+\t\tend
+\t\ti = i + 1
+\tend
+\tprintln(\"{}\", total)
+\treturn 0
+end
+");
+    let output: Output = sl(&["run", &source]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "162\n", "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(output.status.success());
+}
+
+// sl.toml's lists can go over several lines, with a comma after the last
+// item and comments between them
+#[test]
+fn manifest_lists() {
+    let scratch: Scratch = Scratch::new("manifest");
+    scratch.file("main.sl", "func main(): int\n\treturn helper()\nend\n");
+    scratch.file("helper.sl", "func helper(): int\n\treturn 0\nend\n");
+    let check = |manifest: &str| -> Output {
+        scratch.file("sl.toml", manifest);
+        Command::new(env!("CARGO_BIN_EXE_scripting-language")).arg("check").current_dir(&scratch.0).output().unwrap()
+    };
+    for manifest in [
+        "sources = [\"main.sl\", \"helper.sl\",]\n",
+        "sources = [\n\t\"main.sl\",  # where it starts\n\t# \"other.sl\",\n\t\"helper.sl\",\n]\nfeatures = [\n\t\"release\"\n]\n",
+    ] {
+        let output: Output = check(manifest);
+        assert!(output.status.success(), "{}{}", manifest, String::from_utf8_lossy(&output.stderr));
+    }
+    let output: Output = check("name = \"game\"\nsources = [\n\t\"main.sl\",\n");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("line 2: the list is never closed"), "{}", String::from_utf8_lossy(&output.stdout));
+}
+
+const SMALL_HEADER: &str = "#include <stddef.h>
+struct point { long x; unsigned long y; };
+typedef struct point point;
+unsigned long twice(unsigned long x) { return 2 * x; }
+long long negate(long long x) { return -x; }
+size_t measure(const char* text, unsigned char mark, short width, _Bool flag);
+void move(point* p, long dx, double scale);
+int sum(int count, ...);
+";
+
+const SMALL_BINDINGS: &str = "// Declarations from small.h, written by `sl bindgen`
+import \"small.h\"
+
+external type unsigned_long
+external type long_long
+external type unsigned_char
+external type short
+external type long
+external type double
+
+external struct point
+\tx: long
+\ty: unsigned_long
+end
+
+external func twice(x: unsigned_long): unsigned_long
+external func negate(x: long_long): long_long
+external func measure(text: const char*, mark: unsigned_char, width: short, flag: bool): usize
+external func move(p: point*, dx: long, scale: double)
+
+// Left out, since .sl can't declare them, though they can still be
+// called through the import:
+//   sum: it takes a variable number of arguments
+";
+
+// C's integer types are .sl's own where it has them and `external type`s
+// named as C writes them otherwise, which build against the header again
+#[test]
+fn bindgen_integers() {
+    let scratch: Scratch = Scratch::new("bindgen");
+    let header: String = scratch.file("small.h", SMALL_HEADER);
+    let output: Output = Command::new(env!("CARGO_BIN_EXE_scripting-language")).args(["bindgen", "small.h"]).current_dir(&scratch.0).output().unwrap();
+    assert!(output.status.success(), "{} {}", header, String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), SMALL_BINDINGS);
+    scratch.file("small.sl", SMALL_BINDINGS);
+    let source: String = scratch.file("main.sl", "import \"small.sl\"
+
+func main(): int
+\tvar x: unsigned_long = twice(21)
+\tvar y: long_long = negate(-21)
+\tprintln(\"{}\", (x as int) + (y as int))
+\treturn 0
+end
+");
+    let output: Output = sl(&["run", &source]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "63\n", "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+// A warning made an error is still a lint, not a type error
+#[test]
+fn denied_warnings() {
+    let scratch: Scratch = Scratch::new("deny");
+    let source: String = scratch.file("unused.sl", "func main(): int\n\tvar x: int = 1\n\treturn 0\nend\n");
+    for flags in [&["-Wunused", "-Werror"][..], &["-Werror=unused"]] {
+        let output: Output = sl(&[&["check", "--color=never"], flags, &[source.as_str()]].concat());
+        assert_eq!(output.status.code(), Some(1));
+        let printed: String = format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+        assert!(printed.contains("] error[W0002]: `x` is never used"), "{}", printed);
+    }
+}
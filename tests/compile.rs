@@ -0,0 +1,93 @@
+use scripting_language::compile::{compile, fuzz, CompileOptions, CompileOutput};
+use std::process::Command;
+
+const PROGRAM: &str = "import \"std/stdio.h\"
+
+struct Point
+\tx: int
+\ty: int
+end
+
+func main(): int
+\tvar p: Point* = new Point(1, 2)
+\tprintf(\"%d\\n\", p.x + p.y)
+\treturn 0
+end
+";
+
+// `sl emit` and `compile` run the same pipeline, so the files written for a
+// program are what `compile` gives back for it
+#[test]
+fn emit_matches_compile() {
+    let directory: std::path::PathBuf = std::env::temp_dir().join(format!("sl-compile-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let filename: std::path::PathBuf = directory.join("point.sl");
+    std::fs::write(&filename, PROGRAM).unwrap();
+    for flags in [vec![], vec!["--release"], vec!["--arc"], vec!["--target=c++"]] {
+        let status = Command::new(env!("CARGO_BIN_EXE_scripting-language")).arg("emit").args(&flags).arg(&filename).status().unwrap();
+        assert!(status.success());
+        let options: CompileOptions = CompileOptions {
+            filename: filename.display().to_string(),
+            release: flags.contains(&"--release"),
+            arc: flags.contains(&"--arc"),
+            cpp: flags.contains(&"--target=c++"),
+            ..CompileOptions::default()
+        };
+        let output: CompileOutput = compile(PROGRAM, &options).unwrap();
+        let (code, header): (&str, &str) = if options.cpp { ("cpp", "hpp") } else { ("c", "h") };
+        assert_eq!(std::fs::read_to_string(filename.with_extension(code)).unwrap(), output.code, "{:?}", flags);
+        assert_eq!(std::fs::read_to_string(filename.with_extension(header)).unwrap(), output.header, "{:?}", flags);
+    }
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+// Input a fuzzer would try first: nothing, stray and unclosed tokens, deep
+// nesting and long chains of operators, and bytes that aren't UTF-8. Tests run
+// on threads with 2MiB of stack, which is what nesting is limited to fit in.
+#[test]
+fn fuzz_malformed_input() {
+    let deep = |body: String| format!("func main(): int\n\treturn {}\nend\n", body).into_bytes();
+    let inputs: Vec<Vec<u8>> = vec![
+        b"".to_vec(),
+        b"\n\n\t".to_vec(),
+        b"\"".to_vec(),
+        b"'".to_vec(),
+        b"func".to_vec(),
+        b"func main(".to_vec(),
+        b"struct S\n\tx: int".to_vec(),
+        b"end end end".to_vec(),
+        b"var x: int = 99999999999999999999999999".to_vec(),
+        b"func f[T](x: T): T => x\nfunc main()\n\tf[int[int[int]]](1)\nend".to_vec(),
+        b"import \"std/missing.sl\"".to_vec(),
+        b"\xff\xfe\x00func\x80".to_vec(),
+        deep(format!("{}1{}", "(".repeat(500), ")".repeat(500))),
+        deep(format!("{}1{}", "[".repeat(500), "]".repeat(500))),
+        deep(format!("{}1{}", "f(".repeat(500), ")".repeat(500))),
+        deep(format!("{}1", "-".repeat(500))),
+        deep(format!("1{}", " + 1".repeat(5000))),
+        deep(format!("a{}", ".b".repeat(5000))),
+        deep(format!("a{}", "[0]".repeat(5000))),
+    ];
+    for input in inputs.iter() {
+        fuzz(input);
+    }
+}
+
+// Every sample cut off at each of its characters, which leaves blocks, calls
+// and literals open in every way the samples have them
+#[test]
+fn fuzz_truncated_samples() {
+    let mut samples: usize = 0;
+    for entry in std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/tests")).unwrap() {
+        let path: std::path::PathBuf = entry.unwrap().path();
+        if path.extension().is_none_or(|extension| extension != "sl") {
+            continue;
+        }
+        let source: Vec<u8> = std::fs::read(&path).unwrap();
+        for end in 0..=source.len() {
+            fuzz(&source[..end]);
+        }
+        samples += 1;
+    }
+    assert!(samples > 0);
+}
@@ -0,0 +1,52 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+// Runs the compiler with a program on stdin
+fn compiler(args: &[&str], source: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_scripting-language"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(source.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+const UNUSED: &str = "func main(): int
+\tvar x: int = 1
+\treturn 0
+end
+";
+
+// What's built from stdin without `--output` is printed, so a warning
+// printed along with it would end up in the C
+#[test]
+fn stdin_warnings_go_to_stderr() {
+    for command in ["emit", "build"] {
+        let output: Output = compiler(&[command, "-Wunused", "-"], UNUSED);
+        assert!(output.status.success());
+        let stdout: String = String::from_utf8(output.stdout).unwrap();
+        let stderr: String = String::from_utf8(output.stderr).unwrap();
+        assert!(stdout.contains("int main("), "{}", stdout);
+        assert!(!stdout.contains("W0002"), "{}", stdout);
+        assert!(stderr.contains("W0002"), "{}", stderr);
+    }
+}
+
+// The same goes for what `--emit` prints, and for the errors found on the
+// way to printing the syntax tree
+#[test]
+fn emitted_warnings_go_to_stderr() {
+    let output: Output = compiler(&["emit", "--emit=c", "-Wunused", "-"], UNUSED);
+    let stdout: String = String::from_utf8(output.stdout).unwrap();
+    let stderr: String = String::from_utf8(output.stderr).unwrap();
+    assert!(stdout.contains("int main("), "{}", stdout);
+    assert!(!stdout.contains("W0002"), "{}", stdout);
+    assert!(stderr.contains("W0002"), "{}", stderr);
+    let output: Output = compiler(&["emit", "--emit=ast", "-"], "func main(): int\n\treturn (\nend\n");
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(!output.stderr.is_empty());
+}
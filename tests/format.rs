@@ -0,0 +1,92 @@
+use scripting_language::diagnostics::Diagnostic;
+use scripting_language::format::Formatter;
+use scripting_language::lexer::Lexer;
+use scripting_language::parser::Parser;
+use scripting_language::unparse::Unparser;
+
+fn codes(errors: &[Diagnostic]) -> Vec<&str> {
+    errors.iter().map(|error| error.code).collect()
+}
+
+// A string left open at the end of the file used to have its token run past
+// the source, which slicing out its text panicked on
+#[test]
+fn unterminated_string_at_end_of_file() {
+    let errors: Vec<Diagnostic> = Formatter::format("func main(): int\n\treturn 0\nend\n\"").unwrap_err();
+    assert_eq!(codes(&errors), ["E0039"]);
+    assert!(!Formatter::tokens("\"").is_empty());
+}
+
+#[test]
+fn refuses_what_does_not_parse() {
+    let errors: Vec<Diagnostic> = Formatter::format("func main(): int\n\treturn +\nend\n").unwrap_err();
+    assert!(!errors.is_empty());
+}
+
+const MESSY: &str = "import \"std/stdio.h\"
+struct   Point
+  x:int
+    y : int
+end
+// Adds them up
+func main():int
+    var p:Point* = new Point(1,2)
+  if p.x > 0
+ printf(\"%d\\n\",p.x+p.y)
+ end
+
+
+
+  return 0
+end
+";
+
+const FORMATTED: &str = "import \"std/stdio.h\"
+struct Point
+\tx: int
+\ty: int
+end
+// Adds them up
+func main(): int
+\tvar p: Point* = new Point(1, 2)
+\tif p.x > 0
+\t\tprintf(\"%d\\n\", p.x + p.y)
+\tend
+
+\treturn 0
+end
+";
+
+// The program written back out from what was parsed
+fn unparsed(source: &str) -> String {
+    Unparser::unparse(&Parser::new(Lexer::new(source.to_string()), 0).parse())
+}
+
+#[test]
+fn round_trip() {
+    let formatted: String = Formatter::format(MESSY).unwrap();
+    assert_eq!(formatted, FORMATTED);
+    assert_eq!(Formatter::format(&formatted).unwrap(), formatted);
+    assert_eq!(unparsed(&formatted), unparsed(MESSY));
+}
+
+// Every sample that parses comes out of the formatter as the same program,
+// and formatting it again changes nothing
+#[test]
+fn samples_round_trip() {
+    let mut formatted: usize = 0;
+    for entry in std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/tests")).unwrap() {
+        let path: std::path::PathBuf = entry.unwrap().path();
+        if path.extension().is_none_or(|extension| extension != "sl") {
+            continue;
+        }
+        let source: String = std::fs::read_to_string(&path).unwrap();
+        let Ok(once) = Formatter::format(&source) else {
+            continue;
+        };
+        assert_eq!(Formatter::format(&once).unwrap(), once, "{}", path.display());
+        assert_eq!(unparsed(&once), unparsed(&source), "{}", path.display());
+        formatted += 1;
+    }
+    assert!(formatted > 0);
+}
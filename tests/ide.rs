@@ -0,0 +1,83 @@
+use scripting_language::ide::{Ide, SignatureHelp};
+use scripting_language::lexer::TokenLocation;
+
+// The signature help at the end of `before` with `after` following it
+fn signature_help(before: &str, after: &str) -> Option<SignatureHelp> {
+    let ide: Ide = Ide::new("test.sl".to_string(), format!("{}{}", before, after));
+    ide.signature_help(before.len())
+}
+
+fn parameters(help: &SignatureHelp) -> Vec<String> {
+    help.parameters.iter().map(|(name, _)| name.to_string()).collect()
+}
+
+const PERSON: &str = "struct Person
+\tname: cstring
+\tage: int
+
+\tconstructor: func(cstring, int): Person*
+\tgreet: func(Person*)
+end
+
+func Person.greet(self: Person*)
+end
+";
+
+// The constructor made from a struct's fields sets them in order
+#[test]
+fn new_takes_constructor_parameters() {
+    for call in ["new Person(", "Person("] {
+        let help: SignatureHelp = signature_help(&format!("{}func main()\n\tvar p: Person* = {}\"a\", ", PERSON, call), "1)\nend\n").unwrap();
+        assert_eq!(parameters(&help), ["name", "age"]);
+        assert_eq!(help.active, Some(1));
+        assert!(!help.label.contains("constructor"), "{}", help.label);
+    }
+}
+
+#[test]
+fn written_constructor_names_parameters() {
+    let source: String = format!("{}func Person.constructor(who: cstring, years: int): Person*
+\tvar self: Person* = malloc(sizeof Person) as Person*
+\treturn self
+end
+func main()
+\tvar p: Person* = new Person(", PERSON);
+    let help: SignatureHelp = signature_help(&source, "\"a\", 1)\nend\n").unwrap();
+    assert_eq!(parameters(&help), ["who", "years"]);
+}
+
+#[test]
+fn without_constructor_takes_fields() {
+    let help: SignatureHelp = signature_help("struct Point\n\tx: int\n\ty: int\nend\nfunc main()\n\tvar p: Point* = new Point(", "1, 2)\nend\n").unwrap();
+    assert_eq!(parameters(&help), ["x", "y"]);
+}
+
+const NODE: &str = "struct Node
+\tvalue: int
+\tsum: func(Node*): int
+end
+
+func Node.sum(self: Node*): int
+\treturn self.value
+end
+
+func main(): int
+\tvar b: Node* = new Node(1)
+\treturn b.sum()
+end
+";
+
+// A method call goes to the method's name in its declaration, as the name
+// there does, rather than to the struct
+#[test]
+fn method_definition() {
+    let ide: Ide = Ide::new("test.sl".to_string(), NODE.to_string());
+    let declared: usize = NODE.find("Node.sum").unwrap() + "Node.".len();
+    let called: usize = NODE.rfind("sum").unwrap();
+    for offset in [declared, called] {
+        let definition: TokenLocation = ide.definition(offset + 1).unwrap();
+        assert_eq!((definition.start, definition.end), (declared, declared + 3));
+    }
+    let references: Vec<usize> = ide.references(called + 1, true).iter().map(|location| location.start).collect();
+    assert_eq!(references, [declared, called]);
+}
@@ -0,0 +1,51 @@
+use scripting_language::json::{json_string, Json};
+
+fn parsed(text: &str) -> Option<String> {
+    Json::parse(text).and_then(|value| value.as_str().map(str::to_string))
+}
+
+// Whatever a string holds comes back the same after it's written and read
+#[test]
+fn strings_round_trip() {
+    for value in ["", "plain", "\"quoted\"", "back\\slash", "line\nbreak\r\n", "\ttab", "\u{1}\u{1f}\u{7f}", "é ü", "😀 𝄞", "\\u0041"] {
+        let written: String = json_string(value);
+        assert!(!written[1..written.len() - 1].chars().any(|character| (character as u32) < 0x20), "{}", written);
+        assert_eq!(parsed(&written).as_deref(), Some(value), "{}", written);
+    }
+}
+
+#[test]
+fn escapes() {
+    for (text, value) in [
+        (r#""\"\\\/\b\f\n\r\t""#, "\"\\/\u{8}\u{c}\n\r\t"),
+        (r#""\u0041\u00e9\u20AC""#, "Aé€"),
+        (r#""\ud83d\ude00!""#, "😀!"),
+        (r#""\uD834\uDD1E""#, "𝄞"),
+        // Half of a surrogate pair alone isn't a character
+        (r#""\ud83d""#, "\u{fffd}"),
+        (r#""\ude00x""#, "\u{fffd}x"),
+        (r#""\ud83dA""#, "\u{fffd}A"),
+        (r#""\ud83d\u0041""#, "\u{fffd}A"),
+    ] {
+        assert_eq!(parsed(text).as_deref(), Some(value), "{}", text);
+    }
+    for text in [r#""\u00""#, r#""\u12G4""#, r#""unterminated"#, r#""\"#] {
+        assert_eq!(parsed(text), None, "{}", text);
+    }
+}
+
+// A request's id and params are echoed back as they came
+#[test]
+fn values_round_trip() {
+    let text: &str = r#"{"id":7,"params":{"text":"a\n\"b\"","list":[1.5,-2,true,false,null,[]],"empty":{}}}"#;
+    let value: Json = Json::parse(text).unwrap();
+    assert_eq!(value.render(), text);
+    assert_eq!(Json::parse(&value.render()), Some(value.clone()));
+    assert_eq!(value.get("id").as_usize(), Some(7));
+    assert_eq!(value.get("params").get("text").as_str(), Some("a\n\"b\""));
+    assert_eq!(value.get("missing"), &Json::Null);
+    assert_eq!(Json::parse(" [1, 2] "), Some(Json::Array(vec![Json::Number(1.0), Json::Number(2.0)])));
+    for text in ["[1,]", "{\"a\" 1}", "[1] 2", "{"] {
+        assert_eq!(Json::parse(text), None, "{}", text);
+    }
+}
@@ -0,0 +1,28 @@
+use scripting_language::ast::Statement;
+use scripting_language::lexer::{Lexer, Token, TokenKind};
+use scripting_language::parser::Parser;
+
+fn kinds(source: &str) -> Vec<TokenKind> {
+    Lexer::new(source.to_string()).lex().into_iter().map(|token: Token| token.kind).collect()
+}
+
+// A comment at the end of a statement used to take the newline after it
+// along, which ran the statement into the next one
+#[test]
+fn trailing_comment_keeps_newline() {
+    assert_eq!(kinds("x // one\ny"), kinds("x\ny"));
+    let comments: Vec<TokenKind> = Lexer::with_comments("x // one\ny".to_string()).lex().into_iter().map(|token| token.kind).collect();
+    assert_eq!(comments[1], TokenKind::Comment);
+    assert_eq!(comments[2], TokenKind::Newline);
+}
+
+#[test]
+fn statement_after_trailing_comment() {
+    let mut parser: Parser = Parser::new(Lexer::new("func main(): int\n\tvar x: int = 1 // one\n\treturn x\nend\n".to_string()), 0);
+    let statements: Vec<Statement> = parser.parse();
+    assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+    let Statement::Function(_, _, _, body, _) = &statements[0] else {
+        panic!("{:?}", statements);
+    };
+    assert_eq!(body.len(), 2);
+}
@@ -0,0 +1,85 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+use scripting_language::json::{json_string, Json};
+
+const URI: &str = "file:///project/main.sl";
+const SOURCE: &str = "func twice(x: int): int\n\treturn x * 2\nend\n\nfunc main(): int\n\treturn twice(1 + true)\nend\n";
+
+// Starts `sl lsp`, sends it each message in turn, and reads back everything it
+// wrote once it's gone
+fn session(messages: &[String]) -> (Option<i32>, Vec<Json>) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_scripting-language"))
+        .arg("lsp")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    for message in messages {
+        write!(stdin, "Content-Length: {}\r\n\r\n{}", message.len(), message).unwrap();
+    }
+    drop(stdin);
+    let output: Output = child.wait_with_output().unwrap();
+    let mut rest: &str = std::str::from_utf8(&output.stdout).unwrap();
+    let mut replies: Vec<Json> = vec![];
+    while let Some((header, after)) = rest.split_once("\r\n\r\n") {
+        let length: usize = header.trim_start_matches("Content-Length: ").parse().unwrap();
+        replies.push(Json::parse(&after[..length]).unwrap());
+        rest = &after[length..];
+    }
+    (output.status.code(), replies)
+}
+
+fn request(id: usize, method: &str, params: &str) -> String {
+    format!("{{\"jsonrpc\":\"2.0\",\"id\":{},\"method\":\"{}\",\"params\":{}}}", id, method, params)
+}
+
+fn notification(method: &str, params: &str) -> String {
+    format!("{{\"jsonrpc\":\"2.0\",\"method\":\"{}\",\"params\":{}}}", method, params)
+}
+
+fn at(line: usize, character: usize) -> String {
+    format!("{{\"textDocument\":{{\"uri\":\"{}\"}},\"position\":{{\"line\":{},\"character\":{}}}}}", URI, line, character)
+}
+
+fn reply(replies: &[Json], id: usize) -> &Json {
+    replies.iter().find(|reply| reply.get("id").as_usize() == Some(id)).unwrap().get("result")
+}
+
+// An editor opens a file, is told what's wrong with it, asks about a name and
+// where it's defined, and shuts the server down
+#[test]
+fn session_answers() {
+    let opened: String = format!("{{\"textDocument\":{{\"uri\":\"{}\",\"languageId\":\"sl\",\"version\":1,\"text\":{}}}}}", URI, json_string(SOURCE));
+    let (code, replies): (Option<i32>, Vec<Json>) = session(&[
+        request(1, "initialize", "{\"capabilities\":{}}"),
+        notification("initialized", "{}"),
+        notification("textDocument/didOpen", &opened),
+        request(2, "textDocument/hover", &at(5, 9)),
+        request(3, "textDocument/definition", &at(5, 9)),
+        request(4, "textDocument/unknown", "{}"),
+        request(5, "shutdown", "null"),
+        notification("exit", "null"),
+    ]);
+    assert_eq!(code, Some(0));
+    assert_eq!(reply(&replies, 1).get("capabilities").get("hoverProvider"), &Json::Bool(true));
+    let published: &Json = replies.iter().find(|reply| reply.get("method").as_str() == Some("textDocument/publishDiagnostics")).unwrap().get("params");
+    assert_eq!(published.get("uri").as_str(), Some(URI));
+    let Json::Array(diagnostics) = published.get("diagnostics") else { panic!("no diagnostics") };
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].get("code").as_str(), Some("E0017"));
+    assert_eq!(diagnostics[0].get("range").get("start").get("line").as_usize(), Some(5));
+    let hover: &str = reply(&replies, 2).get("contents").get("value").as_str().unwrap();
+    assert!(hover.contains("func twice(x: int): int"), "{}", hover);
+    assert_eq!(reply(&replies, 3).get("range").get("start").get("line").as_usize(), Some(0));
+    let missing: &Json = replies.iter().find(|reply| reply.get("id").as_usize() == Some(4)).unwrap();
+    assert_eq!(missing.get("error").get("code"), &Json::Number(-32601.0));
+}
+
+// Without a shutdown first, exiting is an error, as is stdin closing
+#[test]
+fn exit_codes() {
+    assert_eq!(session(&[notification("exit", "null")]).0, Some(1));
+    assert_eq!(session(&[request(1, "initialize", "{}")]).0, Some(1));
+}
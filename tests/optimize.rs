@@ -0,0 +1,91 @@
+use std::collections::HashSet;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+use scripting_language::ast::Statement;
+use scripting_language::lexer::Lexer;
+use scripting_language::optimize::{Optimization, Optimizer};
+use scripting_language::parser::Parser;
+use scripting_language::target::TargetMachine;
+use scripting_language::unparse::Unparser;
+
+// A program with some optimizations run over it, written back out as source
+fn optimize(source: &str, optimizations: &[Optimization]) -> String {
+    let mut parser: Parser = Parser::new(Lexer::new(source.to_string()), 0);
+    let statements: Vec<Statement> = parser.parse();
+    assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+    let enabled: HashSet<Optimization> = optimizations.iter().cloned().collect();
+    Unparser::unparse(&Optimizer::new(&enabled, &TargetMachine::host()).run(statements))
+}
+
+// The body of `main` in a program, after optimizing
+fn main_body(body: &str, optimizations: &[Optimization]) -> String {
+    let source: String = format!("func main(): int\n{}end\n", body);
+    let optimized: String = optimize(&source, optimizations);
+    optimized.trim_start_matches("func main(): int\n").trim_end().trim_end_matches("end").to_string()
+}
+
+#[test]
+fn folds_constants() {
+    let body: String = main_body("\tvar a: int = 2 * 3 + 4\n\tvar b: int = (7 - 10) * -1\n\tvar c: bool = !0\n\treturn a + b\n", &[Optimization::ConstantFolding]);
+    assert_eq!(body, "\tvar a: int = 10\n\tvar b: int = 3\n\tvar c: bool = 1\n\treturn a + b\n");
+}
+
+// What C would make undefined, or can't write as a literal, is left for it
+// to compute
+#[test]
+fn leaves_what_cannot_fold() {
+    let body: String = main_body("\tvar a: int = 1 / 0\n\tvar b: int = 2147483647 + 1\n\treturn a + b\n", &[Optimization::ConstantFolding]);
+    assert_eq!(body, "\tvar a: int = 1 / 0\n\tvar b: int = 2147483647 + 1\n\treturn a + b\n");
+}
+
+#[test]
+fn removes_dead_branches() {
+    let source: &str = "\tif 1 - 1\n\t\treturn 1\n\tend\n\treturn 2\n\treturn 3\n";
+    assert_eq!(main_body(source, &Optimization::all()), "\treturn 2\n");
+    // Without constant folding the condition isn't known, and without either
+    // pass nothing changes
+    assert_eq!(main_body(source, &[Optimization::DeadBranches]), "\tif 1 - 1\n\t\treturn 1\n\tend\n\treturn 2\n");
+    assert_eq!(main_body(source, &[]), source);
+}
+
+const INLINED: &str = "import \"std/stdio.h\"
+
+var calls: int = 0
+
+func next(): int
+\tcalls = calls + 1
+\treturn calls
+end
+
+inline func twice(x: int): int => x + x
+
+func main(): int
+\tvar a: int = twice(next())
+\tvar b: int = twice(a)
+\tprintf(\"%d %d %d\\n\", a, b, calls)
+\treturn 0
+end
+";
+
+// An argument with side effects goes into a temporary so that it's still
+// evaluated once, and a plain variable is used as it is
+#[test]
+fn inlines_calls() {
+    let inlined: String = optimize(INLINED, &[Optimization::Inline]);
+    let main: &str = &inlined[inlined.find("func main").unwrap()..];
+    assert_eq!(main.matches("next()").count(), 1, "{}", main);
+    assert!(!main.contains("twice"), "{}", main);
+    assert!(main.contains("\tvar b: int = (a + a)\n"), "{}", main);
+    let output: Output = Command::new(env!("CARGO_BIN_EXE_scripting-language"))
+        .args(["run", "-O", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            child.stdin.take().unwrap().write_all(INLINED.as_bytes())?;
+            child.wait_with_output()
+        })
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "2 4 1\n", "{}", String::from_utf8_lossy(&output.stderr));
+}
@@ -0,0 +1,36 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+// Builds and runs a program read from stdin, which needs a C compiler
+fn run(source: &str, args: &[&str]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_scripting-language"))
+        .arg("run")
+        .args(args)
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(source.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+const STRINGS: &str = "func main(): int
+\tvar s: string = \"variable\"
+\tprintln(\"{} {} {}\", \"literal\", s, 3)
+\tprintln(\"{}\", \"alone\")
+\treturn 0
+end
+";
+
+// A literal among the values is C's string, not a `string`, so it used to be
+// handed to the helper for those, which wasn't there when nothing else was a
+// `string` and didn't take it when something was
+#[test]
+fn literals_and_strings() {
+    for args in [vec![], vec!["--target=c++"]] {
+        let output: Output = run(STRINGS, &args);
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "literal variable 3\nalone\n", "{}", String::from_utf8_lossy(&output.stderr));
+    }
+}
@@ -0,0 +1,15 @@
+use scripting_language::symbol::Symbol;
+
+// A name interned anywhere in the process is the same symbol, so symbols made
+// on another thread, as the language server's are, still compare equal
+#[test]
+fn interned_once() {
+    let name: Symbol = Symbol::intern("interned_once");
+    let other: Symbol = std::thread::spawn(|| Symbol::intern(&format!("interned_{}", "once"))).join().unwrap();
+    assert_eq!(name, other);
+    assert!(std::ptr::eq(name.as_str(), other.as_str()));
+    assert_ne!(name, Symbol::intern("interned_twice"));
+    let mut names: Vec<Symbol> = ["b", "c", "a"].into_iter().map(Symbol::intern).collect();
+    names.sort();
+    assert_eq!(names, ["a", "b", "c"].map(Symbol::intern));
+}